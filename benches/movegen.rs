@@ -0,0 +1,354 @@
+// How fast `MoveGen::new_legal` generates moves, through perft.  Run with `cargo bench --bench
+// movegen`, and again with `--features movegen-stats` to see what counting the moves costs.
+// Without the feature there is nothing to count, and the numbers are the same.
+//
+// Then it counts the start position at depth 6 on every thread with
+// `MoveGen::perft_parallel_depth`, splitting only at the root and then deeper, where the work is
+// handed out in smaller pieces.
+//
+// Then it takes only the first few moves of each position two plies into the same positions, as
+// a search does when one of them cuts off, to compare `MoveGen::new_legal` with generating the
+// pieces lazily through `MoveGen::with_piece_order`.  It prints how many moves were generated
+// before the first one was given, and how long taking the first few moves took.
+//
+// Then it goes through all the moves of the same positions, once straight from the `MoveGen`,
+// which keeps them inline and never allocates, and once collected into a `Vec` first, which pays
+// for an allocation in every position as a move list on the heap would.
+//
+// Then it generates the moves of the uno positions with `MoveGen::new_legal`, and with
+// `MoveGen::new_legal_with` by `UnoRules` and by a `CaptureRules` of its own that only calls
+// them, to see that going through the trait costs nothing.
+//
+// Then it takes the positions a ply further with a pinned piece, and generates their moves with
+// `MoveGen::new_legal`, which keeps a pinned piece on its pin line, next to
+// `MoveGen::new_legal_with`, which makes every move to see if it leaves my king in check.
+//
+// Then it checks moves that did not come from the move generator, as a search does with the move
+// of a transposition table entry, once with `Board::legal` and once with `Board::sanitize_move`.
+//
+// Then it generates the moves of a quiescence search with checks, with
+// `MoveGen::new_quiescence` next to finding the checks by making every legal move, and
+// compares `Board::gives_check` with making the move the same way.
+//
+// Last it looks up the moves of rooks and bishops on random occupancies.  Built with
+// `RUSTFLAGS="-C target-cpu=native"` on a CPU with BMI2, it also looks them up in the pext
+// tables, which `get_rook_moves` and `get_bishop_moves` use instead of the magics with
+// `--features pext`.
+
+use chess::{
+    get_bishop_moves, get_rook_moves, BitBoard, Board, CaptureRules, ChessMove, Color, MoveGen,
+    Piece, Square, StandardRules, UnoRules, Variant, ALL_PIECES, ALL_SQUARES, EMPTY,
+};
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::Instant;
+
+const POSITIONS: [(&str, usize); 4] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        5,
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 variant:standard",
+        4,
+    ),
+    (
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 variant:standard",
+        5,
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        4,
+    ),
+];
+
+fn main() {
+    for (fen, depth) in POSITIONS.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let start = Instant::now();
+        let nodes = MoveGen::movegen_perft_test(&board, *depth);
+        let seconds = start.elapsed().as_secs_f64();
+        println!(
+            "{:<10} depth {}  {:>10} nodes  {:>8.2} M nodes/s",
+            board.variant().to_string(),
+            depth,
+            nodes,
+            nodes as f64 / seconds / 1e6
+        );
+    }
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for split_depth in 1..=3 {
+        let start = Instant::now();
+        let nodes = MoveGen::perft_parallel_depth(&Board::default(), 6, split_depth);
+        let seconds = start.elapsed().as_secs_f64();
+        println!(
+            "parallel   depth 6  split {}  {} threads  {:>10} nodes  {:>8.2} M nodes/s",
+            split_depth,
+            threads,
+            nodes,
+            nodes as f64 / seconds / 1e6
+        );
+    }
+
+    let mut boards = Vec::new();
+    for (fen, _) in POSITIONS.iter() {
+        let board = Board::from_str(fen).unwrap();
+        for m in MoveGen::new_legal(&board) {
+            let child = board.make_move_new(m);
+            boards.extend(MoveGen::new_legal(&child).map(|m| child.make_move_new(m)));
+        }
+    }
+    // the pieces left out follow from the pawns up
+    let heavy_first = [Piece::Queen, Piece::Rook];
+    for taken in 1..=3 {
+        let legal = first_moves(&boards, |board| {
+            let movegen = MoveGen::new_legal(board);
+            let generated = movegen.len();
+            (movegen.take(taken).last(), generated)
+        });
+        let default = first_moves(&boards, |board| {
+            let mut movegen = MoveGen::with_piece_order(board, &ALL_PIECES);
+            let first = movegen.next();
+            let generated = movegen.generated();
+            (movegen.take(taken - 1).last().or(first), generated)
+        });
+        let heavy = first_moves(&boards, |board| {
+            let mut movegen = MoveGen::with_piece_order(board, &heavy_first);
+            let first = movegen.next();
+            let generated = movegen.generated();
+            (movegen.take(taken - 1).last().or(first), generated)
+        });
+        for (name, (generated, seconds)) in [
+            ("new_legal", legal),
+            ("by pieces", default),
+            ("heavy first", heavy),
+        ]
+        .iter()
+        {
+            println!(
+                "{:<11} first {} moves  {:>6.2} moves generated before the first  {:>8.2} ms",
+                name,
+                taken,
+                *generated as f64 / boards.len() as f64,
+                seconds * 1e3
+            );
+        }
+    }
+
+    let inline = count_moves(&boards, |board| {
+        MoveGen::new_legal(board).map(black_box).count()
+    });
+    let heap = count_moves(&boards, |board| {
+        let moves: Vec<ChessMove> = MoveGen::new_legal(board).collect();
+        moves.into_iter().map(black_box).count()
+    });
+    for (name, (moves, seconds)) in [("new_legal", inline), ("into a Vec", heap)].iter() {
+        println!("{:<11} {:>8} moves  {:>8.2} ms", name, moves, seconds * 1e3);
+    }
+
+    let uno: Vec<Board> = boards
+        .iter()
+        .filter(|board| board.variant() == Variant::Uno)
+        .copied()
+        .collect();
+    let legal = count_moves(&uno, |board| MoveGen::new_legal(board).len());
+    let rules = count_moves(&uno, |board| {
+        MoveGen::new_legal_with::<UnoRules>(board).len()
+    });
+    let forwarded = count_moves(&uno, |board| {
+        MoveGen::new_legal_with::<Forward>(board).len()
+    });
+    for (name, (moves, seconds)) in [
+        ("new_legal", legal),
+        ("UnoRules", rules),
+        ("forwarded", forwarded),
+    ]
+    .iter()
+    {
+        println!("{:<11} {:>8} moves  {:>8.2} ms", name, moves, seconds * 1e3);
+    }
+
+    // a ply further, where more of the positions have pins
+    let pinned: Vec<Board> = boards
+        .iter()
+        .flat_map(|board| MoveGen::new_legal(board).map(move |m| board.make_move_new(m)))
+        .filter(|board| *board.pinned() != EMPTY)
+        .collect();
+    let masked = count_moves(&pinned, |board| MoveGen::new_legal(board).len());
+    let made = count_moves(&pinned, |board| match board.variant() {
+        Variant::Uno => MoveGen::new_legal_with::<UnoRules>(board).len(),
+        Variant::Standard => MoveGen::new_legal_with::<StandardRules>(board).len(),
+    });
+    println!("{} positions with a pinned piece", pinned.len());
+    for (name, (moves, seconds)) in [("pin lines", masked), ("made moves", made)].iter() {
+        println!("{:<11} {:>8} moves  {:>8.2} ms", name, moves, seconds * 1e3);
+    }
+
+    // the moves of the board before, which has the same side to move and is mostly the same
+    // position, so many of them are legal here as well
+    let mut probes = Vec::new();
+    for pair in boards.windows(2).take(20_000) {
+        probes.extend(MoveGen::new_legal(&pair[0]).map(|m| (pair[1], m)));
+    }
+    let legal = check_moves(&probes, |board, m| Some(m).filter(|m| board.legal(*m)));
+    let sanitized = check_moves(&probes, |board, m| board.sanitize_move(m));
+    for (name, (accepted, seconds)) in [("legal", legal), ("sanitize_move", sanitized)].iter() {
+        println!(
+            "{:<13} {} of {} moves  {:>8.2} M moves/s",
+            name,
+            accepted,
+            probes.len(),
+            probes.len() as f64 / seconds / 1e6
+        );
+    }
+
+    let checks = |board: &Board, m: ChessMove| *board.make_move_new(m).checkers() != EMPTY;
+    let quiescence = count_moves(&boards, |board| MoveGen::new_quiescence(board, true).len());
+    let naive = count_moves(&boards, |board| {
+        if *board.checkers() != EMPTY {
+            return MoveGen::new_legal(board).len();
+        }
+        MoveGen::new_legal(board)
+            .filter(|m| {
+                // a pawn moving to another file without taking takes en passant
+                let capture = board.piece_on(m.get_dest()).is_some()
+                    || (board.piece_on(m.get_source()) == Some(Piece::Pawn)
+                        && m.get_source().get_file() != m.get_dest().get_file());
+                match m.get_promotion() {
+                    Some(Piece::Queen) => true,
+                    Some(_) => false,
+                    None => capture || checks(board, *m),
+                }
+            })
+            .count()
+    });
+    let gives_check = count_moves(&boards, |board| {
+        MoveGen::new_legal(board)
+            .filter(|m| board.gives_check(*m))
+            .count()
+    });
+    let made = count_moves(&boards, |board| {
+        MoveGen::new_legal(board)
+            .filter(|m| checks(board, *m))
+            .count()
+    });
+    for (name, (moves, seconds)) in [
+        ("new_quiescence", quiescence),
+        ("made quiescence", naive),
+        ("gives_check", gives_check),
+        ("made checks", made),
+    ]
+    .iter()
+    {
+        println!("{:<15} {:>8} moves  {:>8.2} ms", name, moves, seconds * 1e3);
+    }
+
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    let occupancies: Vec<BitBoard> = (0..4096)
+        .map(|_| {
+            let mut next = || {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed
+            };
+            // about a quarter of the squares, as in a middlegame
+            BitBoard::new(next() & next())
+        })
+        .collect();
+    let path = if cfg!(all(feature = "pext", target_feature = "bmi2")) {
+        "pext"
+    } else {
+        "magic"
+    };
+    slider_moves(&occupancies, path, get_rook_moves, get_bishop_moves);
+    #[cfg(target_feature = "bmi2")]
+    slider_moves(
+        &occupancies,
+        "bmi",
+        chess::get_rook_moves_bmi,
+        chess::get_bishop_moves_bmi,
+    );
+}
+
+// The uno rules, through a `CaptureRules` that is not the crate's own.
+struct Forward;
+
+impl CaptureRules for Forward {
+    fn capture_targets(
+        board: &Board,
+        src: Square,
+        piece: Piece,
+        color: Color,
+        occupied: BitBoard,
+    ) -> BitBoard {
+        UnoRules::capture_targets(board, src, piece, color, occupied)
+    }
+}
+
+// Take the first `taken` moves of each board with `take`, which gives the last of them and how
+// many moves it generated before the first, and give the average of that with the time it took.
+fn first_moves<F>(boards: &[Board], mut take: F) -> (usize, f64)
+where
+    F: FnMut(&Board) -> (Option<ChessMove>, usize),
+{
+    let start = Instant::now();
+    let mut generated = 0;
+    for board in boards.iter() {
+        let (m, count) = black_box(take(board));
+        black_box(m);
+        generated += count;
+    }
+    (generated, start.elapsed().as_secs_f64())
+}
+
+// Check each move on its board with `check`, and give how many it let through with the time it
+// took.
+fn check_moves<F>(probes: &[(Board, ChessMove)], mut check: F) -> (usize, f64)
+where
+    F: FnMut(&Board, ChessMove) -> Option<ChessMove>,
+{
+    let start = Instant::now();
+    let mut accepted = 0;
+    for (board, m) in probes.iter() {
+        if black_box(check(board, *m)).is_some() {
+            accepted += 1;
+        }
+    }
+    (accepted, start.elapsed().as_secs_f64())
+}
+
+// Count the moves `count` gives for each board, with the time it took.
+fn count_moves<F>(boards: &[Board], mut count: F) -> (usize, f64)
+where
+    F: FnMut(&Board) -> usize,
+{
+    let start = Instant::now();
+    let moves = boards.iter().map(|board| black_box(count(board))).sum();
+    (moves, start.elapsed().as_secs_f64())
+}
+
+// Look up the rook and bishop moves from every square on each of `occupancies`, and print how
+// fast that was.
+fn slider_moves(
+    occupancies: &[BitBoard],
+    name: &str,
+    rook: fn(Square, BitBoard) -> BitBoard,
+    bishop: fn(Square, BitBoard) -> BitBoard,
+) {
+    let start = Instant::now();
+    let mut check = EMPTY;
+    for occupied in occupancies.iter() {
+        for sq in ALL_SQUARES.iter() {
+            check ^= rook(*sq, black_box(*occupied)) ^ bishop(*sq, black_box(*occupied));
+        }
+    }
+    let lookups = occupancies.len() * ALL_SQUARES.len() * 2;
+    println!(
+        "{:<6} slider moves  {:>8.2} M lookups/s  ({:x})",
+        name,
+        lookups as f64 / start.elapsed().as_secs_f64() / 1e6,
+        check.0
+    );
+}
@@ -0,0 +1,86 @@
+// How fast positions come back from a file of packed boards, next to parsing the same positions
+// from FEN.  Run with `cargo bench --features positions`.
+
+use chess::positions::{records, Reader, Writer};
+use chess::{Board, BoardBuilder, ChessMove, MoveGen, Variant, ALL_VARIANTS};
+use std::convert::TryFrom;
+use std::io::Cursor;
+use std::str::FromStr;
+use std::time::Instant;
+
+const GAMES: usize = 2_000;
+
+// every position of random games from the start, in both variants
+fn positions() -> Vec<Board> {
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut boards = Vec::new();
+    for game in 0..GAMES {
+        let variant: Variant = ALL_VARIANTS[game % ALL_VARIANTS.len()];
+        let mut board = Board::try_from(BoardBuilder::default().variant(variant)).unwrap();
+        for _ in 0..100 {
+            boards.push(board);
+            let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+            if moves.is_empty() {
+                break;
+            }
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            board = board.make_move_new(moves[(seed % moves.len() as u64) as usize]);
+        }
+    }
+    boards
+}
+
+fn report(name: &str, count: usize, start: Instant, check: u64) {
+    let seconds = start.elapsed().as_secs_f64();
+    println!(
+        "{:<28} {:>8.2} M positions/s  ({:x})",
+        name,
+        count as f64 / seconds / 1e6,
+        check
+    );
+}
+
+fn main() {
+    let boards = positions();
+    let fens: Vec<String> = boards.iter().map(|board| board.to_tagged_fen()).collect();
+    let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+    for board in boards.iter() {
+        writer.write(board).unwrap();
+    }
+    let file = writer.finish().unwrap().into_inner();
+    println!(
+        "{} positions, {} bytes of FEN, {} bytes packed",
+        boards.len(),
+        fens.iter().map(|fen| fen.len() + 1).sum::<usize>(),
+        file.len()
+    );
+
+    let start = Instant::now();
+    let check = fens.iter().fold(0, |check, fen| {
+        check ^ Board::from_str(fen).unwrap().get_hash()
+    });
+    report("parse FEN", boards.len(), start, check);
+
+    let start = Instant::now();
+    let check = records(&file)
+        .unwrap()
+        .iter()
+        .fold(0, |check, packed| check ^ packed.hash());
+    report("records, hash only", boards.len(), start, check);
+
+    let start = Instant::now();
+    let check = records(&file).unwrap().iter().fold(0, |check, packed| {
+        check ^ packed.validate().unwrap().get_hash()
+    });
+    report("records, validated", boards.len(), start, check);
+
+    let start = Instant::now();
+    let check = Reader::new(Cursor::new(&file))
+        .unwrap()
+        .fold(0, |check, packed| {
+            check ^ packed.unwrap().validate().unwrap().get_hash()
+        });
+    report("Reader, validated", boards.len(), start, check);
+}
@@ -0,0 +1,19 @@
+// Any text either fails to parse as a FEN or gives a board that writes itself back the same way
+// and whose moves can all be made.
+
+#![no_main]
+
+use chess::{Board, BoardBuilder, MoveGen};
+use libfuzzer_sys::fuzz_target;
+use std::str::FromStr;
+
+fuzz_target!(|text: &str| {
+    let _ = BoardBuilder::from_str(text);
+    if let Ok(board) = Board::from_str(text) {
+        assert!(board.is_sane());
+        assert_eq!(Board::from_str(&board.to_tagged_fen()).unwrap(), board);
+        for m in MoveGen::new_legal(&board) {
+            assert!(board.make_move_new(m).is_sane());
+        }
+    }
+});
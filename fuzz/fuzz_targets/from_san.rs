@@ -0,0 +1,72 @@
+// Any text either fails to parse as a SAN move on a board, or is a legal move of it.  The board
+// is set up from the input the way `any::<Board>()` does with the `proptest` feature: the kings
+// first, then each piece only if the position stays valid.
+
+#![no_main]
+
+use chess::{Board, BoardBuilder, ChessMove, Color, Piece, Square, Variant, ALL_PIECES};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use std::convert::TryFrom;
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    standard: bool,
+    black_to_move: bool,
+    white_king: u8,
+    black_king: u8,
+    pieces: Vec<(u8, u8, bool)>,
+    san: &'a str,
+}
+
+fn color(black: bool) -> Color {
+    if black {
+        Color::Black
+    } else {
+        Color::White
+    }
+}
+
+fn board(input: &Input) -> Option<Board> {
+    let mut builder = BoardBuilder::new();
+    builder
+        .variant(if input.standard {
+            Variant::Standard
+        } else {
+            Variant::Uno
+        })
+        .side_to_move(color(input.black_to_move))
+        .piece(
+            Square::new(input.white_king % 64),
+            Piece::King,
+            Color::White,
+        )
+        .piece(
+            Square::new(input.black_king % 64),
+            Piece::King,
+            Color::Black,
+        );
+    Board::try_from(&builder).ok()?;
+
+    for (square, piece, black) in input.pieces.iter() {
+        let square = Square::new(square % 64);
+        if builder[square].is_some() {
+            continue;
+        }
+        // anything but a king
+        let piece = ALL_PIECES[usize::from(*piece) % (ALL_PIECES.len() - 1)];
+        builder.piece(square, piece, color(*black));
+        if Board::try_from(&builder).is_err() {
+            builder.clear_square(square);
+        }
+    }
+    Board::try_from(&builder).ok()
+}
+
+fuzz_target!(|input: Input| {
+    if let Some(board) = board(&input) {
+        if let Ok(m) = ChessMove::from_san(&board, input.san) {
+            assert!(board.legal(m));
+        }
+    }
+});
@@ -0,0 +1,146 @@
+use crate::color::Color;
+use crate::piece::Piece;
+use crate::square::Square;
+
+#[cfg(test)]
+use crate::board::Board;
+#[cfg(test)]
+use crate::chess_move::ChessMove;
+#[cfg(test)]
+use crate::color::ALL_COLORS;
+#[cfg(test)]
+use crate::movegen::MoveGen;
+#[cfg(test)]
+use crate::piece::ALL_PIECES;
+#[cfg(test)]
+use alloc::vec::Vec;
+#[cfg(test)]
+use core::str::FromStr;
+
+/// Something kept up to date from the pieces on the board, one (piece, color, square) at a time,
+/// such as the sum of piece-square tables or the first layer of a neural network.
+///
+/// `Board::make_move_with_accumulator` tells it each piece a move takes off a square, and then
+/// each piece it puts on one.  An en passant capture removes the pawn next to the square the
+/// pawn moves to, a promotion removes the pawn and adds the new piece, and a castle moves both
+/// the king and the rook.
+///
+/// ```
+/// use chess::{Accumulator, Board, ChessMove, Color, Piece, Square};
+///
+/// // the number of pieces on the light squares
+/// struct Light(i32);
+///
+/// impl Accumulator for Light {
+///     fn add(&mut self, _: Piece, _: Color, square: Square) {
+///         if square.to_index() % 2 != square.get_rank().to_index() % 2 {
+///             self.0 += 1;
+///         }
+///     }
+///
+///     fn remove(&mut self, _: Piece, _: Color, square: Square) {
+///         if square.to_index() % 2 != square.get_rank().to_index() % 2 {
+///             self.0 -= 1;
+///         }
+///     }
+/// }
+///
+/// // the knight on g1 goes to a light square
+/// let mut light = Light(16);
+/// let board = Board::default();
+/// board.make_move_with_accumulator(ChessMove::new(Square::G1, Square::F3, None), &mut light);
+/// assert_eq!(light.0, 17);
+/// ```
+pub trait Accumulator {
+    /// `piece` of `color` was put on `square`.
+    fn add(&mut self, piece: Piece, color: Color, square: Square);
+
+    /// `piece` of `color` was taken off `square`.
+    fn remove(&mut self, piece: Piece, color: Color, square: Square);
+}
+
+// a piece-square table sum, with a different value for every feature
+#[cfg(test)]
+#[derive(Default)]
+struct Psqt(i64);
+
+#[cfg(test)]
+fn psqt_value(piece: Piece, color: Color, square: Square) -> i64 {
+    let feature = (color.to_index() * 6 + piece.to_index()) * 64 + square.to_index();
+    (feature as i64 * 7919) % 1009 - 504
+}
+
+#[cfg(test)]
+impl Psqt {
+    fn of(board: &Board) -> Psqt {
+        let mut psqt = Psqt::default();
+        for color in ALL_COLORS.iter() {
+            for piece in ALL_PIECES.iter() {
+                for square in board.pieces(*piece) & board.color_combined(*color) {
+                    psqt.add(*piece, *color, square);
+                }
+            }
+        }
+        psqt
+    }
+}
+
+#[cfg(test)]
+impl Accumulator for Psqt {
+    fn add(&mut self, piece: Piece, color: Color, square: Square) {
+        self.0 += psqt_value(piece, color, square);
+    }
+
+    fn remove(&mut self, piece: Piece, color: Color, square: Square) {
+        self.0 -= psqt_value(piece, color, square);
+    }
+}
+
+#[test]
+fn test_special_moves_update_the_accumulator() {
+    let fens_and_moves = [
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard", "e5d6"),
+        ("r3k3/8/8/8/8/8/8/4K3 b q - 0 1", "e8c8"),
+        ("r3k3/8/8/8/8/8/8/4K3 b q - 0 1", "e8a8"),
+        ("4k3/8/8/8/8/8/8/1RK1R3 w EB - 0 1", "c1b1"),
+        ("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1 variant:standard", "a7b8n"),
+        ("4k3/8/8/3p4/4Q3/8/8/4K3 b - - 0 1", "d5e4"),
+    ];
+    for (fen, m) in fens_and_moves.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let m = ChessMove::from_str(m).unwrap();
+        assert!(board.legal(m));
+        let mut psqt = Psqt::of(&board);
+        let child = board.make_move_with_accumulator(m, &mut psqt);
+        assert_eq!(child, board.make_move_new(m));
+        assert_eq!(psqt.0, Psqt::of(&child).0, "{} {}", fen, m);
+    }
+}
+
+#[test]
+fn test_random_playouts_match_a_recomputed_accumulator() {
+    let fens = [
+        "r3k2r/pppq1ppp/2npbn2/4p3/2B1P1b1/2NP1N2/PPPBQPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/pppq1ppp/2npbn2/4p3/2B1P1b1/2NP1N2/PPPBQPPP/R3K2R w KQkq - 0 1 variant:standard",
+        "4k3/1P4pp/8/2pP4/8/8/1p4PP/4K3 w - c6 0 1 variant:standard",
+    ];
+    let mut seed: u64 = 1;
+    for fen in fens.iter() {
+        for _ in 0..20 {
+            let mut board = Board::from_str(fen).unwrap();
+            let mut psqt = Psqt::of(&board);
+            for _ in 0..60 {
+                let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+                if moves.is_empty() {
+                    break;
+                }
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let m = moves[(seed >> 33) as usize % moves.len()];
+                board = board.make_move_with_accumulator(m, &mut psqt);
+                assert_eq!(psqt.0, Psqt::of(&board).0);
+            }
+        }
+    }
+}
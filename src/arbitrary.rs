@@ -0,0 +1,306 @@
+//! `proptest` support, behind the `proptest` feature.
+//!
+//! `any::<Board>()` gives valid positions, with the kings placed first and the other pieces added
+//! one at a time, each only if the position stays valid.  So pawns stay off the back ranks, no
+//! side has more than sixteen pieces, and the side not to move is never in check.  Every castle
+//! right that the placement allows is given, and there is no en passant.  Shrinking removes
+//! pieces, so a failing case shrinks toward the two kings alone.  The variant is the parameter,
+//! so `any_with::<Board>(Variant::Standard)` gives normal chess positions.
+//!
+//! `any_with::<ChessMove>(board)` picks one of the legal moves of `board`, which must have at
+//! least one, and `any::<BitBoard>()` shrinks toward `EMPTY`.
+//!
+//! ```
+//! use chess::Board;
+//! use proptest::prelude::*;
+//!
+//! proptest!(|(board in any::<Board>())| {
+//!     prop_assert_eq!(board.get_hash(), board.recompute_hash());
+//! });
+//! ```
+
+use crate::bitboard::{BitBoard, EMPTY};
+use crate::board::Board;
+use crate::board_builder::BoardBuilder;
+use crate::castle_rights::CastleRights;
+use crate::chess_move::ChessMove;
+use crate::color::{Color, ALL_COLORS};
+use crate::file::File;
+use crate::magic::get_king_moves;
+use crate::movegen::MoveGen;
+use crate::piece::{Piece, ALL_PIECES, NUM_PIECES};
+use crate::square::{Square, ALL_SQUARES};
+use crate::variant::Variant;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use proptest::prelude::*;
+
+// the most pieces `any::<Board>()` tries to add next to the kings
+const MAX_PIECES: usize = 30;
+
+impl Arbitrary for BitBoard {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<BitBoard>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<u64>().prop_map(BitBoard::new).boxed()
+    }
+}
+
+impl Arbitrary for Board {
+    type Parameters = Variant;
+    type Strategy = BoxedStrategy<Board>;
+
+    fn arbitrary_with(variant: Variant) -> Self::Strategy {
+        let square = || (0..64u8).prop_map(Square::new);
+        // pawn first, so that pieces shrink toward pawns
+        let piece = (0..NUM_PIECES - 1).prop_map(|i| ALL_PIECES[i]);
+        let color = any::<bool>().prop_map(|black| if black { Color::Black } else { Color::White });
+        (
+            square(),
+            square(),
+            color.clone(),
+            prop::collection::vec((square(), piece, color), 0..=MAX_PIECES),
+        )
+            .prop_map(move |(white_king, black_king, side_to_move, pieces)| {
+                place(variant, white_king, black_king, side_to_move, &pieces)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for ChessMove {
+    type Parameters = Board;
+    type Strategy = BoxedStrategy<ChessMove>;
+
+    /// Pick one of the legal moves of the board.  This panics if there are none.
+    fn arbitrary_with(board: Board) -> Self::Strategy {
+        let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        prop::sample::select(moves).boxed()
+    }
+}
+
+fn place(
+    variant: Variant,
+    white_king: Square,
+    black_king: Square,
+    side_to_move: Color,
+    pieces: &[(Square, Piece, Color)],
+) -> Board {
+    // move the black king to the next square the kings can stand on together
+    let black_king = ALL_SQUARES
+        .iter()
+        .cycle()
+        .skip(black_king.to_index())
+        .find(|sq| {
+            **sq != white_king && get_king_moves(white_king) & BitBoard::from_square(**sq) == EMPTY
+        })
+        .copied()
+        .unwrap();
+
+    let mut builder = BoardBuilder::new();
+    builder
+        .variant(variant)
+        .side_to_move(side_to_move)
+        .piece(white_king, Piece::King, Color::White)
+        .piece(black_king, Piece::King, Color::Black);
+
+    for (square, piece, color) in pieces.iter() {
+        if builder[*square].is_some() {
+            continue;
+        }
+        builder.piece(*square, *piece, *color);
+        if Board::try_from(&builder).is_err() {
+            builder.clear_square(*square);
+        }
+    }
+
+    for color in ALL_COLORS.iter() {
+        let backrank = color.back_rank();
+        if builder[Square::make_square(backrank, File::E)] != Some((Piece::King, *color)) {
+            continue;
+        }
+        let rook =
+            |file| builder[Square::make_square(backrank, file)] == Some((Piece::Rook, *color));
+        let rights = match (rook(File::H), rook(File::A)) {
+            (true, true) => CastleRights::Both,
+            (true, false) => CastleRights::KingSide,
+            (false, true) => CastleRights::QueenSide,
+            (false, false) => CastleRights::NoRights,
+        };
+        builder.castle_rights(*color, rights);
+    }
+
+    Board::try_from(&builder).expect("every piece was checked as it was added")
+}
+
+#[cfg(test)]
+use core::str::FromStr;
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn boards_are_valid(board in any::<Board>()) {
+        prop_assert!(board.is_sane());
+        let fen = board.to_tagged_fen();
+        prop_assert_eq!(Board::from_str(&fen).unwrap(), board);
+    }
+
+    // `Board` is copy-make, so there is no unmake to check.  Instead, check that the child of a
+    // move is the same however it is reached, and that a null move can be taken back.
+    #[test]
+    fn make_move_round_trips(
+        (board, m) in prop_oneof![Just(Variant::Standard), Just(Variant::Uno)]
+            .prop_flat_map(any_with::<Board>)
+            .prop_filter("needs a legal move", |board| MoveGen::new_legal(board).len() > 0)
+            .prop_flat_map(|board| (Just(board), any_with::<ChessMove>(board)))
+    ) {
+        prop_assert!(board.legal(m));
+        let child = board.make_move_new(m);
+        prop_assert!(child.is_sane());
+        prop_assert_eq!(child.get_hash(), child.recompute_hash());
+        prop_assert_eq!(child.get_hash(), board.hash_after(m));
+        prop_assert_eq!(Board::from_str(&child.to_tagged_fen()).unwrap(), child);
+
+        let mut result = Board::default();
+        board.make_move(m, &mut result);
+        prop_assert_eq!(result, child);
+
+        // a null move forgets the en passant square
+        if child.en_passant().is_none() {
+            if let Some(passed) = child.null_move() {
+                prop_assert_eq!(passed.null_move(), Some(child));
+            }
+        }
+    }
+
+    // The quiet moves are the legal moves that neither the quiescence generator nor the checking
+    // one gives.  Those promote to a queen only, or to every piece once one of them checks, so a
+    // promotion is just never quiet.
+    #[test]
+    fn quiet_moves_partition_the_legal_moves(
+        board in prop_oneof![Just(Variant::Standard), Just(Variant::Uno)]
+            .prop_flat_map(any_with::<Board>)
+            .prop_filter("not in check", |board| *board.checkers() == EMPTY)
+    ) {
+        let tactical: Vec<ChessMove> = MoveGen::new_quiescence(&board, false).collect();
+        let checks: Vec<ChessMove> = MoveGen::new_checks(&board).collect();
+        for m in MoveGen::new_legal(&board) {
+            if m.get_promotion().is_some() {
+                prop_assert!(!board.is_quiet(m));
+                continue;
+            }
+            let captures = tactical.contains(&m);
+            prop_assert_eq!(captures, board.is_capture(m));
+            let checks = checks.contains(&m);
+            prop_assert_eq!(board.is_quiet(m), !captures && !checks);
+        }
+    }
+
+    // A position in check is reached through a checking move.  The checking generator gives
+    // every promotion of a pawn once one of them checks, so only the moves that check are kept.
+    #[test]
+    fn evasions_are_the_legal_moves_in_check(
+        board in prop_oneof![Just(Variant::Standard), Just(Variant::Uno)]
+            .prop_flat_map(any_with::<Board>)
+            .prop_filter("needs a check", |board| {
+                MoveGen::new_checks(board).any(|m| board.gives_check(m))
+            })
+            .prop_flat_map(|board| {
+                let checks: Vec<ChessMove> = MoveGen::new_checks(&board)
+                    .filter(|m| board.gives_check(*m))
+                    .collect();
+                prop::sample::select(checks).prop_map(move |m| board.make_move_new(m))
+            })
+    ) {
+        prop_assert!(*board.checkers() != EMPTY);
+        let evasions: Vec<ChessMove> = MoveGen::new_evasions(&board).collect();
+        prop_assert_eq!(evasions, MoveGen::new_legal(&board).collect::<Vec<ChessMove>>());
+    }
+
+    // Any bits read as a move, as from a transposition table entry of another position, are
+    // either turned down or a move that can be made.
+    #[test]
+    fn sanitized_moves_can_be_made(
+        board in prop_oneof![Just(Variant::Standard), Just(Variant::Uno)]
+            .prop_flat_map(any_with::<Board>),
+        bits in prop::collection::vec(any::<u16>(), 64)
+    ) {
+        for bits in bits {
+            let promotion = match (bits >> 12) as usize % (NUM_PIECES + 1) {
+                0 => None,
+                i => Some(ALL_PIECES[i - 1]),
+            };
+            let m = ChessMove::new(
+                Square::new((bits & 63) as u8),
+                Square::new(((bits >> 6) & 63) as u8),
+                promotion,
+            );
+            match board.sanitize_move(m) {
+                Some(sanitized) => {
+                    prop_assert!(board.legal(sanitized));
+                    prop_assert!(board.make_move_new(sanitized).is_sane());
+                }
+                None => prop_assert!(!board.legal(m)),
+            }
+        }
+    }
+
+    // Mate and stalemate are just no legal moves, in check or not, by the rules of the variant.
+    #[test]
+    fn checkmate_and_stalemate_match_the_legal_moves(
+        board in prop_oneof![Just(Variant::Standard), Just(Variant::Uno)]
+            .prop_flat_map(any_with::<Board>)
+    ) {
+        let stuck = MoveGen::new_legal(&board).len() == 0;
+        let checked = *board.checkers() != EMPTY;
+        prop_assert_eq!(board.is_checkmate(), stuck && checked);
+        prop_assert_eq!(board.is_stalemate(), stuck && !checked);
+    }
+
+    #[test]
+    fn rotations_turn_back(bb in any::<BitBoard>()) {
+        let turned = bb.rotate_90_cw().rotate_90_cw().rotate_90_cw().rotate_90_cw();
+        prop_assert_eq!(turned, bb);
+        prop_assert_eq!(bb.rotate_90_cw().rotate_90_ccw(), bb);
+        prop_assert_eq!(bb.rotate_90_cw().rotate_90_cw(), bb.rotate_180());
+        prop_assert_eq!(bb.rotate_180(), bb.flip_horizontal().flip_vertical());
+        prop_assert_eq!(bb.flip_diagonal().flip_diagonal(), bb);
+        prop_assert_eq!(bb.rotate_180().popcnt(), bb.popcnt());
+    }
+
+    // Turning a board only moves its pieces, each keeping its color.
+    #[test]
+    fn rotate_180_keeps_the_colors(board in any::<Board>()) {
+        if let Ok(turned) = board.rotate_180() {
+            for color in ALL_COLORS.iter() {
+                let bb = board.color_combined(*color).rotate_180();
+                prop_assert_eq!(*turned.color_combined(*color), bb);
+            }
+            for piece in ALL_PIECES.iter() {
+                prop_assert_eq!(*turned.pieces(*piece), board.pieces(*piece).rotate_180());
+            }
+        }
+    }
+
+    // The children of a board differ in every way a position can, and two moves reach the same
+    // child when a castle is written both ways, so their keys are equal just when they are.
+    #[test]
+    fn canonical_keys_tell_boards_apart(
+        board in prop_oneof![Just(Variant::Standard), Just(Variant::Uno)]
+            .prop_flat_map(any_with::<Board>)
+    ) {
+        let mut boards: Vec<Board> = MoveGen::new_legal(&board)
+            .map(|m| board.make_move_new(m))
+            .collect();
+        boards.push(board);
+        boards.extend(board.null_move());
+        let keys: Vec<[u8; 24]> = boards.iter().map(Board::canonical_key).collect();
+        for (a, key) in boards.iter().zip(keys.iter()) {
+            prop_assert_eq!(Board::from_canonical_key(key).unwrap(), *a);
+            for (b, other) in boards.iter().zip(keys.iter()) {
+                prop_assert_eq!(a == b, key == other);
+            }
+        }
+    }
+}
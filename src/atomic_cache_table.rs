@@ -0,0 +1,133 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+struct AtomicCacheTableEntry {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Store a cache of entries, each with an associated hash, that can be shared between threads
+/// without a lock.
+///
+/// Each entry is packed into a `u64` and stored next to `hash ^ data`.  Two threads writing the
+/// same slot at once can leave the key from one write next to the data from the other; such a torn
+/// entry no longer xors back to either hash, so `get` treats it as a miss.  Writes are not ordered
+/// against each other, so the last writer to a slot wins, and a `get` may miss an entry that
+/// another thread is adding at the same time.  Either way, a `get` that succeeds returns data that
+/// was added with exactly that hash.
+///
+/// ```
+/// use chess::{AtomicCacheTable, Board};
+///
+/// let table: AtomicCacheTable<u32> = AtomicCacheTable::new(1024, 0);
+/// let board = Board::default();
+///
+/// assert_eq!(table.get(board.get_hash()), None);
+/// table.add(board.get_hash(), 20);
+/// assert_eq!(table.get(board.get_hash()), Some(20));
+/// ```
+pub struct AtomicCacheTable<T: Copy + Into<u64> + TryFrom<u64>> {
+    table: Box<[AtomicCacheTableEntry]>,
+    mask: usize,
+    _entry: PhantomData<T>,
+}
+
+impl<T: Copy + Into<u64> + TryFrom<u64>> AtomicCacheTable<T> {
+    /// Create a new `AtomicCacheTable` with each associated entry initialized with a hash of '0'
+    /// Note: You must pass in a size where only 1 bit is set. (AKA: 2, 4, 8, 16, 1024, 65536,
+    /// etc.)
+    /// Panics when size is invalid.
+    #[inline]
+    pub fn new(size: usize, default: T) -> AtomicCacheTable<T> {
+        if size.count_ones() != 1 {
+            panic!("You cannot create an AtomicCacheTable with a non-binary number.");
+        }
+        let data = default.into();
+        let values: Vec<AtomicCacheTableEntry> = (0..size)
+            .map(|_| AtomicCacheTableEntry {
+                key: AtomicU64::new(data),
+                data: AtomicU64::new(data),
+            })
+            .collect();
+        AtomicCacheTable {
+            table: values.into_boxed_slice(),
+            mask: size - 1,
+            _entry: PhantomData,
+        }
+    }
+
+    /// Get a particular entry with the hash specified
+    #[inline]
+    pub fn get(&self, hash: u64) -> Option<T> {
+        let e = unsafe { self.table.get_unchecked((hash as usize) & self.mask) };
+        let key = e.key.load(Ordering::Relaxed);
+        let data = e.data.load(Ordering::Relaxed);
+        if key ^ data == hash {
+            T::try_from(data).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Add (or overwrite) an entry with the associated hash
+    #[inline]
+    pub fn add(&self, hash: u64, entry: T) {
+        let e = unsafe { self.table.get_unchecked((hash as usize) & self.mask) };
+        let data = entry.into();
+        e.key.store(hash ^ data, Ordering::Relaxed);
+        e.data.store(data, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_add_overwrite_and_get() {
+    let table: AtomicCacheTable<u16> = AtomicCacheTable::new(16, 0);
+    assert_eq!(table.get(3), None);
+    table.add(3, 1);
+    assert_eq!(table.get(3), Some(1));
+    table.add(3 | (1 << 40), 2);
+    assert_eq!(table.get(3), None);
+    assert_eq!(table.get(3 | (1 << 40)), Some(2));
+}
+
+#[test]
+fn test_threads_never_see_torn_entries() {
+    use crate::playouts::XorShift;
+    use std::sync::Arc;
+    use std::thread;
+
+    // every hash has exactly one valid payload, so any successful get can be checked
+    fn payload(hash: u64) -> u64 {
+        hash.rotate_left(23) ^ 0x5555_AAAA_5555_AAAA
+    }
+
+    // a tiny table, so the threads keep fighting over the same slots
+    let table: Arc<AtomicCacheTable<u64>> = Arc::new(AtomicCacheTable::new(64, payload(0)));
+    let handles: Vec<_> = (0..8u64)
+        .map(|i| {
+            let table = Arc::clone(&table);
+            thread::spawn(move || {
+                let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15 ^ i);
+                let mut hits = 0;
+                for _ in 0..200_000 {
+                    let state = rng.next_u64();
+                    // only a few hashes per slot, so gets hit often
+                    let hash = state & 0x0300_0000_0000_00ff;
+                    if state & 0x100 == 0 {
+                        table.add(hash, payload(hash));
+                    } else if let Some(data) = table.get(hash) {
+                        assert_eq!(data, payload(hash));
+                        hits += 1;
+                    }
+                }
+                hits
+            })
+        })
+        .collect();
+
+    let hits: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    assert!(hits > 0);
+}
@@ -0,0 +1,122 @@
+use crate::bitboard::{BitBoard, EMPTY};
+use crate::board::Board;
+use crate::color::{Color, ALL_COLORS, NUM_COLORS};
+use crate::magic::attacks_of;
+use crate::piece::{Piece, ALL_PIECES, NUM_PIECES};
+
+/// The squares each side attacks, split up by piece type.
+///
+/// A `Board` keeps one of these up to date as moves are made, when the `attack-info` feature is
+/// enabled.  After a move only the attacks that could have changed are recomputed: those of the
+/// piece types that moved, were captured or were promoted to, and those of the sliders that
+/// attacked a square that was emptied or filled.
+///
+/// ```
+/// use chess::{BitBoard, Board, Color, Piece, Square, EMPTY};
+///
+/// let board = Board::default();
+/// let info = board.attack_info();
+///
+/// assert_eq!(info.piece_attacks(Piece::Knight, Color::White).popcnt(), 6);
+/// assert_ne!(info.attacks(Color::Black) & BitBoard::from_square(Square::F6), EMPTY);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AttackInfo {
+    pieces: [[BitBoard; NUM_PIECES]; NUM_COLORS],
+    attacks: [BitBoard; NUM_COLORS],
+}
+
+impl AttackInfo {
+    /// An `AttackInfo` with no attacks at all, for an empty board.
+    pub(crate) const EMPTY: AttackInfo = AttackInfo {
+        pieces: [[EMPTY; NUM_PIECES]; NUM_COLORS],
+        attacks: [EMPTY; NUM_COLORS],
+    };
+
+    /// Compute the attacks on `board` from scratch.
+    pub fn new(board: &Board) -> AttackInfo {
+        let mut result = AttackInfo::EMPTY;
+        for color in ALL_COLORS.iter() {
+            for piece in ALL_PIECES.iter() {
+                result.pieces[color.to_index()][piece.to_index()] =
+                    AttackInfo::compute(board, *piece, *color);
+            }
+        }
+        result.update_attacks();
+        result
+    }
+
+    /// Every square attacked by `color`.
+    #[inline]
+    pub fn attacks(&self, color: Color) -> BitBoard {
+        unsafe { *self.attacks.get_unchecked(color.to_index()) }
+    }
+
+    /// Every square attacked by a `piece` of `color`.
+    #[inline]
+    pub fn piece_attacks(&self, piece: Piece, color: Color) -> BitBoard {
+        unsafe {
+            *self
+                .pieces
+                .get_unchecked(color.to_index())
+                .get_unchecked(piece.to_index())
+        }
+    }
+
+    /// Bring the attacks up to date after `before` became `after`.
+    pub(crate) fn update(&mut self, before: &Board, after: &Board) {
+        let changed = before.combined() ^ after.combined();
+        for color in ALL_COLORS.iter() {
+            for piece in ALL_PIECES.iter() {
+                let old = before.pieces(*piece) & before.color_combined(*color);
+                let new = after.pieces(*piece) & after.color_combined(*color);
+                let slider =
+                    *piece == Piece::Bishop || *piece == Piece::Rook || *piece == Piece::Queen;
+                let entry = &mut self.pieces[color.to_index()][piece.to_index()];
+                if old != new || (slider && *entry & changed != EMPTY) {
+                    *entry = AttackInfo::compute(after, *piece, *color);
+                }
+            }
+        }
+        self.update_attacks();
+    }
+
+    // Compute the attacks of every `piece` of `color` on `board`.
+    fn compute(board: &Board, piece: Piece, color: Color) -> BitBoard {
+        (board.pieces(piece) & board.color_combined(color))
+            .map(|sq| attacks_of(piece, sq, color, *board.combined()))
+            .fold(EMPTY, |attacks, bb| attacks | bb)
+    }
+
+    // Recompute the per-color unions from the per-piece attacks.
+    fn update_attacks(&mut self) {
+        for color in ALL_COLORS.iter() {
+            self.attacks[color.to_index()] = self.pieces[color.to_index()]
+                .iter()
+                .fold(EMPTY, |attacks, bb| attacks | *bb);
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::playouts::random_playouts;
+#[cfg(test)]
+use crate::variant::Variant;
+
+#[test]
+fn test_incremental_attack_info_matches_scratch() {
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+    ];
+    random_playouts(&starts, &[Variant::Uno], 150, 200, |board, moves| {
+        assert_eq!(*board.attack_info(), AttackInfo::new(board));
+        // the games are played with `make_move_new`, so check the other way of making a move
+        for m in moves.iter() {
+            let mut result = *board;
+            board.make_move(*m, &mut result);
+            assert_eq!(*result.attack_info(), AttackInfo::new(&result));
+        }
+    });
+}
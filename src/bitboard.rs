@@ -1,8 +1,11 @@
 use crate::file::File;
 use crate::rank::Rank;
 use crate::square::*;
-use std::fmt;
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Mul, Not};
+use core::fmt;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Mul, Not};
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
 
 /// A good old-fashioned bitboard
 /// You *do* have access to the actual value, but you are probably better off
@@ -288,18 +291,130 @@ impl BitBoard {
         BitBoard(1u64 << sq.to_int())
     }
 
+    /// Construct a new `BitBoard` with every square of a file set, see `File::ALL_BB`.
+    ///
+    /// ```
+    /// use chess::{get_file, BitBoard, File};
+    ///
+    /// assert_eq!(BitBoard::from_file(File::E), get_file(File::E));
+    /// assert_eq!(BitBoard::from_file(File::E).popcnt(), 8);
+    /// ```
+    #[inline]
+    pub fn from_file(file: File) -> BitBoard {
+        File::ALL_BB[file.to_index()]
+    }
+
+    /// Construct a new `BitBoard` with every square of a rank set, see `Rank::ALL_BB`.
+    ///
+    /// ```
+    /// use chess::{get_rank, BitBoard, Rank};
+    ///
+    /// assert_eq!(BitBoard::from_rank(Rank::Second), get_rank(Rank::Second));
+    /// ```
+    #[inline]
+    pub fn from_rank(rank: Rank) -> BitBoard {
+        Rank::ALL_BB[rank.to_index()]
+    }
+
+    /// Construct a new `BitBoard` with every square of the files between two files set, not
+    /// including the two files themselves, in either order.
+    ///
+    /// ```
+    /// use chess::{BitBoard, File, EMPTY};
+    ///
+    /// let middle = BitBoard::from_file(File::D) | BitBoard::from_file(File::E);
+    /// assert_eq!(BitBoard::files_between(File::C, File::F), middle);
+    /// assert_eq!(BitBoard::files_between(File::F, File::C), middle);
+    /// assert_eq!(BitBoard::files_between(File::C, File::D), EMPTY);
+    /// ```
+    #[inline]
+    pub fn files_between(a: File, b: File) -> BitBoard {
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+        (low.to_index() + 1..high.to_index()).fold(EMPTY, |files, i| files | File::ALL_BB[i])
+    }
+
     /// Convert an `Option<Square>` to an `Option<BitBoard>`
     #[inline]
     pub fn from_maybe_square(sq: Option<Square>) -> Option<BitBoard> {
         sq.map(|s| BitBoard::from_square(s))
     }
 
-    /// Convert a `BitBoard` to a `Square`.  This grabs the least-significant `Square`
+    /// Convert a `BitBoard` to a `Square`.  This grabs the least-significant `Square`, or `None`
+    /// if the `BitBoard` is empty.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Square, EMPTY};
+    ///
+    /// let bb = BitBoard::from_square(Square::C3) | BitBoard::from_square(Square::F6);
+    /// assert_eq!(bb.to_square(), Some(Square::C3));
+    /// assert_eq!(EMPTY.to_square(), None);
+    /// ```
     #[inline]
-    pub fn to_square(&self) -> Square {
+    pub fn to_square(&self) -> Option<Square> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(Square::new(self.0.trailing_zeros() as u8))
+        }
+    }
+
+    /// Grab the least-significant `Square` without checking that there is one.  The move
+    /// generator uses this where the `BitBoard` is known to have a square, like that of a king.
+    /// An empty `BitBoard` panics in debug builds, and gives a square that is not on it otherwise.
+    #[inline]
+    pub fn to_square_unchecked(self) -> Square {
+        debug_assert!(self.0 != 0);
         Square::new(self.0.trailing_zeros() as u8)
     }
 
+    /// Give me a `BitBoard` with only the least-significant `Square` of this one.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Square, EMPTY};
+    ///
+    /// let bb = BitBoard::from_square(Square::C3) | BitBoard::from_square(Square::F6);
+    /// assert_eq!(bb.isolate_lsb(), BitBoard::from_square(Square::C3));
+    /// assert_eq!(EMPTY.isolate_lsb(), EMPTY);
+    /// ```
+    #[inline]
+    pub fn isolate_lsb(&self) -> BitBoard {
+        BitBoard(self.0 & self.0.wrapping_neg())
+    }
+
+    /// Give me this `BitBoard` without its least-significant `Square`.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Square, EMPTY};
+    ///
+    /// let bb = BitBoard::from_square(Square::C3) | BitBoard::from_square(Square::F6);
+    /// assert_eq!(bb.without_lsb(), BitBoard::from_square(Square::F6));
+    /// assert_eq!(EMPTY.without_lsb(), EMPTY);
+    /// ```
+    #[inline]
+    pub fn without_lsb(&self) -> BitBoard {
+        BitBoard(self.0 & self.0.wrapping_sub(1))
+    }
+
+    /// Split the least-significant `Square` off this `BitBoard`, giving it and the rest, or
+    /// `None` if the `BitBoard` is empty.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Square, EMPTY};
+    ///
+    /// let mut bb = BitBoard::from_square(Square::C3) | BitBoard::from_square(Square::F6);
+    /// let mut squares = vec![];
+    /// while let Some((sq, rest)) = bb.split_first() {
+    ///     squares.push(sq);
+    ///     bb = rest;
+    /// }
+    /// assert_eq!(squares, vec![Square::C3, Square::F6]);
+    /// assert_eq!(bb, EMPTY);
+    /// ```
+    #[inline]
+    pub fn split_first(&self) -> Option<(Square, BitBoard)> {
+        self.to_square().map(|sq| (sq, self.without_lsb()))
+    }
+
     /// Count the number of `Squares` set in this `BitBoard`
     #[inline]
     pub fn popcnt(&self) -> u32 {
@@ -312,6 +427,68 @@ impl BitBoard {
         BitBoard(self.0.swap_bytes())
     }
 
+    /// Flip this `BitBoard` top to bottom, so a1 goes to a8.  This is `reverse_colors`.
+    #[inline]
+    pub fn flip_vertical(&self) -> BitBoard {
+        BitBoard(self.0.swap_bytes())
+    }
+
+    /// Flip this `BitBoard` left to right, so a1 goes to h1.
+    #[inline]
+    pub fn flip_horizontal(&self) -> BitBoard {
+        const K1: u64 = 0x5555_5555_5555_5555;
+        const K2: u64 = 0x3333_3333_3333_3333;
+        const K4: u64 = 0x0f0f_0f0f_0f0f_0f0f;
+        let mut x = self.0;
+        x = ((x >> 1) & K1) | ((x & K1) << 1);
+        x = ((x >> 2) & K2) | ((x & K2) << 2);
+        x = ((x >> 4) & K4) | ((x & K4) << 4);
+        BitBoard(x)
+    }
+
+    /// Flip this `BitBoard` about the a1-h8 diagonal, so a8 goes to h1.
+    #[inline]
+    pub fn flip_diagonal(&self) -> BitBoard {
+        const K1: u64 = 0x5500_5500_5500_5500;
+        const K2: u64 = 0x3333_0000_3333_0000;
+        const K4: u64 = 0x0f0f_0f0f_0000_0000;
+        let mut x = self.0;
+        let t = K4 & (x ^ (x << 28));
+        x ^= t ^ (t >> 28);
+        let t = K2 & (x ^ (x << 14));
+        x ^= t ^ (t >> 14);
+        let t = K1 & (x ^ (x << 7));
+        x ^= t ^ (t >> 7);
+        BitBoard(x)
+    }
+
+    /// Turn this `BitBoard` a quarter clockwise, as seen from white, so a1 goes to a8.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Square};
+    ///
+    /// let bb = BitBoard::from_square(Square::A1) | BitBoard::from_square(Square::B1);
+    /// let turned = BitBoard::from_square(Square::A8) | BitBoard::from_square(Square::A7);
+    /// assert_eq!(bb.rotate_90_cw(), turned);
+    /// assert_eq!(turned.rotate_90_ccw(), bb);
+    /// ```
+    #[inline]
+    pub fn rotate_90_cw(&self) -> BitBoard {
+        self.flip_diagonal().flip_vertical()
+    }
+
+    /// Turn this `BitBoard` a quarter counterclockwise, as seen from white, so a1 goes to h1.
+    #[inline]
+    pub fn rotate_90_ccw(&self) -> BitBoard {
+        self.flip_vertical().flip_diagonal()
+    }
+
+    /// Turn this `BitBoard` halfway around, so a1 goes to h8.
+    #[inline]
+    pub fn rotate_180(&self) -> BitBoard {
+        BitBoard(self.0.reverse_bits())
+    }
+
     /// Convert this `BitBoard` to a `usize` (for table lookups)
     #[inline]
     pub fn to_size(&self, rightshift: u8) -> usize {
@@ -325,12 +502,47 @@ impl Iterator for BitBoard {
 
     #[inline]
     fn next(&mut self) -> Option<Square> {
-        if self.0 == 0 {
-            None
-        } else {
-            let result = self.to_square();
-            *self ^= BitBoard::from_square(result);
-            Some(result)
-        }
+        let (result, rest) = self.split_first()?;
+        *self = rest;
+        Some(result)
+    }
+}
+
+#[test]
+fn test_lsb_helpers_on_an_empty_board() {
+    assert_eq!(EMPTY.to_square(), None);
+    assert_eq!(EMPTY.isolate_lsb(), EMPTY);
+    assert_eq!(EMPTY.without_lsb(), EMPTY);
+    assert_eq!(EMPTY.split_first(), None);
+    assert_eq!(EMPTY.count(), 0);
+}
+
+#[test]
+fn test_lsb_helpers_on_single_squares() {
+    for i in 0..64 {
+        let sq = Square::new(i);
+        let bb = BitBoard::from_square(sq);
+        assert_eq!(bb.to_square(), Some(sq));
+        assert_eq!(bb.to_square_unchecked(), sq);
+        assert_eq!(bb.isolate_lsb(), bb);
+        assert_eq!(bb.without_lsb(), EMPTY);
+        assert_eq!(bb.split_first(), Some((sq, EMPTY)));
+
+        // every square above it is left behind
+        let above = !EMPTY & !BitBoard((1 << i) - 1);
+        assert_eq!(above.split_first(), Some((sq, above ^ bb)));
     }
 }
+
+#[test]
+fn test_flips_and_rotations_move_a_square() {
+    let bb = BitBoard::from_square(Square::B3);
+    assert_eq!(bb.flip_vertical(), BitBoard::from_square(Square::B6));
+    assert_eq!(bb.flip_horizontal(), BitBoard::from_square(Square::G3));
+    assert_eq!(bb.flip_diagonal(), BitBoard::from_square(Square::C2));
+    assert_eq!(bb.rotate_90_cw(), BitBoard::from_square(Square::C7));
+    assert_eq!(bb.rotate_90_ccw(), BitBoard::from_square(Square::F2));
+    assert_eq!(bb.rotate_180(), BitBoard::from_square(Square::G6));
+    assert_eq!(EMPTY.rotate_90_cw(), EMPTY);
+    assert_eq!((!EMPTY).flip_diagonal(), !EMPTY);
+}
@@ -1,32 +1,67 @@
+use crate::accumulator::Accumulator;
+#[cfg(feature = "attack-info")]
+use crate::attack_info::AttackInfo;
 use crate::bitboard::{BitBoard, EMPTY};
 use crate::board_builder::BoardBuilder;
-use crate::castle_rights::CastleRights;
-use crate::chess_move::ChessMove;
+use crate::capture_rules::{CapturePattern, CaptureRules, StandardRules, UnoRules};
+use crate::castle_rights::{
+    castle_move, castle_side, castle_squares, CastleRights, CastleSide, Castled,
+};
+use crate::chess_move::{ChessMove, MoveEncoding, MoveFormat};
 use crate::color::{Color, ALL_COLORS, NUM_COLORS};
-use crate::error::Error;
-use crate::file::File;
+use crate::error::{ApplyError, Error, Span};
+use crate::file::ALL_FILES;
 use crate::magic::{
-    get_castle_moves, get_file, get_king_moves, get_rank,
+    attacks_of, between, get_adjacent_files, get_bishop_moves, get_bishop_rays, get_king_moves,
+    get_knight_moves, get_pawn_attacks, get_pawn_quiets, get_rank, get_rook_moves, get_rook_rays,
+    line, xray_bishop_attacks, xray_rook_attacks,
 };
 use crate::movegen::*;
-use crate::piece::{Piece, ALL_PIECES, NUM_PIECES};
+use crate::pawns::{camp, pawn_attack_span, pawn_attacks};
+use crate::piece::{
+    MaterialCount, Piece, ALL_PIECES, KING_ATTACK_WEIGHTS, MAX_PHASE, NUM_PIECES, PHASE_WEIGHTS,
+};
+#[cfg(feature = "rand")]
+use crate::random::{random, RandomBoardOptions};
+use crate::rank::Rank;
+use crate::render::{render, RenderOptions};
 use crate::square::{Square, ALL_SQUARES};
+use crate::symmetry::{SymmetryOp, ALL_SYMMETRY_OPS};
+use crate::values::{self, Value, ValueTable};
+use crate::variant::Variant;
 use crate::zobrist::Zobrist;
-use std::convert::{TryFrom, TryInto};
-use std::fmt;
-use std::hash::{Hash, Hasher};
-use std::str::FromStr;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
 /// A representation of a chess board.  That's why you're here, right?
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+///
+/// Two boards are equal when they are the same position, so whether each side has castled,
+/// see `Board::castled`, is not compared.
+#[derive(Copy, Clone, Eq, Debug)]
 pub struct Board {
     pieces: [BitBoard; NUM_PIECES],
     color_combined: [BitBoard; NUM_COLORS],
     combined: BitBoard,
     side_to_move: Color,
     castle_rights: [CastleRights; NUM_COLORS],
+    castled: [Castled; NUM_COLORS],
+    pinned: BitBoard,
     checkers: BitBoard,
+    check_squares: [BitBoard; NUM_PIECES],
     hash: u64,
+    pawn_hash: u64,
+    material_key: u64,
+    en_passant: Option<Square>,
+    variant: Variant,
+    #[cfg(feature = "attack-info")]
+    attack_info: AttackInfo,
 }
 
 /// What is the status of this game?
@@ -37,6 +72,133 @@ pub enum BoardStatus {
     Checkmate,
 }
 
+/// One square that differs between two boards, as given by `Board::diff`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SquareChange {
+    /// The square that changed.
+    pub square: Square,
+    /// What stood on the square on the first board, if anything.
+    pub before: Option<(Piece, Color)>,
+    /// What stands on the square on the second board, if anything.
+    pub after: Option<(Piece, Color)>,
+}
+
+/// What a move did, as given by `Board::make_move_with_info`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MoveInfo {
+    /// The piece that moved, which for a castle is the king.
+    pub piece: Piece,
+    /// The square the moved piece ended on.  This is the destination of the move except for a
+    /// castle written as my king taking my own rook.
+    pub dest: Square,
+    /// The piece that was taken, and the square it stood on, which for en passant is not the
+    /// destination of the move.
+    pub captured: Option<(Piece, Square)>,
+    /// For a castle, the square the rook came from and the square it went to.
+    pub castle: Option<(Square, Square)>,
+    /// The piece a pawn promoted to.
+    pub promotion: Option<Piece>,
+}
+
+// The most pieces a side may have, which is as many as it starts with, as `MoveGen` relies on
+pub(crate) const MAX_PIECES: u32 = 16;
+
+// The knights of a Chess960 start position, as indices into the five squares the bishops and
+// queen leave free
+const CHESS960_KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+// The back rank of Chess960 start position `n`, from the a file to the h file.  The two lowest
+// base 4 digits place the light and the dark squared bishop, the next base 6 digit the queen on
+// one of the six free squares and the rest the knights, and the rooks and king fill the three
+// squares that are left in that order.
+fn chess960_back_rank(n: u16) -> [Piece; 8] {
+    let n = n as usize;
+    let mut back_rank = [None; 8];
+    back_rank[(n % 4) * 2 + 1] = Some(Piece::Bishop);
+    back_rank[(n / 4 % 4) * 2] = Some(Piece::Bishop);
+
+    let (first, second) = CHESS960_KNIGHTS[n / 96];
+    let placed = [
+        (n / 16 % 6, Piece::Queen),
+        (second, Piece::Knight),
+        (first, Piece::Knight),
+    ];
+    for (index, piece) in placed.iter() {
+        let file = (0..8)
+            .filter(|file| back_rank[*file].is_none())
+            .nth(*index)
+            .unwrap();
+        back_rank[file] = Some(*piece);
+    }
+
+    let mut result = [Piece::Rook; 8];
+    let mut rest = [Piece::Rook, Piece::King, Piece::Rook].iter();
+    for (square, piece) in back_rank.iter().zip(result.iter_mut()) {
+        *piece = square.unwrap_or_else(|| *rest.next().unwrap());
+    }
+    result
+}
+
+// The operations of the EPD `line` after its position, which ends at `start`: each is its opcode
+// and then its operands, with where each starts in `line`.  A quoted operand is given without its
+// quotes, and may hold spaces and ';'.  The last operation may leave out its ';'.
+fn epd_operations(line: &str, start: usize) -> Result<Vec<Vec<(usize, &str)>>, Error> {
+    let invalid = |start, end| Error::InvalidEpd {
+        span: Span { start, end },
+    };
+    let mut operations = Vec::new();
+    let mut operation = Vec::new();
+    let mut next = start;
+    loop {
+        let token_start = line.len() - line[next..].trim_start().len();
+        let text = &line[token_start..];
+        if text.is_empty() {
+            break;
+        } else if text.starts_with(';') {
+            if operation.is_empty() {
+                return Err(invalid(token_start, token_start + 1));
+            }
+            operations.push(core::mem::take(&mut operation));
+            next = token_start + 1;
+        } else if text.starts_with('"') && !operation.is_empty() {
+            let end = match text[1..].find('"') {
+                Some(end) => end + 1,
+                None => return Err(invalid(token_start, line.len())),
+            };
+            operation.push((token_start + 1, &text[1..end]));
+            next = token_start + end + 1;
+        } else {
+            let end = text
+                .find(|c: char| c.is_whitespace() || c == ';')
+                .unwrap_or(text.len());
+            let token = &text[..end];
+            // an opcode is a letter, then letters, digits and underscores
+            let opcode = token.starts_with(|c: char| c.is_ascii_alphabetic())
+                && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if operation.is_empty() && !opcode {
+                return Err(invalid(token_start, token_start + end));
+            }
+            operation.push((token_start, token));
+            next = token_start + end;
+        }
+    }
+    if !operation.is_empty() {
+        operations.push(operation);
+    }
+    Ok(operations)
+}
+
 /// Construct the initial position.
 impl Default for Board {
     #[inline]
@@ -46,6 +208,34 @@ impl Default for Board {
     }
 }
 
+impl PartialEq for Board {
+    fn eq(&self, other: &Board) -> bool {
+        // everything but `castled`, which the position does not depend on
+        let position = |board: &Board| {
+            (
+                board.pieces,
+                board.color_combined,
+                board.combined,
+                board.side_to_move,
+                board.castle_rights,
+                board.pinned,
+                board.checkers,
+                board.check_squares,
+                (board.hash, board.pawn_hash, board.material_key),
+                board.en_passant,
+                board.variant,
+            )
+        };
+        #[cfg(feature = "attack-info")]
+        {
+            if self.attack_info != other.attack_info {
+                return false;
+            }
+        }
+        position(self) == position(other)
+    }
+}
+
 impl Hash for Board {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.hash.hash(state);
@@ -62,8 +252,17 @@ impl Board {
             combined: EMPTY,
             side_to_move: Color::White,
             castle_rights: [CastleRights::NoRights; NUM_COLORS],
+            castled: [Castled::No; NUM_COLORS],
+            pinned: EMPTY,
             checkers: EMPTY,
+            check_squares: [EMPTY; NUM_PIECES],
             hash: 0,
+            pawn_hash: 0,
+            material_key: 0,
+            en_passant: None,
+            variant: Variant::Uno,
+            #[cfg(feature = "attack-info")]
+            attack_info: AttackInfo::EMPTY,
         }
     }
 
@@ -110,9 +309,14 @@ impl Board {
     /// Is this game Ongoing, is it Stalemate, or is it Checkmate?
     ///
     /// ```
-    /// use chess::{Board, BoardStatus, Square, ChessMove};
+    /// use chess::{Board, BoardBuilder, BoardStatus, Square, ChessMove, Variant};
+    /// use std::convert::TryInto;
     ///
-    /// let mut board = Board::default();
+    /// // the fastest checkmate in normal chess
+    /// let mut board: Board = BoardBuilder::default()
+    ///     .variant(Variant::Standard)
+    ///     .try_into()
+    ///     .expect("Valid Position");
     ///
     /// assert_eq!(board.status(), BoardStatus::Ongoing);
     ///
@@ -161,6 +365,85 @@ impl Board {
         }
     }
 
+    /// Is the side to move checkmated: in check, by the rules of my `Variant`, with no legal
+    /// move?  This is `Board::status` being `BoardStatus::Checkmate`.
+    ///
+    /// ```
+    /// use chess::Board;
+    /// use std::str::FromStr;
+    ///
+    /// // under the uno rules the knight next to the king checks it, and the king cannot take
+    /// // the knight, which does not attack it, nor step away from it
+    /// let board = Board::from_str("6Nk/8/8/8/8/8/8/K7 b - - 0 1").expect("Valid FEN");
+    /// assert!(board.is_checkmate());
+    ///
+    /// // in normal chess the king just takes it
+    /// let board = Board::from_str("6Nk/8/8/8/8/8/8/K7 b - - 0 1 variant:standard")
+    ///     .expect("Valid FEN");
+    /// assert!(!board.is_checkmate());
+    /// ```
+    #[inline]
+    pub fn is_checkmate(&self) -> bool {
+        self.status() == BoardStatus::Checkmate
+    }
+
+    /// Is the side to move stalemated: not in check, by the rules of my `Variant`, with no legal
+    /// move?  This is `Board::status` being `BoardStatus::Stalemate`.
+    ///
+    /// ```
+    /// use chess::Board;
+    /// use std::str::FromStr;
+    ///
+    /// // in normal chess the queen covers every square around the king
+    /// let board = Board::from_str("k7/2Q5/8/8/8/8/8/K7 b - - 0 1 variant:standard")
+    ///     .expect("Valid FEN");
+    /// assert!(board.is_stalemate());
+    ///
+    /// // under the uno rules only the squares next to the queen are, so a7 is free
+    /// let board = Board::from_str("k7/2Q5/8/8/8/8/8/K7 b - - 0 1").expect("Valid FEN");
+    /// assert!(!board.is_stalemate());
+    /// ```
+    #[inline]
+    pub fn is_stalemate(&self) -> bool {
+        self.status() == BoardStatus::Stalemate
+    }
+
+    /// Does the side to move have a legal capture, by the rules of the board's `Variant`?  A
+    /// quiescence search needs to know this, along with whether I am in check, before it lets
+    /// me stand pat.
+    ///
+    /// The moves are generated one kind of piece at a time, and this stops at the first capture.
+    ///
+    /// ```
+    /// use chess::Board;
+    /// use std::str::FromStr;
+    ///
+    /// assert!(!Board::default().legal_captures_exist());
+    /// let board = Board::from_str("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").expect("Valid FEN");
+    /// assert!(board.legal_captures_exist());
+    /// ```
+    pub fn legal_captures_exist(&self) -> bool {
+        MoveGen::has_legal_capture(self)
+    }
+
+    /// Give me the legal move of the side to move, if it has exactly one, or `None` if it has
+    /// none or more than one.  A search may extend a forced move like this.
+    ///
+    /// The moves are generated one kind of piece at a time, and this stops as soon as there is
+    /// a second move.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(Board::default().only_move(), None);
+    /// let board = Board::from_str("k7/8/1Q6/8/8/8/8/4K3 b - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.only_move(), Some(ChessMove::new(Square::A8, Square::B8, None)));
+    /// ```
+    pub fn only_move(&self) -> Option<ChessMove> {
+        MoveGen::only_move(self)
+    }
+
     /// Grab the "combined" `BitBoard`.  This is a `BitBoard` with every piece.
     ///
     /// ```
@@ -204,6 +487,10 @@ impl Board {
 
     /// Give me the `Square` the `color` king is on.
     ///
+    /// Every `Board` has exactly one king of each color, as every way of making one checks it, so
+    /// this always has an answer.  Use `BoardBuilder::king_square` for a position that has not
+    /// been checked yet.
+    ///
     /// ```
     /// use chess::{Board, Square, Color};
     ///
@@ -214,7 +501,8 @@ impl Board {
     /// ```
     #[inline]
     pub fn king_square(&self, color: Color) -> Square {
-        (self.pieces(Piece::King) & self.color_combined(color)).to_square()
+        // every board has one king of each color
+        (self.pieces(Piece::King) & self.color_combined(color)).to_square_unchecked()
     }
 
     /// Grab the "pieces" `BitBoard`.  This is a `BitBoard` with every piece of a particular type.
@@ -237,6 +525,152 @@ impl Board {
         unsafe { self.pieces.get_unchecked(piece.to_index()) }
     }
 
+    /// Grab the `BitBoard` of one kind of piece of one color.
+    ///
+    /// ```
+    /// use chess::{Board, BitBoard, Color, Piece, Square};
+    ///
+    /// let board = Board::default();
+    ///
+    /// let black_knights = BitBoard::from_square(Square::B8) | BitBoard::from_square(Square::G8);
+    /// assert_eq!(board.piece_squares(Piece::Knight, Color::Black), black_knights);
+    /// ```
+    #[inline]
+    pub fn piece_squares(&self, piece: Piece, color: Color) -> BitBoard {
+        self.pieces(piece) & self.color_combined(color)
+    }
+
+    /// Iterate over every piece on the board with its square and color, from a1 up to h8.
+    ///
+    /// ```
+    /// use chess::{Board, Color, Piece, Square};
+    ///
+    /// let board = Board::default();
+    ///
+    /// let mut pieces = board.piece_iter();
+    /// assert_eq!(pieces.next(), Some((Square::A1, Piece::Rook, Color::White)));
+    /// assert_eq!(pieces.next(), Some((Square::B1, Piece::Knight, Color::White)));
+    /// assert_eq!(board.piece_iter().last(), Some((Square::H8, Piece::Rook, Color::Black)));
+    /// assert_eq!(board.piece_iter().count(), 32);
+    /// ```
+    #[inline]
+    pub fn piece_iter(&self) -> impl Iterator<Item = (Square, Piece, Color)> + '_ {
+        self.combined.filter_map(move |square| {
+            Some((square, self.piece_on(square)?, self.color_on(square)?))
+        })
+    }
+
+    /// Iterate over the pieces of one color with their squares, from a1 up to h8.
+    ///
+    /// ```
+    /// use chess::{Board, Color, Piece, Square};
+    ///
+    /// let board = Board::default();
+    ///
+    /// let mut pieces = board.pieces_of(Color::Black);
+    /// assert_eq!(pieces.next(), Some((Square::A7, Piece::Pawn)));
+    /// assert_eq!(board.pieces_of(Color::Black).count(), 16);
+    /// ```
+    #[inline]
+    pub fn pieces_of(&self, color: Color) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.color_combined(color)
+            .filter_map(move |square| Some((square, self.piece_on(square)?)))
+    }
+
+    /// List the squares whose piece differs between this board and `other`, from a1 up to h8.
+    ///
+    /// Only the pieces are compared, not the side to move, castle rights or en passant square.
+    /// Castling changes four squares and en passant three, so this is how a GUI knows what to
+    /// redraw after any move.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Color, Piece, Square, SquareChange};
+    ///
+    /// let board = Board::default();
+    /// let after = board.make_move_new(ChessMove::new(Square::G1, Square::F3, None));
+    ///
+    /// assert_eq!(
+    ///     board.diff(&after),
+    ///     vec![
+    ///         SquareChange {
+    ///             square: Square::G1,
+    ///             before: Some((Piece::Knight, Color::White)),
+    ///             after: None,
+    ///         },
+    ///         SquareChange {
+    ///             square: Square::F3,
+    ///             before: None,
+    ///             after: Some((Piece::Knight, Color::White)),
+    ///         },
+    ///     ]
+    /// );
+    /// assert!(board.diff(&board).is_empty());
+    /// ```
+    pub fn diff(&self, other: &Board) -> Vec<SquareChange> {
+        let on = |board: &Board, square| Some((board.piece_on(square)?, board.color_on(square)?));
+        (self.combined | other.combined)
+            .filter_map(|square| {
+                let (before, after) = (on(self, square), on(other, square));
+                if before == after {
+                    None
+                } else {
+                    Some(SquareChange {
+                        square,
+                        before,
+                        after,
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// Find the legal move that turns this board into `other`, such as the move an opponent made
+    /// on a board read from a diagram or a camera.
+    ///
+    /// Only the pieces and the side to move are compared, so `other` may have lost its castle
+    /// rights or en passant square along the way.  Returns `None` if no legal move leads there.
+    /// If several did, the first in move generation order would be returned, but under the rules
+    /// of both variants the pieces after a move give away its source, destination and promotion,
+    /// so at most one move ever matches.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    /// let castled = Board::from_diagram(
+    ///     "
+    ///     r . . . k . . r
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     R . . . . R K .
+    ///     b
+    ///     ",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     board.find_connecting_move(&castled),
+    ///     Some(ChessMove::new(Square::E1, Square::G1, None))
+    /// );
+    /// assert_eq!(board.find_connecting_move(&board), None);
+    /// ```
+    pub fn find_connecting_move(&self, other: &Board) -> Option<ChessMove> {
+        if other.side_to_move != !self.side_to_move {
+            return None;
+        }
+        MoveGen::new_legal(self).find(|m| {
+            let result = self.make_move_new(*m);
+            result.combined == other.combined
+                && result.color_combined == other.color_combined
+                && result.pieces == other.pieces
+        })
+    }
+
     /// Grab the `CastleRights` for a particular side.
     ///
     /// ```
@@ -276,6 +710,32 @@ impl Board {
         unsafe { *self.castle_rights.get_unchecked(color.to_index()) }
     }
 
+    /// Has `color` castled, and to which side?  A castle made on the board, or on one it came
+    /// from through `make_move`, sets this, and so does `BoardBuilder::castled`.
+    ///
+    /// A FEN cannot say, so a board read from one, or written to one and read back, has not
+    /// castled.  A `positions::PackedBoard` and a `Game` keep it, and boards are equal whether
+    /// they castled or not.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Castled, Color, MoveFormat};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::default()
+    ///     .apply_moves("e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1".split(' '), MoveFormat::Coordinate)
+    ///     .unwrap();
+    /// assert_eq!(board.castled(Color::White), Castled::KingSide);
+    /// assert_eq!(board.castled(Color::Black), Castled::No);
+    ///
+    /// let read = Board::from_str(&board.to_string()).unwrap();
+    /// assert_eq!(read.castled(Color::White), Castled::No);
+    /// assert_eq!(read, board);
+    /// ```
+    #[inline]
+    pub fn castled(&self, color: Color) -> Castled {
+        self.castled[color.to_index()]
+    }
+
     /// Add castle rights for a particular side.  Note: this can create an invalid position.
     #[deprecated(
         since = "3.1.0",
@@ -325,6 +785,212 @@ impl Board {
         self.side_to_move
     }
 
+    /// Which rules is this board played by?
+    ///
+    /// ```
+    /// use chess::{Board, Variant};
+    ///
+    /// let board = Board::default();
+    /// assert_eq!(board.variant(), Variant::Uno);
+    /// ```
+    #[inline]
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Give me the square of the pawn that can be captured en passant, if there is one.  This is
+    /// the square the pawn moved to, not the one it skipped over, and is only ever set in
+    /// `Variant::Standard`.
+    ///
+    /// ```
+    /// use chess::{Board, BoardBuilder, ChessMove, Square, Variant};
+    /// use std::convert::TryInto;
+    /// use std::str::FromStr;
+    ///
+    /// let board: Board = BoardBuilder::from_str("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1")
+    ///     .expect("Valid FEN")
+    ///     .variant(Variant::Standard)
+    ///     .try_into()
+    ///     .expect("Valid Position");
+    ///
+    /// let board = board.make_move_new(ChessMove::new(Square::E2, Square::E4, None));
+    /// assert_eq!(board.en_passant(), Some(Square::E4));
+    /// ```
+    #[inline]
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// Write the position as a FEN that records my `Variant`, see `BoardBuilder::to_tagged_fen`.
+    ///
+    /// ```
+    /// use chess::{Board, BoardBuilder, Variant};
+    /// use std::convert::TryInto;
+    /// use std::str::FromStr;
+    ///
+    /// let board: Board = BoardBuilder::default()
+    ///     .variant(Variant::Standard)
+    ///     .try_into()
+    ///     .expect("Valid Position");
+    /// let fen = board.to_tagged_fen();
+    /// assert_eq!(Board::from_str(&fen).unwrap().variant(), Variant::Standard);
+    /// ```
+    pub fn to_tagged_fen(&self) -> String {
+        BoardBuilder::from(self).to_tagged_fen()
+    }
+
+    /// Draw the position as a diagram, see `BoardBuilder::to_ascii`.
+    ///
+    /// ```
+    /// use chess::Board;
+    ///
+    /// let board = Board::default();
+    /// assert_eq!(Board::from_diagram(&board.to_ascii()).unwrap(), board);
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        BoardBuilder::from(self).to_ascii()
+    }
+
+    /// Draw the board for a person to read, with the squares of the last move marked with `[`
+    /// and `]` and my checkers marked with `(` and `)`.  See `RenderOptions` for the
+    /// perspective, unicode pieces and terminal colors.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, RenderOptions, Square};
+    ///
+    /// let m = ChessMove::new(Square::E2, Square::E4, None);
+    /// let board = Board::default().make_move_new(m);
+    /// let options = RenderOptions {
+    ///     last_move: Some(m),
+    ///     ..RenderOptions::default()
+    /// };
+    /// let lines: Vec<String> = board.render(options).lines().map(String::from).collect();
+    /// assert_eq!(lines[4], "4  .  .  .  . [P] .  .  . ");
+    /// assert_eq!(lines[6], "2  P  P  P  P [.] P  P  P ");
+    /// ```
+    pub fn render(&self, options: RenderOptions) -> String {
+        render(self, options)
+    }
+
+    /// Read the position from a diagram, see `BoardBuilder::from_diagram`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// let board = Board::from_diagram(
+    ///     "
+    ///       a b c d e f g h
+    ///     8 . . . . k . . .
+    ///     7 . . . . . . . .
+    ///     6 . . . . . . . .
+    ///     5 . . . . r . . .
+    ///     4 . . . . . . . .
+    ///     3 . . . . . . . .
+    ///     2 . . . . . . . .
+    ///     1 . . . . K . . .
+    ///     ",
+    /// )
+    /// .unwrap();
+    /// assert!(board.legal(ChessMove::new(Square::E1, Square::E5, None)));
+    /// ```
+    pub fn from_diagram(diagram: &str) -> Result<Board, Error> {
+        BoardBuilder::from_diagram(diagram)?.try_into()
+    }
+
+    /// Set up a random valid position, see `RandomBoardOptions` for what goes in it.  The same
+    /// generator state always gives the same board.  This needs the `rand` feature.
+    ///
+    /// ```
+    /// use chess::{Board, RandomBoardOptions, Variant};
+    /// use rand::rngs::SmallRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let options = RandomBoardOptions {
+    ///     variant: Variant::Standard,
+    ///     allow_check: false,
+    ///     ..RandomBoardOptions::default()
+    /// };
+    /// let board = Board::random(&mut SmallRng::seed_from_u64(1), options);
+    /// assert!(board.is_sane());
+    /// assert_eq!(board, Board::random(&mut SmallRng::seed_from_u64(1), options));
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn random(rng: &mut impl Rng, options: RandomBoardOptions) -> Board {
+        random(rng, options)
+    }
+
+    /// The Chess960 start position with Scharnagl number `n`, in the default variant.  Both
+    /// sides may castle with both rooks, and position 518 is the normal start position.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is not below 960.
+    ///
+    /// ```
+    /// use chess::{Board, Piece, Square};
+    ///
+    /// assert_eq!(Board::chess960_start(518), Board::default());
+    ///
+    /// let board = Board::chess960_start(0);
+    /// assert_eq!(board.to_string(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+    /// assert_eq!(board.chess960_number(), Some(0));
+    /// ```
+    pub fn chess960_start(n: u16) -> Board {
+        assert!(n < 960, "there are only 960 Chess960 start positions");
+        let back_rank = chess960_back_rank(n);
+        let mut pieces = Vec::with_capacity(32);
+        for color in ALL_COLORS.iter() {
+            let rank = color.back_rank();
+            let pawns = color.to_second_rank();
+            for (file, piece) in ALL_FILES.iter().zip(back_rank.iter()) {
+                pieces.push((Square::make_square(rank, *file), *piece, *color));
+                pieces.push((Square::make_square(pawns, *file), Piece::Pawn, *color));
+            }
+        }
+
+        let mut rooks = ALL_FILES
+            .iter()
+            .zip(back_rank.iter())
+            .filter(|(_, piece)| **piece == Piece::Rook)
+            .map(|(file, _)| *file);
+        let queenside = rooks.next();
+        let rights = CastleRights::new(rooks.next(), queenside);
+
+        BoardBuilder::setup(&pieces, Color::White, rights, rights, None)
+            .try_into()
+            .expect("Valid Position")
+    }
+
+    /// The Scharnagl number of the Chess960 start position the pieces stand in, or `None` if
+    /// they stand in none.  Only the pieces are compared, not the castle rights or the side to
+    /// move.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// assert_eq!(Board::default().chess960_number(), Some(518));
+    ///
+    /// let board = Board::default().make_move_new(ChessMove::new(Square::E2, Square::E4, None));
+    /// assert_eq!(board.chess960_number(), None);
+    /// ```
+    pub fn chess960_number(&self) -> Option<u16> {
+        let mut back_rank = [Piece::Pawn; 8];
+        for (file, piece) in ALL_FILES.iter().zip(back_rank.iter_mut()) {
+            *piece = self.piece_on(Square::make_square(Rank::First, *file))?;
+        }
+        let n = (0..960).find(|n| chess960_back_rank(*n) == back_rank)?;
+
+        let start = Board::chess960_start(n);
+        if start.combined == self.combined
+            && start.color_combined == self.color_combined
+            && start.pieces == self.pieces
+        {
+            Some(n)
+        } else {
+            None
+        }
+    }
+
     /// Grab my `CastleRights`.
     ///
     /// ```
@@ -426,12 +1092,18 @@ impl Board {
     }
 
     /// Add or remove a piece from the bitboards in this struct.
-    fn xor(&mut self, piece: Piece, bb: BitBoard, color: Color) {
+    pub(crate) fn xor(&mut self, piece: Piece, bb: BitBoard, color: Color) {
+        // adding or removing, this is the index of the piece in the material keys
+        let others = (self.pieces(piece) & self.color_combined(color) & !bb).popcnt();
+        self.material_key ^= Zobrist::material(piece, others, color);
         unsafe {
             *self.pieces.get_unchecked_mut(piece.to_index()) ^= bb;
             *self.color_combined.get_unchecked_mut(color.to_index()) ^= bb;
-            self.combined ^= bb;
-            self.hash ^= Zobrist::piece(piece, bb.to_square(), color);
+        }
+        self.combined ^= bb;
+        self.hash ^= Zobrist::piece(piece, bb.to_square_unchecked(), color);
+        if piece == Piece::Pawn {
+            self.pawn_hash ^= Zobrist::pawn(bb.to_square_unchecked(), color);
         }
     }
 
@@ -471,6 +1143,11 @@ impl Board {
             }
         }
 
+        // a board without exactly one king of each color is not a valid chess board
+        if !result.one_king_each() {
+            return None;
+        }
+
         // If setting this piece down leaves my opponent in check, and it's my move, then the
         // position is not a valid chess board
         result.side_to_move = !result.side_to_move;
@@ -483,6 +1160,11 @@ impl Board {
         result.side_to_move = !result.side_to_move;
         result.update_check_info();
 
+        #[cfg(feature = "attack-info")]
+        {
+            result.attack_info = AttackInfo::new(&result);
+        }
+
         Some(result)
     }
 
@@ -518,6 +1200,11 @@ impl Board {
             }
         }
 
+        // a board without exactly one king of each color is not a valid chess board
+        if !result.one_king_each() {
+            return None;
+        }
+
         // If setting this piece down leaves my opponent in check, and it's my move, then the
         // position is not a valid chess board
         result.side_to_move = !result.side_to_move;
@@ -530,6 +1217,11 @@ impl Board {
         result.side_to_move = !result.side_to_move;
         result.update_check_info();
 
+        #[cfg(feature = "attack-info")]
+        {
+            result.attack_info = AttackInfo::new(&result);
+        }
+
         Some(result)
     }
 
@@ -554,61 +1246,230 @@ impl Board {
         } else {
             let mut result = *self;
             result.side_to_move = !result.side_to_move;
+            result.en_passant = None;
             result.update_check_info();
             Some(result)
         }
     }
 
-    /// Does this board "make sense"?
-    /// Do all the pieces make sense, do the bitboards combine correctly, etc?
-    /// This is for sanity checking.
+    /// Give the same position with the other player to move, as an analysis GUI does when the
+    /// turn is flipped by hand.
+    ///
+    /// Unlike `null_move`, this is an edit of the position, so the new board is checked like any
+    /// other, and it fails with `Error::OpponentInCheck` when I am giving check.  There is no en
+    /// passant square afterwards.
     ///
     /// ```
-    /// use chess::{Board, Color, Piece, Square};
+    /// use chess::{Board, Color, Error};
+    /// use std::str::FromStr;
     ///
-    /// let board = Board::default();
+    /// let board = Board::default().swap_side_to_move().unwrap();
+    /// assert_eq!(board.side_to_move(), Color::Black);
     ///
-    /// assert_eq!(board.is_sane(), true);
+    /// // white is giving check, so black's turn cannot be skipped
+    /// let check = Board::from_str("4k3/8/8/8/8/8/8/4QK2 b - - 0 1 variant:standard").unwrap();
+    /// assert!(matches!(check.swap_side_to_move(), Err(Error::OpponentInCheck)));
+    /// ```
+    pub fn swap_side_to_move(&self) -> Result<Board, Error> {
+        let mut builder = BoardBuilder::from(self);
+        builder.side_to_move(!self.side_to_move).en_passant(None);
+        Board::try_from(&builder)
+    }
+
+    /// Give the position turned halfway around, so a1 goes to h8.  Unlike flipping the board for
+    /// the other side, each piece keeps its color, so the white pawns end up moving toward the
+    /// side they came from.  This is handy for making symmetric test positions.
+    ///
+    /// The castle rights and the en passant square are dropped, and the new board is checked
+    /// like any other, since a pawn that now moves the other way may give check.
     ///
-    /// // Remove the king
-    /// let bad_board = board.clear_square(Square::E1).expect("Valid Position");
-    /// assert_eq!(bad_board.is_sane(), false);
     /// ```
-    pub fn is_sane(&self) -> bool {
-        // make sure there is no square with multiple pieces on it
-        for x in ALL_PIECES.iter() {
-            for y in ALL_PIECES.iter() {
-                if *x != *y {
-                    if self.pieces(*x) & self.pieces(*y) != EMPTY {
-                        return false;
-                    }
+    /// use chess::{Board, Square, Piece, Color};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/8/1P6/4K3 w - - 0 1").unwrap();
+    /// let turned = board.rotate_180().unwrap();
+    /// assert_eq!(turned.piece_on(Square::G7), Some(Piece::Pawn));
+    /// assert_eq!(turned.color_on(Square::G7), Some(Color::White));
+    /// assert_eq!(turned.king_square(Color::White), Square::D8);
+    /// ```
+    pub fn rotate_180(&self) -> Result<Board, Error> {
+        let mut builder = BoardBuilder::from(self);
+        for sq in self.combined {
+            builder.clear_square(sq);
+        }
+        for color in ALL_COLORS.iter() {
+            for piece in ALL_PIECES.iter() {
+                let bb = self.pieces(*piece) & self.color_combined(*color);
+                for sq in bb.rotate_180() {
+                    builder.piece(sq, *piece, *color);
                 }
             }
+            builder.castle_rights(*color, CastleRights::NoRights);
         }
+        builder.en_passant(None);
+        Board::try_from(&builder)
+    }
 
-        // make sure the colors don't overlap, either
-        if self.color_combined(Color::White) & self.color_combined(Color::Black) != EMPTY {
-            return false;
-        }
-
-        // grab all the pieces by OR'ing together each piece() BitBoard
-        let combined = ALL_PIECES
-            .iter()
+    /// Give me the positions that are the same game as this one, to train on more positions, with
+    /// the `SymmetryOp` that makes each.  `ChessMove::transform` takes a move along.
+    ///
+    /// The mirror with the colors swapped is always one.  Without pawns, which only move one way,
+    /// and without castle rights, which need the king and rooks on their first rank, the board
+    /// can also be turned and flipped every way a square can, for seven more.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen, SymmetryOp};
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(Board::default().symmetries().len(), 1);
+    ///
+    /// let board = Board::from_str("8/8/8/8/8/2k5/1r6/KQ6 w - - 0 1").unwrap();
+    /// let symmetries = board.symmetries();
+    /// assert_eq!(symmetries.len(), 8);
+    /// for (other, op) in symmetries {
+    ///     assert_eq!(MoveGen::new_legal(&other).len(), MoveGen::new_legal(&board).len());
+    ///     for m in MoveGen::new_legal(&board) {
+    ///         assert!(other.legal(m.transform(op)));
+    ///     }
+    /// }
+    /// ```
+    pub fn symmetries(&self) -> Vec<(Board, SymmetryOp)> {
+        let any_shape = *self.pieces(Piece::Pawn) == EMPTY
+            && ALL_COLORS
+                .iter()
+                .all(|color| self.castle_rights(*color) == CastleRights::NoRights);
+        ALL_SYMMETRY_OPS
+            .iter()
+            .filter(|op| any_shape || op.swaps_colors())
+            .map(|op| (self.transform(*op), *op))
+            .collect()
+    }
+
+    // The position `op` turns or flips this one into, which is always valid where `symmetries`
+    // uses it.
+    fn transform(&self, op: SymmetryOp) -> Board {
+        let color_after = |color: Color| if op.swaps_colors() { !color } else { color };
+        let mut builder = BoardBuilder::from(self);
+        for sq in self.combined {
+            builder.clear_square(sq);
+        }
+        for color in ALL_COLORS.iter() {
+            for piece in ALL_PIECES.iter() {
+                let bb = self.pieces(*piece) & self.color_combined(*color);
+                for sq in op.bitboard(bb) {
+                    builder.piece(sq, *piece, color_after(*color));
+                }
+            }
+            builder
+                .castle_rights(color_after(*color), self.castle_rights(*color))
+                .castled(color_after(*color), self.castled(*color));
+        }
+        builder.side_to_move(color_after(self.side_to_move));
+        Board::try_from(&builder).expect("a symmetry keeps the position valid")
+    }
+
+    // does each side have exactly one king, which `king_square` relies on?
+    fn one_king_each(&self) -> bool {
+        ALL_COLORS
+            .iter()
+            .all(|color| (self.pieces(Piece::King) & self.color_combined(*color)).popcnt() == 1)
+    }
+
+    /// Does this board "make sense"?
+    /// Do all the pieces make sense, do the bitboards combine correctly, etc?
+    /// This is for sanity checking.
+    ///
+    /// Every way of making a `Board` checks this, and gives an error that says what is wrong
+    /// instead, see `BoardBuilder` for the checks that can be changed.
+    ///
+    /// ```
+    /// use chess::{Board, Color, Piece, Square};
+    ///
+    /// let board = Board::default();
+    ///
+    /// assert_eq!(board.is_sane(), true);
+    ///
+    /// // Remove a rook, that white may still castle with
+    /// let bad_board = board.clear_square(Square::A1).expect("Valid Position");
+    /// assert_eq!(bad_board.is_sane(), false);
+    /// ```
+    pub fn is_sane(&self) -> bool {
+        self.validate(MAX_PIECES, false).is_ok()
+    }
+
+    // why this board does not make sense, if it does not, allowing `max_pieces` for each side
+    // and, with `strict_material`, only as many promoted pieces as there are missing pawns
+    pub(crate) fn validate(&self, max_pieces: u32, strict_material: bool) -> Result<(), Error> {
+        // make sure there is no square with multiple pieces on it
+        for x in ALL_PIECES.iter() {
+            for y in ALL_PIECES.iter() {
+                if *x != *y {
+                    if self.pieces(*x) & self.pieces(*y) != EMPTY {
+                        return Err(Error::InvalidBoard);
+                    }
+                }
+            }
+        }
+
+        // make sure the colors don't overlap, either
+        if self.color_combined(Color::White) & self.color_combined(Color::Black) != EMPTY {
+            return Err(Error::InvalidBoard);
+        }
+
+        // grab all the pieces by OR'ing together each piece() BitBoard
+        let combined = ALL_PIECES
+            .iter()
             .fold(EMPTY, |cur, next| cur | self.pieces(*next));
 
         // make sure that's equal to the combined bitboard
         if combined != *self.combined() {
-            return false;
+            return Err(Error::InvalidBoard);
+        }
+
+        // make sure there is exactly one king of each color
+        if !self.one_king_each() {
+            return Err(Error::InvalidKingCount);
         }
 
-        // make sure there is exactly one white king
-        if (self.pieces(Piece::King) & self.color_combined(Color::White)).popcnt() != 1 {
-            return false;
+        // make sure neither side has more pieces than it may, which is at most what it starts
+        // with, as `MoveGen` relies on
+        for color in ALL_COLORS.iter() {
+            if self.color_combined(*color).popcnt() > max_pieces {
+                return Err(Error::TooManyPieces);
+            }
+        }
+
+        // a pawn promotes on the last rank, and only under the uno rules can it capture back
+        // onto its own first rank
+        for color in ALL_COLORS.iter() {
+            let mut ranks = color.to_their_backrank_bb();
+            if self.variant == Variant::Standard {
+                ranks |= color.to_my_backrank_bb();
+            }
+            if self.piece_squares(Piece::Pawn, *color) & ranks != EMPTY {
+                return Err(Error::PawnOnBackRank);
+            }
         }
 
-        // make sure there is exactly one black king
-        if (self.pieces(Piece::King) & self.color_combined(Color::Black)).popcnt() != 1 {
-            return false;
+        // every piece past those a side starts with was promoted from one of its eight pawns
+        if strict_material {
+            let starts = [
+                (Piece::Knight, 2),
+                (Piece::Bishop, 2),
+                (Piece::Rook, 2),
+                (Piece::Queen, 1),
+            ];
+            for color in ALL_COLORS.iter() {
+                let count = |piece| self.piece_squares(piece, *color).popcnt();
+                let promoted: u32 = starts
+                    .iter()
+                    .map(|(piece, start)| count(*piece).saturating_sub(*start))
+                    .sum();
+                if count(Piece::Pawn) + promoted > 8 {
+                    return Err(Error::ImpossibleMaterial);
+                }
+            }
         }
 
         // make sure my opponent is not currently in check (because that would be illegal)
@@ -616,7 +1477,21 @@ impl Board {
         board_copy.side_to_move = !board_copy.side_to_move;
         board_copy.update_check_info();
         if board_copy.checkers != EMPTY {
-            return false;
+            return Err(Error::OpponentInCheck);
+        }
+
+        // a pawn that can be captured en passant must have just moved two squares
+        if let Some(ep) = self.en_passant {
+            let color = !self.side_to_move;
+            let passed = BitBoard::from_square(ep.ubackward(color))
+                | BitBoard::from_square(ep.ubackward(color).ubackward(color));
+            if ep.get_rank() != color.double_push_rank()
+                || self.pieces(Piece::Pawn) & self.color_combined(color) & BitBoard::from_square(ep)
+                    == EMPTY
+                || self.combined & passed != EMPTY
+            {
+                return Err(Error::InvalidBoard);
+            }
         }
 
         // for each color, verify that, if they have castle rights, that they haven't moved their
@@ -632,29 +1507,34 @@ impl Board {
                 & self.color_combined(*color)
                 != castle_rights.unmoved_rooks(*color)
             {
-                return false;
+                return Err(Error::InvalidBoard);
             }
-            // if we have castle rights, make sure we have a king on the (E, {1,8}) square,
-            // depending on the color
+            // if we have castle rights, make sure we have a king on our back rank, between the
+            // rooks we castle with
             if castle_rights != CastleRights::NoRights {
-                if self.pieces(Piece::King) & self.color_combined(*color)
-                    != get_file(File::E) & get_rank(color.to_my_backrank())
+                let ksq = self.king_square(*color);
+                if ksq.get_rank() != color.back_rank()
+                    || matches!(castle_rights.kingside_rook(), Some(f) if f <= ksq.get_file())
+                    || matches!(castle_rights.queenside_rook(), Some(f) if f >= ksq.get_file())
                 {
-                    return false;
+                    return Err(Error::InvalidBoard);
                 }
             }
         }
 
-        // we must make sure the kings aren't touching
+        // we must make sure the kings aren't touching, as then my king attacks my opponent's
         if get_king_moves(self.king_square(Color::White)) & self.pieces(Piece::King) != EMPTY {
-            return false;
+            return Err(Error::OpponentInCheck);
         }
 
         // it checks out
-        return true;
+        Ok(())
     }
 
     /// Get a hash of the board.
+    ///
+    /// The hash scheme is versioned by `stability::HASH_SCHEME_VERSION`, and the same board only
+    /// hashes differently under a new version.
     #[inline]
     pub fn get_hash(&self) -> u64 {
         self.hash
@@ -671,14 +1551,224 @@ impl Board {
             } else {
                 0
             }
+            ^ if let Some(ep) = self.en_passant {
+                Zobrist::en_passant(ep.get_file(), !self.side_to_move)
+            } else {
+                0
+            }
+    }
+
+    /// Compute the hash of the board from scratch.  This is always the same as `get_hash`, which
+    /// is updated incrementally as moves are made, and is here to check that it is.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// let board = Board::default().make_move_new(ChessMove::new(Square::E2, Square::E4, None));
+    /// assert_eq!(board.recompute_hash(), board.get_hash());
+    /// ```
+    pub fn recompute_hash(&self) -> u64 {
+        let mut board = *self;
+        board.hash = 0;
+        for sq in *self.combined() {
+            if let (Some(piece), Some(color)) = (self.piece_on(sq), self.color_on(sq)) {
+                board.hash ^= Zobrist::piece(piece, sq, color);
+            }
+        }
+        board.get_hash()
+    }
+
+    /// Get a hash of just the pawns on the board, for pawn structure tables.  It only changes
+    /// when a pawn moves, is captured or promotes, and uses its own zobrist keys.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// let board = Board::default();
+    /// let knight = board.make_move_new(ChessMove::new(Square::G1, Square::F3, None));
+    /// let pawn = board.make_move_new(ChessMove::new(Square::E2, Square::E4, None));
+    ///
+    /// assert_eq!(knight.pawn_hash(), board.pawn_hash());
+    /// assert_ne!(pawn.pawn_hash(), board.pawn_hash());
+    /// ```
+    #[inline]
+    pub fn pawn_hash(&self) -> u64 {
+        self.pawn_hash
+    }
+
+    /// Get a key that only depends on how many of each piece each side has, for material and
+    /// endgame tables.  Like the other hashes, it is updated incrementally.
+    ///
+    /// ```
+    /// use chess::Board;
+    /// use std::str::FromStr;
+    ///
+    /// let a = Board::from_str("4k3/8/8/3q4/8/8/1N6/4K3 w - - 0 1").expect("Valid FEN");
+    /// let b = Board::from_str("4k3/6q1/8/8/8/2N5/8/3K4 b - - 0 1").expect("Valid FEN");
+    /// assert_eq!(a.material_key(), b.material_key());
+    /// ```
+    #[inline]
+    pub fn material_key(&self) -> u64 {
+        self.material_key
+    }
+
+    /// How many of `piece` does `color` have?
+    #[inline]
+    pub fn material_count(&self, color: Color, piece: Piece) -> u8 {
+        (self.pieces(piece) & self.color_combined(color)).popcnt() as u8
+    }
+
+    /// The value of the knights, bishops, rooks and queens of `color`, using `PIECE_VALUES`.
+    ///
+    /// ```
+    /// use chess::{Board, Color};
+    ///
+    /// assert_eq!(Board::default().non_pawn_material(Color::White), 2 * (300 + 300 + 500) + 900);
+    /// ```
+    #[inline]
+    pub fn non_pawn_material(&self, color: Color) -> u32 {
+        self.non_pawn_material_with(color, &values::DEFAULT).0 as u32
+    }
+
+    /// Like `Board::non_pawn_material`, with the values of `table`.
+    ///
+    /// ```
+    /// use chess::values::{Value, DEFAULT};
+    /// use chess::{Board, Color, Piece};
+    ///
+    /// let mut table = DEFAULT;
+    /// table[Piece::Queen.to_index()] = Value(1000);
+    /// let material = Board::default().non_pawn_material_with(Color::White, &table);
+    /// assert_eq!(material, Value(2 * (300 + 300 + 500) + 1000));
+    /// ```
+    #[inline]
+    pub fn non_pawn_material_with<T: ValueTable>(&self, color: Color, table: &T) -> Value {
+        let mut material = Value::ZERO;
+        for piece in ALL_PIECES[1..].iter() {
+            material += table.value(*piece) * self.material_count(color, *piece) as i32;
+        }
+        material
+    }
+
+    /// How many of each piece does `color` have?
+    ///
+    /// ```
+    /// use chess::{Board, Color, Piece};
+    ///
+    /// let material = Board::default().material(Color::Black);
+    /// assert_eq!(material, [8, 2, 2, 2, 1, 1]);
+    /// assert_eq!(material[Piece::Rook.to_index()], 2);
+    /// ```
+    #[inline]
+    pub fn material(&self, color: Color) -> MaterialCount {
+        let mut material = [0; NUM_PIECES];
+        for (count, piece) in material.iter_mut().zip(ALL_PIECES.iter()) {
+            *count = self.material_count(color, *piece);
+        }
+        material
+    }
+
+    /// The value of the white pieces less the value of the black ones, pawns included, using
+    /// `PIECE_VALUES`.
+    ///
+    /// ```
+    /// use chess::Board;
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/3q4/8/8/1NPP4/4K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.material_balance(), 300 + 2 * 100 - 900);
+    /// ```
+    #[inline]
+    pub fn material_balance(&self) -> i32 {
+        self.material_balance_with(&values::DEFAULT).0
+    }
+
+    /// Like `Board::material_balance`, with the values of `table`.
+    ///
+    /// ```
+    /// use chess::values::{Value, DEFAULT};
+    /// use chess::{Board, Piece};
+    /// use std::str::FromStr;
+    ///
+    /// let mut table = DEFAULT;
+    /// table[Piece::Knight.to_index()] = Value(320);
+    /// let board = Board::from_str("4k3/8/8/3q4/8/8/1NPP4/4K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.material_balance_with(&table), Value(320 + 2 * 100 - 900));
+    /// ```
+    #[inline]
+    pub fn material_balance_with<T: ValueTable>(&self, table: &T) -> Value {
+        let mut balance = Value::ZERO;
+        for piece in ALL_PIECES.iter() {
+            let count = |color| self.material_count(color, *piece) as i32;
+            balance += table.value(*piece) * (count(Color::White) - count(Color::Black));
+        }
+        balance
     }
 
-    /// Get a pawn hash of the board (a hash that only changes on color change and pawn moves).
+    /// How far the game is from the endgame, for tapered evaluation: the `PHASE_WEIGHTS` of the
+    /// pieces of both sides, from `MAX_PHASE` at the start down to 0 with only kings and pawns.
+    /// Promoted pieces may add up to more, which counts as `MAX_PHASE`.
+    ///
+    /// ```
+    /// use chess::{Board, MAX_PHASE};
+    /// use std::str::FromStr;
     ///
-    /// Currently not implemented...
+    /// assert_eq!(Board::default().phase(), MAX_PHASE);
+    /// let board = Board::from_str("4k3/pp6/8/8/8/8/6PP/R3K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.phase(), 2);
+    /// ```
+    #[inline]
+    pub fn phase(&self) -> u8 {
+        let phase: u32 = ALL_PIECES
+            .iter()
+            .map(|piece| self.pieces(*piece).popcnt() * PHASE_WEIGHTS[piece.to_index()] as u32)
+            .sum();
+        phase.min(MAX_PHASE as u32) as u8
+    }
+
+    /// Get a pawn hash of the board.  This is the same as `pawn_hash`.
     #[inline]
     pub fn get_pawn_hash(&self) -> u64 {
-        0
+        self.pawn_hash
+    }
+
+    /// The squares in the half of the board of the other side that a pawn of `color` defends,
+    /// and that no pawn of the other side can ever attack, since none is left on the files next
+    /// to them further up the board.  A piece placed there can only be driven off by pieces.
+    ///
+    /// As in `chess::pawns`, this goes by where the pawns stand, and is the same in every
+    /// variant.
+    ///
+    /// ```
+    /// use chess::{Board, Color, Square};
+    /// use std::str::FromStr;
+    ///
+    /// // no black pawn on the c or e file can still come to attack d5
+    /// let board = Board::from_str("4k3/pp4pp/3p4/4p3/4P3/8/PP4PP/4K3 w - - 0 1")
+    ///     .expect("Valid FEN");
+    /// assert!(board.outposts(Color::White).into_iter().any(|sq| sq == Square::D5));
+    /// ```
+    #[inline]
+    pub fn outposts(&self, color: Color) -> BitBoard {
+        let pawns = *self.pieces(Piece::Pawn);
+        let ours = pawns & self.color_combined(color);
+        let theirs = pawns & self.color_combined(!color);
+        pawn_attacks(ours, color) & !pawn_attack_span(theirs, !color) & camp(!color)
+    }
+
+    /// The squares in the half of the board of `color` that no pawn of `color` can ever attack.
+    /// The ones a pawn of the other side defends are its `outposts`.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, Color};
+    ///
+    /// // before a pawn moves, only the first two ranks are out of reach
+    /// assert_eq!(Board::default().holes(Color::White), BitBoard(0xFFFF));
+    /// ```
+    #[inline]
+    pub fn holes(&self, color: Color) -> BitBoard {
+        let ours = self.pieces(Piece::Pawn) & self.color_combined(color);
+        !pawn_attack_span(ours, color) & camp(color)
     }
 
     /// What piece is on a particular `Square`?  Is there even one?
@@ -760,207 +1850,1991 @@ impl Board {
     /// ```
     #[inline]
     pub fn legal(&self, m: ChessMove) -> bool {
+        let m = self.normalize_move(m);
         MoveGen::new_legal(&self).find(|x| *x == m).is_some()
     }
 
-    /// Make a chess move onto a new board.
+    /// Check a move that did not come from the move generator here, such as one stored for
+    /// another position whose hash collided with this one, and give it back written the way
+    /// `MoveGen` does if it is legal.  A move this gives can be made with `make_move` without
+    /// breaking the board.
     ///
-    /// panic!() if king is captured.
+    /// The cheap checks come first, on `m` as it was given: that I have a piece on the source
+    /// square, and that the move promotes exactly when it takes a pawn to its last rank.  Then
+    /// only the moves of the kind of piece on the source square are generated to find it, so
+    /// this costs a lot less than `legal`, which generates all of them.
     ///
     /// ```
-    /// use chess::{Board, ChessMove, Square, Color};
-    ///
-    /// let m = ChessMove::new(Square::D2,
-    ///                        Square::D4,
-    ///                        None);
+    /// use chess::{Board, ChessMove, Piece, Square};
     ///
     /// let board = Board::default();
-    /// assert_eq!(board.make_move_new(m).side_to_move(), Color::Black);
+    /// let e2e4 = ChessMove::new(Square::E2, Square::E4, None);
+    /// assert_eq!(board.sanitize_move(e2e4), Some(e2e4));
+    ///
+    /// // black's pawn, and a promotion that does not reach the last rank
+    /// assert_eq!(board.sanitize_move(ChessMove::new(Square::E7, Square::E5, None)), None);
+    /// let promotion = ChessMove::new(Square::E2, Square::E4, Some(Piece::Queen));
+    /// assert_eq!(board.sanitize_move(promotion), None);
     /// ```
-    #[inline]
-    pub fn make_move_new(&self, m: ChessMove) -> Board {
-        let mut result = *self;
+    pub fn sanitize_move(&self, m: ChessMove) -> Option<ChessMove> {
         let source = m.get_source();
-        let dest = m.get_dest();
-
-        let source_bb = BitBoard::from_square(source);
-        let dest_bb = BitBoard::from_square(dest);
-        let move_bb = source_bb ^ dest_bb;
-        let moved = self.piece_on(source).unwrap();
-
-        result.xor(moved, source_bb, self.side_to_move);
-        result.xor(moved, dest_bb, self.side_to_move);
-        if let Some(captured) = self.piece_on(dest) {
-            result.xor(captured, dest_bb, !self.side_to_move);
+        let piece = self.piece_on(source)?;
+        if self.color_on(source) != Some(self.side_to_move) {
+            return None;
+        }
+        let promotes = piece == Piece::Pawn
+            && m.get_dest().get_rank() == self.side_to_move.to_their_backrank();
+        if promotes != m.get_promotion().is_some() {
+            return None;
         }
 
-        #[allow(deprecated)]
-        result.remove_their_castle_rights(CastleRights::square_to_castle_rights(
-            !self.side_to_move,
-            dest,
-        ));
-
-        #[allow(deprecated)]
-        result.remove_my_castle_rights(CastleRights::square_to_castle_rights(
-            self.side_to_move,
-            source,
-        ));
-
-        let castles = moved == Piece::King && (move_bb & get_castle_moves()) == move_bb;
-
-        const CASTLE_ROOK_START: [File; 8] = [
-            File::A,
-            File::A,
-            File::A,
-            File::A,
-            File::H,
-            File::H,
-            File::H,
-            File::H,
-        ];
-        const CASTLE_ROOK_END: [File; 8] = [
-            File::D,
-            File::D,
-            File::D,
-            File::D,
-            File::F,
-            File::F,
-            File::F,
-            File::F,
-        ];
+        let m = self.normalize_move(m);
+        if MoveGen::is_piece_move(self, piece, m) {
+            Some(m)
+        } else {
+            None
+        }
+    }
 
-        if moved == Piece::Pawn {
-            if let Some(promotion) = m.get_promotion() {
-                result.xor(Piece::Pawn, dest_bb, self.side_to_move);
-                result.xor(promotion, dest_bb, self.side_to_move);
-            }
-        } else if castles {
-            let my_backrank = self.side_to_move.to_my_backrank();
-            let index = dest.get_file().to_index();
-            let start = BitBoard::set(my_backrank, unsafe {
-                *CASTLE_ROOK_START.get_unchecked(index)
-            });
-            let end = BitBoard::set(my_backrank, unsafe {
-                *CASTLE_ROOK_END.get_unchecked(index)
-            });
-            result.xor(Piece::Rook, start, self.side_to_move);
-            result.xor(Piece::Rook, end, self.side_to_move);
+    /// Write `m` the way `MoveGen` does.  A castle in either `MoveEncoding` becomes the move
+    /// described by `castle_rook`, and any other move is left as it is.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// let board = Board::default();
+    /// let e1h1 = ChessMove::new(Square::E1, Square::H1, None);
+    /// assert_eq!(board.normalize_move(e1h1), ChessMove::new(Square::E1, Square::G1, None));
+    /// ```
+    #[inline]
+    pub fn normalize_move(&self, m: ChessMove) -> ChessMove {
+        match self.castle_rook(m) {
+            Some(rook) => castle_move(m.get_source(), rook),
+            None => m,
         }
-        
-        result.update_check_info();
+    }
 
-        result.side_to_move = !result.side_to_move;
-        result
+    /// Write `m` with castling in `encoding`.  Any move that does not castle is left as it is.
+    ///
+    /// A castle where my king moves one square or not at all is written as my king taking my
+    /// rook in either encoding, since as my king's destination it would read as an ordinary
+    /// king move, or as no move.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, MoveEncoding, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/8/8/1K3R2 w F - 0 1").expect("Valid FEN");
+    /// let castles = ChessMove::new(Square::B1, Square::F1, None);
+    /// assert_eq!(
+    ///     board.encode_move(castles, MoveEncoding::KingToDestination),
+    ///     ChessMove::new(Square::B1, Square::G1, None)
+    /// );
+    /// assert_eq!(board.encode_move(castles, MoveEncoding::KingTakesRook), castles);
+    /// ```
+    pub fn encode_move(&self, m: ChessMove, encoding: MoveEncoding) -> ChessMove {
+        let source = m.get_source();
+        let rook = match self.castle_rook(m) {
+            Some(rook) => rook,
+            None => return m,
+        };
+        let king_dest = castle_squares(source, rook).0;
+        let steps = get_king_moves(source) | BitBoard::from_square(source);
+        if encoding == MoveEncoding::KingToDestination
+            && steps & BitBoard::from_square(king_dest) == EMPTY
+        {
+            ChessMove::new(source, king_dest, None)
+        } else {
+            ChessMove::new(source, rook, None)
+        }
     }
 
-    /// Make a chess move onto an already allocated `Board`.
+    /// If `m` castles, give me the square of the rook it castles with.  This does not check that
+    /// `m` is legal, or that it is the turn of the king that castles.
     ///
-    /// panic!() if king is captured.
+    /// From the squares of normal chess, `MoveGen` writes castling as my king moving two squares
+    /// toward the rook, such as `e1g1`.  Otherwise, as in Chess960, it is written as my king
+    /// moving onto my own rook, such as `b1a1`, which works even when my king does not move.
+    /// Either way my king lands on the g or c file, with the rook next to it.  A castle onto
+    /// the rook lands on my own piece, so a `MoveGen` iterator mask of the empty squares or of
+    /// the enemy pieces skips it.
+    ///
+    /// Both `MoveEncoding`s are recognized here, and so by `make_move` as well: my king taking
+    /// my rook, and my king moving at least two squares to where it lands.  A king move with a
+    /// promotion never castles.
     ///
     /// ```
-    /// use chess::{Board, ChessMove, Square, Color};
+    /// use chess::{Board, ChessMove, Square};
+    /// use std::str::FromStr;
     ///
-    /// let m = ChessMove::new(Square::D2,
-    ///                        Square::D4,
-    ///                        None);
+    /// let board = Board::from_str("4k3/8/8/8/8/8/8/RK2R3 w EA - 0 1").expect("Valid FEN");
+    /// let queenside = ChessMove::new(Square::B1, Square::A1, None);
+    /// assert_eq!(board.castle_rook(queenside), Some(Square::A1));
+    /// assert_eq!(board.castle_rook(ChessMove::new(Square::B1, Square::E1, None)), Some(Square::E1));
+    /// assert_eq!(board.castle_rook(ChessMove::new(Square::B1, Square::C1, None)), None);
+    /// assert_eq!(board.castle_rook(ChessMove::new(Square::B1, Square::G1, None)), Some(Square::E1));
     ///
-    /// let board = Board::default();
-    /// let mut result = Board::default();
-    /// board.make_move(m, &mut result);
-    /// assert_eq!(result.side_to_move(), Color::Black);
+    /// let castled = board.make_move_new(queenside);
+    /// assert_eq!(castled.to_string(), "4k3/8/8/8/8/8/8/2KRR3 b - - 0 1");
     /// ```
     #[inline]
-    pub fn make_move(&self, m: ChessMove, result: &mut Board) {
-        *result = *self;
+    pub fn castle_rook(&self, m: ChessMove) -> Option<Square> {
         let source = m.get_source();
         let dest = m.get_dest();
-
-        let source_bb = BitBoard::from_square(source);
-        let dest_bb = BitBoard::from_square(dest);
-        let move_bb = source_bb ^ dest_bb;
-        let moved = self.piece_on(source).unwrap();
-
-        result.xor(moved, source_bb, self.side_to_move);
-        result.xor(moved, dest_bb, self.side_to_move);
-        if let Some(captured) = self.piece_on(dest) {
-            result.xor(captured, dest_bb, !self.side_to_move);
+        let color = self.color_on(source)?;
+        let rights = self.castle_rights(color);
+        if rights == CastleRights::NoRights
+            || source != self.king_square(color)
+            || m.get_promotion().is_some()
+        {
+            return None;
         }
 
-        #[allow(deprecated)]
-        result.remove_their_castle_rights(CastleRights::square_to_castle_rights(
-            !self.side_to_move,
-            dest,
-        ));
-
-        #[allow(deprecated)]
-        result.remove_my_castle_rights(CastleRights::square_to_castle_rights(
-            self.side_to_move,
-            source,
-        ));
-
-        let castles = moved == Piece::King && (move_bb & get_castle_moves()) == move_bb;
-
-        const CASTLE_ROOK_START: [File; 8] = [
-            File::A,
-            File::A,
-            File::A,
-            File::A,
-            File::H,
-            File::H,
-            File::H,
-            File::H,
-        ];
-        const CASTLE_ROOK_END: [File; 8] = [
-            File::D,
-            File::D,
-            File::D,
-            File::D,
-            File::F,
-            File::F,
-            File::F,
-            File::F,
-        ];
+        // a king can capture from a distance under the uno rules, so only a quiet king move
+        // castles toward the rook, and only if it is not an ordinary step of one square
+        let rook = if self.color_on(dest) == Some(color) {
+            dest
+        } else if self.color_on(dest).is_some()
+            || dest.get_rank() != source.get_rank()
+            || get_king_moves(source) & BitBoard::from_square(dest) != EMPTY
+        {
+            return None;
+        } else if dest == CastleRights::king_destination(color, CastleSide::KingSide) {
+            rights.kingside_rook_square(color)?
+        } else if dest == CastleRights::king_destination(color, CastleSide::QueenSide) {
+            rights.queenside_rook_square(color)?
+        } else {
+            return None;
+        };
+        if rights.for_rook(color, rook) != CastleRights::NoRights {
+            Some(rook)
+        } else {
+            None
+        }
+    }
+
+    /// Does `m` capture a piece?  This is a move onto a piece of the other side, or an en
+    /// passant capture.  A castle onto my own rook is not a capture.
+    ///
+    /// panic!() if there is no piece on the source square of `m`.
+    #[inline]
+    pub fn is_capture(&self, m: ChessMove) -> bool {
+        let dest = m.get_dest();
+        self.color_on(dest) == Some(!self.side_to_move)
+            || (self.piece_on(m.get_source()).unwrap() == Piece::Pawn
+                && self.en_passant.map(|ep| ep.uforward(self.side_to_move)) == Some(dest))
+    }
+
+    /// Does `m` reset the count of the fifty-move rule?  Pawn moves and captures do.
+    ///
+    /// panic!() if there is no piece on the source square of `m`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// let board = Board::default();
+    /// assert!(board.is_zeroing(ChessMove::new(Square::E2, Square::E4, None)));
+    /// assert!(!board.is_zeroing(ChessMove::new(Square::G1, Square::F3, None)));
+    /// ```
+    #[inline]
+    pub fn is_zeroing(&self, m: ChessMove) -> bool {
+        self.piece_on(m.get_source()) == Some(Piece::Pawn) || self.is_capture(m)
+    }
+
+    /// Is the legal move `m` quiet, so that a search may prune or reduce it?  A quiet move does
+    /// not capture, is not a promotion and does not give check.  Whether it gives check is
+    /// decided as in `gives_check`, from the check squares and the pieces in the way, so only a
+    /// castle is made to find out.
+    ///
+    /// panic!() if there is no piece on the source square of `m`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/3p4/4P3/3RK3 w - - 0 1").expect("Valid FEN");
+    /// assert!(board.is_quiet(ChessMove::new(Square::E2, Square::E4, None)));
+    /// assert!(!board.is_quiet(ChessMove::new(Square::E2, Square::D3, None)));
+    /// ```
+    #[inline]
+    pub fn is_quiet(&self, m: ChessMove) -> bool {
+        m.get_promotion().is_none() && !self.is_capture(m) && !self.gives_check(m)
+    }
+
+    /// Can no position from before `m` come back after it?  This is true of the moves that are
+    /// `is_zeroing`, and also of castling and of every move that loses a castle right, so a
+    /// search for repetitions can stop at the first of them.
+    ///
+    /// panic!() if there is no piece on the source square of `m`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").expect("Valid FEN");
+    /// let rook_move = ChessMove::new(Square::H1, Square::H2, None);
+    /// assert!(board.is_irreversible(rook_move));
+    /// assert!(!board.is_zeroing(rook_move));
+    /// ```
+    #[inline]
+    pub fn is_irreversible(&self, m: ChessMove) -> bool {
+        let us = self.side_to_move;
+        let lost = if self.piece_on(m.get_source()) == Some(Piece::King) {
+            self.my_castle_rights()
+        } else {
+            self.my_castle_rights().for_rook(us, m.get_source())
+        };
+        self.is_zeroing(m)
+            || lost != CastleRights::NoRights
+            || self.their_castle_rights().for_rook(!us, m.get_dest()) != CastleRights::NoRights
+    }
+
+    /// Make a chess move onto a new board.
+    ///
+    /// panic!() if king is captured.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square, Color};
+    ///
+    /// let m = ChessMove::new(Square::D2,
+    ///                        Square::D4,
+    ///                        None);
+    ///
+    /// let board = Board::default();
+    /// assert_eq!(board.make_move_new(m).side_to_move(), Color::Black);
+    /// ```
+    #[inline]
+    pub fn make_move_new(&self, m: ChessMove) -> Board {
+        let mut result = *self;
+        result.en_passant = None;
+        let source = m.get_source();
+        let dest = m.get_dest();
+
+        let source_bb = BitBoard::from_square(source);
+        let dest_bb = BitBoard::from_square(dest);
+        let moved = self.piece_on(source).unwrap();
+
+        // a castle may move my king onto my own rook, or not move it at all
+        let castles = if moved == Piece::King {
+            self.castle_rook(m)
+        } else {
+            None
+        };
+        if let Some(rook) = castles {
+            let (king_dest, rook_dest) = castle_squares(source, rook);
+            result.castled[self.side_to_move.to_index()] = castle_side(source, rook).into();
+            result.xor(Piece::King, source_bb, self.side_to_move);
+            result.xor(
+                Piece::King,
+                BitBoard::from_square(king_dest),
+                self.side_to_move,
+            );
+            result.xor(Piece::Rook, BitBoard::from_square(rook), self.side_to_move);
+            result.xor(
+                Piece::Rook,
+                BitBoard::from_square(rook_dest),
+                self.side_to_move,
+            );
+        } else {
+            result.xor(moved, source_bb, self.side_to_move);
+            result.xor(moved, dest_bb, self.side_to_move);
+            if let Some(captured) = self.piece_on(dest) {
+                result.xor(captured, dest_bb, !self.side_to_move);
+            }
+        }
+
+        #[allow(deprecated)]
+        result.remove_their_castle_rights(
+            self.their_castle_rights()
+                .for_rook(!self.side_to_move, dest),
+        );
+
+        #[allow(deprecated)]
+        result.remove_my_castle_rights(if moved == Piece::King {
+            CastleRights::Both
+        } else {
+            self.my_castle_rights().for_rook(self.side_to_move, source)
+        });
 
         if moved == Piece::Pawn {
             if let Some(promotion) = m.get_promotion() {
                 result.xor(Piece::Pawn, dest_bb, self.side_to_move);
                 result.xor(promotion, dest_bb, self.side_to_move);
+            } else if source.get_rank() == self.side_to_move.to_second_rank()
+                && dest.get_rank() == self.side_to_move.double_push_rank()
+            {
+                if self.can_en_passant(dest, !self.side_to_move) {
+                    result.en_passant = Some(dest);
+                }
+            } else if Some(dest.ubackward(self.side_to_move)) == self.en_passant {
+                result.xor(
+                    Piece::Pawn,
+                    BitBoard::from_square(dest.ubackward(self.side_to_move)),
+                    !self.side_to_move,
+                );
             }
-        } else if castles {
-            let my_backrank = self.side_to_move.to_my_backrank();
-            let index = dest.get_file().to_index();
-            let start = BitBoard::set(my_backrank, unsafe {
-                *CASTLE_ROOK_START.get_unchecked(index)
-            });
-            let end = BitBoard::set(my_backrank, unsafe {
-                *CASTLE_ROOK_END.get_unchecked(index)
-            });
-            result.xor(Piece::Rook, start, self.side_to_move);
-            result.xor(Piece::Rook, end, self.side_to_move);
         }
-
+        
+        result.side_to_move = !result.side_to_move;
         result.update_check_info();
 
+        #[cfg(feature = "attack-info")]
+        result.update_attack_info(self);
+
+        #[cfg(test)]
+        debug_assert_eq!(result.get_hash(), result.recompute_hash(), "{} {}", self, m);
+        #[cfg(test)]
+        debug_assert!(result.threats_consistent(false), "{} {}", self, m);
+        result
+    }
+
+    /// Make a chess move onto an already allocated `Board`.
+    ///
+    /// panic!() if king is captured.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square, Color};
+    ///
+    /// let m = ChessMove::new(Square::D2,
+    ///                        Square::D4,
+    ///                        None);
+    ///
+    /// let board = Board::default();
+    /// let mut result = Board::default();
+    /// board.make_move(m, &mut result);
+    /// assert_eq!(result.side_to_move(), Color::Black);
+    /// ```
+    #[inline]
+    pub fn make_move(&self, m: ChessMove, result: &mut Board) {
+        *result = *self;
+        result.en_passant = None;
+        let source = m.get_source();
+        let dest = m.get_dest();
+
+        let source_bb = BitBoard::from_square(source);
+        let dest_bb = BitBoard::from_square(dest);
+        let moved = self.piece_on(source).unwrap();
+
+        // a castle may move my king onto my own rook, or not move it at all
+        let castles = if moved == Piece::King {
+            self.castle_rook(m)
+        } else {
+            None
+        };
+        if let Some(rook) = castles {
+            let (king_dest, rook_dest) = castle_squares(source, rook);
+            result.castled[self.side_to_move.to_index()] = castle_side(source, rook).into();
+            result.xor(Piece::King, source_bb, self.side_to_move);
+            result.xor(
+                Piece::King,
+                BitBoard::from_square(king_dest),
+                self.side_to_move,
+            );
+            result.xor(Piece::Rook, BitBoard::from_square(rook), self.side_to_move);
+            result.xor(
+                Piece::Rook,
+                BitBoard::from_square(rook_dest),
+                self.side_to_move,
+            );
+        } else {
+            result.xor(moved, source_bb, self.side_to_move);
+            result.xor(moved, dest_bb, self.side_to_move);
+            if let Some(captured) = self.piece_on(dest) {
+                result.xor(captured, dest_bb, !self.side_to_move);
+            }
+        }
+
+        #[allow(deprecated)]
+        result.remove_their_castle_rights(
+            self.their_castle_rights()
+                .for_rook(!self.side_to_move, dest),
+        );
+
+        #[allow(deprecated)]
+        result.remove_my_castle_rights(if moved == Piece::King {
+            CastleRights::Both
+        } else {
+            self.my_castle_rights().for_rook(self.side_to_move, source)
+        });
+
+        if moved == Piece::Pawn {
+            if let Some(promotion) = m.get_promotion() {
+                result.xor(Piece::Pawn, dest_bb, self.side_to_move);
+                result.xor(promotion, dest_bb, self.side_to_move);
+            } else if source.get_rank() == self.side_to_move.to_second_rank()
+                && dest.get_rank() == self.side_to_move.double_push_rank()
+            {
+                if self.can_en_passant(dest, !self.side_to_move) {
+                    result.en_passant = Some(dest);
+                }
+            } else if Some(dest.ubackward(self.side_to_move)) == self.en_passant {
+                result.xor(
+                    Piece::Pawn,
+                    BitBoard::from_square(dest.ubackward(self.side_to_move)),
+                    !self.side_to_move,
+                );
+            }
+        }
+
         result.side_to_move = !result.side_to_move;
+        result.update_check_info();
+
+        #[cfg(feature = "attack-info")]
+        result.update_attack_info(self);
+
+        #[cfg(test)]
+        debug_assert_eq!(result.get_hash(), result.recompute_hash(), "{} {}", self, m);
+        #[cfg(test)]
+        debug_assert!(result.threats_consistent(false), "{} {}", self, m);
+    }
+
+    /// Make a chess move onto a new board, and tell what it did: which piece moved, which piece it
+    /// took and from where, how the rook moved if it was a castle, and what a pawn promoted to.
+    /// `Board::make_move_new` is faster when that is not needed.
+    ///
+    /// panic!() if there is no piece on the source square of `m`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Piece, Square};
+    /// use std::str::FromStr;
+    ///
+    /// // en passant takes the pawn next to mine, not on the square I move to
+    /// let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard";
+    /// let board = Board::from_str(fen).expect("Valid FEN");
+    /// let (child, info) = board.make_move_with_info(ChessMove::new(Square::E5, Square::D6, None));
+    /// assert_eq!(child.piece_on(Square::D5), None);
+    /// assert_eq!(info.piece, Piece::Pawn);
+    /// assert_eq!(info.captured, Some((Piece::Pawn, Square::D5)));
+    /// assert_eq!(info.castle, None);
+    /// ```
+    pub fn make_move_with_info(&self, m: ChessMove) -> (Board, MoveInfo) {
+        let source = m.get_source();
+        let dest = m.get_dest();
+        let piece = self.piece_on(source).unwrap();
+        let mut info = MoveInfo {
+            piece,
+            dest,
+            captured: None,
+            castle: None,
+            promotion: m.get_promotion(),
+        };
+
+        let castles = if piece == Piece::King {
+            self.castle_rook(m)
+        } else {
+            None
+        };
+        if let Some(rook) = castles {
+            let (king_dest, rook_dest) = castle_squares(source, rook);
+            info.dest = king_dest;
+            info.castle = Some((rook, rook_dest));
+        } else if let Some(captured) = self.piece_on(dest) {
+            info.captured = Some((captured, dest));
+        } else if piece == Piece::Pawn && self.en_passant == Some(dest.ubackward(self.side_to_move))
+        {
+            info.captured = Some((Piece::Pawn, dest.ubackward(self.side_to_move)));
+        }
+        (self.make_move_new(m), info)
+    }
+
+    /// Make a chess move onto a new board, and tell `accumulator` each piece it takes off a
+    /// square and then each piece it puts on one, see `Accumulator`.
+    ///
+    /// panic!() if there is no piece on the source square of `m`.
+    ///
+    /// ```
+    /// use chess::{Accumulator, Board, ChessMove, Color, Piece, Square};
+    /// use std::str::FromStr;
+    ///
+    /// #[derive(Default)]
+    /// struct Changes(Vec<(bool, Piece, Square)>);
+    ///
+    /// impl Accumulator for Changes {
+    ///     fn add(&mut self, piece: Piece, _: Color, square: Square) {
+    ///         self.0.push((true, piece, square));
+    ///     }
+    ///
+    ///     fn remove(&mut self, piece: Piece, _: Color, square: Square) {
+    ///         self.0.push((false, piece, square));
+    ///     }
+    /// }
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/8/8/4K2R w K - 0 1").expect("Valid FEN");
+    /// let mut changes = Changes::default();
+    /// board.make_move_with_accumulator(ChessMove::new(Square::E1, Square::G1, None), &mut changes);
+    /// assert_eq!(
+    ///     changes.0,
+    ///     vec![
+    ///         (false, Piece::King, Square::E1),
+    ///         (false, Piece::Rook, Square::H1),
+    ///         (true, Piece::King, Square::G1),
+    ///         (true, Piece::Rook, Square::F1),
+    ///     ]
+    /// );
+    /// ```
+    pub fn make_move_with_accumulator(
+        &self,
+        m: ChessMove,
+        accumulator: &mut impl Accumulator,
+    ) -> Board {
+        let (result, info) = self.make_move_with_info(m);
+        let color = self.side_to_move;
+
+        // a castle in Chess960 may put the king where its rook was, so every piece is taken off
+        // before any is put on
+        accumulator.remove(info.piece, color, m.get_source());
+        if let Some((captured, square)) = info.captured {
+            accumulator.remove(captured, !color, square);
+        }
+        if let Some((rook, _)) = info.castle {
+            accumulator.remove(Piece::Rook, color, rook);
+        }
+        accumulator.add(info.promotion.unwrap_or(info.piece), color, info.dest);
+        if let Some((_, rook)) = info.castle {
+            accumulator.add(Piece::Rook, color, rook);
+        }
+        result
+    }
+
+    /// Make each of `moves`, written in `format`, in turn, checking that each is legal in the
+    /// position the ones before it led to, and give me the position after the last one.  If one
+    /// cannot be read or made, give me where it is, the position it was made in and why.
+    ///
+    /// ```
+    /// use chess::{Board, MoveFormat};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::default()
+    ///     .apply_moves(["e4", "e5", "Nf3"].iter().copied(), MoveFormat::San)
+    ///     .unwrap();
+    /// let expected = "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2";
+    /// assert_eq!(board, Board::from_str(expected).unwrap());
+    ///
+    /// let error = board
+    ///     .apply_moves(["d7d6", "f3f5"].iter().copied(), MoveFormat::Coordinate)
+    ///     .unwrap_err();
+    /// assert_eq!(error.index, 1);
+    /// assert_eq!(error.text, "f3f5");
+    /// ```
+    pub fn apply_moves<'a>(
+        &self,
+        moves: impl IntoIterator<Item = &'a str>,
+        format: MoveFormat,
+    ) -> Result<Board, ApplyError> {
+        let mut board = *self;
+        for (index, text) in moves.into_iter().enumerate() {
+            let m = match format {
+                MoveFormat::Coordinate => {
+                    ChessMove::from_coordinates(&board, text).and_then(|m| board.legal_or_error(m))
+                }
+                MoveFormat::San => ChessMove::from_san(&board, text),
+            };
+            match m {
+                Ok(m) => board = board.make_move_new(m),
+                Err(error) => return Err(board.apply_error(index, text.to_string(), error)),
+            }
+        }
+        Ok(board)
+    }
+
+    /// Make each of `moves` in turn, like `apply_moves`, with the `text` of an error being the
+    /// move in coordinate notation.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// let moves = [
+    ///     ChessMove::new(Square::E2, Square::E4, None),
+    ///     ChessMove::new(Square::E2, Square::E4, None),
+    /// ];
+    /// let error = Board::default().apply_chess_moves(&moves).unwrap_err();
+    /// assert_eq!(error.index, 1);
+    /// assert_eq!(error.text, "e2e4");
+    /// ```
+    pub fn apply_chess_moves(&self, moves: &[ChessMove]) -> Result<Board, ApplyError> {
+        let mut board = *self;
+        for (index, m) in moves.iter().enumerate() {
+            match board.legal_or_error(*m) {
+                Ok(m) => board = board.make_move_new(m),
+                Err(error) => return Err(board.apply_error(index, m.to_string(), error)),
+            }
+        }
+        Ok(board)
+    }
+
+    /// Read a line of SAN moves, such as the moves of a PGN game, each made in turn from this
+    /// position.  Move numbers like "1." and "12...", and a result at the end, are skipped.  A
+    /// move that cannot be read or made gives `Error::InvalidSanMove`, with the span of the move
+    /// in `line`.
+    ///
+    /// ```
+    /// use chess::{Board, Error, Span};
+    ///
+    /// let moves = Board::default().parse_line("1. e4 e5 2. Nf3 Nc6 1-0").unwrap();
+    /// assert_eq!(moves.len(), 4);
+    ///
+    /// let error = Board::default().parse_line("1. e4 e5 2. Ke3").unwrap_err();
+    /// assert_eq!(error.span(), Some(Span { start: 12, end: 15 }));
+    /// ```
+    pub fn parse_line(&self, line: &str) -> Result<Vec<ChessMove>, Error> {
+        let mut board = *self;
+        let mut moves = Vec::new();
+        let mut start = None;
+        // a space after the end ends the last move
+        let end = core::iter::once((line.len(), ' '));
+        for (i, c) in line.char_indices().chain(end) {
+            match (start, c.is_whitespace()) {
+                (None, false) => start = Some(i),
+                (Some(token_start), true) => {
+                    start = None;
+                    let token = &line[token_start..i];
+                    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&token) {
+                        continue;
+                    }
+                    // a move number, which may have the move right after it
+                    let digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+                    let san = match digits.trim_start_matches('.') {
+                        san if san.len() < digits.len() => san,
+                        _ => token,
+                    };
+                    if san.is_empty() {
+                        continue;
+                    }
+                    let offset = token_start + token.len() - san.len();
+                    let m = ChessMove::from_san(&board, san).map_err(|error| match error {
+                        Error::InvalidSanMove { span } => Error::InvalidSanMove {
+                            span: span.shift(offset),
+                        },
+                        error => error,
+                    })?;
+                    board = board.make_move_new(m);
+                    moves.push(m);
+                }
+                _ => {}
+            }
+        }
+        Ok(moves)
+    }
+
+    /// Read a line of an EPD file, such as one of a tactics suite: the position, the moves of its
+    /// `bm` operation, which are empty if it has none, and the moves of its `am` operation, if it
+    /// has one.  The moves are SAN, read in the position.  A `variant` operation, such as
+    /// `variant standard;`, picks the rules, which are otherwise the default `Variant`, as in a
+    /// FEN without a tag.  Other operations, such as `id`, are skipped.
+    ///
+    /// Text that is not EPD gives `Error::InvalidEpd`, and a move that cannot be read or made
+    /// `Error::InvalidSanMove`, each with the span in `line`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let line = "4k3/8/8/8/8/8/4P3/4K3 w - - bm e4 e3; am Kd1; id \"pawn; push\";";
+    /// let (board, best, avoid) = Board::from_epd(line).unwrap();
+    /// assert_eq!(board, Board::from_str("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap());
+    /// assert_eq!(best[0], ChessMove::new(Square::E2, Square::E4, None));
+    /// assert_eq!(best.len(), 2);
+    /// assert_eq!(avoid, Some(vec![ChessMove::new(Square::E1, Square::D1, None)]));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn from_epd(line: &str) -> Result<(Board, Vec<ChessMove>, Option<Vec<ChessMove>>), Error> {
+        // the four fields of the position, each with where it starts in `line` and in the FEN
+        let mut fen = String::new();
+        let mut fields = [(0, 0); 4];
+        let mut fields_end = 0;
+        for field in fields.iter_mut() {
+            let start = line.len() - line[fields_end..].trim_start().len();
+            let rest = &line[start..];
+            let end = start + rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if end == start {
+                return Err(Error::InvalidEpd {
+                    span: Span::of(line),
+                });
+            }
+            *field = (start, fen.len());
+            fen.push_str(&line[start..end]);
+            fen.push(' ');
+            fields_end = end;
+        }
+        fen.push_str("0 1");
+        // a FEN error is in one of the fields, so move it to where that field is in `line`
+        let mut builder = BoardBuilder::from_str(&fen).map_err(|error| match error {
+            Error::InvalidFen { span, .. } => {
+                let (start, in_fen) = fields.iter().rev().find(|(_, f)| *f <= span.start).unwrap();
+                Error::InvalidFen {
+                    fen: line.to_string(),
+                    span: Span {
+                        start: span.start - in_fen,
+                        end: span.end - in_fen,
+                    }
+                    .shift(*start),
+                }
+            }
+            error => error,
+        })?;
+
+        let operations = epd_operations(line, fields_end)?;
+        for operation in operations.iter() {
+            if let [(_, "variant"), (start, variant)] = operation[..] {
+                let variant = Variant::from_str(variant).map_err(|_| Error::InvalidEpd {
+                    span: Span::of(variant).shift(start),
+                })?;
+                builder.variant(variant);
+            }
+        }
+        let board = Board::try_from(&builder)?;
+
+        let moves = |opcode: &str| -> Result<Option<Vec<ChessMove>>, Error> {
+            let operation = match operations.iter().find(|op| op[0].1 == opcode) {
+                Some(operation) => operation,
+                None => return Ok(None),
+            };
+            let mut moves = Vec::new();
+            for (offset, san) in operation[1..].iter() {
+                let m = ChessMove::from_san(&board, san).map_err(|error| match error {
+                    Error::InvalidSanMove { span } => Error::InvalidSanMove {
+                        span: span.shift(*offset),
+                    },
+                    error => error,
+                })?;
+                moves.push(m);
+            }
+            Ok(Some(moves))
+        };
+        let best = moves("bm")?.unwrap_or_default();
+        let avoid = moves("am")?;
+        Ok((board, best, avoid))
+    }
+
+    fn legal_or_error(&self, m: ChessMove) -> Result<ChessMove, Error> {
+        if self.legal(m) {
+            Ok(m)
+        } else {
+            Err(Error::IllegalMove)
+        }
+    }
+
+    fn apply_error(&self, index: usize, text: String, error: Error) -> ApplyError {
+        ApplyError {
+            index,
+            text,
+            fen: self.to_tagged_fen(),
+            error,
+        }
+    }
+
+    /// Get the hash of the board `m` would lead to, without making the move.  This is the same as
+    /// `self.make_move_new(m).get_hash()`, but only touches the hash, so a search can prefetch
+    /// the transposition table entry for a child before it makes the move.
+    ///
+    /// panic!() if there is no piece on the source square of `m`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// let m = ChessMove::new(Square::E2, Square::E4, None);
+    ///
+    /// let board = Board::default();
+    /// assert_eq!(board.hash_after(m), board.make_move_new(m).get_hash());
+    /// ```
+    #[inline]
+    pub fn hash_after(&self, m: ChessMove) -> u64 {
+        let source = m.get_source();
+        let dest = m.get_dest();
+        let us = self.side_to_move;
+        let moved = self.piece_on(source).unwrap();
+
+        // the same test as make_move for castling
+        let castles = if moved == Piece::King {
+            self.castle_rook(m)
+        } else {
+            None
+        };
+        let mut hash = self.get_hash() ^ Zobrist::color();
+        if let Some(rook) = castles {
+            let (king_dest, rook_dest) = castle_squares(source, rook);
+            hash ^= Zobrist::piece(Piece::King, source, us)
+                ^ Zobrist::piece(Piece::King, king_dest, us)
+                ^ Zobrist::piece(Piece::Rook, rook, us)
+                ^ Zobrist::piece(Piece::Rook, rook_dest, us);
+        } else {
+            hash ^= Zobrist::piece(moved, source, us)
+                ^ Zobrist::piece(m.get_promotion().unwrap_or(moved), dest, us);
+            if let Some(captured) = self.piece_on(dest) {
+                hash ^= Zobrist::piece(captured, dest, !us);
+            }
+        }
+
+        // the same tests as make_move for en passant
+        if let Some(ep) = self.en_passant {
+            hash ^= Zobrist::en_passant(ep.get_file(), !us);
+        }
+        if moved == Piece::Pawn && m.get_promotion().is_none() {
+            let double_push = dest.get_rank() == us.double_push_rank();
+            if source.get_rank() == us.to_second_rank() && double_push {
+                if self.can_en_passant(dest, !us) {
+                    hash ^= Zobrist::en_passant(dest.get_file(), us);
+                }
+            } else if Some(dest.ubackward(us)) == self.en_passant {
+                hash ^= Zobrist::piece(Piece::Pawn, dest.ubackward(us), !us);
+            }
+        }
+
+        let my_rights = self.my_castle_rights();
+        let their_rights = self.their_castle_rights();
+        let my_lost = if moved == Piece::King {
+            CastleRights::Both
+        } else {
+            my_rights.for_rook(us, source)
+        };
+        hash ^ Zobrist::castles(my_rights, us)
+            ^ Zobrist::castles(my_rights.remove(my_lost), us)
+            ^ Zobrist::castles(their_rights, !us)
+            ^ Zobrist::castles(their_rights.remove(their_rights.for_rook(!us, dest)), !us)
+    }
+
+    /// Get the hash of the board after `null_move`, without making it.  Passing only changes the
+    /// side to move and clears the en passant square, and their keys with it.
+    ///
+    /// ```
+    /// use chess::{Board, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1 variant:standard")
+    ///     .expect("Valid FEN");
+    /// assert_eq!(board.en_passant(), Some(Square::E4));
+    /// let passed = board.null_move().expect("Valid Position");
+    /// assert_eq!(passed.en_passant(), None);
+    /// assert_eq!(board.hash_after_null(), passed.get_hash());
+    /// ```
+    #[inline]
+    pub fn hash_after_null(&self) -> u64 {
+        let hash = self.get_hash() ^ Zobrist::color();
+        match self.en_passant {
+            Some(ep) => hash ^ Zobrist::en_passant(ep.get_file(), !self.side_to_move),
+            None => hash,
+        }
     }
 
-    /// Update the check information.
+    /// Update the check and pin information for the side to move.
     fn update_check_info(&mut self) {
         self.checkers = EMPTY;
+        self.pinned = EMPTY;
+
+        let ksq = self.king_square(self.side_to_move);
+        let their_ksq = self.king_square(!self.side_to_move);
+        let them = *self.color_combined(!self.side_to_move);
+
+        match self.variant {
+            Variant::Uno => {
+                self.checkers |= get_king_moves(ksq) & them;
+
+                self.check_squares = [get_king_moves(their_ksq); NUM_PIECES];
+            }
+            Variant::Standard => {
+                let bishops = (self.pieces(Piece::Bishop) | self.pieces(Piece::Queen)) & them;
+                let rooks = (self.pieces(Piece::Rook) | self.pieces(Piece::Queen)) & them;
+                let mine = *self.color_combined(self.side_to_move);
+                self.checkers |= (get_bishop_moves(ksq, self.combined) & bishops)
+                    | (get_rook_moves(ksq, self.combined) & rooks);
+                // a slider that reaches my king once one of my pieces is out of the way pins it
+                let pinners = (xray_bishop_attacks(ksq, self.combined, mine) & bishops)
+                    | (xray_rook_attacks(ksq, self.combined, mine) & rooks);
+                for sq in pinners {
+                    self.pinned |= between(sq, ksq) & mine;
+                }
+                self.checkers |= get_knight_moves(ksq) & self.pieces(Piece::Knight) & them;
+                // only after an illegal move, but then it is true to the capture rules
+                self.checkers |= get_king_moves(ksq) & self.pieces(Piece::King) & them;
+                self.checkers |=
+                    get_pawn_attacks(ksq, self.side_to_move, self.pieces(Piece::Pawn) & them);
+
+                let bishop = get_bishop_moves(their_ksq, self.combined);
+                let rook = get_rook_moves(their_ksq, self.combined);
+                self.check_squares = [
+                    get_pawn_attacks(their_ksq, !self.side_to_move, !EMPTY),
+                    get_knight_moves(their_ksq),
+                    bishop,
+                    rook,
+                    bishop | rook,
+                    EMPTY,
+                ];
+            }
+        }
+    }
+
+    /// Can a pawn of `color` capture a pawn that just moved two squares to `sq` en passant?
+    fn can_en_passant(&self, sq: Square, color: Color) -> bool {
+        self.variant == Variant::Standard
+            && get_adjacent_files(sq.get_file())
+                & get_rank(sq.get_rank())
+                & self.pieces(Piece::Pawn)
+                & self.color_combined(color)
+                != EMPTY
+    }
+
+    /// Give me the squares a `piece` of the side to move would give check from, not counting
+    /// discovered checks.  This is worked out once per position, as moves are made.
+    ///
+    /// Only a piece next to the king gives check in `Variant::Uno`, so there this is the ring
+    /// around the opponents king for every kind of piece.
+    ///
+    /// ```
+    /// use chess::{get_king_moves, Board, Color, Piece};
+    ///
+    /// let board = Board::default();
+    /// assert_eq!(
+    ///     board.check_squares(Piece::Knight),
+    ///     get_king_moves(board.king_square(Color::Black))
+    /// );
+    /// ```
+    #[inline]
+    pub fn check_squares(&self, piece: Piece) -> BitBoard {
+        unsafe { *self.check_squares.get_unchecked(piece.to_index()) }
+    }
+
+    /// Would the legal move `m` put the opponent in check?  This is the same as
+    /// `self.make_move_new(m).checkers() != &EMPTY`, without making the move.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/8/8/3RK3 w - - 0 1").expect("Valid FEN");
+    /// assert!(board.gives_check(ChessMove::new(Square::D1, Square::D7, None)));
+    /// assert!(!board.gives_check(ChessMove::new(Square::D1, Square::D6, None)));
+    /// ```
+    #[inline]
+    pub fn gives_check(&self, m: ChessMove) -> bool {
+        let source = m.get_source();
+        let source_bb = BitBoard::from_square(source);
+        let dest_bb = BitBoard::from_square(m.get_dest());
+        let moved = self.piece_on(source).unwrap();
+
+        // castling is rare enough to just make the move, and the rook does not land on the
+        // destination of the move
+        if moved == Piece::King && self.castle_rook(m).is_some() {
+            return *self.make_move_new(m).checkers() != EMPTY;
+        }
+
+        match self.variant {
+            Variant::Uno => {
+                self.check_squares(m.get_promotion().unwrap_or(moved)) & dest_bb != EMPTY
+            }
+            Variant::Standard => {
+                // so are promotions and en passant
+                let en_passant = moved == Piece::Pawn
+                    && self.combined & dest_bb == EMPTY
+                    && source.get_file() != m.get_dest().get_file();
+                if m.get_promotion().is_some() || en_passant {
+                    return *self.make_move_new(m).checkers() != EMPTY;
+                }
+
+                if self.check_squares(moved) & dest_bb != EMPTY {
+                    return true;
+                }
+
+                // otherwise only a discovered check, by a slider the moved piece was blocking
+                let their_ksq = self.king_square(!self.side_to_move);
+                let combined = (self.combined ^ source_bb) | dest_bb;
+                let mine = self.color_combined(self.side_to_move) & !source_bb;
+                let bishops = (self.pieces(Piece::Bishop) | self.pieces(Piece::Queen)) & mine;
+                let rooks = (self.pieces(Piece::Rook) | self.pieces(Piece::Queen)) & mine;
+                (get_bishop_moves(their_ksq, combined) & bishops)
+                    | (get_rook_moves(their_ksq, combined) & rooks)
+                    != EMPTY
+            }
+        }
+    }
+
+    /// Give me the pieces of `color` that would give a discovered check by moving off the line
+    /// between one of their own sliders and the enemy king.
+    ///
+    /// This is always empty in `Variant::Uno`.  There only a piece next to the king gives check,
+    /// and moving one piece out of the way never brings another piece next to the king.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, BoardBuilder, Color, Square, Variant, EMPTY};
+    /// use std::convert::TryInto;
+    /// use std::str::FromStr;
+    ///
+    /// // the knight on e4 stands between the bishop on b1 and the king on h7
+    /// let fen = "8/7k/8/8/4N3/8/8/1B2K3 w - - 0 1";
+    /// let mut builder = BoardBuilder::from_str(fen).expect("Valid FEN");
+    /// let board: Board = builder.variant(Variant::Standard).try_into().expect("Valid Position");
+    /// assert_eq!(
+    ///     board.discovered_check_candidates(Color::White),
+    ///     BitBoard::from_square(Square::E4)
+    /// );
+    ///
+    /// let board = Board::from_str(fen).expect("Valid FEN");
+    /// assert_eq!(board.discovered_check_candidates(Color::White), EMPTY);
+    /// ```
+    pub fn discovered_check_candidates(&self, color: Color) -> BitBoard {
+        let mut candidates = EMPTY;
+        if self.variant == Variant::Uno {
+            return candidates;
+        }
+
+        let their_ksq = self.king_square(!color);
+        let mine = *self.color_combined(color);
+        let bishops = self.pieces(Piece::Bishop) | self.pieces(Piece::Queen);
+        let rooks = self.pieces(Piece::Rook) | self.pieces(Piece::Queen);
+        let sliders =
+            mine & ((get_bishop_rays(their_ksq) & bishops) | (get_rook_rays(their_ksq) & rooks));
+        for sq in sliders {
+            let between = between(sq, their_ksq) & self.combined;
+            if between.popcnt() == 1 {
+                candidates |= between & mine;
+            }
+        }
+        candidates
+    }
+
+    /// Give me the pieces of the other side that could give check to the king of `color` with one
+    /// move, if it were their turn: by moving to one of the squares their kind attacks the king
+    /// from, or, in normal chess, by moving out of the way of a slider, see
+    /// `discovered_check_candidates`.  A pawn that promotes counts as a knight or a queen.
+    ///
+    /// Pins and checks are not taken into account, so this can be asked of the side to move, to
+    /// see what would attack my king if I passed.  Under the uno rules these are the pieces that
+    /// can move next to the king.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, Color, Square, EMPTY};
+    /// use std::str::FromStr;
+    ///
+    /// // the rook on a2 may step next to my king, the bishop on a8 may not
+    /// let board = Board::from_str("b3k3/8/8/8/8/8/r7/4K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.king_threats(Color::White), BitBoard::from_square(Square::A2));
+    /// assert_eq!(board.king_threats(Color::Black), EMPTY);
+    /// ```
+    pub fn king_threats(&self, color: Color) -> BitBoard {
+        let ksq = self.king_square(color);
+        let them = !color;
+        let check_squares = |piece: Piece| match self.variant {
+            Variant::Uno => get_king_moves(ksq),
+            Variant::Standard => match piece {
+                Piece::Pawn => get_pawn_attacks(ksq, color, !EMPTY),
+                Piece::King => EMPTY,
+                _ => attacks_of(piece, ksq, color, self.combined),
+            },
+        };
+        let promotion = them.promotion_rank();
+        let discovers = self.discovered_check_candidates(them);
+
+        let mut threats = EMPTY;
+        for src in self.color_combined(them) & !self.pieces(Piece::King) {
+            let piece = self.piece_on(src).unwrap();
+            let quiets = match piece {
+                Piece::Pawn => get_pawn_quiets(src, them, self.combined),
+                _ => attacks_of(piece, src, them, self.combined) & !self.combined,
+            };
+            let dests = quiets | self.rule_capture_targets(src, piece, them);
+
+            let mut checks = dests & check_squares(piece);
+            if piece == Piece::Pawn {
+                checks |= dests
+                    & get_rank(promotion)
+                    & (check_squares(Piece::Knight) | check_squares(Piece::Queen));
+            }
+            if discovers & BitBoard::from_square(src) != EMPTY {
+                checks |= dests & !line(src, ksq);
+            }
+            if checks != EMPTY {
+                threats |= BitBoard::from_square(src);
+            }
+        }
+        threats
+    }
+
+    /// Give me the `Bitboard` of the pieces putting me in check.
+    #[inline]
+    pub fn checkers(&self) -> &BitBoard {
+        &self.checkers
+    }
+
+    /// Give me the `BitBoard` of my pieces that are pinned to my king.  Nothing is ever pinned in
+    /// `Variant::Uno`, where only a piece next to the king gives check.
+    #[inline]
+    pub fn pinned(&self) -> &BitBoard {
+        &self.pinned
+    }
+
+    /// Update the attack information after the move from `before`.
+    #[cfg(feature = "attack-info")]
+    #[inline]
+    fn update_attack_info(&mut self, before: &Board) {
+        let mut attack_info = self.attack_info;
+        attack_info.update(before, self);
+        self.attack_info = attack_info;
+    }
+
+    /// Give me the squares each side attacks.  This is kept up to date as moves are made, so it
+    /// costs nothing to call.
+    #[cfg(feature = "attack-info")]
+    #[inline]
+    pub fn attack_info(&self) -> &AttackInfo {
+        &self.attack_info
+    }
+
+    /// Give me the sliding pieces of `color` that line up with `square` behind exactly one other
+    /// piece of either color.  These are the pieces that reach `square` once the piece in front
+    /// of them moves away.
+    ///
+    /// ```
+    /// use chess::{Board, BitBoard, Color, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/q7/8/8/P7/8/R3K3 w - - 0 1").expect("Valid FEN");
+    ///
+    /// assert_eq!(
+    ///     board.xray_attackers_to(Square::A1, Color::Black),
+    ///     BitBoard::from_square(Square::A6)
+    /// );
+    /// ```
+    #[inline]
+    pub fn xray_attackers_to(&self, square: Square, color: Color) -> BitBoard {
+        let rooks =
+            (self.pieces(Piece::Rook) | self.pieces(Piece::Queen)) & self.color_combined(color);
+        let bishops =
+            (self.pieces(Piece::Bishop) | self.pieces(Piece::Queen)) & self.color_combined(color);
+
+        (xray_rook_attacks(square, self.combined, self.combined) & rooks)
+            | (xray_bishop_attacks(square, self.combined, self.combined) & bishops)
+    }
+
+    /// Give me the pieces of `color` that attack `square`, with `combined` blocking the sliders.
+    /// These are the pieces that could capture an enemy king standing on `square`, which is what
+    /// the move generator checks to keep my king safe.
+    ///
+    /// In `Variant::Uno` a piece may only capture a piece that attacks it, and a king only attacks
+    /// the squares next to it, so these are the pieces of `color` next to `square`.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, BoardBuilder, Color, Square, Variant, EMPTY};
+    /// use std::convert::TryInto;
+    /// use std::str::FromStr;
+    ///
+    /// let fen = "4k3/8/8/8/8/8/2n5/r3K3 w - - 0 1";
+    ///
+    /// // next to d1, the knight on c2 is the only danger under the uno rules
+    /// let board = Board::from_str(fen).expect("Valid FEN");
+    /// let attackers = board.attackers_to(Square::D1, Color::Black, *board.combined());
+    /// assert_eq!(attackers, BitBoard::from_square(Square::C2));
+    /// assert!(board.is_attacked(Square::D1, Color::Black));
+    /// assert!(!board.is_attacked(Square::F1, Color::Black));
+    ///
+    /// // in normal chess the rook on a1 attacks d1 and the knight does not
+    /// let mut builder = BoardBuilder::from_str(fen).expect("Valid FEN");
+    /// let board: Board = builder.variant(Variant::Standard).try_into().expect("Valid Position");
+    /// let attackers = board.attackers_to(Square::D1, Color::Black, *board.combined());
+    /// assert_eq!(attackers, BitBoard::from_square(Square::A1));
+    /// assert_eq!(board.attackers_to(Square::F1, Color::Black, *board.combined()), EMPTY);
+    /// ```
+    pub fn attackers_to(&self, square: Square, color: Color, combined: BitBoard) -> BitBoard {
+        let them = *self.color_combined(color);
+        match self.variant {
+            Variant::Uno => get_king_moves(square) & them,
+            Variant::Standard => {
+                let bishops = (self.pieces(Piece::Bishop) | self.pieces(Piece::Queen)) & them;
+                let rooks = (self.pieces(Piece::Rook) | self.pieces(Piece::Queen)) & them;
+                (get_knight_moves(square) & self.pieces(Piece::Knight) & them)
+                    | get_pawn_attacks(square, !color, self.pieces(Piece::Pawn) & them)
+                    | (get_bishop_moves(square, combined) & bishops)
+                    | (get_rook_moves(square, combined) & rooks)
+                    | (get_king_moves(square) & self.pieces(Piece::King) & them)
+            }
+        }
+    }
+
+    /// Give me the squares the piece on `sq` attacks, by how it moves, with the pieces on the
+    /// board blocking the sliders, or `EMPTY` if there is no piece there.
+    ///
+    /// A pawn attacks the two squares diagonally in front of it, whatever stands on them.  These
+    /// are not the captures of `Variant::Uno`, where a piece may only take a piece that attacks
+    /// it, see `attackers_to`.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, Square, EMPTY};
+    ///
+    /// let board = Board::default();
+    /// let rook = BitBoard::from_square(Square::A2) | BitBoard::from_square(Square::B1);
+    /// assert_eq!(board.attacks_from(Square::A1), rook);
+    /// assert_eq!(board.attacks_from(Square::E2).popcnt(), 2);
+    /// assert_eq!(board.attacks_from(Square::E4), EMPTY);
+    /// ```
+    pub fn attacks_from(&self, sq: Square) -> BitBoard {
+        match (self.piece_on(sq), self.color_on(sq)) {
+            (Some(piece), Some(color)) => attacks_of(piece, sq, color, self.combined),
+            _ => EMPTY,
+        }
+    }
+
+    /// Give me the empty squares the piece on `sq` can move to without capturing, or `EMPTY` if
+    /// there is no piece there.  For a pawn these are its pushes, and for any other piece the
+    /// empty squares it attacks.  Castling, and whether a move leaves my king safe, are up to
+    /// `MoveGen`.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, Square, EMPTY};
+    ///
+    /// let board = Board::default();
+    /// let pushes = BitBoard::from_square(Square::E3) | BitBoard::from_square(Square::E4);
+    /// assert_eq!(board.moves_from(Square::E2), pushes);
+    /// assert_eq!(board.moves_from(Square::A1), EMPTY);
+    /// ```
+    pub fn moves_from(&self, sq: Square) -> BitBoard {
+        match (self.piece_on(sq), self.color_on(sq)) {
+            (Some(Piece::Pawn), Some(color)) => get_pawn_quiets(sq, color, self.combined),
+            (Some(_), Some(_)) => self.attacks_from(sq) & !self.combined,
+            _ => EMPTY,
+        }
+    }
+
+    /// Score the attack on the king of `defender`, in the classic way of attack units.  Each
+    /// piece of the other side that attacks the king zone, the king's square and the squares next
+    /// to it, adds the `KING_ATTACK_WEIGHTS` of its type once.  Turning the units into
+    /// centipawns, usually through a table that grows faster than the units do, is left to the
+    /// caller.
+    ///
+    /// Gives the units and the squares of the zone that are attacked.  A square is attacked as in
+    /// `attackers_to`, so under the uno rules only pieces next to the zone count.
+    ///
+    /// ```
+    /// use chess::{Board, Color, EMPTY};
+    ///
+    /// assert_eq!(Board::default().king_attack_units(Color::Black), (0, EMPTY));
+    /// ```
+    pub fn king_attack_units(&self, defender: Color) -> (u32, BitBoard) {
+        let king = self.king_square(defender);
+        let zone = get_king_moves(king) | BitBoard::from_square(king);
+        let mut attackers = EMPTY;
+        let mut attacked = EMPTY;
+        for square in zone {
+            let here = self.attackers_to(square, !defender, self.combined);
+            if here != EMPTY {
+                attackers |= here;
+                attacked |= BitBoard::from_square(square);
+            }
+        }
+        let units = ALL_PIECES
+            .iter()
+            .map(|piece| {
+                KING_ATTACK_WEIGHTS[piece.to_index()] * (attackers & self.pieces(*piece)).popcnt()
+            })
+            .sum();
+        (units, attacked)
+    }
+
+    /// Is `square` attacked by a piece of `color`?  This is the same as
+    /// `self.attackers_to(square, color, *self.combined()) != EMPTY`, but stops looking at the
+    /// first attacker it finds.
+    ///
+    /// ```
+    /// use chess::{Board, Color, Square};
+    ///
+    /// // under the uno rules, e6 is next to the black pawns
+    /// let board = Board::default();
+    /// assert!(board.is_attacked(Square::E6, Color::Black));
+    /// assert!(!board.is_attacked(Square::E5, Color::Black));
+    /// ```
+    #[inline]
+    pub fn is_attacked(&self, square: Square, color: Color) -> bool {
+        self.is_attacked_with(square, color, self.combined)
+    }
+
+    // `Board::is_attacked`, with `combined` blocking the sliders.
+    #[inline]
+    pub(crate) fn is_attacked_with(
+        &self,
+        square: Square,
+        color: Color,
+        combined: BitBoard,
+    ) -> bool {
+        let them = *self.color_combined(color);
+        match self.variant {
+            Variant::Uno => get_king_moves(square) & them != EMPTY,
+            Variant::Standard => {
+                if get_knight_moves(square) & self.pieces(Piece::Knight) & them != EMPTY
+                    || get_pawn_attacks(square, !color, self.pieces(Piece::Pawn) & them) != EMPTY
+                {
+                    return true;
+                }
+                let bishops = (self.pieces(Piece::Bishop) | self.pieces(Piece::Queen)) & them;
+                if get_bishop_moves(square, combined) & bishops != EMPTY {
+                    return true;
+                }
+                let rooks = (self.pieces(Piece::Rook) | self.pieces(Piece::Queen)) & them;
+                get_rook_moves(square, combined) & rooks != EMPTY
+                    || get_king_moves(square) & self.pieces(Piece::King) & them != EMPTY
+            }
+        }
+    }
+
+    /// Give me the least valuable piece of `color` that could capture on `square`, counting only
+    /// the pieces on `occupied`, which also block the sliders.  Pieces are tried in the order
+    /// pawn, knight, bishop, rook, queen, king.  Take the square it returns off `occupied` to find
+    /// the next one.
+    ///
+    /// In `Variant::Uno` a piece may only capture a piece that attacks it, so this depends on the
+    /// piece standing on `square`, and is `None` if that square is empty or holds a piece of
+    /// `color`.  In an exchange the capturing pieces take turns on `square`, which
+    /// `Board::see` keeps track of.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, Color, Piece, Square};
+    /// use std::str::FromStr;
+    ///
+    /// // the knight on d5 attacks the rook on f4 and the pawn on e3
+    /// let board = Board::from_str("4k3/8/8/3n4/5R2/4P3/8/4K3 b - - 0 1").expect("Valid FEN");
+    /// let mut occupied = *board.combined();
+    ///
+    /// let lva = board.least_valuable_attacker(Square::D5, Color::White, occupied);
+    /// assert_eq!(lva, Some((Square::E3, Piece::Pawn)));
+    /// occupied ^= BitBoard::from_square(Square::E3);
+    /// let lva = board.least_valuable_attacker(Square::D5, Color::White, occupied);
+    /// assert_eq!(lva, Some((Square::F4, Piece::Rook)));
+    /// occupied ^= BitBoard::from_square(Square::F4);
+    /// assert_eq!(board.least_valuable_attacker(Square::D5, Color::White, occupied), None);
+    /// ```
+    pub fn least_valuable_attacker(
+        &self,
+        square: Square,
+        color: Color,
+        occupied: BitBoard,
+    ) -> Option<(Square, Piece)> {
+        let target = match (self.piece_on(square), self.color_on(square)) {
+            (Some(piece), Some(target_color)) => Some((piece, target_color)),
+            _ => None,
+        };
+        self.least_valuable_of(self.capturers(square, target, color, occupied))
+    }
+
+    /// Give me the pieces of `color` that could capture back on `square` if the piece there were
+    /// taken.  This is `attackers_to` from the other side: the pieces that defend `square`.
+    ///
+    /// In `Variant::Uno` a piece may only capture a piece that attacks it, so who can capture
+    /// back depends on the piece that took.  This counts the defenders against a piece of the
+    /// other side of the same kind as the one on `square`, as if it had been taken by its like,
+    /// and an empty square has no defenders.  In normal chess the kind makes no difference.
+    /// `Board::hanging_pieces` looks at each piece that could take instead.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, BoardBuilder, Color, Square, Variant, EMPTY};
+    /// use std::convert::TryInto;
+    /// use std::str::FromStr;
+    ///
+    /// // the knights defend each other
+    /// let fen = "4k3/8/8/8/4N3/2N5/8/4K3 w - - 0 1";
+    /// let board = Board::from_str(fen).expect("Valid FEN");
+    /// assert_eq!(board.defenders_of(Square::C3, Color::White), BitBoard::from_square(Square::E4));
+    /// assert_eq!(board.defenders_of(Square::D5, Color::White), EMPTY);
+    ///
+    /// let mut builder = BoardBuilder::from_str(fen).expect("Valid FEN");
+    /// let board: Board = builder.variant(Variant::Standard).try_into().expect("Valid Position");
+    /// assert_eq!(board.defenders_of(Square::D5, Color::White), BitBoard::from_square(Square::C3));
+    /// ```
+    pub fn defenders_of(&self, square: Square, color: Color) -> BitBoard {
+        let target = self.piece_on(square).map(|piece| (piece, !color));
+        match (self.variant, target) {
+            (Variant::Uno, None) => EMPTY,
+            _ => self.capturers(square, target, color, self.combined),
+        }
+    }
+
+    /// Give me the pieces of `color` that hang: an enemy piece could take one of them, and then
+    /// no piece of `color` could take back.  The captures follow the rules of my `Variant`, and
+    /// under the uno rules each piece that could take is tried on its own, since who can take
+    /// back depends on it.  The king never hangs.
+    ///
+    /// Only the first capture and the answer to it are looked at, so a piece that is attacked
+    /// twice and defended once does not hang.  `Board::see` finds out what such a piece is worth.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, Color, Square, EMPTY};
+    /// use std::str::FromStr;
+    ///
+    /// // the knight on a5 attacks the rook on c6, which may take it, and nothing takes back
+    /// let board = Board::from_str("4k3/8/2r5/N7/8/8/8/4K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.hanging_pieces(Color::White), BitBoard::from_square(Square::A5));
+    /// assert_eq!(board.hanging_pieces(Color::Black), EMPTY);
+    ///
+    /// // unless a rook on a1 takes back on the file
+    /// let board = Board::from_str("4k3/8/2r5/N7/8/8/8/R3K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.hanging_pieces(Color::White), EMPTY);
+    /// ```
+    pub fn hanging_pieces(&self, color: Color) -> BitBoard {
+        let mut hanging = EMPTY;
+        for square in self.color_combined(color) & !self.pieces(Piece::King) {
+            let piece = self.piece_on(square).unwrap();
+            let capturers = self.capturers(square, Some((piece, color)), !color, self.combined);
+            let hangs = capturers.into_iter().any(|src| {
+                let occupied = self.combined ^ BitBoard::from_square(src);
+                let taker = Some((self.piece_on(src).unwrap(), !color));
+                self.capturers(square, taker, color, occupied) == EMPTY
+            });
+            if hangs {
+                hanging |= BitBoard::from_square(square);
+            }
+        }
+        hanging
+    }
+
+    /// Give me the pieces of `color` that the other side could win material from, if it were
+    /// its turn: the pieces it may capture, by the rules of my `Variant`, with a capture that
+    /// `Board::see` scores above 0 for it.  A piece defended well enough that every capture of it
+    /// loses or trades evenly is not threatened.  The king never is.
+    ///
+    /// Pins and checks are not taken into account, so this can be asked of the side to move as
+    /// well as of the other side, without making a null move.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, Color, Square, EMPTY};
+    /// use std::str::FromStr;
+    ///
+    /// // the knight on c3 attacks the rook on b5, which may take it for nothing
+    /// let board = Board::from_str("4k3/8/8/1r6/8/2N5/8/4K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.threatened_pieces(Color::White), BitBoard::from_square(Square::C3));
+    /// assert_eq!(board.threatened_pieces(Color::Black), EMPTY);
+    ///
+    /// // but not once the rook on c1 would take back, winning the rook for the knight
+    /// let board = Board::from_str("4k3/8/8/1r6/8/2N5/8/2R1K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.threatened_pieces(Color::White), EMPTY);
+    /// ```
+    pub fn threatened_pieces(&self, color: Color) -> BitBoard {
+        let mut threatened = EMPTY;
+        for square in self.color_combined(color) & !self.pieces(Piece::King) {
+            let piece = self.piece_on(square).unwrap();
+            let capturers = self.capturers(square, Some((piece, color)), !color, self.combined);
+            if capturers
+                .into_iter()
+                .any(|src| self.exchange(src, square, !color, &values::DEFAULT) > Value::ZERO)
+            {
+                threatened |= BitBoard::from_square(square);
+            }
+        }
+        threatened
+    }
+
+    /// The static exchange evaluation of `m`: what the side to move wins on the destination of
+    /// `m`, in `PIECE_VALUES`, if both sides keep capturing there with their least valuable piece
+    /// for as long as that pays.  A king only captures if it cannot be captured back.
+    ///
+    /// `m` should be a legal move, but it does not have to be a capture.  The captures follow the
+    /// rules of my `Variant`, with pieces that are only revealed along a line once the pieces in
+    /// front of them are gone.  Pins, checks and promotions are not taken into account.  A castle
+    /// wins nothing, even a Chess960 one that moves my king onto my own rook.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    /// use std::str::FromStr;
+    ///
+    /// // the pawn takes the rook that attacks it, and the knight it then attacks takes it back
+    /// let board = Board::from_str("7k/8/2n5/3r4/8/8/3P4/K7 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.see(ChessMove::new(Square::D2, Square::D5, None)), 400);
+    ///
+    /// // unless the bishop on e3, which that knight would attack, covers it
+    /// let board = Board::from_str("7k/8/2n5/3r4/8/4B3/3P4/K7 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(board.see(ChessMove::new(Square::D2, Square::D5, None)), 500);
+    /// ```
+    pub fn see(&self, m: ChessMove) -> i32 {
+        self.see_with(m, &values::DEFAULT).0
+    }
+
+    /// Like `Board::see`, with the values of `table`.
+    ///
+    /// ```
+    /// use chess::values::{Value, DEFAULT};
+    /// use chess::{Board, ChessMove, Piece, Square};
+    /// use std::str::FromStr;
+    ///
+    /// // the rook takes the knight, and the pawn takes it back
+    /// let board = Board::from_str("4k3/8/2p5/3n4/8/8/3R4/4K3 w - - 0 1 variant:standard")
+    ///     .expect("Valid FEN");
+    /// let m = ChessMove::new(Square::D2, Square::D5, None);
+    /// assert_eq!(board.see(m), 300 - 500);
+    ///
+    /// let mut table = DEFAULT;
+    /// table[Piece::Rook.to_index()] = Value(450);
+    /// assert_eq!(board.see_with(m, &table), Value(300 - 450));
+    /// ```
+    pub fn see_with<T: ValueTable>(&self, m: ChessMove, table: &T) -> Value {
+        if self.castle_rook(m).is_some() {
+            return Value::ZERO;
+        }
+        self.exchange(m.get_source(), m.get_dest(), self.side_to_move, table)
+    }
+
+    // `Board::see_with` of the piece of `color` on `source` moving to `square`, whichever side is
+    // to move.  Only the side to move may take en passant.
+    fn exchange<T: ValueTable>(
+        &self,
+        source: Square,
+        square: Square,
+        color: Color,
+        table: &T,
+    ) -> Value {
+        let value = |piece: Piece| table.value(piece);
+        let mut piece = self.piece_on(source).unwrap();
+        let mut color = color;
+        let mut occupied = self.combined ^ BitBoard::from_square(source);
+
+        // every piece but the first is captured at most once, so there are at most 32 entries
+        let mut gain = [Value::ZERO; 32];
+        gain[0] = match self.piece_on(square) {
+            Some(captured) => value(captured),
+            None if piece == Piece::Pawn
+                && color == self.side_to_move
+                && Some(square.ubackward(color)) == self.en_passant =>
+            {
+                occupied ^= BitBoard::from_square(square.ubackward(color));
+                value(Piece::Pawn)
+            }
+            None => Value::ZERO,
+        };
+
+        let mut depth = 0;
+        while let Some((src, next)) =
+            self.least_valuable_of(self.capturers(square, Some((piece, color)), !color, occupied))
+        {
+            occupied ^= BitBoard::from_square(src);
+            if next == Piece::King {
+                let recapture = self.capturers(square, Some((next, !color)), color, occupied);
+                if recapture != EMPTY {
+                    break;
+                }
+            }
+            depth += 1;
+            gain[depth] = value(piece) - gain[depth - 1];
+            piece = next;
+            color = !color;
+        }
+        // either side may stop capturing when that is better for it
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+        gain[0]
+    }
+
+    // The pieces of `color` on `occupied` that could capture the `target` piece on `square`, or
+    // in `Variant::Standard` whatever stands there.
+    fn capturers(
+        &self,
+        square: Square,
+        target: Option<(Piece, Color)>,
+        color: Color,
+        occupied: BitBoard,
+    ) -> BitBoard {
+        match self.variant {
+            Variant::Uno => match target {
+                Some((piece, target_color)) if target_color != color => {
+                    attacks_of(piece, square, target_color, occupied)
+                        & self.color_combined(color)
+                        & occupied
+                }
+                _ => EMPTY,
+            },
+            Variant::Standard => self.attackers_to(square, color, occupied) & occupied,
+        }
+    }
+
+    // The least valuable of `pieces`, with its square.
+    fn least_valuable_of(&self, pieces: BitBoard) -> Option<(Square, Piece)> {
+        ALL_PIECES.iter().find_map(|piece| {
+            let bb = pieces & self.pieces(*piece);
+            bb.to_square().map(|sq| (sq, *piece))
+        })
+    }
+
+    /// Give me the squares the piece on `src` may capture on, by the rules of my `Variant`.  This
+    /// is empty unless one of my pieces is on `src`.
+    ///
+    /// These are the capture rules only, so a capture that would leave my king in check is still
+    /// included, and en passant is not.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, BoardBuilder, Square, Variant, EMPTY};
+    /// use std::convert::TryInto;
+    /// use std::str::FromStr;
+    ///
+    /// let fen = "4k3/8/8/8/8/1r6/8/1N2K3 w - - 0 1";
+    ///
+    /// // the rook attacks the knight, so the knight may capture it
+    /// let board = Board::from_str(fen).expect("Valid FEN");
+    /// assert_eq!(board.capture_targets(Square::B1), BitBoard::from_square(Square::B3));
+    /// assert_eq!(board.capture_targets(Square::B3), EMPTY);
+    ///
+    /// // in normal chess it does not attack the rook, so it cannot
+    /// let mut builder = BoardBuilder::from_str(fen).expect("Valid FEN");
+    /// let board: Board = builder.variant(Variant::Standard).try_into().expect("Valid Position");
+    /// assert_eq!(board.capture_targets(Square::B1), EMPTY);
+    /// ```
+    #[inline]
+    pub fn capture_targets(&self, src: Square) -> BitBoard {
+        match self.piece_on(src) {
+            Some(piece) if self.color_on(src) == Some(self.side_to_move) => {
+                self.rule_capture_targets(src, piece, self.side_to_move)
+            }
+            _ => EMPTY,
+        }
+    }
+
+    /// Give me the enemy pieces that may capture whatever stands on `square`, by the rules of my
+    /// `Variant`.  This is empty if `square` is empty.
+    ///
+    /// Like `Board::capture_targets`, this ignores whether the capture would be legal.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Board, Square, EMPTY};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/1r6/8/1N2K3 w - - 0 1").expect("Valid FEN");
+    ///
+    /// // only the knight attacked by the rook may capture it
+    /// assert_eq!(board.capturable_by(Square::B3), BitBoard::from_square(Square::B1));
+    /// // and the knight does not attack the rook, so it is safe from it
+    /// assert_eq!(board.capturable_by(Square::B1), EMPTY);
+    /// ```
+    pub fn capturable_by(&self, square: Square) -> BitBoard {
+        let color = match self.color_on(square) {
+            Some(color) => !color,
+            None => return EMPTY,
+        };
+        let target = BitBoard::from_square(square);
+        (*self.color_combined(color))
+            .filter(|src| {
+                let piece = self.piece_on(*src).unwrap();
+                self.rule_capture_targets(*src, piece, color) & target != EMPTY
+            })
+            .fold(EMPTY, |bb, src| bb | BitBoard::from_square(src))
+    }
+
+    /// Explain why the capture `m` is allowed, by the rules of my `Variant`.  This is `None` if
+    /// `m` is not a legal capture.  See `CapturePattern` for which reason wins when several
+    /// apply.
+    ///
+    /// ```
+    /// use chess::{Board, CapturePattern, ChessMove, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/1b6/8/3RK3 w - - 0 1").expect("Valid FEN");
+    ///
+    /// // the bishop could reach the rook diagonally, so the rook may take it
+    /// assert_eq!(
+    ///     board.capture_pattern(ChessMove::new(Square::D1, Square::B3, None)),
+    ///     Some(CapturePattern::BishopLine)
+    /// );
+    /// assert_eq!(board.capture_pattern(ChessMove::new(Square::D1, Square::D5, None)), None);
+    /// ```
+    pub fn capture_pattern(&self, m: ChessMove) -> Option<CapturePattern> {
+        let src = m.get_source();
+        let dest = m.get_dest();
+        let piece = self.piece_on(src)?;
+        let color = self.side_to_move;
+        if piece == Piece::Pawn
+            && self.piece_on(dest).is_none()
+            && Some(dest.ubackward(color)) == self.en_passant
+        {
+            return if self.legal(m) {
+                Some(CapturePattern::EnPassant)
+            } else {
+                None
+            };
+        }
+        if self.color_on(dest) != Some(!color) || !self.legal(m) {
+            return None;
+        }
+
+        let from = BitBoard::from_square(src);
+        let reaches = |piece: Piece, sq: Square, color: Color, to: BitBoard| {
+            attacks_of(piece, sq, color, self.combined) & to != EMPTY
+        };
+        if reaches(piece, src, color, BitBoard::from_square(dest)) {
+            return Some(CapturePattern::OwnMovement);
+        }
+        match self.piece_on(dest) {
+            Some(Piece::Pawn) if reaches(Piece::Pawn, dest, !color, from) => {
+                Some(CapturePattern::PawnAttack)
+            }
+            Some(Piece::Knight) if reaches(Piece::Knight, dest, !color, from) => {
+                Some(CapturePattern::KnightLeap)
+            }
+            Some(Piece::Bishop) | Some(Piece::Queen)
+                if reaches(Piece::Bishop, dest, !color, from) =>
+            {
+                Some(CapturePattern::BishopLine)
+            }
+            Some(Piece::Rook) | Some(Piece::Queen) if reaches(Piece::Rook, dest, !color, from) => {
+                Some(CapturePattern::RookLine)
+            }
+            _ => None,
+        }
+    }
+
+    // Does this position hang together with the capture rules of my variant?  `checkers` has
+    // to be exactly the enemy pieces that could capture my king.  If the position was reached by
+    // a `legal` move, nothing of mine may be able to capture the enemy king either, or that move
+    // left its own king en prise.
+    #[cfg(test)]
+    fn threats_consistent(&self, legal: bool) -> bool {
+        let ksq = self.king_square(self.side_to_move);
+        let their_ksq = self.king_square(!self.side_to_move);
+        self.checkers == self.capturable_by(ksq)
+            && (!legal || self.capturable_by(their_ksq) == EMPTY)
+    }
+
+    // The squares the `piece` of `color` on `src` may capture on, by the rules of my variant.
+    #[inline]
+    fn rule_capture_targets(&self, src: Square, piece: Piece, color: Color) -> BitBoard {
+        match self.variant {
+            Variant::Uno => UnoRules::capture_targets(self, src, piece, color, self.combined),
+            Variant::Standard => {
+                StandardRules::capture_targets(self, src, piece, color, self.combined)
+            }
+        }
+    }
+
+    /// Export the position as a stack of 8x8 planes, for use as neural network input.
+    ///
+    /// The board is viewed from `perspective`: when `perspective` is `Color::Black` the ranks are
+    /// mirrored, so the perspective player always moves "up".  Within a plane, entry `i` is the
+    /// (possibly mirrored) square with `Square::to_index() == i`, so a1 is 0 and h8 is 63.
+    ///
+    /// The layout is frozen:
+    ///
+    /// * planes 0-5: the perspective player's pawns, knights, bishops, rooks, queens and king
+    /// * planes 6-11: the opponent's pieces, in the same order
+    /// * plane 12: 1.0 everywhere if the perspective player may castle kingside
+    /// * plane 13: 1.0 everywhere if the perspective player may castle queenside
+    /// * plane 14: 1.0 everywhere if the opponent may castle kingside
+    /// * plane 15: 1.0 everywhere if the opponent may castle queenside
+    /// * plane 16: 1.0 everywhere if the perspective player is the side to move
+    /// * plane 17: 1.0 on the en passant square, the one a pawn that takes en passant lands on, as
+    ///   a FEN writes it.  Only `Variant::Standard` boards have one.
+    /// * plane 18: `halfmove_clock / 100` everywhere, at most 1.0, for the fifty-move rule
+    ///
+    /// Every other entry is 0.0.  The `Board` does not keep a halfmove clock, so the caller
+    /// passes it in, for example counted by a `Game`.
+    ///
+    /// ```
+    /// use chess::{Board, Color, NUM_PLANES};
+    ///
+    /// let board = Board::default();
+    /// let planes = board.to_planes(Color::Black, 25);
+    ///
+    /// assert_eq!(planes.len(), NUM_PLANES);
+    /// // black's pawns are mirrored onto the second rank
+    /// assert_eq!(planes[0][8], 1.0);
+    /// // white is to move, not black
+    /// assert_eq!(planes[16][0], 0.0);
+    /// assert_eq!(planes[18][0], 0.25);
+    /// ```
+    pub fn to_planes(&self, perspective: Color, halfmove_clock: u32) -> [[f32; 64]; NUM_PLANES] {
+        let mut planes = [[0.0; 64]; NUM_PLANES];
+
+        let flip = |bb: BitBoard| {
+            if perspective == Color::White {
+                bb
+            } else {
+                bb.reverse_colors()
+            }
+        };
+
+        for (i, color) in [perspective, !perspective].iter().enumerate() {
+            for piece in ALL_PIECES.iter() {
+                let plane = &mut planes[i * NUM_PIECES + piece.to_index()];
+                for sq in flip(self.pieces(*piece) & self.color_combined(*color)) {
+                    plane[sq.to_index()] = 1.0;
+                }
+            }
+
+            let castle_rights = self.castle_rights(*color);
+            if castle_rights.has_kingside() {
+                planes[12 + 2 * i] = [1.0; 64];
+            }
+            if castle_rights.has_queenside() {
+                planes[13 + 2 * i] = [1.0; 64];
+            }
+        }
+
+        if self.side_to_move == perspective {
+            planes[16] = [1.0; 64];
+        }
+
+        if let Some(ep) = self.en_passant {
+            let target = BitBoard::from_square(ep.uforward(self.side_to_move));
+            for sq in flip(target) {
+                planes[17][sq.to_index()] = 1.0;
+            }
+        }
+
+        planes[18] = [halfmove_clock.min(100) as f32 / 100.0; 64];
+
+        planes
+    }
+
+    /// An exact key for the position, which two boards share only if they are equal.  Unlike
+    /// `get_hash`, it never collides, and `Board::from_canonical_key` gives the board back.
+    ///
+    /// The layout is stable:
+    ///
+    /// * bytes 0-7: the occupied squares, as the little-endian `u64` of `BitBoard`
+    /// * bytes 8-23: one nibble for each occupied square, from a1 to h8, low nibble first, with
+    ///   the nibbles past the last piece 0
+    ///
+    /// A nibble below 12 is the index of the piece, plus 6 for a black piece.  The others mark
+    /// what the position keeps besides where the pieces are:
+    ///
+    /// * 12: a rook that may still castle, of the side whose back rank it is on, and on the side
+    ///   of its king that it is on
+    /// * 13: a pawn that can be taken en passant, of the side whose fourth rank it is on
+    /// * 14: the black king, with black to move
+    /// * 15: the white king, in `Variant::Standard`
+    ///
+    /// A side has at most 16 pieces, so 32 nibbles always fit.  The layout is
+    /// `stability::CANONICAL_KEY_VERSION`, and only changes with it.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove};
+    ///
+    /// let play = |moves: &[&str]| {
+    ///     moves.iter().fold(Board::default(), |board, m| {
+    ///         board.make_move_new(m.parse::<ChessMove>().unwrap())
+    ///     })
+    /// };
+    /// let board = play(&["g1f3", "g8f6", "b1c3"]);
+    /// let key = board.canonical_key();
+    /// assert_eq!(key, play(&["b1c3", "g8f6", "g1f3"]).canonical_key());
+    /// // the rook on a1 may castle, and the bishop on c1 is next
+    /// assert_eq!(key[8], 0x2C);
+    /// assert_eq!(Board::from_canonical_key(&key).unwrap(), board);
+    /// ```
+    pub fn canonical_key(&self) -> [u8; 24] {
+        let mut key = [0; 24];
+        key[..8].copy_from_slice(&self.combined.0.to_le_bytes());
+
+        let castles = self.castle_rights[0].unmoved_rooks(Color::White)
+            | self.castle_rights[1].unmoved_rooks(Color::Black);
+        for (i, sq) in self.combined.enumerate() {
+            let bb = BitBoard::from_square(sq);
+            let piece = self.piece_on(sq).unwrap();
+            let color = self.color_on(sq).unwrap();
+            let nibble = if castles & bb != EMPTY {
+                12
+            } else if self.en_passant == Some(sq) {
+                13
+            } else if piece == Piece::King
+                && color == Color::Black
+                && self.side_to_move == Color::Black
+            {
+                14
+            } else if piece == Piece::King
+                && color == Color::White
+                && self.variant == Variant::Standard
+            {
+                15
+            } else {
+                piece.to_index() + color.to_index() * NUM_PIECES
+            };
+            key[8 + i / 2] |= (nibble as u8) << (4 * (i % 2));
+        }
+        key
+    }
+
+    /// Read a board from the key `Board::canonical_key` gives.
+    ///
+    /// Every key of a board gives that board back, and any other key is an error, so that no two
+    /// keys give the same board.  A key of an older `stability::CANONICAL_KEY_VERSION` goes
+    /// through `stability::migrate` first.
+    ///
+    /// ```
+    /// use chess::{Board, Error};
+    ///
+    /// let key = Board::default().canonical_key();
+    /// assert_eq!(Board::from_canonical_key(&key).unwrap(), Board::default());
+    ///
+    /// let mut other = key;
+    /// other[23] = 1;
+    /// assert!(matches!(Board::from_canonical_key(&other), Err(Error::InvalidCanonicalKey)));
+    /// ```
+    pub fn from_canonical_key(key: &[u8; 24]) -> Result<Board, Error> {
+        let mut occupied = [0; 8];
+        occupied.copy_from_slice(&key[..8]);
+        let combined = BitBoard::new(u64::from_le_bytes(occupied));
+        if combined.popcnt() > 2 * MAX_PIECES {
+            return Err(Error::InvalidCanonicalKey);
+        }
 
-        let ksq = self.king_square(!self.side_to_move);
+        let mut builder = BoardBuilder::new();
+        builder.variant(Variant::Uno);
+        let mut castles = Vec::new();
+        for (i, sq) in combined.enumerate() {
+            let nibble = (key[8 + i / 2] >> (4 * (i % 2))) as usize & 0xF;
+            let (piece, color) = match nibble {
+                12 if sq.get_rank() == Color::White.back_rank() => {
+                    castles.push(sq);
+                    (Piece::Rook, Color::White)
+                }
+                12 => {
+                    castles.push(sq);
+                    (Piece::Rook, Color::Black)
+                }
+                13 => {
+                    let color = if sq.get_rank() == Color::White.double_push_rank() {
+                        Color::White
+                    } else {
+                        Color::Black
+                    };
+                    builder.side_to_move(!color).en_passant(Some(sq.get_file()));
+                    (Piece::Pawn, color)
+                }
+                14 => {
+                    builder.side_to_move(Color::Black);
+                    (Piece::King, Color::Black)
+                }
+                15 => {
+                    builder.variant(Variant::Standard);
+                    (Piece::King, Color::White)
+                }
+                _ => (
+                    ALL_PIECES[nibble % NUM_PIECES],
+                    ALL_COLORS[nibble / NUM_PIECES],
+                ),
+            };
+            builder.piece(sq, piece, color);
+        }
 
-        self.checkers |= get_king_moves(ksq)
-            & self.color_combined(self.side_to_move);
-    }
+        for color in ALL_COLORS.iter() {
+            let rank = color.back_rank();
+            let king = builder
+                .king_square(*color)
+                .filter(|sq| sq.get_rank() == rank);
+            let mut rights = CastleRights::NoRights;
+            for rook in castles.iter().filter(|sq| sq.get_rank() == rank) {
+                let file = Some(rook.get_file());
+                rights = match king {
+                    Some(king) if rook.get_file() > king.get_file() => {
+                        rights.add(CastleRights::new(file, None))
+                    }
+                    _ => rights.add(CastleRights::new(None, file)),
+                };
+            }
+            builder.castle_rights(*color, rights);
+        }
 
-    /// Give me the `Bitboard` of the pieces putting me in check.
-    #[inline]
-    pub fn checkers(&self) -> &BitBoard {
-        &self.checkers
+        // anything the board does not keep, or would write differently, is not its key
+        match Board::try_from(&builder) {
+            Ok(board) if board.canonical_key() == *key => Ok(board),
+            _ => Err(Error::InvalidCanonicalKey),
+        }
     }
 }
 
+/// How many planes does `Board::to_planes` produce?
+pub const NUM_PLANES: usize = 19;
+
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let fen: BoardBuilder = self.into();
@@ -972,6 +3846,14 @@ impl TryFrom<&BoardBuilder> for Board {
     type Error = Error;
 
     fn try_from(fen: &BoardBuilder) -> Result<Self, Self::Error> {
+        // the en passant and check squares below are found from the kings
+        if ALL_COLORS
+            .iter()
+            .any(|color| fen.king_square(*color).is_none())
+        {
+            return Err(Error::InvalidKingCount);
+        }
+
         let mut board = Board::new();
 
         for sq in ALL_SQUARES.iter() {
@@ -981,19 +3863,33 @@ impl TryFrom<&BoardBuilder> for Board {
         }
 
         board.side_to_move = fen.get_side_to_move();
+        board.variant = fen.get_variant();
+        for color in ALL_COLORS.iter() {
+            board.castled[color.to_index()] = fen.get_castled(*color);
+        }
 
         #[allow(deprecated)]
         board.add_castle_rights(Color::White, fen.get_castle_rights(Color::White));
         #[allow(deprecated)]
         board.add_castle_rights(Color::Black, fen.get_castle_rights(Color::Black));
 
+        if let Some(file) = fen.get_en_passant() {
+            // the pawn that just moved is my opponents
+            let sq = Square::make_square((!board.side_to_move).double_push_rank(), file);
+            if board.can_en_passant(sq, board.side_to_move) {
+                board.en_passant = Some(sq);
+            }
+        }
+
         board.update_check_info();
 
-        if board.is_sane() {
-            Ok(board)
-        } else {
-            Err(Error::InvalidBoard)
+        #[cfg(feature = "attack-info")]
+        {
+            board.attack_info = AttackInfo::new(&board);
         }
+
+        board.validate(fen.get_max_pieces(), fen.get_strict_material())?;
+        Ok(board)
     }
 }
 
@@ -1020,3 +3916,1513 @@ impl FromStr for Board {
         Ok(BoardBuilder::from_str(value)?.try_into()?)
     }
 }
+
+#[cfg(test)]
+use crate::playouts::random_playouts;
+
+#[test]
+fn test_piece_iter_rebuilds_the_board() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+    ];
+    for fen in fens.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let pieces: Vec<(Square, Piece, Color)> = board.piece_iter().collect();
+        assert!(pieces.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let mut builder = BoardBuilder::from(&board);
+        for square in ALL_SQUARES.iter() {
+            builder.clear_square(*square);
+        }
+        for (square, piece, color) in pieces.iter() {
+            builder.piece(*square, *piece, *color);
+        }
+        assert_eq!(Board::try_from(&builder).unwrap(), board);
+
+        for color in ALL_COLORS.iter() {
+            let mine: Vec<(Square, Piece)> = pieces
+                .iter()
+                .filter(|(_, _, c)| c == color)
+                .map(|(square, piece, _)| (*square, *piece))
+                .collect();
+            assert_eq!(board.pieces_of(*color).collect::<Vec<_>>(), mine);
+
+            for piece in ALL_PIECES.iter() {
+                let squares = mine
+                    .iter()
+                    .filter(|(_, p)| p == piece)
+                    .fold(EMPTY, |bb, (square, _)| bb | BitBoard::from_square(*square));
+                assert_eq!(board.piece_squares(*piece, *color), squares);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_to_planes_initial_position() {
+    // a1 is bit 0, h8 is bit 63, exactly like a `BitBoard`
+    const EXPECTED: [u64; NUM_PLANES] = [
+        0x0000_0000_0000_ff00, // my pawns
+        0x0000_0000_0000_0042, // my knights
+        0x0000_0000_0000_0024, // my bishops
+        0x0000_0000_0000_0081, // my rooks
+        0x0000_0000_0000_0008, // my queen
+        0x0000_0000_0000_0010, // my king
+        0x00ff_0000_0000_0000, // their pawns
+        0x4200_0000_0000_0000, // their knights
+        0x2400_0000_0000_0000, // their bishops
+        0x8100_0000_0000_0000, // their rooks
+        0x0800_0000_0000_0000, // their queen
+        0x1000_0000_0000_0000, // their king
+        !0,                    // my kingside castle
+        !0,                    // my queenside castle
+        !0,                    // their kingside castle
+        !0,                    // their queenside castle
+        !0,                    // my move
+        0,                     // en passant
+        0,                     // halfmove clock
+    ];
+
+    let board = Board::default();
+    for perspective in ALL_COLORS.iter() {
+        let planes = board.to_planes(*perspective, 0);
+        for (i, plane) in planes.iter().enumerate() {
+            for (sq, value) in plane.iter().enumerate() {
+                let set = (EXPECTED[i] >> sq) & 1 == 1;
+                // black is not to move in the initial position
+                let set = if i == 16 && *perspective == Color::Black {
+                    false
+                } else {
+                    set
+                };
+                assert_eq!(*value, if set { 1.0 } else { 0.0 });
+            }
+        }
+    }
+}
+
+#[test]
+fn test_to_planes_en_passant_and_clock() {
+    let with = Board::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard").unwrap();
+    let without = Board::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1 variant:standard").unwrap();
+    assert_eq!(with.en_passant(), Some(Square::D5));
+
+    // the boards differ only by the en passant square, and so do their planes
+    let planes = with.to_planes(Color::White, 0);
+    let mut expected = without.to_planes(Color::White, 0);
+    expected[17][Square::D6.to_index()] = 1.0;
+    assert_eq!(planes[..], expected[..]);
+
+    // from black's side the square is mirrored too
+    let planes = with.to_planes(Color::Black, 0);
+    let set: Vec<usize> = (0..64).filter(|i| planes[17][*i] == 1.0).collect();
+    assert_eq!(set, vec![Square::D3.to_index()]);
+
+    // the clock counts toward the fifty-move rule, and stops at 1.0
+    let half = with.to_planes(Color::White, 50);
+    assert!(half[18].iter().all(|v| *v == 0.5));
+    let capped = with.to_planes(Color::White, 150);
+    assert!(capped[18].iter().all(|v| *v == 1.0));
+}
+
+#[test]
+fn test_xray_attackers_to() {
+    // rook, friendly pawn and enemy queen all on the a-file
+    let board = Board::from_str("4k3/8/q7/8/8/P7/8/R3K3 w - - 0 1").unwrap();
+    assert_eq!(
+        board.xray_attackers_to(Square::A1, Color::Black),
+        BitBoard::from_square(Square::A6)
+    );
+    assert_eq!(
+        board.xray_attackers_to(Square::A6, Color::White),
+        BitBoard::from_square(Square::A1)
+    );
+
+    // with nothing in between there is nothing to x-ray
+    let board = Board::from_str("4k3/8/q7/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    assert_eq!(board.xray_attackers_to(Square::A1, Color::Black), EMPTY);
+}
+
+#[test]
+fn test_incremental_hash_matches_recomputed_hash() {
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ];
+    random_playouts(&starts, &[Variant::Uno], 10_000, 60, |board, _| {
+        assert_eq!(board.get_hash(), board.recompute_hash());
+        // the same position parsed from a FEN must hash the same as the one reached by moves
+        let parsed = Board::from_str(&board.to_string()).unwrap();
+        assert_eq!(parsed.get_hash(), board.get_hash());
+        assert_eq!(parsed.pawn_hash(), board.pawn_hash());
+        assert_eq!(parsed.material_key(), board.material_key());
+    });
+}
+
+#[test]
+fn test_hash_after_matches_make_move() {
+    use crate::variant::ALL_VARIANTS;
+
+    // castling both ways, rook captures that take away castle rights, promotions and en passant
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/1P4P1/8/8/8/8/1p4p1/R3K2R w KQkq - 0 1",
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+        "4k3/2p1p3/8/3P1P2/2p1p3/8/3P1P2/4K3 w - - 0 1",
+    ];
+    random_playouts(&starts, &ALL_VARIANTS, 1_000, 60, |board, moves| {
+        for m in moves.iter() {
+            assert_eq!(
+                board.hash_after(*m),
+                board.make_move_new(*m).get_hash(),
+                "{} {}",
+                board,
+                m
+            );
+        }
+    });
+}
+
+#[test]
+fn test_capture_targets_match_movegen() {
+    use crate::variant::ALL_VARIANTS;
+
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/1P4P1/8/8/8/8/1p4p1/R3K2R w KQkq - 0 1",
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+    ];
+    random_playouts(&starts, &ALL_VARIANTS, 200, 60, |board, moves| {
+        let them = *board.color_combined(!board.side_to_move());
+        for src in ALL_SQUARES.iter() {
+            let targets = board.capture_targets(*src);
+            if board.color_on(*src) != Some(board.side_to_move()) {
+                assert_eq!(targets, EMPTY);
+                continue;
+            }
+            // the legal captures are the capture targets that do not leave me in check
+            let captures = moves
+                .iter()
+                .filter(|m| m.get_source() == *src)
+                .map(|m| BitBoard::from_square(m.get_dest()))
+                .fold(EMPTY, |bb, dest| bb | dest)
+                & them;
+            let legal = targets
+                .filter(|dest| {
+                    let promotion = if board.piece_on(*src) == Some(Piece::Pawn)
+                        && board.side_to_move().to_promotion_board() & BitBoard::from_square(*dest)
+                            != EMPTY
+                    {
+                        Some(Piece::Queen)
+                    } else {
+                        None
+                    };
+                    board.legal(ChessMove::new(*src, *dest, promotion))
+                })
+                .fold(EMPTY, |bb, dest| bb | BitBoard::from_square(dest));
+            assert_eq!(captures, legal, "{} {}", board, src);
+            for dest in targets {
+                let capturers = board.capturable_by(dest);
+                assert_ne!(capturers & BitBoard::from_square(*src), EMPTY);
+            }
+        }
+        for square in them {
+            for src in board.capturable_by(square) {
+                let targets = board.capture_targets(src);
+                assert_ne!(targets & BitBoard::from_square(square), EMPTY);
+            }
+        }
+    });
+}
+
+#[test]
+fn test_capture_pattern() {
+    use crate::variant::ALL_VARIANTS;
+
+    let pattern = |fen: &str, variant: Variant, src: Square, dest: Square| {
+        let board: Board = BoardBuilder::from_str(fen)
+            .unwrap()
+            .variant(variant)
+            .try_into()
+            .unwrap();
+        board.capture_pattern(ChessMove::new(src, dest, None))
+    };
+    let uno = |fen: &str, src: Square, dest: Square| pattern(fen, Variant::Uno, src, dest);
+
+    // the rooks attack each other, so both the rook line and the rook's own movement allow the
+    // capture, and the own movement wins
+    let fen = "4k3/8/8/8/8/8/8/r2RK3 w - - 0 1";
+    assert_eq!(
+        uno(fen, Square::D1, Square::A1),
+        Some(CapturePattern::OwnMovement)
+    );
+    let fen = "4k3/8/8/8/8/2b5/8/Q3K3 w - - 0 1";
+    assert_eq!(
+        uno(fen, Square::A1, Square::C3),
+        Some(CapturePattern::OwnMovement)
+    );
+
+    let fen = "4k3/8/8/8/8/2q5/8/N3K3 w - - 0 1";
+    assert_eq!(
+        uno(fen, Square::A1, Square::C3),
+        Some(CapturePattern::BishopLine)
+    );
+    let fen = "4k3/8/8/8/8/1r6/8/1N2K3 w - - 0 1";
+    assert_eq!(
+        uno(fen, Square::B1, Square::B3),
+        Some(CapturePattern::RookLine)
+    );
+    let fen = "4k3/8/8/8/3r4/3P4/8/4K3 w - - 0 1";
+    assert_eq!(
+        uno(fen, Square::D3, Square::D4),
+        Some(CapturePattern::RookLine)
+    );
+    let fen = "4k3/8/8/4p3/3N4/8/8/4K3 w - - 0 1";
+    assert_eq!(
+        uno(fen, Square::D4, Square::E5),
+        Some(CapturePattern::PawnAttack)
+    );
+    let fen = "4k3/8/8/8/3B4/5n2/8/K7 w - - 0 1";
+    assert_eq!(
+        uno(fen, Square::D4, Square::F3),
+        Some(CapturePattern::KnightLeap)
+    );
+    // the king takes the rook that attacks it from the far side of the board
+    let fen = "4k2r/8/8/8/8/8/8/7K w - - 0 1";
+    assert_eq!(
+        uno(fen, Square::H1, Square::H8),
+        Some(CapturePattern::RookLine)
+    );
+
+    // not a capture, not allowed by the capture rules, and leaving my king in check
+    assert_eq!(uno(fen, Square::H1, Square::G1), None);
+    let fen = "4k3/8/8/8/8/1n6/8/1R2K3 w - - 0 1";
+    assert_eq!(uno(fen, Square::B1, Square::B3), None);
+    let fen = "4k3/8/8/1r6/8/8/3q4/1N2K3 w - - 0 1";
+    assert_eq!(uno(fen, Square::B1, Square::B5), None);
+
+    // normal chess only has its own captures
+    let fen = "4k3/8/8/8/8/1n6/8/1R2K3 w - - 0 1";
+    let rook_takes = pattern(fen, Variant::Standard, Square::B1, Square::B3);
+    assert_eq!(rook_takes, Some(CapturePattern::OwnMovement));
+    let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+    let en_passant = pattern(fen, Variant::Standard, Square::E5, Square::D6);
+    assert_eq!(en_passant, Some(CapturePattern::EnPassant));
+
+    let start = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    random_playouts(&[start], &ALL_VARIANTS, 50, 60, |board, moves| {
+        let them = *board.color_combined(!board.side_to_move());
+        for m in moves.iter() {
+            let captures = them & BitBoard::from_square(m.get_dest()) != EMPTY;
+            match board.capture_pattern(*m) {
+                None => assert!(!captures, "{} {}", board, m),
+                Some(CapturePattern::EnPassant) => {
+                    assert!(!captures && board.variant() == Variant::Standard)
+                }
+                Some(CapturePattern::OwnMovement) => assert!(captures),
+                Some(_) => assert!(captures && board.variant() == Variant::Uno),
+            }
+        }
+    });
+}
+
+#[test]
+fn test_discovered_check_candidates() {
+    let standard = |fen: &str| -> Board {
+        BoardBuilder::from_str(fen)
+            .unwrap()
+            .variant(Variant::Standard)
+            .try_into()
+            .unwrap()
+    };
+    let candidates = |fen: &str, color: Color| standard(fen).discovered_check_candidates(color);
+
+    // a knight in front of a bishop, and one in front of a rook
+    let fen = "8/7k/8/8/4N3/8/8/1B2K3 w - - 0 1";
+    assert_eq!(
+        candidates(fen, Color::White),
+        BitBoard::from_square(Square::E4)
+    );
+    let fen = "4k3/8/8/8/4N3/8/8/K3R3 w - - 0 1";
+    assert_eq!(
+        candidates(fen, Color::White),
+        BitBoard::from_square(Square::E4)
+    );
+    // a black knight in front of the rook is pinned, and no candidate for either side
+    let fen = "4k3/8/8/8/4n3/8/8/K3R3 b - - 0 1";
+    assert_eq!(candidates(fen, Color::White), EMPTY);
+    assert_eq!(candidates(fen, Color::Black), EMPTY);
+    let fen = "4r2k/8/8/8/4n3/8/8/4K3 w - - 0 1";
+    assert_eq!(
+        candidates(fen, Color::Black),
+        BitBoard::from_square(Square::E4)
+    );
+    // two pieces in the way, and the queen behind a pawn on a diagonal
+    let fen = "4k3/8/8/4P3/4N3/8/8/K3R3 w - - 0 1";
+    assert_eq!(candidates(fen, Color::White), EMPTY);
+    let fen = "5k2/8/8/8/1P6/Q7/8/K7 w - - 0 1";
+    assert_eq!(
+        candidates(fen, Color::White),
+        BitBoard::from_square(Square::B4)
+    );
+
+    // and there are no discovered checks in the uno variant
+    let fen = "4k3/8/8/8/4N3/8/8/K3R3 w - - 0 1";
+    let board = Board::from_str(fen).unwrap();
+    assert_eq!(board.discovered_check_candidates(Color::White), EMPTY);
+
+    // a candidate that moves off the line gives check, other pieces only check from the check
+    // squares
+    let board = standard("4k3/8/8/8/4N3/8/8/K3R3 w - - 0 1");
+    let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+    for m in moves.iter() {
+        let piece = board.piece_on(m.get_source()).unwrap();
+        let direct = board.check_squares(piece) & BitBoard::from_square(m.get_dest()) != EMPTY;
+        let discovered = m.get_source() == Square::E4;
+        assert_eq!(board.gives_check(*m), direct || discovered, "{}", m);
+    }
+}
+
+#[test]
+fn test_is_attacked_matches_attackers_to() {
+    use crate::variant::ALL_VARIANTS;
+
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+    random_playouts(&starts, &ALL_VARIANTS, 40, 60, |board, _| {
+        for square in ALL_SQUARES.iter() {
+            for color in ALL_COLORS.iter() {
+                let attackers = board.attackers_to(*square, *color, board.combined);
+                assert_eq!(board.is_attacked(*square, *color), attackers != EMPTY);
+            }
+        }
+        // my king is attacked exactly when I am in check
+        let ksq = board.king_square(board.side_to_move);
+        let attackers = board.attackers_to(ksq, !board.side_to_move, board.combined);
+        assert_eq!(attackers, board.checkers, "{}", board);
+    });
+}
+
+#[test]
+fn test_least_valuable_attacker_and_see() {
+    let board_of = |fen: &str, variant: Variant| -> Board {
+        BoardBuilder::from_str(fen)
+            .unwrap()
+            .variant(variant)
+            .try_into()
+            .unwrap()
+    };
+    // take the attackers off one by one, and give me them in the order they came
+    let order = |board: &Board, square: Square, color: Color| {
+        let mut occupied = board.combined;
+        let mut attackers = Vec::new();
+        while let Some((src, piece)) = board.least_valuable_attacker(square, color, occupied) {
+            assert_eq!(board.piece_on(src), Some(piece));
+            occupied ^= BitBoard::from_square(src);
+            attackers.push(src);
+        }
+        attackers
+    };
+
+    // one of each piece attacks the knight on d5 in normal chess
+    let board = board_of("k7/8/4K3/3n3Q/4PN2/1B6/8/3R4 w - - 0 1", Variant::Standard);
+    let expected = [
+        Square::E4,
+        Square::F4,
+        Square::B3,
+        Square::D1,
+        Square::H5,
+        Square::E6,
+    ];
+    assert_eq!(order(&board, Square::D5, Color::White), expected);
+
+    // and under the uno rules, the queen on d4 attacks one of each, with the queen on d7 only
+    // attacking once the rook in front of it is gone
+    let board = board_of("k7/3Q4/3R1N2/8/B2q4/3P4/1K6/8 b - - 0 1", Variant::Uno);
+    let expected = [
+        Square::D3,
+        Square::F6,
+        Square::A4,
+        Square::D6,
+        Square::D7,
+        Square::B2,
+    ];
+    assert_eq!(order(&board, Square::D4, Color::White), expected);
+    assert_eq!(order(&board, Square::D4, Color::Black), []);
+    assert_eq!(order(&board, Square::E4, Color::White), []);
+
+    let see = |fen: &str, variant: Variant, src: Square, dest: Square| {
+        board_of(fen, variant).see(ChessMove::new(src, dest, None))
+    };
+    // a rook that takes a defended pawn, alone and with another rook behind it
+    let fen = "4k3/8/2p5/3p4/8/8/8/3RK3 w - - 0 1";
+    assert_eq!(see(fen, Variant::Standard, Square::D1, Square::D5), -400);
+    let fen = "4k3/8/2p5/3p4/8/8/3R4/3RK3 w - - 0 1";
+    assert_eq!(see(fen, Variant::Standard, Square::D2, Square::D5), -300);
+    let fen = "4k3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1";
+    assert_eq!(see(fen, Variant::Standard, Square::D2, Square::D5), 100);
+    // the king may only take back if nothing can take it in turn
+    let fen = "8/8/4k3/3p4/8/8/8/3QK3 w - - 0 1";
+    assert_eq!(see(fen, Variant::Standard, Square::D1, Square::D5), -800);
+    let fen = "8/8/4k3/3p4/8/8/3R4/3QK3 w - - 0 1";
+    assert_eq!(see(fen, Variant::Standard, Square::D2, Square::D5), 100);
+    // a quiet move onto a square the pawn on e6 covers
+    let fen = "4k3/8/4p3/8/8/8/8/3RK3 w - - 0 1";
+    assert_eq!(see(fen, Variant::Standard, Square::D1, Square::D5), -500);
+    // en passant
+    let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+    assert_eq!(see(fen, Variant::Standard, Square::E5, Square::D6), 100);
+
+    // under the uno rules that square is safe for the rook, as the pawn does not attack it
+    let fen = "4k3/8/4p3/8/8/8/8/3RK3 w - - 0 1";
+    assert_eq!(see(fen, Variant::Uno, Square::D1, Square::D5), 0);
+    // and a rook that attacks the king next to it may be taken by it
+    let fen = "8/8/3k4/8/8/8/8/3RK3 w - - 0 1";
+    assert_eq!(see(fen, Variant::Uno, Square::D1, Square::D5), -500);
+}
+
+#[test]
+fn test_see_with_a_value_table() {
+    // the bishop takes the rook, and the pawn takes it back
+    let fen = "4k3/8/2p5/3r4/8/8/6B1/4K3 w - - 0 1 variant:standard";
+    let board = Board::from_str(fen).unwrap();
+    let m = ChessMove::new(Square::G2, Square::D5, None);
+    assert_eq!(board.see(m), 200);
+    assert_eq!(board.see_with(m, &values::DEFAULT), Value(200));
+
+    // which loses material once bishops are worth more than rooks
+    let mut table = values::DEFAULT;
+    table[Piece::Bishop.to_index()] = Value(600);
+    assert_eq!(board.see_with(m, &table), Value(-100));
+
+    // without the pawn the rook is won for nothing, whatever the bishop is worth
+    let board = Board::from_str("4k3/8/8/3r4/8/8/6B1/4K3 w - - 0 1 variant:standard").unwrap();
+    assert_eq!(board.see_with(m, &table), values::ROOK);
+}
+
+#[test]
+fn test_legal_moves_keep_threats_consistent() {
+    use crate::variant::ALL_VARIANTS;
+
+    // checks, double checks, pins, castling, promotions, en passant and distant king captures
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/1P4P1/8/8/8/8/1p4p1/R3K2R w KQkq - 0 1",
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "4k3/8/8/4r2r/8/8/8/4K3 w - - 0 1",
+    ];
+    random_playouts(&starts, &ALL_VARIANTS, 1_200, 80, |board, moves| {
+        assert!(board.threats_consistent(false), "{}", board);
+        for m in moves.iter() {
+            assert!(
+                board.make_move_new(*m).threats_consistent(true),
+                "{} {}",
+                board,
+                m
+            );
+        }
+    });
+}
+
+#[test]
+fn test_pawn_hash() {
+    // the same pawns, but different pieces and a different side to move
+    let a = Board::from_str("r3k2r/ppp2ppp/8/3pp3/3PP3/8/PPP2PPP/R3K2R w KQkq - 0 1").unwrap();
+    let b = Board::from_str("4k3/ppp2ppp/2n5/3pp3/3PP3/5N2/PPP2PPP/4K3 b - - 0 1").unwrap();
+    assert_eq!(a.pawn_hash(), b.pawn_hash());
+    assert_ne!(a.get_hash(), b.get_hash());
+
+    let knight = b.make_move_new(ChessMove::new(Square::C6, Square::B4, None));
+    assert_eq!(knight.pawn_hash(), b.pawn_hash());
+    let pawn = b.make_move_new(ChessMove::new(Square::H7, Square::H6, None));
+    assert_ne!(pawn.pawn_hash(), b.pawn_hash());
+
+    // promoting removes the pawn from the pawn hash
+    let promote = Board::from_str("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let queen = promote.make_move_new(ChessMove::new(Square::B7, Square::B8, Some(Piece::Queen)));
+    assert_eq!(queen.pawn_hash(), 0);
+}
+
+#[test]
+fn test_material_key_and_counts() {
+    // the same material reached by different moves, on different squares
+    let board = Board::default();
+    let a = board
+        .make_move_new(ChessMove::new(Square::G1, Square::F3, None))
+        .make_move_new(ChessMove::new(Square::B8, Square::C6, None));
+    let b = board
+        .make_move_new(ChessMove::new(Square::B1, Square::C3, None))
+        .make_move_new(ChessMove::new(Square::G8, Square::H6, None));
+    assert_eq!(a.material_key(), b.material_key());
+    assert_eq!(a.material_key(), board.material_key());
+
+    // promoting swaps a pawn for a queen in the key
+    let promote = Board::from_str("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let queen = promote.make_move_new(ChessMove::new(Square::B7, Square::B8, Some(Piece::Queen)));
+    let expected = Board::from_str("1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert_ne!(queen.material_key(), promote.material_key());
+    assert_eq!(queen.material_key(), expected.material_key());
+
+    for color in ALL_COLORS.iter() {
+        for piece in ALL_PIECES.iter() {
+            assert_eq!(
+                board.material_count(*color, *piece) as u32,
+                (board.pieces(*piece) & board.color_combined(*color)).popcnt()
+            );
+        }
+    }
+    assert_eq!(queen.material_count(Color::White, Piece::Queen), 1);
+    assert_eq!(queen.material_count(Color::White, Piece::Pawn), 0);
+    assert_eq!(queen.non_pawn_material(Color::White), 900);
+    assert_eq!(queen.non_pawn_material(Color::Black), 0);
+}
+
+#[test]
+fn test_diff_after_each_move_type() {
+    let white = |piece| Some((piece, Color::White));
+    let black = |piece| Some((piece, Color::Black));
+    let changes = |fen: &str, m: &str| {
+        let board = Board::from_str(fen).unwrap();
+        let m = ChessMove::from_str(m).unwrap();
+        assert!(board.legal(m));
+        let changes: Vec<_> = board
+            .diff(&board.make_move_new(m))
+            .iter()
+            .map(|change| (change.square, change.before, change.after))
+            .collect();
+        changes
+    };
+
+    assert_eq!(
+        changes("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e2e4"),
+        vec![
+            (Square::E2, white(Piece::Pawn), None),
+            (Square::E4, None, white(Piece::Pawn))
+        ]
+    );
+    assert_eq!(
+        changes("4k3/8/8/3p4/4Q3/8/8/4K3 b - - 0 1", "d5e4"),
+        vec![
+            (Square::E4, white(Piece::Queen), black(Piece::Pawn)),
+            (Square::D5, black(Piece::Pawn), None)
+        ]
+    );
+    assert_eq!(
+        changes("r3k3/8/8/8/8/8/8/4K3 b q - 0 1", "e8c8"),
+        vec![
+            (Square::A8, black(Piece::Rook), None),
+            (Square::C8, None, black(Piece::King)),
+            (Square::D8, None, black(Piece::Rook)),
+            (Square::E8, black(Piece::King), None)
+        ]
+    );
+    assert_eq!(
+        changes("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard", "e5d6"),
+        vec![
+            (Square::D5, black(Piece::Pawn), None),
+            (Square::E5, white(Piece::Pawn), None),
+            (Square::D6, None, white(Piece::Pawn))
+        ]
+    );
+    assert_eq!(
+        changes("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1 variant:standard", "a7b8n"),
+        vec![
+            (Square::A7, white(Piece::Pawn), None),
+            (Square::B8, black(Piece::Knight), white(Piece::Knight))
+        ]
+    );
+}
+
+#[test]
+fn test_find_connecting_move() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 variant:standard",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1 variant:standard",
+        "n1n5/PPP5/8/2k5/8/8/5ppp/K4N1N b - - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1 variant:standard",
+    ];
+    for fen in fens.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        for m in moves.iter() {
+            // every move leads somewhere no other move does, so it is the one found, even once
+            // the castle rights and en passant square are forgotten
+            let child = board.make_move_new(*m);
+            assert_eq!(board.find_connecting_move(&child), Some(*m));
+            let mut forgotten = BoardBuilder::from(child);
+            forgotten
+                .castle_rights(Color::White, CastleRights::NoRights)
+                .castle_rights(Color::Black, CastleRights::NoRights)
+                .en_passant(None);
+            let forgotten = Board::try_from(forgotten).unwrap();
+            assert_eq!(board.find_connecting_move(&forgotten), Some(*m));
+            assert!(moves
+                .iter()
+                .filter(|other| *other != m)
+                .all(|other| !board.make_move_new(*other).diff(&child).is_empty()));
+
+            // the same pieces with the wrong side to move are not connected
+            if let Some(passed) = child.null_move() {
+                assert_eq!(board.find_connecting_move(&passed), None);
+            }
+        }
+        assert_eq!(board.find_connecting_move(&board), None);
+    }
+}
+
+#[test]
+fn test_chess960_starts() {
+    use std::collections::HashSet;
+
+    assert_eq!(Board::chess960_start(518), Board::default());
+
+    let mut seen = HashSet::new();
+    for n in 0..960 {
+        let board = Board::chess960_start(n);
+        assert!(board.is_sane(), "{}", n);
+        assert_eq!(board.chess960_number(), Some(n));
+        assert_eq!(Board::from_str(&board.to_string()).unwrap(), board);
+        assert!(seen.insert(board.to_string()), "{}", n);
+
+        // the bishops stand on opposite colors and the king between the rooks
+        let bishops = board.piece_squares(Piece::Bishop, Color::White);
+        assert_eq!((bishops & BitBoard::new(0x55)).popcnt(), 1, "{}", n);
+        let ksq = board.king_square(Color::White);
+        let rights = board.castle_rights(Color::White);
+        assert!(rights.queenside_rook().unwrap() < ksq.get_file());
+        assert!(rights.kingside_rook().unwrap() > ksq.get_file());
+        assert_eq!(rights, board.castle_rights(Color::Black));
+    }
+
+    // only the pieces count, not the castle rights
+    let mut builder = BoardBuilder::from(Board::chess960_start(0));
+    builder.castle_rights(Color::White, CastleRights::NoRights);
+    assert_eq!(
+        Board::try_from(&builder).unwrap().chess960_number(),
+        Some(0)
+    );
+    builder.clear_square(Square::A7);
+    assert_eq!(Board::try_from(&builder).unwrap().chess960_number(), None);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_board_edits_keep_the_kings() {
+    let board = Board::default();
+    assert_eq!(board.clear_square(Square::E1), None);
+    assert_eq!(
+        board.set_piece(Piece::Queen, Color::Black, Square::E8),
+        None
+    );
+    assert_eq!(board.set_piece(Piece::King, Color::White, Square::E4), None);
+    assert_eq!(board.set_piece(Piece::King, Color::White, Square::E8), None);
+    assert!(board
+        .set_piece(Piece::Queen, Color::White, Square::E4)
+        .is_some());
+}
+
+#[cfg(test)]
+fn squares(text: &str) -> BitBoard {
+    text.split_whitespace()
+        .map(|sq| BitBoard::from_square(Square::from_str(sq).unwrap()))
+        .fold(EMPTY, |a, b| a | b)
+}
+
+#[test]
+fn test_outposts_and_holes() {
+    // the d5 square of the Sveshnikov, which no black pawn can attack after ...e5 and ...d6
+    let board = Board::from_str("4k3/pp3ppp/3p4/4p3/4P3/8/PPP2PPP/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.outposts(Color::White), squares("d5"));
+    // but the white c and g pawns can still come up to d4 and f4
+    assert_eq!(board.outposts(Color::Black), EMPTY);
+
+    // a knight on b5 is safe while the a pawn defends it, but a4-a5 leaves it to b6
+    let board = Board::from_str("4k3/8/3p4/8/P7/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.outposts(Color::White), squares("b5"));
+    let board = board.make_move_new(ChessMove::new(Square::A4, Square::A5, None));
+    assert_eq!(board.outposts(Color::White), squares("b6"));
+
+    // and the outposts of one side are the holes of the other that its pawns defend
+    for fen in [
+        "4k3/pp3ppp/3p4/4p3/4P3/8/PPP2PPP/4K3 w - - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "4k3/1p1p4/2p1p3/3P4/2P1P3/1P3P2/P5P1/4K3 b - - 0 1",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        for color in ALL_COLORS.iter() {
+            let theirs = board.pieces(Piece::Pawn) & board.color_combined(!*color);
+            assert_eq!(
+                board.outposts(!*color),
+                board.holes(*color) & pawn_attacks(theirs, !*color),
+                "{}",
+                fen
+            );
+        }
+    }
+}
+
+#[test]
+fn test_king_attack_units() {
+    // nothing comes near either king
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 variant:standard",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 variant:uno",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        for color in ALL_COLORS.iter() {
+            assert_eq!(board.king_attack_units(*color), (0, EMPTY), "{}", fen);
+        }
+    }
+
+    // after the greek gift Bxh7+ Kxh7 Ng5+ Kg8 Qh5, the knight hits f7 and h7 for two units
+    // and the queen f7, h7 and h8 for five
+    let fen = "r1b2rk1/pppn1pp1/4p3/3pP1NQ/3P4/8/PPP2PPP/R1B1K2R b KQ - 0 1";
+    let board = Board::from_str(&format!("{} variant:standard", fen)).unwrap();
+    assert_eq!(
+        board.king_attack_units(Color::Black),
+        (7, squares("f7 h7 h8"))
+    );
+    assert_eq!(board.king_attack_units(Color::White).0, 0);
+
+    // under the uno rules the attackers have to stand next to the zone
+    let board = Board::from_str(fen).unwrap();
+    assert_eq!(board.king_attack_units(Color::Black), (0, EMPTY));
+    let board = Board::from_str("6k1/5N2/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+    assert_eq!(
+        board.king_attack_units(Color::Black),
+        (2, squares("f8 g8 g7"))
+    );
+    let board = Board::from_str("6k1/5N2/8/8/8/8/8/6K1 b - - 0 1 variant:standard").unwrap();
+    assert_eq!(board.king_attack_units(Color::Black), (2, squares("h8")));
+}
+
+#[test]
+fn test_zeroing_and_irreversible_moves() {
+    let m = |text: &str| ChessMove::from_str(text).unwrap();
+
+    // a rook move that loses a castle right is irreversible, but does not zero the count
+    let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    for text in ["h1h2", "e1f1", "e1g1", "a1b1"].iter() {
+        assert!(board.is_irreversible(m(text)), "{}", text);
+        assert!(!board.is_zeroing(m(text)), "{}", text);
+    }
+    // and so is taking a rook that could castle, which does zero it
+    assert!(board.is_capture(m("h1h8")));
+    assert!(board.is_zeroing(m("h1h8")) && board.is_irreversible(m("h1h8")));
+    // once the right is gone, the rook and the king move freely
+    let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1").unwrap();
+    assert!(!board.is_irreversible(m("h1h2")));
+    assert!(board.is_irreversible(m("a1a2")));
+    let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1").unwrap();
+    assert!(!board.is_irreversible(m("e1f1")));
+
+    // an en passant capture, which only normal chess has, lands on an empty square
+    for fen in [
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 1 variant:standard",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        let en_passant = ChessMove::new(
+            Square::E5,
+            board.en_passant().unwrap().uforward(Color::White),
+            None,
+        );
+        assert!(board.legal(en_passant), "{}", fen);
+        assert!(board.piece_on(en_passant.get_dest()).is_none());
+        assert!(board.is_capture(en_passant), "{}", fen);
+        assert!(board.is_zeroing(en_passant) && board.is_irreversible(en_passant));
+    }
+
+    // a pawn push zeroes without capturing
+    let board = Board::default();
+    assert!(!board.is_capture(m("e2e4")) && board.is_zeroing(m("e2e4")));
+    assert!(!board.is_irreversible(m("g1f3")));
+}
+
+#[test]
+fn test_is_quiet() {
+    let m = |text: &str| ChessMove::from_str(text).unwrap();
+
+    // the bishop uncovers the rook in normal chess, but in uno the rook is too far to check
+    let standard = Board::from_str("4k3/8/8/8/8/8/4B3/4R1K1 w - - 0 1 variant:standard").unwrap();
+    let uno = Board::from_str("4k3/8/8/8/8/8/4B3/4R1K1 w - - 0 1").unwrap();
+    assert!(!standard.is_quiet(m("e2d3")));
+    assert!(uno.is_quiet(m("e2d3")));
+    assert!(standard.is_quiet(m("g1h2")) && uno.is_quiet(m("g1h2")));
+
+    // an en passant capture and a quiet promotion are never quiet
+    let board = Board::from_str("7k/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard").unwrap();
+    assert!(!board.is_quiet(m("e5d6")));
+    assert!(!board.is_quiet(m("b7b8n")));
+    assert!(board.is_quiet(m("e5e6")));
+}
+
+#[test]
+fn test_legal_captures_exist_and_only_move() {
+    let m = |text: &str| Some(ChessMove::from_str(text).unwrap());
+    for (fen, only_move) in [
+        // the king has one square left, in uno and in normal chess
+        ("k7/8/1Q6/8/8/8/8/4K3 b - - 0 1", m("a8b8")),
+        (
+            "k7/1R6/8/8/8/8/8/K5R1 b - - 0 1 variant:standard",
+            m("a8b7"),
+        ),
+        // in check with one way out, a capture in uno and a block in normal chess
+        ("7k/6Q1/8/8/8/8/8/K7 b - - 0 1", m("h8g7")),
+        (
+            "R6k/6pp/8/8/8/8/7K/1r6 b - - 0 1 variant:standard",
+            m("b1b8"),
+        ),
+        // one pawn with four promotions
+        ("8/1P6/8/8/8/1k6/p7/K7 w - - 0 1 variant:standard", None),
+        // no moves at all
+        ("7k/6N1/8/8/8/8/8/K7 b - - 0 1", None),
+        (
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1",
+            None,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 variant:standard",
+            None,
+        ),
+        (
+            "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1 variant:standard",
+            None,
+        ),
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(board.only_move(), *only_move, "{}", fen);
+
+        let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        assert_eq!(board.only_move().is_some(), moves.len() == 1, "{}", fen);
+        assert_eq!(
+            board.legal_captures_exist(),
+            moves.iter().any(|m| board.is_capture(*m)),
+            "{}",
+            fen
+        );
+    }
+
+    // the only capture is en passant
+    let board = Board::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard").unwrap();
+    assert!(board.legal_captures_exist());
+    let board = Board::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1 variant:standard").unwrap();
+    assert!(!board.legal_captures_exist());
+}
+
+#[test]
+fn test_defenders_and_hanging_pieces() {
+    // an undefended knight, attacked by the bishop
+    let board = Board::from_str("4k3/2b5/8/N7/8/8/8/4K3 w - - 0 1 variant:standard").unwrap();
+    assert_eq!(board.defenders_of(Square::A5, Color::White), EMPTY);
+    assert_eq!(board.hanging_pieces(Color::White), squares("a5"));
+    assert_eq!(board.hanging_pieces(Color::Black), EMPTY);
+
+    // a pawn defended once and attacked twice does not hang, and neither does the pawn it
+    // attacks, which the knight defends
+    let board = Board::from_str("4k3/8/5n2/3p4/4P3/3P4/8/4K3 w - - 0 1 variant:standard").unwrap();
+    assert_eq!(board.defenders_of(Square::E4, Color::White), squares("d3"));
+    assert_eq!(
+        board.attackers_to(Square::E4, Color::Black, board.combined),
+        squares("d5 f6")
+    );
+    assert_eq!(board.defenders_of(Square::D5, Color::Black), squares("f6"));
+    assert_eq!(board.hanging_pieces(Color::White), EMPTY);
+    assert_eq!(board.hanging_pieces(Color::Black), EMPTY);
+    // without the pawn on d3 it does
+    let board = Board::from_str("4k3/8/5n2/3p4/4P3/8/8/4K3 w - - 0 1 variant:standard").unwrap();
+    assert_eq!(board.hanging_pieces(Color::White), squares("e4"));
+
+    // under the uno rules the rook may take the knight that attacks it, and the rook on a1 takes
+    // back, although it could not take back from a knight, which `defenders_of` counts against
+    let board = Board::from_str("4k3/8/2r5/N7/8/8/8/R3K3 w - - 0 1").unwrap();
+    assert_eq!(board.hanging_pieces(Color::White), EMPTY);
+    assert_eq!(board.defenders_of(Square::A5, Color::White), EMPTY);
+    let board = Board::from_str("4k3/8/2r5/N7/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.hanging_pieces(Color::White), squares("a5"));
+}
+
+#[test]
+fn test_make_move_with_info() {
+    let info = |fen: &str, m: &str| {
+        let board = Board::from_str(fen).unwrap();
+        let m = ChessMove::from_str(m).unwrap();
+        assert!(board.legal(m));
+        let (child, info) = board.make_move_with_info(m);
+        assert_eq!(child, board.make_move_new(m));
+        info
+    };
+    let quiet = |piece, dest| MoveInfo {
+        piece,
+        dest,
+        captured: None,
+        castle: None,
+        promotion: None,
+    };
+
+    assert_eq!(
+        info("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e2e4"),
+        quiet(Piece::Pawn, Square::E4)
+    );
+    assert_eq!(
+        info("4k3/8/8/3p4/4Q3/8/8/4K3 b - - 0 1", "d5e4"),
+        MoveInfo {
+            captured: Some((Piece::Queen, Square::E4)),
+            ..quiet(Piece::Pawn, Square::E4)
+        }
+    );
+    assert_eq!(
+        info("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard", "e5d6"),
+        MoveInfo {
+            captured: Some((Piece::Pawn, Square::D5)),
+            ..quiet(Piece::Pawn, Square::D6)
+        }
+    );
+
+    // a castle is the same whether the king moves two squares or onto its rook
+    let castle = MoveInfo {
+        castle: Some((Square::A8, Square::D8)),
+        ..quiet(Piece::King, Square::C8)
+    };
+    assert_eq!(info("r3k3/8/8/8/8/8/8/4K3 b q - 0 1", "e8c8"), castle);
+    assert_eq!(info("r3k3/8/8/8/8/8/8/4K3 b q - 0 1", "e8a8"), castle);
+
+    assert_eq!(
+        info("4k3/P7/8/8/8/8/8/4K3 w - - 0 1 variant:standard", "a7a8q"),
+        MoveInfo {
+            promotion: Some(Piece::Queen),
+            ..quiet(Piece::Pawn, Square::A8)
+        }
+    );
+    assert_eq!(
+        info("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1 variant:standard", "a7b8n"),
+        MoveInfo {
+            captured: Some((Piece::Knight, Square::B8)),
+            promotion: Some(Piece::Knight),
+            ..quiet(Piece::Pawn, Square::B8)
+        }
+    );
+}
+
+#[test]
+fn test_canonical_key() {
+    let key = |fen: &str| {
+        let board = Board::from_str(fen).unwrap();
+        let key = board.canonical_key();
+        assert_eq!(Board::from_canonical_key(&key).unwrap(), board);
+        key
+    };
+
+    // everything but the placement tells boards apart
+    let fens = [
+        "r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1 variant:standard",
+        "r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq - 0 1 variant:standard",
+        "r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq - 0 1",
+        "r3k2r/8/8/3pP3/8/8/8/R3K2R b KQkq - 0 1",
+        "r3k2r/8/8/3pP3/8/8/8/R3K2R w Qkq - 0 1",
+        "r3k2r/8/8/3pP3/8/8/8/R3K2R w - - 0 1",
+        "1r2k1r1/8/8/3pP3/8/8/8/R3K2R w KQgb - 0 1",
+        "1r2k1r1/8/8/3pP3/8/8/8/R3K2R w KQ - 0 1",
+    ];
+    let keys: Vec<[u8; 24]> = fens.iter().map(|fen| key(fen)).collect();
+    for (i, a) in keys.iter().enumerate() {
+        for b in keys[i + 1..].iter() {
+            assert_ne!(a, b);
+        }
+    }
+
+    // an en passant square that no pawn can take on is not kept, and so not in the key
+    assert_eq!(
+        key("4k3/8/8/3p4/8/8/8/4K3 w - d6 0 1 variant:standard"),
+        key("4k3/8/8/3p4/8/8/8/4K3 w - - 0 1 variant:standard")
+    );
+
+    // nibbles no board writes are errors
+    let invalid = |key: &[u8; 24]| {
+        assert!(matches!(
+            Board::from_canonical_key(key),
+            Err(Error::InvalidCanonicalKey)
+        ))
+    };
+    let uno = key("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq - 0 1");
+    let mut passant = uno;
+    // the pawn on d5 is the fourth piece, after the rooks and king of the first rank
+    passant[9] = (passant[9] & 0x0F) | 13 << 4;
+    invalid(&passant);
+    let mut kings = uno;
+    // and the king on e1 the second, which a black king cannot be with another on e8
+    kings[8] = 0xBC;
+    invalid(&kings);
+    invalid(&[0; 24]);
+}
+
+#[test]
+fn test_material_balance_and_phase() {
+    let board = Board::default();
+    assert_eq!(board.phase(), MAX_PHASE);
+    assert_eq!(board.material_balance(), 0);
+    assert_eq!(board.material(Color::White), board.material(Color::Black));
+
+    // taking the queen loses her four points of phase, and her value from the balance
+    let queen = Piece::Queen.to_index();
+    let fen = "rnb1kbnr/pppp1ppp/8/4p1q1/3P4/2N2B2/PPP1PPPP/R1BQK1NR w KQkq - 0 1";
+    let m = ChessMove::new(Square::C1, Square::G5, None);
+    let before = Board::from_str(fen).unwrap();
+    assert!(before.legal(m));
+    let board = before.make_move_new(m);
+    assert_eq!(board.phase(), MAX_PHASE - PHASE_WEIGHTS[queen]);
+    assert_eq!(board.material_balance(), values::QUEEN.0);
+    assert_eq!(board.material(Color::Black)[queen], 0);
+
+    // promoted queens do not take the phase past its most
+    let board = Board::from_str("QQQ1k3/8/8/8/8/8/8/RNBQKBNR w KQ - 0 1").unwrap();
+    assert_eq!(board.phase(), MAX_PHASE);
+    assert_eq!(board.material(Color::White), [0, 2, 2, 2, 4, 1]);
+}
+
+#[test]
+fn test_threats_if_i_passed() {
+    // the bishop on c4 hangs to the knight, the knight on f3 is defended against the bishop,
+    // and the queen in front of the rook on the d file checks on d1
+    let fen = "3r2k1/1b1q1ppp/1n6/8/2B5/5N2/5PPP/6K1 w - - 0 1 variant:standard";
+    let board = Board::from_str(fen).unwrap();
+    assert_eq!(board.threatened_pieces(Color::White), squares("c4"));
+    assert_eq!(board.threatened_pieces(Color::Black), EMPTY);
+    assert_eq!(board.king_threats(Color::White), squares("d7"));
+    // the bishop takes on f7 with check, although the king takes it back
+    assert_eq!(board.king_threats(Color::Black), squares("c4"));
+
+    // with the queen gone the rook checks on d1 instead
+    let fen = "3r2k1/1b3ppp/1n6/8/2B5/5N2/5PPP/6K1 w - - 0 1 variant:standard";
+    let board = Board::from_str(fen).unwrap();
+    assert_eq!(board.king_threats(Color::White), squares("d8"));
+
+    // a bishop in front of a rook discovers check, and could not give it itself
+    let fen = "4r1k1/8/8/8/4b3/8/8/4K3 w - - 0 1 variant:standard";
+    let board = Board::from_str(fen).unwrap();
+    assert_eq!(board.king_threats(Color::White), squares("e4"));
+
+    // under the uno rules the rook may take the knight that attacks it, and the knight on g4
+    // may step next to the king
+    let board = Board::from_str("4k3/8/8/1r6/6n1/2N5/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.threatened_pieces(Color::White), squares("c3"));
+    assert_eq!(board.threatened_pieces(Color::Black), EMPTY);
+    assert_eq!(board.king_threats(Color::White), squares("g4"));
+    assert_eq!(board.king_threats(Color::Black), EMPTY);
+}
+
+#[test]
+fn test_apply_moves_replays_a_game() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 variant:standard";
+    let board = Board::from_str(fen).unwrap();
+    let game = "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 d6 c3 O-O h3 Nb8 d4 Nbd7 \
+                Nbd2 Bb7 Bc2 Re8 Nf1 Bf8 Ng3 g6 a4 c5 d5 c4 Bg5 h6 Be3 Nc5 Qd2 h5 Bg5 Be7";
+    let moves: Vec<&str> = game.split_whitespace().collect();
+    assert_eq!(moves.len(), 40);
+    let end = board
+        .apply_moves(moves.iter().copied(), MoveFormat::San)
+        .unwrap();
+    let fen = "r2qr1k1/1b2bp2/p2p1np1/1pnPp1Bp/P1p1P3/2P2NNP/1PBQ1PP1/R3R1K1 w - - 0 1";
+    assert_eq!(end.to_string(), fen);
+
+    // the same game in coordinates, and as moves
+    let coordinates: Vec<String> = moves
+        .iter()
+        .scan(board, |position, text| {
+            let m = ChessMove::from_san(position, text).unwrap();
+            *position = position.make_move_new(m);
+            Some(m.to_string())
+        })
+        .collect();
+    let coordinates: Vec<&str> = coordinates.iter().map(String::as_str).collect();
+    assert_eq!(
+        board
+            .apply_moves(coordinates.iter().copied(), MoveFormat::Coordinate)
+            .unwrap(),
+        end
+    );
+    let chess_moves: Vec<ChessMove> = coordinates.iter().map(|m| m.parse().unwrap()).collect();
+    assert_eq!(board.apply_chess_moves(&chess_moves).unwrap(), end);
+
+    // a pawn cannot jump three squares, so the 17th move fails in the position after 8...O-O
+    let mut illegal = coordinates[..16].to_vec();
+    illegal.push("d2d5");
+    let error = board
+        .apply_moves(illegal.iter().copied(), MoveFormat::Coordinate)
+        .unwrap_err();
+    assert_eq!(error.index, 16);
+    assert_eq!(error.text, "d2d5");
+    let before = "r1bq1rk1/2p1bppp/p1np1n2/1p2p3/4P3/1BP2N2/PP1P1PPP/RNBQR1K1 w - - 0 1";
+    assert_eq!(error.fen, format!("{} variant:standard", before));
+    assert!(matches!(error.error, Error::IllegalMove));
+
+    let mut illegal = moves[..16].to_vec();
+    illegal.push("d5");
+    let error = board.apply_moves(illegal, MoveFormat::San).unwrap_err();
+    assert_eq!((error.index, error.text.as_str()), (16, "d5"));
+    assert!(matches!(error.error, Error::InvalidSanMove { .. }));
+
+    // and a move that cannot be read at all
+    let error = end
+        .apply_moves(vec!["e2e9"], MoveFormat::Coordinate)
+        .unwrap_err();
+    assert_eq!(error.index, 0);
+    assert!(matches!(error.error, Error::InvalidSquare));
+}
+
+#[test]
+fn test_castled_is_kept_until_the_fen() {
+    let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 variant:standard").unwrap();
+    let castle = |board: &Board, m: &str| {
+        let m = ChessMove::from_san(board, m).unwrap();
+        let mut result = Board::default();
+        board.make_move(m, &mut result);
+        let color = board.side_to_move();
+        assert_eq!(result.castled(color), board.make_move_new(m).castled(color));
+        result
+    };
+    let after = castle(&castle(&board, "O-O"), "O-O-O");
+    assert_eq!(after.castled(Color::White), Castled::KingSide);
+    assert_eq!(after.castled(Color::Black), Castled::QueenSide);
+    assert_eq!(board.castled(Color::White), Castled::No);
+
+    // moving the king again, or passing, keeps it
+    let moved = after.make_move_new(ChessMove::from_str("g1h1").unwrap());
+    assert_eq!(moved.castled(Color::White), Castled::KingSide);
+    let passed = after.null_move().unwrap();
+    assert_eq!(passed.castled(Color::Black), Castled::QueenSide);
+
+    // a Chess960 castle, with the king taking its rook
+    let chess960 = Board::from_str("4k3/8/8/8/8/8/8/1R2K3 w B - 0 1 variant:standard").unwrap();
+    let castled = chess960.make_move_new(ChessMove::new(Square::E1, Square::B1, None));
+    assert_eq!(castled.castled(Color::White), Castled::QueenSide);
+
+    // a builder keeps it, and a FEN does not
+    let builder = BoardBuilder::from(&after);
+    assert_eq!(builder.get_castled(Color::Black), Castled::QueenSide);
+    let built = Board::try_from(&builder).unwrap();
+    assert_eq!(built.castled(Color::White), Castled::KingSide);
+    let read = Board::from_str(&after.to_tagged_fen()).unwrap();
+    assert_eq!(read.castled(Color::White), Castled::No);
+    assert_eq!(read.castled(Color::Black), Castled::No);
+    assert_eq!(read, after);
+}
+
+#[test]
+fn test_swap_side_to_move() {
+    // the pawn that just moved cannot be taken en passant after the turn is flipped back
+    let board = Board::from_str("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1 variant:standard").unwrap();
+    let swapped = board.swap_side_to_move().unwrap();
+    assert_eq!(swapped.side_to_move(), Color::White);
+    assert_eq!(swapped.en_passant(), None);
+    let fen = "4k3/8/8/8/3pP3/8/8/4K3 w - - 0 1 variant:standard";
+    assert_eq!(swapped, Board::from_str(fen).unwrap());
+    assert_eq!(swapped.get_hash(), swapped.recompute_hash());
+    let back = swapped.swap_side_to_move().unwrap();
+    assert_eq!(back.en_passant(), None);
+    let passed_twice = board.null_move().unwrap().null_move().unwrap();
+    assert_eq!(back.get_hash(), passed_twice.get_hash());
+
+    // the side in check may not pass its turn on
+    let check = Board::from_str("4k3/8/8/8/8/4r3/8/4K3 w - - 0 1 variant:standard").unwrap();
+    let swapped = check.swap_side_to_move();
+    assert!(matches!(swapped, Err(Error::OpponentInCheck)));
+    // which in the uno variant is only from next to the king
+    let uno = Board::from_str("4k3/8/8/8/8/4r3/8/4K3 w - - 0 1").unwrap();
+    assert!(uno.swap_side_to_move().is_ok());
+    let uno = Board::from_str("4k3/8/8/8/8/8/8/3rK3 w - - 0 1").unwrap();
+    assert!(uno.swap_side_to_move().is_err());
+}
+
+#[test]
+fn test_attacks_and_moves_from_each_piece() {
+    let board =
+        Board::from_str("4k3/1p6/8/3n4/8/1R1P4/P3B3/1Q2K3 w - - 0 1 variant:standard").unwrap();
+    let squares = |names: &str| {
+        names
+            .split(' ')
+            .map(|name| BitBoard::from_square(Square::from_str(name).unwrap()))
+            .fold(EMPTY, |bb, sq| bb | sq)
+    };
+
+    // the rook is blocked by its own pawn on d3 and the queen on b1, and attacks both
+    assert_eq!(
+        board.attacks_from(Square::B3),
+        squares("a3 c3 d3 b2 b1 b4 b5 b6 b7")
+    );
+    assert_eq!(board.moves_from(Square::B3), squares("a3 c3 b2 b4 b5 b6"));
+    assert_eq!(
+        board.attacks_from(Square::B1),
+        squares("a1 c1 d1 e1 b2 c2 d3 a2 b3")
+    );
+    assert_eq!(board.attacks_from(Square::E2), squares("d1 f1 d3 f3 g4 h5"));
+    assert_eq!(
+        board.attacks_from(Square::D5),
+        squares("b4 b6 c3 c7 e3 e7 f4 f6")
+    );
+    assert_eq!(board.attacks_from(Square::E1), squares("d1 f1 d2 e2 f2"));
+    assert_eq!(board.moves_from(Square::E1), squares("d1 f1 d2 f2"));
+
+    // pawns attack diagonally, and push straight ahead
+    assert_eq!(board.attacks_from(Square::A2), squares("b3"));
+    assert_eq!(board.moves_from(Square::A2), squares("a3 a4"));
+    assert_eq!(board.attacks_from(Square::D3), squares("c4 e4"));
+    assert_eq!(board.moves_from(Square::D3), squares("d4"));
+    assert_eq!(board.attacks_from(Square::B7), squares("a6 c6"));
+    assert_eq!(board.moves_from(Square::B7), squares("b6 b5"));
+
+    assert_eq!(board.attacks_from(Square::H8), EMPTY);
+    assert_eq!(board.moves_from(Square::H8), EMPTY);
+}
+
+#[test]
+fn test_rotate_180_keeps_the_colors() {
+    // the position is the same turned around, with the colors swapped
+    let board = Board::from_str("3k3r/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let swapped = Board::from_str("3K3R/8/8/8/8/8/8/r3k3 w - - 0 1").unwrap();
+    assert_eq!(board.rotate_180().unwrap(), swapped);
+
+    // turning twice gives the board back, without its castle rights
+    let turned = Board::default().rotate_180().unwrap();
+    assert_eq!(turned.color_on(Square::E2), Some(Color::Black));
+    let start = Board::from_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1").unwrap();
+    assert_eq!(turned.rotate_180().unwrap(), start);
+
+    // a pawn that now moves the other way gives check, under the normal rules
+    let board = Board::from_str("8/8/8/8/8/8/1P2K3/k7 w - - 0 1 variant:standard").unwrap();
+    assert!(board.rotate_180().is_err());
+}
+
+#[test]
+fn test_parse_line_gives_the_span_of_a_bad_move() {
+    // both knights can go to d2, so the move is ambiguous
+    let line = "1. d4 d5 2. Nf3 Nf6 3. Nd2 e6";
+    let error = Board::default().parse_line(line).unwrap_err();
+    assert_eq!(error.span(), Some(Span { start: 23, end: 26 }));
+    let message = error.display_with_source(line);
+    assert!(message.ends_with(&format!("\n{}\n{}^^^", line, " ".repeat(23))));
+
+    // a move number written against the move is not part of it
+    let error = Board::default().parse_line("1.e4 e5 2.Ke3").unwrap_err();
+    assert_eq!(error.span(), Some(Span { start: 10, end: 13 }));
+
+    // and the line can go on over several lines
+    let line = "1. d4 d5\n2. Nf3 Nf6\n3. Nd2";
+    let error = Board::default().parse_line(line).unwrap_err();
+    let message = error.display_with_source(line);
+    assert!(message.ends_with("\n3. Nd2\n   ^^^"));
+    let line = "1. d4 d5\n2. Nf3 Nf6\n3. Nbd2 *";
+    assert_eq!(Board::default().parse_line(line).unwrap().len(), 5);
+}
+
+#[test]
+fn test_from_epd() {
+    let fen = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - -";
+    let ops = "bm Qg6 Nxf7+; id \"WAC.001; two\"; variant standard;";
+    let line = format!("{} {}", fen, ops);
+    let (board, best, avoid) = Board::from_epd(&line).unwrap();
+    let expected = Board::from_str(&format!("{} 0 1 variant:standard", fen)).unwrap();
+    assert_eq!(board, expected);
+    let qg6 = ChessMove::new(Square::G3, Square::G6, None);
+    let nxf7 = ChessMove::new(Square::E5, Square::F7, None);
+    assert_eq!(best, vec![qg6, nxf7]);
+    assert_eq!(avoid, None);
+
+    // without the variant, the knight may not take the pawn that does not attack it
+    let line = format!("{} bm Qg6 Nxf7+; id \"WAC.001\";", fen);
+    let error = Board::from_epd(&line).unwrap_err();
+    let start = line.find("Nxf7").unwrap();
+    let end = start + "Nxf7+".len();
+    assert_eq!(error.span(), Some(Span { start, end }));
+
+    // the spaces between fields and operations do not matter, nor a missing last ';'
+    let line = format!("  {}  am  Qg6 ;bm Qg6", fen.replace(' ', "   "));
+    let (uno, best, avoid) = Board::from_epd(&line).unwrap();
+    assert_eq!(uno, Board::from_str(&format!("{} 0 1", fen)).unwrap());
+    assert_eq!((best, avoid), (vec![qg6], Some(vec![qg6])));
+
+    // what is not EPD, with where it is
+    let errors = [
+        ("8/8/8 w - -", 0, 5),
+        ("4k3/8/8/8/8/8/8/4K3 w -", 0, 23),
+        ("4k3/8/8/8/8/8/8/4K3 w - -  0 1", 27, 28),
+        ("4k3/8/8/8/8/8/8/4K3 w - - bm Kd1;;", 33, 34),
+        ("4k3/8/8/8/8/8/8/4K3 w - - id \"open", 29, 34),
+        ("4k3/8/8/8/8/8/8/4K3 w - - variant chess;", 34, 39),
+        ("4k3/8/8/8/8/8/8/4K3 x - - bm Kd1;", 20, 21),
+    ];
+    for (line, start, end) in errors.iter() {
+        let (start, end) = (*start, *end);
+        let error = Board::from_epd(line).unwrap_err();
+        assert_eq!(error.span(), Some(Span { start, end }), "{}", line);
+    }
+}
+
+#[test]
+fn test_sanitize_move_accepts_just_the_legal_moves() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 variant:standard",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 variant:standard",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1 variant:standard",
+        "n1n5/PPP5/8/2k5/8/8/5ppp/K4N1N b - - 0 1",
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+    ];
+    let promotions = [
+        None,
+        Some(Piece::Pawn),
+        Some(Piece::Knight),
+        Some(Piece::Queen),
+        Some(Piece::King),
+    ];
+    for fen in fens.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let legal: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        for source in ALL_SQUARES.iter() {
+            for dest in ALL_SQUARES.iter() {
+                for promotion in promotions.iter() {
+                    let m = ChessMove::new(*source, *dest, *promotion);
+                    match board.sanitize_move(m) {
+                        Some(sanitized) => {
+                            assert_eq!(sanitized, board.normalize_move(m), "{} {}", fen, m);
+                            assert!(legal.contains(&sanitized), "{} {}", fen, m);
+                            assert!(board.make_move_new(sanitized).is_sane(), "{} {}", fen, m);
+                        }
+                        None => assert!(!board.legal(m), "{} {}", fen, m),
+                    }
+                }
+            }
+        }
+        for m in legal.iter() {
+            assert_eq!(board.sanitize_move(*m), Some(*m), "{} {}", fen, m);
+        }
+    }
+
+    // a castle with a promotion is not a castle
+    let board = Board::from_str(fens[2]).unwrap();
+    let castle = ChessMove::new(Square::E1, Square::A1, Some(Piece::Queen));
+    assert_eq!(board.sanitize_move(castle), None);
+}
+
+#[test]
+fn test_symmetries() {
+    // a pawnless endgame can be turned and flipped every way
+    for fen in [
+        "8/8/8/3k4/8/2N5/1r6/KB6 w - - 0 1",
+        "8/8/8/3k4/8/2N5/1r6/KB6 b - - 0 1 variant:standard",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        let symmetries = board.symmetries();
+        assert_eq!(symmetries.len(), 8);
+        let mut boards: Vec<Board> = symmetries.iter().map(|(other, _)| *other).collect();
+        boards.push(board);
+        boards.sort_by_key(|other| other.get_hash());
+        boards.dedup();
+        assert_eq!(boards.len(), 9, "{}", fen);
+
+        let legal: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        for (other, op) in symmetries.iter() {
+            assert!(other.is_sane());
+            assert_eq!(other.get_hash(), other.recompute_hash());
+            let swapped = other.side_to_move() != board.side_to_move();
+            assert_eq!(swapped, op.swaps_colors());
+            let mut moves: Vec<ChessMove> = legal.iter().map(|m| m.transform(*op)).collect();
+            let mut expected: Vec<ChessMove> = MoveGen::new_legal(other).collect();
+            moves.sort();
+            expected.sort();
+            assert_eq!(moves, expected, "{} {:?}", fen, op);
+        }
+    }
+
+    // with pawns, castle rights and en passant there is only the mirror
+    let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3 variant:standard";
+    let board = Board::from_str(fen).unwrap();
+    let symmetries = board.symmetries();
+    assert_eq!(symmetries.len(), 1);
+    let (mirror, op) = symmetries[0];
+    assert_eq!(op, SymmetryOp::Mirror);
+    let expected = "rnbqkbnr/pppp1ppp/8/8/3PpP2/8/PPP1P1PP/RNBQKBNR b KQkq f3 0 1 variant:standard";
+    assert_eq!(mirror, Board::from_str(expected).unwrap());
+    let e5f6 = ChessMove::new(Square::E5, Square::F6, None);
+    assert!(board.legal(e5f6));
+    assert!(mirror.legal(e5f6.transform(op)));
+}
+
+#[test]
+fn test_checkmate_and_stalemate_by_variant() {
+    use BoardStatus::*;
+    // (fen, in normal chess, under the uno rules)
+    let positions = [
+        // fool's mate, where the queen checks from afar, which only a piece next to the king
+        // does under the uno rules
+        (
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+            Checkmate,
+            Ongoing,
+        ),
+        // the bishop covers the queen in normal chess, but under the uno rules the king takes
+        // the queen, which attacks it, and the bishop is not next to it there
+        ("7k/6Q1/8/8/3B4/8/8/K7 b - - 0 1", Checkmate, Ongoing),
+        // the king covers the queen, which is next to it, by either rules
+        ("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", Checkmate, Checkmate),
+        // the knight checks the king it is next to without attacking it, so it cannot be taken
+        ("6Nk/8/8/8/8/8/8/K7 b - - 0 1", Ongoing, Checkmate),
+        // the queen covers a7 in normal chess, but is not next to it
+        ("k7/2Q5/8/8/8/8/8/K7 b - - 0 1", Stalemate, Ongoing),
+        // the knight and the pawn are next to every square around the king
+        ("5N1k/8/7P/8/8/8/8/K7 b - - 0 1", Ongoing, Stalemate),
+        // the king and queen cover every square in both
+        ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", Stalemate, Stalemate),
+    ];
+    for (fen, standard, uno) in positions.iter() {
+        for (variant, status) in [(Variant::Standard, standard), (Variant::Uno, uno)].iter() {
+            let mut builder = BoardBuilder::from_str(fen).unwrap();
+            let board: Board = builder.variant(*variant).try_into().unwrap();
+            assert_eq!(board.status(), **status, "{} {:?}", fen, variant);
+
+            let stuck = MoveGen::new_legal(&board).len() == 0;
+            let checked = *board.checkers() != EMPTY;
+            assert_eq!(board.is_checkmate(), stuck && checked);
+            assert_eq!(board.is_stalemate(), stuck && !checked);
+        }
+    }
+}
+
+#[test]
+fn test_a_promotion_never_castles() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let board = Board::from_str(fen).unwrap();
+    for (dest, promotion) in [
+        (Square::G1, Piece::Queen),
+        (Square::H1, Piece::Queen),
+        (Square::A1, Piece::Knight),
+    ]
+    .iter()
+    {
+        let m = ChessMove::new(Square::E1, *dest, Some(*promotion));
+        assert_eq!(board.castle_rook(m), None, "{}", m);
+        assert!(!board.legal(m), "{}", m);
+    }
+    assert!(board.legal(ChessMove::new(Square::E1, Square::G1, None)));
+}
@@ -1,15 +1,21 @@
-use crate::board::Board;
-use crate::castle_rights::CastleRights;
-use crate::color::Color;
-use crate::error::Error;
-use crate::file::{File, ALL_FILES};
+use crate::board::{Board, MAX_PIECES};
+use crate::castle_rights::{CastleRights, Castled};
+use crate::color::{Color, ALL_COLORS};
+use crate::error::{Error, Span};
+use crate::file::{File, ALL_FILES, NUM_FILES};
 use crate::piece::Piece;
-use crate::rank::{Rank, ALL_RANKS};
+use crate::rank::{Rank, ALL_RANKS, NUM_RANKS};
 use crate::square::{Square, ALL_SQUARES};
+use crate::variant::Variant;
 
-use std::fmt;
-use std::ops::{Index, IndexMut};
-use std::str::FromStr;
+use core::fmt;
+use core::ops::{Index, IndexMut};
+use core::str::FromStr;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// Represents a chess position that has *not* been validated for legality.
 ///
@@ -21,13 +27,16 @@ use std::str::FromStr;
 /// * You want to convert between formats like FEN.
 ///
 /// ```
-/// use chess::{BoardBuilder, Board, Square, Color, Piece};
+/// use chess::{BoardBuilder, Board, Square, Color, Piece, Variant};
 /// use std::convert::TryFrom;
 /// let mut position = BoardBuilder::new();
 /// position.piece(Square::A1, Piece::King, Color::White);
 /// position.piece(Square::A8, Piece::Rook, Color::Black);
 /// position.piece(Square::D1, Piece::King, Color::Black);
 ///
+/// // In the uno variant only a piece next to the king gives check, so use normal chess.
+/// position.variant(Variant::Standard);
+///
 /// // You can index the position by the square:
 /// assert_eq!(position[Square::A1], Some((Piece::King, Color::White)));
 ///
@@ -52,14 +61,22 @@ pub struct BoardBuilder {
     pieces: [Option<(Piece, Color)>; 64],
     side_to_move: Color,
     castle_rights: [CastleRights; 2],
+    castled: [Castled; 2],
+    en_passant: Option<File>,
+    variant: Variant,
+    max_pieces: u32,
+    strict_material: bool,
 }
 
 impl BoardBuilder {
     /// Construct a new, empty, BoardBuilder.
     ///
     /// * No pieces are on the board
-    /// * `CastleRights` are empty for both sides
+    /// * `CastleRights` are empty for both sides, and neither side has castled
     /// * `side_to_move` is Color::White
+    /// * No en passant square
+    /// * `variant` is the default, `Variant::Uno`
+    /// * Each side may have 16 pieces, and the material is not checked strictly
     /// ```
     /// use chess::{BoardBuilder, Board, Square, Color, Piece};
     /// use std::convert::TryInto;
@@ -78,6 +95,11 @@ impl BoardBuilder {
             pieces: [None; 64],
             side_to_move: Color::White,
             castle_rights: [CastleRights::NoRights, CastleRights::NoRights],
+            castled: [Castled::No, Castled::No],
+            en_passant: None,
+            variant: Variant::default(),
+            max_pieces: MAX_PIECES,
+            strict_material: false,
         }
     }
 
@@ -101,16 +123,23 @@ impl BoardBuilder {
     ///     .try_into()?;
     /// # Ok(())
     /// # }
+    /// ```
     pub fn setup<'a>(
         pieces: impl IntoIterator<Item = &'a (Square, Piece, Color)>,
         side_to_move: Color,
         white_castle_rights: CastleRights,
         black_castle_rights: CastleRights,
+        en_passant: Option<File>,
     ) -> BoardBuilder {
         let mut result = BoardBuilder {
             pieces: [None; 64],
             side_to_move: side_to_move,
             castle_rights: [white_castle_rights, black_castle_rights],
+            castled: [Castled::No, Castled::No],
+            en_passant,
+            variant: Variant::default(),
+            max_pieces: MAX_PIECES,
+            strict_material: false,
         };
 
         for piece in pieces.into_iter() {
@@ -144,6 +173,93 @@ impl BoardBuilder {
         self.castle_rights[color.to_index()]
     }
 
+    /// Has a player castled?  A FEN cannot say, so this is `Castled::No` when read from one.
+    ///
+    /// ```
+    /// use chess::{BoardBuilder, Castled, Color};
+    ///
+    /// let mut bb = BoardBuilder::new();
+    /// bb.castled(Color::Black, Castled::QueenSide);
+    /// assert_eq!(bb.get_castled(Color::Black), Castled::QueenSide);
+    /// assert_eq!(bb.get_castled(Color::White), Castled::No);
+    /// ```
+    pub fn get_castled(&self, color: Color) -> Castled {
+        self.castled[color.to_index()]
+    }
+
+    /// Get the file a pawn can be captured en passant on, if there is one.  This is only used
+    /// in `Variant::Standard`.
+    ///
+    /// ```
+    /// use chess::{BoardBuilder, File};
+    /// use std::str::FromStr;
+    ///
+    /// let bb = BoardBuilder::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").expect("Valid FEN");
+    /// assert_eq!(bb.get_en_passant(), Some(File::D));
+    /// ```
+    pub fn get_en_passant(&self) -> Option<File> {
+        self.en_passant
+    }
+
+    /// Get the square of the king of `color`, if it has exactly one.  A `Board` can only be made
+    /// from a position where both sides have one.
+    ///
+    /// ```
+    /// use chess::{BoardBuilder, Color, Piece, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let mut bb = BoardBuilder::from_str("4k3/8/8/8/8/8/8/4K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(bb.king_square(Color::White), Some(Square::E1));
+    ///
+    /// bb.clear_square(Square::E1);
+    /// assert_eq!(bb.king_square(Color::White), None);
+    ///
+    /// bb.piece(Square::A8, Piece::King, Color::Black);
+    /// assert_eq!(bb.king_square(Color::Black), None);
+    /// ```
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        let mut kings = ALL_SQUARES
+            .iter()
+            .filter(|sq| self[**sq] == Some((Piece::King, color)));
+        match (kings.next(), kings.next()) {
+            (Some(square), None) => Some(*square),
+            _ => None,
+        }
+    }
+
+    /// Get the rules the position is played by.
+    ///
+    /// ```
+    /// use chess::{BoardBuilder, Variant};
+    ///
+    /// assert_eq!(BoardBuilder::new().get_variant(), Variant::Uno);
+    /// ```
+    pub fn get_variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Get the most pieces each side may have, counting its king.
+    ///
+    /// ```
+    /// use chess::BoardBuilder;
+    ///
+    /// assert_eq!(BoardBuilder::new().get_max_pieces(), 16);
+    /// ```
+    pub fn get_max_pieces(&self) -> u32 {
+        self.max_pieces
+    }
+
+    /// Get whether the material is checked strictly, see `strict_material`.
+    ///
+    /// ```
+    /// use chess::BoardBuilder;
+    ///
+    /// assert!(!BoardBuilder::new().get_strict_material());
+    /// ```
+    pub fn get_strict_material(&self) -> bool {
+        self.strict_material
+    }
+
     /// Set the side to move on the position
     ///
     /// This function can be used on self directly or in a builder pattern.
@@ -182,6 +298,259 @@ impl BoardBuilder {
         self
     }
 
+    /// Set whether a particular color has castled, and to which side, see `Board::castled`.
+    /// This is not checked against the castle rights or the position.
+    ///
+    /// This function can be used on self directly or in a builder pattern.
+    pub fn castled(&mut self, color: Color, castled: Castled) -> &mut Self {
+        self.castled[color.to_index()] = castled;
+        self
+    }
+
+    /// Set the file a pawn that just moved two squares is on, so that it can be captured en
+    /// passant, or `None` if there is no such pawn.  This is ignored unless the variant is
+    /// `Variant::Standard`.
+    ///
+    /// This function can be used on self directly or in a builder pattern.
+    ///
+    /// ```
+    /// use chess::{BoardBuilder, File};
+    /// BoardBuilder::new()
+    ///              .en_passant(Some(File::E));
+    ///
+    /// let mut bb = BoardBuilder::new();
+    /// bb.en_passant(None);
+    /// ```
+    pub fn en_passant(&mut self, file: Option<File>) -> &mut Self {
+        self.en_passant = file;
+        self
+    }
+
+    /// Set the rules the position is played by.
+    ///
+    /// This function can be used on self directly or in a builder pattern.
+    ///
+    /// ```
+    /// use chess::{BoardBuilder, Variant};
+    /// BoardBuilder::new()
+    ///              .variant(Variant::Standard);
+    ///
+    /// let mut bb = BoardBuilder::new();
+    /// bb.variant(Variant::Uno);
+    /// ```
+    pub fn variant(&mut self, variant: Variant) -> &mut Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Set the most pieces each side may have, counting its king, for experiments with fewer
+    /// pieces.  More than the 16 a side starts with is never allowed, as move generation relies
+    /// on it.
+    ///
+    /// ```
+    /// use chess::{Board, BoardBuilder, Error};
+    /// use std::convert::TryFrom;
+    ///
+    /// let mut bb = BoardBuilder::default();
+    /// assert!(Board::try_from(&bb).is_ok());
+    ///
+    /// bb.max_pieces(8);
+    /// assert!(matches!(Board::try_from(&bb), Err(Error::TooManyPieces)));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `max_pieces` is more than 16.
+    pub fn max_pieces(&mut self, max_pieces: u32) -> &mut Self {
+        assert!(max_pieces <= MAX_PIECES, "at most 16 pieces per side");
+        self.max_pieces = max_pieces;
+        self
+    }
+
+    /// Check the material strictly: every knight, bishop and rook past the two a side starts
+    /// with, and every queen past the one, must have been promoted from one of its eight pawns.
+    ///
+    /// ```
+    /// use chess::{Board, BoardBuilder, Error};
+    /// use std::convert::TryFrom;
+    /// use std::str::FromStr;
+    ///
+    /// // eight pawns and a third knight
+    /// let mut bb = BoardBuilder::from_str("4k3/8/8/8/8/2N5/PPPPPPPP/RN2K1NR w - - 0 1").unwrap();
+    /// assert!(Board::try_from(&bb).is_ok());
+    ///
+    /// bb.strict_material(true);
+    /// assert!(matches!(Board::try_from(&bb), Err(Error::ImpossibleMaterial)));
+    /// ```
+    pub fn strict_material(&mut self, strict_material: bool) -> &mut Self {
+        self.strict_material = strict_material;
+        self
+    }
+
+    /// Write the position as a FEN with a seventh field naming the variant, such as
+    /// `variant:standard`.  Parsing that FEN restores the variant, while a plain FEN without the
+    /// field gets the default `Variant::Uno`.
+    ///
+    /// ```
+    /// use chess::{BoardBuilder, Variant};
+    /// use std::str::FromStr;
+    ///
+    /// let mut builder = BoardBuilder::default();
+    /// builder.variant(Variant::Standard);
+    ///
+    /// let fen = builder.to_tagged_fen();
+    /// assert_eq!(
+    ///     fen,
+    ///     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 variant:standard"
+    /// );
+    /// assert_eq!(BoardBuilder::from_str(&fen).unwrap().get_variant(), Variant::Standard);
+    ///
+    /// // an unknown variant is an error
+    /// let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 variant:atomic";
+    /// assert!(BoardBuilder::from_str(fen).is_err());
+    /// ```
+    pub fn to_tagged_fen(&self) -> String {
+        format!("{} variant:{}", self, self.variant)
+    }
+
+    /// Draw the position as a diagram, one rank per line from the eighth down, with the rank and
+    /// file labels and then the other fields of `to_tagged_fen`.  `BoardBuilder::from_diagram`
+    /// reads it back.
+    ///
+    /// ```
+    /// use chess::BoardBuilder;
+    ///
+    /// assert_eq!(
+    ///     BoardBuilder::default().to_ascii(),
+    ///     "8 r n b q k b n r\n\
+    ///      7 p p p p p p p p\n\
+    ///      6 . . . . . . . .\n\
+    ///      5 . . . . . . . .\n\
+    ///      4 . . . . . . . .\n\
+    ///      3 . . . . . . . .\n\
+    ///      2 P P P P P P P P\n\
+    ///      1 R N B Q K B N R\n\
+    ///     \x20 a b c d e f g h\n\
+    ///      w KQkq - 0 1 variant:uno\n"
+    /// );
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        let mut result = String::new();
+        for rank in ALL_RANKS.iter().rev() {
+            result.push_str(&format!("{}", rank.to_index() + 1));
+            for file in ALL_FILES.iter() {
+                result.push(' ');
+                result.push(match self[Square::make_square(*rank, *file)] {
+                    Some((piece, color)) => piece.to_char(color),
+                    None => '.',
+                });
+            }
+            result.push('\n');
+        }
+        result.push_str("  a b c d e f g h\n");
+        let fen = self.to_tagged_fen();
+        result.push_str(fen.split_once(' ').map_or("", |(_, fields)| fields));
+        result.push('\n');
+        result
+    }
+
+    /// Read a diagram of the position, such as the one `to_ascii` draws.
+    ///
+    /// The diagram has the eight ranks from the eighth down, one per line, each with a piece
+    /// letter or an empty square ('.' or a space) for every file.  The squares can be separated by
+    /// single spaces or written next to each other, and a rank may leave out empty squares at its
+    /// end.  Rank labels in front of the ranks, file labels above or below them, blank lines and
+    /// an indentation shared by every line are ignored.  An optional last line has the other
+    /// fields of a FEN, and any that are left out default to "w - - 0 1".
+    ///
+    /// ```
+    /// use chess::{BoardBuilder, Color, Piece, Square};
+    ///
+    /// let builder = BoardBuilder::from_diagram(
+    ///     "
+    ///     . . . . k . . .
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     . . . . . . . .
+    ///     . . . . P . . .
+    ///     . . . . K . . R
+    ///     b K
+    ///     ",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(builder[Square::H1], Some((Piece::Rook, Color::White)));
+    /// assert_eq!(builder.get_side_to_move(), Color::Black);
+    ///
+    /// // a rank is missing
+    /// assert!(BoardBuilder::from_diagram("4k3\n8\n8\n8\n8\n8\n4K3").is_err());
+    /// ```
+    pub fn from_diagram(diagram: &str) -> Result<BoardBuilder, Error> {
+        let lines: Vec<&str> = diagram
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let indent = lines
+            .iter()
+            .map(|line| {
+                line.bytes()
+                    .take_while(|b| *b == b' ' || *b == b'\t')
+                    .count()
+            })
+            .min()
+            .unwrap_or(0);
+
+        let mut placement = String::new();
+        let mut ranks = 0;
+        let mut fields = None;
+        for line in lines.iter() {
+            let line = &line[indent..];
+            // file labels
+            if line.split_whitespace().collect::<String>() == "abcdefgh" {
+                continue;
+            }
+            if ranks == NUM_RANKS {
+                if fields.is_some() {
+                    return Err(Error::InvalidDiagram);
+                }
+                fields = Some(line);
+                continue;
+            }
+
+            if ranks != 0 {
+                placement.push('/');
+            }
+            let mut empty = 0;
+            for square in diagram_rank(line)?.iter() {
+                match square {
+                    Some((piece, color)) => {
+                        if empty != 0 {
+                            placement.push_str(&format!("{}", empty));
+                            empty = 0;
+                        }
+                        placement.push(piece.to_char(*color));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty != 0 {
+                placement.push_str(&format!("{}", empty));
+            }
+            ranks += 1;
+        }
+        if ranks != NUM_RANKS {
+            return Err(Error::InvalidDiagram);
+        }
+
+        let mut fields: Vec<&str> = fields.unwrap_or("").split_whitespace().collect();
+        let defaults = ["w", "-", "-", "0", "1"];
+        if fields.len() < defaults.len() {
+            fields.extend_from_slice(&defaults[fields.len()..]);
+        }
+        BoardBuilder::from_str(&format!("{} {}", placement, fields.join(" ")))
+    }
+
     /// Set a piece on a square.
     ///
     /// Note that this can and will overwrite another piece on the square if need.
@@ -220,6 +589,71 @@ impl BoardBuilder {
         self[square] = None;
         self
     }
+
+    // The castle rights of `color` in X-FEN: K or Q for the outermost rook on its side, so
+    // always in normal chess, and the file of the rook otherwise.
+    fn castle_rights_fen(&self, color: Color) -> String {
+        let rights = self.castle_rights[color.to_index()];
+        let rook_on = |file: &File| {
+            self[Square::make_square(color.back_rank(), *file)] == Some((Piece::Rook, color))
+        };
+        let mut result = String::new();
+        if let Some(file) = rights.kingside_rook() {
+            let outermost = !ALL_FILES[file.to_index() + 1..].iter().any(rook_on);
+            result.push(if outermost {
+                'k'
+            } else {
+                (b'a' + file.to_index() as u8) as char
+            });
+        }
+        if let Some(file) = rights.queenside_rook() {
+            let outermost = !ALL_FILES[..file.to_index()].iter().any(rook_on);
+            result.push(if outermost {
+                'q'
+            } else {
+                (b'a' + file.to_index() as u8) as char
+            });
+        }
+
+        if color == Color::White {
+            result.to_uppercase()
+        } else {
+            result
+        }
+    }
+}
+
+// The squares of one rank of a diagram, see `BoardBuilder::from_diagram`.
+fn diagram_rank(line: &str) -> Result<[Option<(Piece, Color)>; NUM_FILES], Error> {
+    let mut line = line;
+    if let Some(rest) = line.strip_prefix(|c| ('1'..='8').contains(&c)) {
+        line = rest.strip_prefix(' ').unwrap_or(rest);
+    }
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.trim_end().strip_suffix('|').unwrap_or(line.trim_end());
+
+    let chars: Vec<char> = line.chars().collect();
+    let squares: Vec<char> = if chars.len() > NUM_FILES {
+        // squares separated by spaces
+        if chars.iter().skip(1).step_by(2).any(|c| *c != ' ') {
+            return Err(Error::InvalidDiagram);
+        }
+        chars.iter().step_by(2).copied().collect()
+    } else {
+        chars
+    };
+    if squares.len() > NUM_FILES {
+        return Err(Error::InvalidDiagram);
+    }
+
+    let mut result = [None; NUM_FILES];
+    for (file, c) in squares.iter().enumerate() {
+        result[file] = match c {
+            '.' | ' ' => None,
+            _ => Some(Piece::from_char(*c).ok_or(Error::InvalidDiagram)?),
+        };
+    }
+    Ok(result)
 }
 
 impl Index<Square> for BoardBuilder {
@@ -249,7 +683,7 @@ impl fmt::Display for BoardBuilder {
                 }
 
                 if let Some((piece, color)) = self.pieces[square] {
-                    write!(f, "{}", piece.to_string(color))?;
+                    write!(f, "{}", piece.to_char(color))?;
                 } else {
                     count += 1;
                 }
@@ -273,16 +707,8 @@ impl fmt::Display for BoardBuilder {
             write!(f, "b ")?;
         }
 
-        write!(
-            f,
-            "{}",
-            self.castle_rights[Color::White.to_index()].to_string(Color::White)
-        )?;
-        write!(
-            f,
-            "{}",
-            self.castle_rights[Color::Black.to_index()].to_string(Color::Black)
-        )?;
+        write!(f, "{}", self.castle_rights_fen(Color::White))?;
+        write!(f, "{}", self.castle_rights_fen(Color::Black))?;
         if self.castle_rights[0] == CastleRights::NoRights
             && self.castle_rights[1] == CastleRights::NoRights
         {
@@ -290,7 +716,16 @@ impl fmt::Display for BoardBuilder {
         }
 
         write!(f, " ")?;
-        write!(f, "-")?;
+        if let Some(file) = self.en_passant {
+            let rank = if self.side_to_move == Color::White {
+                Rank::Sixth
+            } else {
+                Rank::Third
+            };
+            write!(f, "{}", Square::make_square(rank, file))?;
+        } else {
+            write!(f, "-")?;
+        }
 
         write!(f, " 0 1")
     }
@@ -307,125 +742,123 @@ impl FromStr for BoardBuilder {
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         let mut cur_rank = Rank::Eighth;
-        let mut cur_file = File::A;
         let mut fen = &mut BoardBuilder::new();
 
-        let tokens: Vec<&str> = value.split(' ').collect();
+        let invalid = |start: usize, end: usize| Error::InvalidFen {
+            fen: value.to_string(),
+            span: Span { start, end },
+        };
+
+        // each token with where it starts, for the span of an error
+        let mut offset = 0;
+        let tokens: Vec<(usize, &str)> = value
+            .split(' ')
+            .map(|token| {
+                let start = offset;
+                offset += token.len() + 1;
+                (start, token)
+            })
+            .collect();
         if tokens.len() < 4 {
-            return Err(Error::InvalidFen {
-                fen: value.to_string(),
-            });
+            return Err(invalid(0, value.len()));
         }
 
-        let pieces = tokens[0];
+        let pieces = tokens[0].1;
         let side = tokens[1];
-        let castles = tokens[2];
+        let castles = tokens[2].1;
+        let ep = tokens[3].1;
 
-        for x in pieces.chars() {
+        // every rank has to add up to exactly eight squares, so count them instead of letting the
+        // files wrap around
+        let mut files = 0;
+        let mut rank_start = 0;
+        for (i, x) in pieces.char_indices() {
             match x {
                 '/' => {
+                    if cur_rank == Rank::First || i == rank_start {
+                        return Err(invalid(i, i + 1));
+                    }
+                    if files != NUM_FILES {
+                        return Err(invalid(rank_start, i));
+                    }
                     cur_rank = cur_rank.down();
-                    cur_file = File::A;
+                    files = 0;
+                    rank_start = i + 1;
                 }
                 '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' => {
-                    cur_file =
-                        File::from_index(cur_file.to_index() + (x as usize) - ('0' as usize));
-                }
-                'r' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Rook, Color::Black));
-                    cur_file = cur_file.right();
-                }
-                'R' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Rook, Color::White));
-                    cur_file = cur_file.right();
-                }
-                'n' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Knight, Color::Black));
-                    cur_file = cur_file.right();
-                }
-                'N' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Knight, Color::White));
-                    cur_file = cur_file.right();
-                }
-                'b' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Bishop, Color::Black));
-                    cur_file = cur_file.right();
-                }
-                'B' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Bishop, Color::White));
-                    cur_file = cur_file.right();
-                }
-                'p' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Pawn, Color::Black));
-                    cur_file = cur_file.right();
-                }
-                'P' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Pawn, Color::White));
-                    cur_file = cur_file.right();
-                }
-                'q' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Queen, Color::Black));
-                    cur_file = cur_file.right();
-                }
-                'Q' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::Queen, Color::White));
-                    cur_file = cur_file.right();
-                }
-                'k' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::King, Color::Black));
-                    cur_file = cur_file.right();
-                }
-                'K' => {
-                    fen[Square::make_square(cur_rank, cur_file)] =
-                        Some((Piece::King, Color::White));
-                    cur_file = cur_file.right();
-                }
-                _ => {
-                    return Err(Error::InvalidFen {
-                        fen: value.to_string(),
-                    });
+                    files += (x as usize) - ('0' as usize);
                 }
+                _ => match Piece::from_char(x) {
+                    Some(piece) if files < NUM_FILES => {
+                        fen[Square::make_square(cur_rank, File::from_index(files))] = Some(piece);
+                        files += 1;
+                    }
+                    _ => return Err(invalid(i, i + x.len_utf8())),
+                },
             }
         }
-        match side {
+        if files != NUM_FILES {
+            return Err(invalid(rank_start, pieces.len()));
+        }
+        if cur_rank != Rank::First {
+            return Err(invalid(0, pieces.len()));
+        }
+
+        match side.1 {
             "w" | "W" => fen = fen.side_to_move(Color::White),
             "b" | "B" => fen = fen.side_to_move(Color::Black),
-            _ => {
-                return Err(Error::InvalidFen {
-                    fen: value.to_string(),
-                })
-            }
+            _ => return Err(invalid(side.0, side.0 + side.1.len())),
         }
 
-        if castles.contains("K") && castles.contains("Q") {
-            fen.castle_rights[Color::White.to_index()] = CastleRights::Both;
-        } else if castles.contains("K") {
-            fen.castle_rights[Color::White.to_index()] = CastleRights::KingSide;
-        } else if castles.contains("Q") {
-            fen.castle_rights[Color::White.to_index()] = CastleRights::QueenSide;
-        } else {
-            fen.castle_rights[Color::White.to_index()] = CastleRights::NoRights;
+        // As in X-FEN, K and Q castle with the outermost rook on that side of the king, and as in
+        // Shredder-FEN, a file letter castles with the rook on that file, for Chess960.
+        for c in castles.chars() {
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let backrank = color.back_rank();
+            let on = |file: &File, piece| {
+                fen[Square::make_square(backrank, *file)] == Some((piece, color))
+            };
+            let king = ALL_FILES.iter().find(|file| on(file, Piece::King)).copied();
+            // the outermost rook, or the rook of normal chess if there is none
+            let outermost = |files: &mut dyn Iterator<Item = &File>, default| {
+                files
+                    .take_while(|file| Some(**file) != king)
+                    .find(|file| on(file, Piece::Rook))
+                    .copied()
+                    .unwrap_or(default)
+            };
+            let rights = match c.to_ascii_lowercase() {
+                'k' => {
+                    CastleRights::new(Some(outermost(&mut ALL_FILES.iter().rev(), File::H)), None)
+                }
+                'q' => CastleRights::new(None, Some(outermost(&mut ALL_FILES.iter(), File::A))),
+                letter @ 'a'..='h' => {
+                    let file = File::from_index((letter as usize) - ('a' as usize));
+                    if file > king.unwrap_or(File::E) {
+                        CastleRights::new(Some(file), None)
+                    } else {
+                        CastleRights::new(None, Some(file))
+                    }
+                }
+                _ => continue,
+            };
+            fen.castle_rights[color.to_index()] = fen.castle_rights[color.to_index()].add(rights);
         }
 
-        if castles.contains("k") && castles.contains("q") {
-            fen.castle_rights[Color::Black.to_index()] = CastleRights::Both;
-        } else if castles.contains("k") {
-            fen.castle_rights[Color::Black.to_index()] = CastleRights::KingSide;
-        } else if castles.contains("q") {
-            fen.castle_rights[Color::Black.to_index()] = CastleRights::QueenSide;
-        } else {
-            fen.castle_rights[Color::Black.to_index()] = CastleRights::NoRights;
+        if let Ok(sq) = Square::from_str(ep) {
+            fen = fen.en_passant(Some(sq.get_file()));
+        }
+
+        // the variant, written by `BoardBuilder::to_tagged_fen` after the move counters
+        if let Some((start, tag)) = tokens.get(6) {
+            match tag.strip_prefix("variant:").map(Variant::from_str) {
+                Some(Ok(variant)) => fen = fen.variant(variant),
+                _ => return Err(invalid(*start, start + tag.len())),
+            }
         }
 
         Ok(*fen)
@@ -442,12 +875,18 @@ impl From<&Board> for BoardBuilder {
             }
         }
 
-        BoardBuilder::setup(
+        let mut result = BoardBuilder::setup(
             &pieces,
             board.side_to_move(),
             board.castle_rights(Color::White),
             board.castle_rights(Color::Black),
-        )
+            board.en_passant().map(|sq| sq.get_file()),
+        );
+        result.variant(board.variant());
+        for color in ALL_COLORS.iter() {
+            result.castled(*color, board.castled(*color));
+        }
+        result
     }
 }
 
@@ -460,7 +899,7 @@ impl From<Board> for BoardBuilder {
 #[cfg(test)]
 use crate::bitboard::BitBoard;
 #[cfg(test)]
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
 #[test]
 fn check_initial_position() {
@@ -483,6 +922,95 @@ fn invalid_castle_rights() {
     assert!(res.is_err());
 }
 
+#[test]
+fn test_ranks_must_have_eight_squares() {
+    assert!(BoardBuilder::from_str("4k3/8/8/8/8/8/8/4K3 w - - 0 1").is_ok());
+    for fen in [
+        "4k4/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k2/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3/8 w - - 0 1",
+        "4k3/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3R w - - 0 1",
+        "4k3/8/8/8/8/8/8/44K3 w - - 0 1",
+    ]
+    .iter()
+    {
+        assert!(BoardBuilder::from_str(fen).is_err(), "{}", fen);
+    }
+
+    // the ranks add up, but white has seventeen pieces
+    assert!(Board::from_str("4k3/8/8/8/8/QQQQQQQQ/QQQQQQQQ/4K3 w - - 0 1").is_err());
+}
+
+#[test]
+fn test_diagram_round_trip() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 variant:standard",
+        "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1 variant:standard",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        let diagram = board.to_ascii();
+        assert_eq!(Board::from_diagram(&diagram).unwrap(), board, "{}", diagram);
+    }
+}
+
+#[test]
+fn test_diagram_formats() {
+    let expected = Board::from_str("r3k3/8/8/8/8/8/8/4K2R w - - 0 1").unwrap();
+    for diagram in [
+        // spaces between the squares, labels and a border
+        "  a b c d e f g h\n8|r . . . k . . .|\n7|. . . . . . . .|\n6|. . . . . . . .|\n\
+         5|. . . . . . . .|\n4|. . . . . . . .|\n3|. . . . . . . .|\n2|. . . . . . . .|\n\
+         1|. . . . K . . R|",
+        // squares next to each other, with spaces for empty squares and the ends left out
+        "r   k\n.\n.\n.\n.\n.\n.\n    K  R\nw - - 0 1",
+        // indented like a raw string in a test
+        "
+            r...k...
+            ........
+            ........
+            ........
+            ........
+            ........
+            ........
+            ....K..R
+            w
+        ",
+    ]
+    .iter()
+    {
+        assert_eq!(
+            Board::from_diagram(diagram).unwrap(),
+            expected,
+            "{}",
+            diagram
+        );
+    }
+}
+
+#[test]
+fn test_invalid_diagrams() {
+    let rank = ". . . . . . . .\n";
+    let kings = "....k...\n........\n........\n........\n........\n........\n........\n";
+    for diagram in [
+        format!("{}....K...", &kings[9..]),
+        format!("{}{}....K...", kings, rank),
+        format!("{}....K....", kings),
+        format!("{}....K..x", kings),
+        format!("{}. . . . K . .  .", kings),
+        format!("{}....K...\nw - -\nb", kings),
+        format!("{}....K...\nx", kings),
+    ]
+    .iter()
+    {
+        assert!(Board::from_diagram(diagram).is_err(), "{}", diagram);
+    }
+}
+
 #[test]
 fn test_kissing_kings() {
     let res: Result<Board, _> = BoardBuilder::new()
@@ -492,12 +1020,99 @@ fn test_kissing_kings() {
     assert!(res.is_err());
 }
 
+// every way of making a board from `fen`, with the default checks, must fail with `expected`
+#[cfg(test)]
+fn assert_rejected(fen: &str, expected: Error) {
+    use core::mem::discriminant;
+
+    let is_expected = |res: Result<Board, Error>| match res {
+        Err(error) => discriminant(&error) == discriminant(&expected),
+        Ok(_) => false,
+    };
+    let builder = BoardBuilder::from_str(fen).unwrap();
+    assert!(is_expected(builder.try_into()), "{}", fen);
+    assert!(is_expected(Board::from_str(fen)), "{}", fen);
+
+    // a diagram is of the default variant, with white to move and no castling
+    let mut fields = fen.split(' ');
+    let placement = fields.next().unwrap();
+    if fields.collect::<Vec<_>>() == ["w", "-", "-", "0", "1"] {
+        let diagram: Vec<String> = placement
+            .split('/')
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| match c.to_digit(10) {
+                        Some(n) => ".".repeat(n as usize),
+                        None => c.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let diagram = diagram.join("\n");
+        assert!(is_expected(Board::from_diagram(&diagram)), "{}", fen);
+    }
+}
+
+#[test]
+fn test_invalid_boards_are_rejected() {
+    for (fen, expected) in [
+        // a pawn on the rank it promotes on, or on its own first rank in normal chess
+        ("4k2P/8/8/8/8/8/8/4K3 w - - 0 1", Error::PawnOnBackRank),
+        ("4k3/8/8/8/8/8/8/p3K3 w - - 0 1", Error::PawnOnBackRank),
+        (
+            "4k3/8/8/8/8/8/8/P3K3 w - - 0 1 variant:standard",
+            Error::PawnOnBackRank,
+        ),
+        // no king, or two
+        ("8/8/8/8/8/8/8/4K3 w - - 0 1", Error::InvalidKingCount),
+        ("4k3/8/8/8/8/8/8/8 b - - 0 1", Error::InvalidKingCount),
+        ("8/8/8/8/8/8/8/8 w - - 0 1", Error::InvalidKingCount),
+        ("4k3/8/8/8/8/8/8/K3K3 w - - 0 1", Error::InvalidKingCount),
+        // seventeen white pieces
+        (
+            "4k3/8/8/8/PPPPPPPP/PPPPPPPP/8/4K3 w - - 0 1",
+            Error::TooManyPieces,
+        ),
+        // white to move, with black in check, or the kings touching
+        ("4k3/4R3/8/8/8/8/8/4K3 w - - 0 1", Error::OpponentInCheck),
+        (
+            "4k3/8/8/8/8/8/8/3KR3 w - - 0 1 variant:standard",
+            Error::OpponentInCheck,
+        ),
+        ("8/8/8/8/8/8/k7/K7 w - - 0 1", Error::OpponentInCheck),
+    ]
+    .iter()
+    {
+        assert_rejected(fen, expected.clone());
+    }
+
+    // under the uno rules a pawn can capture back onto its own first rank
+    assert!(Board::from_str("4k3/8/8/8/8/8/8/P3K3 w - - 0 1").is_ok());
+
+    // the checks that can be changed
+    let mut builder = BoardBuilder::from_str("4k3/8/8/8/8/P7/PPPPPPPP/4K3 w - - 0 1").unwrap();
+    assert!(Board::try_from(&builder).is_ok());
+    builder.strict_material(true);
+    assert!(matches!(
+        Board::try_from(&builder),
+        Err(Error::ImpossibleMaterial)
+    ));
+    builder.strict_material(false).max_pieces(9);
+    assert!(matches!(
+        Board::try_from(&builder),
+        Err(Error::TooManyPieces)
+    ));
+    builder.max_pieces(10);
+    assert!(Board::try_from(&builder).is_ok());
+}
+
 #[test]
 fn test_in_check() {
     let mut bb: BoardBuilder = BoardBuilder::new();
     bb.piece(Square::A1, Piece::King, Color::White)
         .piece(Square::A8, Piece::King, Color::Black)
-        .piece(Square::H1, Piece::Rook, Color::Black);
+        .piece(Square::H1, Piece::Rook, Color::Black)
+        .variant(Variant::Standard);
 
     let board: Board = (&bb).try_into().unwrap();
     assert_eq!(*board.checkers(), BitBoard::from_square(Square::H1));
@@ -506,3 +1121,99 @@ fn test_in_check() {
     let res: Result<Board, _> = bb.try_into();
     assert!(res.is_err()); // My opponent cannot be in check when it's my move.
 }
+
+#[test]
+fn test_tagged_fen_round_trip() {
+    use crate::variant::ALL_VARIANTS;
+
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1",
+    ];
+    for fen in fens.iter() {
+        // a plain FEN gets the default variant
+        assert_eq!(
+            BoardBuilder::from_str(fen).unwrap().get_variant(),
+            Variant::default()
+        );
+
+        for variant in ALL_VARIANTS.iter() {
+            let mut builder = BoardBuilder::from_str(fen).unwrap();
+            builder.variant(*variant);
+            let board: Board = builder.try_into().unwrap();
+
+            let tagged = board.to_tagged_fen();
+            assert_eq!(tagged, format!("{} variant:{}", board, variant));
+            let parsed = Board::from_str(&tagged).unwrap();
+            assert_eq!(parsed.variant(), *variant);
+            assert_eq!(parsed, board);
+            assert_eq!(parsed.to_tagged_fen(), tagged);
+        }
+    }
+
+    // the seventh field has to name a known variant
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    for tag in ["variant:atomic", "variant:", "variant:Uno", "uno", "xyz"].iter() {
+        let fen = format!("{} {}", start, tag);
+        assert!(BoardBuilder::from_str(&fen).is_err(), "{}", fen);
+        assert!(Board::from_str(&fen).is_err(), "{}", fen);
+    }
+}
+
+#[test]
+fn test_chess960_castle_rights() {
+    use crate::file::File;
+
+    // X-FEN and Shredder-FEN name the same rights in the normal start position
+    let xfen = BoardBuilder::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let shredder = BoardBuilder::from_str("r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1").unwrap();
+    assert_eq!(xfen.get_castle_rights(Color::White), CastleRights::Both);
+    assert_eq!(shredder.get_castle_rights(Color::Black), CastleRights::Both);
+    assert_eq!(format!("{}", shredder), format!("{}", xfen));
+
+    // 'K' is the outermost rook, so an inner rook needs its file letter
+    let inner = BoardBuilder::from_str("4k3/8/8/8/8/8/8/1R2K1RR w G - 0 1").unwrap();
+    let rights = inner.get_castle_rights(Color::White);
+    assert_eq!(rights.kingside_rook(), Some(File::G));
+    assert_eq!(rights.queenside_rook(), None);
+    assert!(format!("{}", inner).contains(" w G - "));
+    let outer = BoardBuilder::from_str("4k3/8/8/8/8/8/8/1R2K1RR w KQ - 0 1").unwrap();
+    let rights = outer.get_castle_rights(Color::White);
+    assert_eq!(rights.kingside_rook(), Some(File::H));
+    assert_eq!(rights.queenside_rook(), Some(File::B));
+    assert!(format!("{}", outer).contains(" w KQ - "));
+
+    let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 0 1";
+    let builder = BoardBuilder::from_str(fen).unwrap();
+    assert_eq!(
+        builder.get_castle_rights(Color::Black),
+        CastleRights::new(Some(File::H), Some(File::F))
+    );
+    assert_eq!(format!("{}", builder), fen);
+    let board: Board = builder.try_into().unwrap();
+    assert_eq!(Board::from_str(fen).unwrap(), board);
+}
+
+#[test]
+fn test_fen_errors_carry_the_span() {
+    let span = |fen: &str| BoardBuilder::from_str(fen).err().unwrap().span();
+
+    // a bad piece letter in the middle of a rank is the letter itself
+    let fen = "rnbqkbnr/pppppppp/8/3Z4/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(span(fen), Some(Span { start: 21, end: 22 }));
+    let error = BoardBuilder::from_str(fen).err().unwrap();
+    let message = error.display_with_source(fen);
+    assert!(message.ends_with(&format!("\n{}\n{}^", fen, " ".repeat(21))));
+
+    // a rank that does not add up to eight squares is the rank
+    let fen = "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(span(fen), Some(Span { start: 9, end: 16 }));
+    // and other tokens are the whole token
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1";
+    assert_eq!(span(fen), Some(Span { start: 44, end: 45 }));
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 chess";
+    assert_eq!(span(fen), Some(Span { start: 57, end: 62 }));
+    assert_eq!(span("8/8 w"), Some(Span { start: 0, end: 5 }));
+}
@@ -0,0 +1,289 @@
+use crate::board::Board;
+use crate::chess_move::ChessMove;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+// One position in a `BoardHistory`.  Nothing before a boundary can repeat it or anything after
+// it: the first position, one after an irreversible move, or one after a null move.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Ply {
+    hash: u64,
+    boundary: bool,
+    null: bool,
+}
+
+/// The hashes of the positions a search went through, to find repetitions as it goes.
+///
+/// A search pushes each move it makes, and pops it when it takes the move back.  A null move is
+/// pushed with `push_null` and popped with `pop_null` instead.  A position from before a null
+/// move never counts as a repetition of one after it, since passing is not a move in the game,
+/// and neither does a position from before a capture, a pawn move or a lost castle right, which
+/// cannot come back.
+///
+/// `with_capacity` makes room for as many moves as a search goes deep, after which pushing and
+/// popping do not allocate.
+///
+/// ```
+/// use chess::{Board, BoardHistory, ChessMove, Square};
+///
+/// let board = Board::default();
+/// let mut history = BoardHistory::new(&board);
+///
+/// let mut position = board;
+/// for m in ["g1f3", "g8f6", "f3g1", "f6g8"].iter() {
+///     let m: ChessMove = m.parse().unwrap();
+///     history.push(&position, m);
+///     position = position.make_move_new(m);
+/// }
+/// assert_eq!(position, board);
+/// assert_eq!(history.repetitions(), 1);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BoardHistory {
+    plies: Vec<Ply>,
+}
+
+impl BoardHistory {
+    /// Start a history at `board`.
+    pub fn new(board: &Board) -> BoardHistory {
+        BoardHistory {
+            plies: vec![Ply {
+                hash: board.get_hash(),
+                boundary: true,
+                null: false,
+            }],
+        }
+    }
+
+    /// Start a history at `board`, with room for `capacity` moves and null moves before it
+    /// allocates again.
+    pub fn with_capacity(board: &Board, capacity: usize) -> BoardHistory {
+        let mut plies = Vec::with_capacity(capacity + 1);
+        plies.push(Ply {
+            hash: board.get_hash(),
+            boundary: true,
+            null: false,
+        });
+        BoardHistory { plies }
+    }
+
+    /// Record `m`, made on `board`, which must be the last position in the history.
+    ///
+    /// panic!() if there is no piece on the source square of `m`.
+    pub fn push(&mut self, board: &Board, m: ChessMove) {
+        debug_assert_eq!(Some(board.get_hash()), self.hash());
+        self.plies.push(Ply {
+            hash: board.hash_after(m),
+            boundary: board.is_irreversible(m),
+            null: false,
+        });
+    }
+
+    /// Take back the last move pushed with `push`.
+    ///
+    /// panic!() if the last entry is a null move, or the starting position.
+    pub fn pop(&mut self) {
+        assert!(
+            self.plies.len() > 1,
+            "the starting position cannot be taken back"
+        );
+        let ply = self.plies.pop().unwrap();
+        assert!(!ply.null, "a null move is taken back with pop_null");
+    }
+
+    /// Record a null move on `board`, which must be the last position in the history.
+    pub fn push_null(&mut self, board: &Board) {
+        debug_assert_eq!(Some(board.get_hash()), self.hash());
+        self.plies.push(Ply {
+            hash: board.hash_after_null(),
+            boundary: true,
+            null: true,
+        });
+    }
+
+    /// Take back the last null move pushed with `push_null`.
+    ///
+    /// panic!() if the last entry is not a null move.
+    pub fn pop_null(&mut self) {
+        let ply = self.plies.pop();
+        assert!(
+            matches!(ply, Some(Ply { null: true, .. })),
+            "only a null move is taken back with pop_null"
+        );
+    }
+
+    /// The hash of the last position in the history.
+    pub fn hash(&self) -> Option<u64> {
+        self.plies.last().map(|ply| ply.hash)
+    }
+
+    /// How many moves and null moves have been pushed since the starting position.
+    pub fn len(&self) -> usize {
+        self.plies.len() - 1
+    }
+
+    /// Have no moves been pushed since the starting position?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many times the last position came before, since the last null move or irreversible
+    /// move.  A search may score a position that came once before as a draw, where the game
+    /// only is after it came twice, see `is_threefold`.
+    pub fn repetitions(&self) -> usize {
+        let (last, earlier) = self.plies.split_last().unwrap();
+        if last.boundary {
+            return 0;
+        }
+        let mut count = 0;
+        for ply in earlier.iter().rev() {
+            if ply.hash == last.hash {
+                count += 1;
+            }
+            if ply.boundary {
+                break;
+            }
+        }
+        count
+    }
+
+    /// Has `current`, which must be the last position in the history, come `count` times
+    /// counting itself, since the last null move or irreversible move?  A search usually asks
+    /// for a `count` of 2, and the game for 3.
+    ///
+    /// ```
+    /// use chess::{Board, BoardHistory, ChessMove};
+    ///
+    /// let mut position = Board::default();
+    /// let mut history = BoardHistory::with_capacity(&position, 64);
+    /// for m in ["b1c3", "b8c6", "c3b1", "c6b8"].iter() {
+    ///     let m: ChessMove = m.parse().unwrap();
+    ///     history.push(&position, m);
+    ///     position = position.make_move_new(m);
+    /// }
+    /// assert!(history.is_repetition(&position, 2));
+    /// assert!(!history.is_repetition(&position, 3));
+    /// ```
+    pub fn is_repetition(&self, current: &Board, count: u8) -> bool {
+        debug_assert_eq!(Some(current.get_hash()), self.hash());
+        self.repetitions() + 1 >= usize::from(count)
+    }
+
+    /// Has the last position come three times, since the last null move or irreversible move?
+    pub fn is_threefold(&self) -> bool {
+        self.repetitions() >= 2
+    }
+}
+
+#[cfg(test)]
+use core::str::FromStr;
+
+#[cfg(test)]
+fn play(history: &mut BoardHistory, board: &mut Board, moves: &str) {
+    for m in moves.split_whitespace() {
+        let m = ChessMove::from_str(m).unwrap();
+        history.push(board, m);
+        *board = board.make_move_new(m);
+        assert_eq!(history.hash(), Some(board.get_hash()));
+    }
+}
+
+#[test]
+fn test_repetitions() {
+    let mut board = Board::default();
+    let mut history = BoardHistory::new(&board);
+    let knights = "g1f3 g8f6 f3g1 f6g8";
+    play(&mut history, &mut board, knights);
+    assert_eq!(history.repetitions(), 1);
+    assert!(!history.is_threefold());
+    play(&mut history, &mut board, knights);
+    assert_eq!(history.repetitions(), 2);
+    assert!(history.is_threefold());
+    assert_eq!(history.len(), 8);
+
+    // a pawn move starts over
+    play(&mut history, &mut board, "e2e4 e7e5");
+    play(&mut history, &mut board, knights);
+    assert_eq!(history.repetitions(), 1);
+
+    // and taking moves back goes back to the same counts
+    for _ in 0..6 {
+        history.pop();
+    }
+    assert_eq!(history.repetitions(), 2);
+}
+
+#[test]
+fn test_null_moves_are_not_repetitions() {
+    let mut board = Board::default();
+    let mut history = BoardHistory::new(&board);
+    play(&mut history, &mut board, "g1f3");
+
+    // each side passes in turn, which brings back the same positions, but not in the game
+    let null_move = |history: &mut BoardHistory, board: &mut Board| {
+        history.push_null(board);
+        *board = board.null_move().unwrap();
+        assert_eq!(history.hash(), Some(board.get_hash()));
+    };
+    for _ in 0..2 {
+        null_move(&mut history, &mut board);
+        play(&mut history, &mut board, "f3g1");
+        null_move(&mut history, &mut board);
+        assert_eq!(board, Board::default());
+        assert_eq!(history.repetitions(), 0);
+        play(&mut history, &mut board, "g1f3");
+        assert_eq!(history.repetitions(), 0);
+    }
+    let before = history.clone();
+
+    // after the last null move, the same positions count again
+    play(&mut history, &mut board, "g8f6 f3g1 f6g8 g1f3");
+    assert_eq!(history.repetitions(), 1);
+    play(&mut history, &mut board, "g8f6 f3g1 f6g8 g1f3");
+    assert!(history.is_threefold());
+
+    // taking the moves and the null move back gives the history before them
+    for _ in 0..8 {
+        history.pop();
+    }
+    assert_eq!(history, before);
+    history.pop();
+    history.pop_null();
+    assert_eq!(history.len(), 7);
+}
+
+#[test]
+fn test_search_line_repeats_the_game() {
+    // the game went out and back with the knights once already
+    let mut board = Board::default();
+    let mut history = BoardHistory::with_capacity(&board, 16);
+    play(&mut history, &mut board, "e2e4 e7e5 g1f3 b8c6 f3g1 c6b8");
+    assert!(history.is_repetition(&board, 2));
+    let game = history.clone();
+    let last = board;
+    let capacity = history.plies.capacity();
+
+    // a search line that goes out again repeats a position of the game, and back again repeats
+    // the last one a third time
+    play(&mut history, &mut board, "g1f3");
+    assert!(history.is_repetition(&board, 2));
+    assert!(!history.is_repetition(&board, 3));
+    play(&mut history, &mut board, "b8c6 f3g1 c6b8");
+    assert!(history.is_repetition(&board, 3));
+    assert!(!history.is_repetition(&board, 4));
+
+    // a capture in the line starts over
+    play(&mut history, &mut board, "d2d4 e5d4");
+    assert!(!history.is_repetition(&board, 2));
+    assert!(history.is_repetition(&board, 1));
+
+    // popping the line gives the detection of the game back, without allocating on the way
+    for _ in 0..6 {
+        history.pop();
+    }
+    assert_eq!(history, game);
+    assert!(history.is_repetition(&last, 2));
+    assert!(!history.is_repetition(&last, 3));
+    assert_eq!(history.plies.capacity(), capacity);
+}
@@ -5,6 +5,7 @@
 #![allow(dead_code)]
 
 // it to be easily followed.
+extern crate alloc;
 extern crate rand;
 mod bitboard;
 mod color;
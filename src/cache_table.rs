@@ -1,16 +1,34 @@
-#[derive(Copy, Clone, PartialEq, PartialOrd)]
-struct CacheTableEntry<T: Copy + Clone + PartialEq + PartialOrd> {
+use alloc::boxed::Box;
+use alloc::vec;
+
+#[derive(Copy, Clone)]
+struct CacheTableEntry<T: Copy> {
     hash: u64,
     entry: T,
 }
 
 /// Store a cache of entries, each with an associated hash.
-pub struct CacheTable<T: Copy + Clone + PartialEq + PartialOrd> {
+///
+/// The low bits of the hash pick the slot, and the whole hash is stored next to the entry, so a
+/// `get` only succeeds for the exact hash that was added.  `add` always replaces whatever was in
+/// the slot before.
+///
+/// ```
+/// use chess::{Board, CacheTable};
+///
+/// let mut table: CacheTable<u32> = CacheTable::new(1024, 0);
+/// let board = Board::default();
+///
+/// assert_eq!(table.get(board.get_hash()), None);
+/// table.add(board.get_hash(), 20);
+/// assert_eq!(table.get(board.get_hash()), Some(20));
+/// ```
+pub struct CacheTable<T: Copy> {
     table: Box<[CacheTableEntry<T>]>,
     mask: usize,
 }
 
-impl<T: Copy + Clone + PartialEq + PartialOrd> CacheTable<T> {
+impl<T: Copy> CacheTable<T> {
     /// Create a new `CacheTable` with each associated entry initialized with a hash of '0'
     /// Note: You must pass in a size where only 1 bit is set. (AKA: 2, 4, 8, 16, 1024, 65536,
     /// etc.)
@@ -89,3 +107,57 @@ impl<T: Copy + Clone + PartialEq + PartialOrd> CacheTable<T> {
         }
     }
 }
+
+#[cfg(test)]
+use crate::board::Board;
+#[cfg(test)]
+use crate::movegen::MoveGen;
+
+#[test]
+fn test_add_overwrite_and_get() {
+    let mut table: CacheTable<u8> = CacheTable::new(16, 0);
+    assert_eq!(table.get(3), None);
+    table.add(3, 1);
+    assert_eq!(table.get(3), Some(1));
+    table.add(3, 2);
+    assert_eq!(table.get(3), Some(2));
+    assert_eq!(table.get(4), None);
+}
+
+#[test]
+fn test_colliding_hashes() {
+    // both hashes land in slot 5, but the stored hash tells them apart
+    let mut table: CacheTable<u8> = CacheTable::new(256, 0);
+    let first = 5;
+    let second = 5 | (1 << 40);
+    table.add(first, 1);
+    assert_eq!(table.get(second), None);
+    table.add(second, 2);
+    assert_eq!(table.get(second), Some(2));
+    assert_eq!(table.get(first), None);
+}
+
+#[cfg(test)]
+fn cached_perft(board: &Board, depth: usize, caches: &mut [CacheTable<usize>]) -> usize {
+    if depth == 1 {
+        return MoveGen::new_legal(board).len();
+    }
+    if let Some(count) = caches[depth].get(board.get_hash()) {
+        return count;
+    }
+    let count = MoveGen::new_legal(board)
+        .map(|m| cached_perft(&board.make_move_new(m), depth - 1, caches))
+        .sum();
+    caches[depth].add(board.get_hash(), count);
+    count
+}
+
+#[test]
+fn test_cached_perft_matches_perft() {
+    let board = Board::default();
+    let mut caches: Vec<CacheTable<usize>> = (0..5).map(|_| CacheTable::new(4096, 0)).collect();
+    assert_eq!(
+        cached_perft(&board, 4, &mut caches),
+        MoveGen::movegen_perft_test(&board, 4)
+    );
+}
@@ -0,0 +1,298 @@
+use crate::bitboard::BitBoard;
+#[cfg(test)]
+use crate::bitboard::EMPTY;
+use crate::board::Board;
+use crate::color::Color;
+use crate::magic::attacks_of;
+use crate::piece::Piece;
+use crate::square::Square;
+
+/// Which enemy pieces a piece may capture.
+///
+/// `MoveGen::new_legal` uses `UnoRules` or `StandardRules`, whichever matches the board's
+/// `Variant`.  Any other implementation can be tried out with `MoveGen::new_legal_with`, which
+/// keeps the rest of the board's rules and only changes what may be captured.  A king is in
+/// check when an enemy piece could capture it.
+///
+/// ```
+/// use chess::{BitBoard, Board, CaptureRules, ChessMove, Color, MoveGen, Piece, Square};
+/// use chess::{StandardRules, UnoRules};
+/// use std::str::FromStr;
+///
+/// // Minor pieces capture the pieces that attack them, the others capture as in normal chess.
+/// struct MinorUnoRules;
+///
+/// impl CaptureRules for MinorUnoRules {
+///     fn capture_targets(
+///         board: &Board,
+///         src: Square,
+///         piece: Piece,
+///         color: Color,
+///         occupied: BitBoard,
+///     ) -> BitBoard {
+///         match piece {
+///             Piece::Knight | Piece::Bishop => {
+///                 UnoRules::capture_targets(board, src, piece, color, occupied)
+///             }
+///             _ => StandardRules::capture_targets(board, src, piece, color, occupied),
+///         }
+///     }
+/// }
+///
+/// // the rook on b3 attacks the knight on b1, so the knight may take it
+/// let board = Board::from_str("4k3/8/8/8/8/1r6/8/1N2K3 w - - 0 1").expect("Valid FEN");
+/// let capture = ChessMove::new(Square::B1, Square::B3, None);
+/// assert!(MoveGen::new_legal_with::<MinorUnoRules>(&board).any(|m| m == capture));
+/// assert!(!MoveGen::new_legal_with::<StandardRules>(&board).any(|m| m == capture));
+/// ```
+pub trait CaptureRules {
+    /// The squares of the enemy pieces that the `piece` of `color` on `src` may capture, with
+    /// `occupied` blocking the sliders.
+    fn capture_targets(
+        board: &Board,
+        src: Square,
+        piece: Piece,
+        color: Color,
+        occupied: BitBoard,
+    ) -> BitBoard;
+}
+
+/// The captures of `Variant::Uno`: a piece may only capture a piece that attacks it.
+pub struct UnoRules;
+
+/// The captures of `Variant::Standard`, where a piece captures on the squares it attacks.
+pub struct StandardRules;
+
+impl CaptureRules for UnoRules {
+    #[inline(always)]
+    fn capture_targets(
+        board: &Board,
+        src: Square,
+        _piece: Piece,
+        color: Color,
+        occupied: BitBoard,
+    ) -> BitBoard {
+        // An enemy piece attacks `src` exactly when the same kind of piece of my color on `src`
+        // would attack it.  For pawns that is why my color is the right one: a black pawn on d5
+        // attacks a white piece on e4, and a white pawn on e4 would attack d5.
+        ((attacks_of(Piece::Bishop, src, color, occupied)
+            & (board.pieces(Piece::Bishop) | board.pieces(Piece::Queen)))
+            | (attacks_of(Piece::Rook, src, color, occupied)
+                & (board.pieces(Piece::Rook) | board.pieces(Piece::Queen)))
+            | (attacks_of(Piece::Pawn, src, color, occupied) & board.pieces(Piece::Pawn))
+            | (attacks_of(Piece::Knight, src, color, occupied) & board.pieces(Piece::Knight))
+            | (attacks_of(Piece::King, src, color, occupied) & board.pieces(Piece::King)))
+            & board.color_combined(!color)
+    }
+}
+
+impl CaptureRules for StandardRules {
+    #[inline(always)]
+    fn capture_targets(
+        board: &Board,
+        src: Square,
+        piece: Piece,
+        color: Color,
+        occupied: BitBoard,
+    ) -> BitBoard {
+        attacks_of(piece, src, color, occupied) & board.color_combined(!color)
+    }
+}
+
+/// Why a capture was allowed, as `Board::capture_pattern` reports it.
+///
+/// A capture can be allowed for more than one reason, for example a rook taking a rook on the
+/// same file.  The first of these that applies is reported:
+///
+/// 1. `EnPassant`
+/// 2. `OwnMovement`
+/// 3. `PawnAttack`, `KnightLeap`, `BishopLine` or `RookLine`, of which at most one can apply
+///
+/// so a capture that would also be allowed in normal chess is always `OwnMovement`, and those of
+/// `Variant::Standard` are never anything else, apart from en passant.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum CapturePattern {
+    /// The captured pawn attacks the capturing piece.
+    PawnAttack,
+    /// The captured knight attacks the capturing piece.
+    KnightLeap,
+    /// The captured bishop or queen attacks the capturing piece along a diagonal.
+    BishopLine,
+    /// The captured rook or queen attacks the capturing piece along a rank or file.
+    RookLine,
+    /// The capturing piece attacks the captured piece, as in normal chess.
+    OwnMovement,
+    /// A pawn captures en passant, which only `Variant::Standard` has.
+    EnPassant,
+}
+
+#[cfg(test)]
+use crate::chess_move::ChessMove;
+#[cfg(test)]
+use crate::movegen::MoveGen;
+#[cfg(test)]
+use crate::playouts::{random_playouts, XorShift};
+#[cfg(test)]
+use crate::variant::{Variant, ALL_VARIANTS};
+#[cfg(test)]
+use alloc::vec::Vec;
+#[cfg(test)]
+use core::str::FromStr;
+
+#[cfg(test)]
+fn sorted_moves(movegen: MoveGen) -> Vec<ChessMove> {
+    let mut moves: Vec<ChessMove> = movegen.collect();
+    moves.sort();
+    moves
+}
+
+#[test]
+fn test_builtin_rules_match_new_legal() {
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+    ];
+    random_playouts(&starts, &ALL_VARIANTS, 60, 60, |board, moves| {
+        let mut moves = moves.to_vec();
+        moves.sort();
+        let with = match board.variant() {
+            Variant::Uno => MoveGen::new_legal_with::<UnoRules>(board),
+            Variant::Standard => MoveGen::new_legal_with::<StandardRules>(board),
+        };
+        assert_eq!(moves, sorted_moves(with), "{}", board);
+    });
+}
+
+#[cfg(test)]
+struct MinorUnoRules;
+
+#[cfg(test)]
+impl CaptureRules for MinorUnoRules {
+    fn capture_targets(
+        board: &Board,
+        src: Square,
+        piece: Piece,
+        color: Color,
+        occupied: BitBoard,
+    ) -> BitBoard {
+        match piece {
+            Piece::Knight | Piece::Bishop => {
+                UnoRules::capture_targets(board, src, piece, color, occupied)
+            }
+            _ => StandardRules::capture_targets(board, src, piece, color, occupied),
+        }
+    }
+}
+
+#[test]
+fn test_custom_capture_rules() {
+    let has = |board: &Board, m: ChessMove| {
+        MoveGen::new_legal_with::<MinorUnoRules>(board).any(|x| x == m)
+    };
+
+    // the knight takes the rook that attacks it, but the rook does not take the knight that
+    // attacks it
+    let board = Board::from_str("4k3/8/8/8/8/1r6/8/1N2K3 w - - 0 1").unwrap();
+    assert!(has(&board, ChessMove::new(Square::B1, Square::B3, None)));
+    let board = Board::from_str("4k3/8/8/8/8/1n6/8/R3K3 w - - 0 1").unwrap();
+    assert!(!has(&board, ChessMove::new(Square::A1, Square::B3, None)));
+    assert!(MoveGen::new_legal(&board).any(|m| m == ChessMove::new(Square::A1, Square::B3, None)));
+
+    // the rook on e8 gives check from a distance, so only moving the king out of the file or
+    // blocking it is legal
+    let board = Board::from_str("k3r3/8/8/8/8/8/3B4/4K3 w - - 0 1").unwrap();
+    let moves = sorted_moves(MoveGen::new_legal_with::<MinorUnoRules>(&board));
+    let mut expected: Vec<ChessMove> = [
+        (Square::E1, Square::D1),
+        (Square::E1, Square::F1),
+        (Square::E1, Square::F2),
+        (Square::D2, Square::E3),
+    ]
+    .iter()
+    .map(|(src, dest)| ChessMove::new(*src, *dest, None))
+    .collect();
+    expected.sort();
+    assert_eq!(moves, expected);
+
+    // play some games, where every capture follows the rules and my king is never left where
+    // it can be captured
+    let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15);
+    for _ in 0..20 {
+        let mut board = Board::default();
+        for _ in 0..100 {
+            let moves: Vec<ChessMove> = MoveGen::new_legal_with::<MinorUnoRules>(&board).collect();
+            if moves.is_empty() {
+                break;
+            }
+            let m = rng.pick(&moves);
+            let color = board.side_to_move();
+            let piece = board.piece_on(m.get_source()).unwrap();
+            if board.color_on(m.get_dest()) == Some(!color) {
+                let targets = MinorUnoRules::capture_targets(
+                    &board,
+                    m.get_source(),
+                    piece,
+                    color,
+                    *board.combined(),
+                );
+                assert_ne!(targets & BitBoard::from_square(m.get_dest()), EMPTY);
+            }
+            board = board.make_move_new(m);
+            let ksq = board.king_square(color);
+            for src in *board.color_combined(!color) {
+                let targets = MinorUnoRules::capture_targets(
+                    &board,
+                    src,
+                    board.piece_on(src).unwrap(),
+                    !color,
+                    *board.combined(),
+                );
+                assert_eq!(targets & BitBoard::from_square(ksq), EMPTY);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_pawn_capture_direction() {
+    // (fen, capturing piece, pawns it may take, pawns it may not)
+    let cases = [
+        // the black pawns on d5 and f5 attack e4, those on d3 and f3 do not
+        (
+            "4k3/8/8/3p1p2/4R3/3p1p2/8/4K3 w - - 0 1",
+            Square::E4,
+            [Square::D5, Square::F5],
+            [Square::D3, Square::F3],
+        ),
+        // the white pawns on d4 and f4 attack e5, those on d6 and f6 do not
+        (
+            "4k3/8/3P1P2/4r3/3P1P2/8/8/4K3 b - - 0 1",
+            Square::E5,
+            [Square::D4, Square::F4],
+            [Square::D6, Square::F6],
+        ),
+    ];
+    for (fen, src, takes, keeps) in cases.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let color = board.side_to_move();
+        let targets = board.capture_targets(*src);
+        let moves = sorted_moves(MoveGen::new_legal(&board));
+        for pawn in takes.iter() {
+            // the enemy pawn attacks the capturing piece
+            assert_ne!(
+                attacks_of(Piece::Pawn, *pawn, !color, EMPTY) & BitBoard::from_square(*src),
+                EMPTY
+            );
+            assert_ne!(targets & BitBoard::from_square(*pawn), EMPTY);
+            assert!(moves.contains(&ChessMove::new(*src, *pawn, None)));
+        }
+        for pawn in keeps.iter() {
+            assert_eq!(targets & BitBoard::from_square(*pawn), EMPTY);
+            assert!(!moves.contains(&ChessMove::new(*src, *pawn, None)));
+        }
+    }
+}
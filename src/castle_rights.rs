@@ -1,26 +1,100 @@
-use std::hint::unreachable_unchecked;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
 
 use crate::bitboard::{BitBoard, EMPTY};
+use crate::chess_move::ChessMove;
 use crate::color::Color;
+use crate::error::Error;
 use crate::file::File;
 use crate::square::Square;
 
 use crate::magic::{KINGSIDE_CASTLE_SQUARES, QUEENSIDE_CASTLE_SQUARES};
 
 /// What castle rights does a particular player have?
-#[repr(u8)]
+///
+/// Each side I may still castle to remembers the file of the rook I castle with, so this works
+/// for Chess960 too.  `NoRights`, `KingSide`, `QueenSide` and `Both` are the rights of normal
+/// chess, with the rooks on the h and a files, and `CastleRights::new` gives any others.
+///
+/// ```
+/// use chess::{CastleRights, File};
+///
+/// let rights = CastleRights::new(Some(File::G), Some(File::B));
+/// assert!(rights.has_kingside() && rights.has_queenside());
+/// assert_eq!(rights.kingside_rook(), Some(File::G));
+/// assert_ne!(rights, CastleRights::Both);
+/// assert_eq!(CastleRights::Both.queenside_rook(), Some(File::A));
+/// ```
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Debug, Hash)]
-pub enum CastleRights {
-    NoRights = 0,
-    KingSide = 1,
-    QueenSide = 2,
-    Both = 3,
+pub struct CastleRights {
+    kingside: Option<File>,
+    queenside: Option<File>,
 }
 
-/// How many different types of `CastleRights` are there?
+/// Which side of my king do I castle to?
+///
+/// A side converts to the `CastleRights` of normal chess to castle to that side only, which is
+/// all `CastleRights::remove` looks at.
+///
+/// ```
+/// use chess::{CastleRights, CastleSide};
+///
+/// assert_eq!(CastleRights::Both.remove(CastleSide::KingSide), CastleRights::QueenSide);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub enum CastleSide {
+    KingSide,
+    QueenSide,
+}
+
+impl CastleSide {
+    /// Both sides, kingside first.
+    pub const ALL: [CastleSide; 2] = [CastleSide::KingSide, CastleSide::QueenSide];
+}
+
+impl From<CastleSide> for CastleRights {
+    fn from(side: CastleSide) -> CastleRights {
+        match side {
+            CastleSide::KingSide => CastleRights::KingSide,
+            CastleSide::QueenSide => CastleRights::QueenSide,
+        }
+    }
+}
+
+/// Has a side castled, and to which side of its king?
+///
+/// `CastleRights` cannot tell afterwards, since a king that moves loses them too.  A `Board`
+/// remembers it from the castle it made, see `Board::castled`.
+///
+/// ```
+/// use chess::{CastleSide, Castled};
+///
+/// assert_eq!(Castled::from(CastleSide::QueenSide), Castled::QueenSide);
+/// assert_eq!(Castled::default(), Castled::No);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Default)]
+pub enum Castled {
+    #[default]
+    No,
+    KingSide,
+    QueenSide,
+}
+
+impl From<CastleSide> for Castled {
+    fn from(side: CastleSide) -> Castled {
+        match side {
+            CastleSide::KingSide => Castled::KingSide,
+            CastleSide::QueenSide => Castled::QueenSide,
+        }
+    }
+}
+
+/// How many `CastleRights` are there in normal chess, where the rooks start on the a and h files?
+/// In Chess960 the rooks can start on other files, which gives more.
 pub const NUM_CASTLE_RIGHTS: usize = 4;
 
-/// Enumerate all castle rights.
+/// The four `CastleRights` of normal chess, with the rooks on the a and h files.
 pub const ALL_CASTLE_RIGHTS: [CastleRights; NUM_CASTLE_RIGHTS] = [
     CastleRights::NoRights,
     CastleRights::KingSide,
@@ -51,17 +125,103 @@ const CASTLES_PER_SQUARE: [[u8; 64]; 2] = [
     ],
 ];
 
+// the names of the rights of normal chess are kept from when `CastleRights` was an enum
+#[allow(non_upper_case_globals)]
 impl CastleRights {
+    /// I may not castle.
+    pub const NoRights: CastleRights = CastleRights::new(None, None);
+
+    /// I may castle kingside with the rook on the h file.
+    pub const KingSide: CastleRights = CastleRights::new(Some(File::H), None);
+
+    /// I may castle queenside with the rook on the a file.
+    pub const QueenSide: CastleRights = CastleRights::new(None, Some(File::A));
+
+    /// I may castle to both sides, with the rooks on the h and a files.
+    pub const Both: CastleRights = CastleRights::new(Some(File::H), Some(File::A));
+
+    /// The four castle rights of normal chess, the same as `ALL_CASTLE_RIGHTS`.
+    pub const ALL: [CastleRights; NUM_CASTLE_RIGHTS] = ALL_CASTLE_RIGHTS;
+
+    /// The rights to castle with the rooks on these files of my back rank, if any.
+    pub const fn new(kingside: Option<File>, queenside: Option<File>) -> CastleRights {
+        CastleRights {
+            kingside,
+            queenside,
+        }
+    }
+
     /// Can I castle kingside?
     pub fn has_kingside(&self) -> bool {
-        self.to_index() & 1 == 1
+        self.kingside.is_some()
     }
 
     /// Can I castle queenside?
     pub fn has_queenside(&self) -> bool {
-        self.to_index() & 2 == 2
+        self.queenside.is_some()
+    }
+
+    /// Which file does the rook I castle kingside with start on?
+    pub fn kingside_rook(&self) -> Option<File> {
+        self.kingside
+    }
+
+    /// Which file does the rook I castle queenside with start on?
+    pub fn queenside_rook(&self) -> Option<File> {
+        self.queenside
     }
 
+    /// Which square does the rook I castle kingside with start on?
+    ///
+    /// ```
+    /// use chess::{CastleRights, Color, File, Square};
+    ///
+    /// assert_eq!(CastleRights::Both.kingside_rook_square(Color::Black), Some(Square::H8));
+    /// let rights = CastleRights::new(Some(File::G), None);
+    /// assert_eq!(rights.kingside_rook_square(Color::White), Some(Square::G1));
+    /// assert_eq!(rights.queenside_rook_square(Color::White), None);
+    /// ```
+    pub fn kingside_rook_square(&self, color: Color) -> Option<Square> {
+        self.kingside
+            .map(|file| Square::make_square(color.back_rank(), file))
+    }
+
+    /// Which square does the rook I castle queenside with start on?
+    pub fn queenside_rook_square(&self, color: Color) -> Option<Square> {
+        self.queenside
+            .map(|file| Square::make_square(color.back_rank(), file))
+    }
+
+    /// Where does my king land when I castle to `side`?  This is the g or c file of my back
+    /// rank, wherever my king starts.
+    ///
+    /// ```
+    /// use chess::{CastleRights, CastleSide, Color, Square};
+    ///
+    /// assert_eq!(CastleRights::king_destination(Color::White, CastleSide::KingSide), Square::G1);
+    /// assert_eq!(CastleRights::rook_destination(Color::Black, CastleSide::QueenSide), Square::D8);
+    /// ```
+    pub fn king_destination(color: Color, side: CastleSide) -> Square {
+        let file = match side {
+            CastleSide::KingSide => File::G,
+            CastleSide::QueenSide => File::C,
+        };
+        Square::make_square(color.back_rank(), file)
+    }
+
+    /// Where does my rook land when I castle to `side`?  This is the f or d file of my back
+    /// rank, next to my king, wherever my rook starts.
+    pub fn rook_destination(color: Color, side: CastleSide) -> Square {
+        let file = match side {
+            CastleSide::KingSide => File::F,
+            CastleSide::QueenSide => File::D,
+        };
+        Square::make_square(color.back_rank(), file)
+    }
+
+    /// Which castle rights are lost when a piece moves from or to `sq`, in normal chess?  This
+    /// only knows the squares of normal chess, so use `for_rook` for Chess960, and remember that
+    /// moving the king loses both.
     pub fn square_to_castle_rights(color: Color, sq: Square) -> CastleRights {
         CastleRights::from_index(unsafe {
             *CASTLES_PER_SQUARE
@@ -70,81 +230,120 @@ impl CastleRights {
         } as usize)
     }
 
-    /// What squares need to be empty to castle kingside?
+    /// Which of these rights castle with a rook of `color` starting on `sq`?  These are lost
+    /// when a piece moves from or to `sq`.
+    ///
+    /// ```
+    /// use chess::{CastleRights, Color, File, Square};
+    ///
+    /// let rights = CastleRights::new(Some(File::G), Some(File::B));
+    /// assert_eq!(
+    ///     rights.for_rook(Color::White, Square::B1),
+    ///     CastleRights::new(None, Some(File::B))
+    /// );
+    /// assert_eq!(rights.for_rook(Color::White, Square::A1), CastleRights::NoRights);
+    /// assert_eq!(rights.for_rook(Color::Black, Square::G1), CastleRights::NoRights);
+    /// ```
+    #[inline]
+    pub fn for_rook(&self, color: Color, sq: Square) -> CastleRights {
+        if sq.get_rank() != color.back_rank() {
+            return CastleRights::NoRights;
+        }
+        let file = Some(sq.get_file());
+        CastleRights::new(
+            self.kingside.filter(|_| self.kingside == file),
+            self.queenside.filter(|_| self.queenside == file),
+        )
+    }
+
+    /// What squares need to be empty to castle kingside in normal chess?
     pub fn kingside_squares(&self, color: Color) -> BitBoard {
         unsafe { *KINGSIDE_CASTLE_SQUARES.get_unchecked(color.to_index()) }
     }
 
-    /// What squares need to be empty to castle queenside?
+    /// What squares need to be empty to castle queenside in normal chess?
     pub fn queenside_squares(&self, color: Color) -> BitBoard {
         unsafe { *QUEENSIDE_CASTLE_SQUARES.get_unchecked(color.to_index()) }
     }
 
-    /// Remove castle rights, and return a new `CastleRights`.
-    pub fn remove(&self, remove: CastleRights) -> CastleRights {
-        CastleRights::from_index(self.to_index() & !remove.to_index())
+    /// Remove castle rights, and return a new `CastleRights`.  Every side `remove` has is
+    /// removed, whichever file its rook is on, and `remove` may also be a single `CastleSide`.
+    ///
+    /// ```
+    /// use chess::{CastleRights, CastleSide, File};
+    ///
+    /// let rights = CastleRights::new(Some(File::G), Some(File::B));
+    /// assert_eq!(rights.remove(CastleRights::KingSide), CastleRights::new(None, Some(File::B)));
+    /// assert_eq!(rights.remove(CastleSide::QueenSide), CastleRights::new(Some(File::G), None));
+    /// ```
+    pub fn remove(&self, remove: impl Into<CastleRights>) -> CastleRights {
+        let remove = remove.into();
+        CastleRights::new(
+            self.kingside.filter(|_| remove.kingside.is_none()),
+            self.queenside.filter(|_| remove.queenside.is_none()),
+        )
     }
 
-    /// Add some castle rights, and return a new `CastleRights`.
+    /// Add some castle rights, and return a new `CastleRights`.  Every side `add` has comes with
+    /// the file of its rook.
     pub fn add(&self, add: CastleRights) -> CastleRights {
-        CastleRights::from_index(self.to_index() | add.to_index())
+        CastleRights::new(
+            add.kingside.or(self.kingside),
+            add.queenside.or(self.queenside),
+        )
     }
 
-    /// Convert `CastleRights` to `usize` for table lookups
-    pub fn to_index(&self) -> usize {
-        *self as usize
+    /// Convert `CastleRights` to `usize` for table lookups.  This only tells which sides I may
+    /// castle to, not where the rooks are.
+    pub const fn to_index(self) -> usize {
+        self.kingside.is_some() as usize | (self.queenside.is_some() as usize) << 1
     }
 
-    /// Convert `usize` to `CastleRights`.
-    pub fn from_index(i: usize) -> CastleRights {
-        match i & 3 {
-            0 => CastleRights::NoRights,
-            1 => CastleRights::KingSide,
-            2 => CastleRights::QueenSide,
-            3 => CastleRights::Both,
-            _ => unsafe { unreachable_unchecked() },
-        }
+    /// Convert `usize` to the `CastleRights` of normal chess.  Like `File::from_index`, this
+    /// wraps around, so every `usize` gives some rights.
+    pub const fn from_index(i: usize) -> CastleRights {
+        ALL_CASTLE_RIGHTS[i & 3]
     }
 
     /// Which rooks can we "guarantee" we haven't moved yet?
     pub fn unmoved_rooks(&self, color: Color) -> BitBoard {
-        match *self {
-            CastleRights::NoRights => EMPTY,
-            CastleRights::KingSide => BitBoard::set(color.to_my_backrank(), File::H),
-            CastleRights::QueenSide => BitBoard::set(color.to_my_backrank(), File::A),
-            CastleRights::Both => {
-                BitBoard::set(color.to_my_backrank(), File::A)
-                    ^ BitBoard::set(color.to_my_backrank(), File::H)
-            }
-        }
+        let rook = |square: Option<Square>| square.map_or(EMPTY, BitBoard::from_square);
+        rook(self.kingside_rook_square(color)) | rook(self.queenside_rook_square(color))
     }
 
     /// Convert the castle rights to an FEN compatible string.
     ///
+    /// A rook on the h or a file is written as `K` or `Q`, and any other rook as its file, as in
+    /// Shredder-FEN.  `BoardBuilder` can see the board, so it uses `K` and `Q` for every rook
+    /// on the outside of its side, as in X-FEN.
+    ///
     /// ```
-    /// use chess::{CastleRights, Color};
+    /// use chess::{CastleRights, Color, File};
     ///
     /// assert_eq!(CastleRights::NoRights.to_string(Color::White), "");
     /// assert_eq!(CastleRights::Both.to_string(Color::Black), "kq");
     /// assert_eq!(CastleRights::KingSide.to_string(Color::White), "K");
     /// assert_eq!(CastleRights::QueenSide.to_string(Color::Black), "q");
+    /// assert_eq!(CastleRights::new(Some(File::G), Some(File::A)).to_string(Color::White), "GQ");
     /// ```
     pub fn to_string(&self, color: Color) -> String {
-        let result = match *self {
-            CastleRights::NoRights => "",
-            CastleRights::KingSide => "k",
-            CastleRights::QueenSide => "q",
-            CastleRights::Both => "kq",
-        };
+        let letter = |file: File| (b'a' + file.to_index() as u8) as char;
+        let mut result = String::new();
+        if let Some(file) = self.kingside {
+            result.push(if file == File::H { 'k' } else { letter(file) });
+        }
+        if let Some(file) = self.queenside {
+            result.push(if file == File::A { 'q' } else { letter(file) });
+        }
 
         if color == Color::White {
             result.to_uppercase()
         } else {
-            result.to_string()
+            result
         }
     }
 
-    /// Given a square of a rook, which side is it on?
+    /// Given a square of a rook, which side is it on in normal chess?
     pub fn rook_square_to_castle_rights(square: Square) -> CastleRights {
         match square.get_file() {
             File::A => CastleRights::QueenSide,
@@ -153,3 +352,177 @@ impl CastleRights {
         }
     }
 }
+
+impl fmt::Display for CastleRights {
+    /// The castling letters of white in a FEN, or "-" when there are none.  `to_string` with a
+    /// color gives the letters of either side.
+    ///
+    /// ```
+    /// use chess::{CastleRights, File};
+    ///
+    /// assert_eq!(format!("{}", CastleRights::Both), "KQ");
+    /// assert_eq!(format!("{}", CastleRights::NoRights), "-");
+    /// assert_eq!(format!("{}", CastleRights::new(Some(File::G), None)), "G");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if *self == CastleRights::NoRights {
+            write!(f, "-")
+        } else {
+            write!(f, "{}", self.to_string(Color::White))
+        }
+    }
+}
+
+impl FromStr for CastleRights {
+    type Err = Error;
+
+    /// Read the castling letters of one side from a FEN, in either case, or "-" for none.
+    ///
+    /// `K` and `Q` castle with the rooks of normal chess, and a file letter with the rook on
+    /// that file, as in Shredder-FEN, kingside when it is past the e file.  In X-FEN, which rook
+    /// `K` and `Q` mean depends on the board, so `Board::from_str` reads those itself.
+    ///
+    /// ```
+    /// use chess::{CastleRights, File};
+    ///
+    /// assert_eq!("kq".parse::<CastleRights>().unwrap(), CastleRights::Both);
+    /// assert_eq!("-".parse::<CastleRights>().unwrap(), CastleRights::NoRights);
+    /// assert_eq!(
+    ///     "GB".parse::<CastleRights>().unwrap(),
+    ///     CastleRights::new(Some(File::G), Some(File::B))
+    /// );
+    /// assert!("Kq".parse::<CastleRights>().is_err());
+    /// assert!("KK".parse::<CastleRights>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            return Ok(CastleRights::NoRights);
+        }
+        if s.is_empty()
+            || !(s.chars().all(|c| c.is_ascii_uppercase())
+                || s.chars().all(|c| c.is_ascii_lowercase()))
+        {
+            return Err(Error::InvalidCastleRights);
+        }
+
+        let mut rights = CastleRights::NoRights;
+        for c in s.chars() {
+            let side = match c.to_ascii_lowercase() {
+                'k' => CastleRights::KingSide,
+                'q' => CastleRights::QueenSide,
+                letter @ 'a'..='h' => {
+                    let file = File::from_index((letter as usize) - ('a' as usize));
+                    if file > File::E {
+                        CastleRights::new(Some(file), None)
+                    } else {
+                        CastleRights::new(None, Some(file))
+                    }
+                }
+                _ => return Err(Error::InvalidCastleRights),
+            };
+            // each side only once
+            if rights.remove(side) != rights {
+                return Err(Error::InvalidCastleRights);
+            }
+            rights = rights.add(side);
+        }
+        Ok(rights)
+    }
+}
+
+/// Where do my king on `king` and my rook on `rook` land when they castle?  The king always goes
+/// to the g or c file and the rook next to it on the f or d file, wherever they start.
+pub(crate) fn castle_squares(king: Square, rook: Square) -> (Square, Square) {
+    // only a king on its back rank castles
+    let color = if king.get_rank() == Color::White.back_rank() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let side = castle_side(king, rook);
+    (
+        CastleRights::king_destination(color, side),
+        CastleRights::rook_destination(color, side),
+    )
+}
+
+/// Which side does my king on `king` castle to with my rook on `rook`?
+pub(crate) fn castle_side(king: Square, rook: Square) -> CastleSide {
+    if rook.get_file() > king.get_file() {
+        CastleSide::KingSide
+    } else {
+        CastleSide::QueenSide
+    }
+}
+
+/// The move my king on `king` castles with my rook on `rook` as.  From the squares of normal
+/// chess this is the king moving two squares, as always, and otherwise it is the king moving
+/// onto the rook, as in Chess960.  A king never moves onto its own rook otherwise, so that
+/// cannot be mistaken for another move, even when the king does not move at all.
+pub(crate) fn castle_move(king: Square, rook: Square) -> ChessMove {
+    let dest = match (king.get_file(), rook.get_file()) {
+        (File::E, File::H) | (File::E, File::A) => castle_squares(king, rook).0,
+        _ => rook,
+    };
+    ChessMove::new(king, dest, None)
+}
+
+#[cfg(test)]
+use alloc::format;
+
+#[test]
+fn test_all_castle_rights() {
+    for (i, rights) in CastleRights::ALL.iter().enumerate() {
+        assert_eq!(rights.to_index(), i);
+        assert_eq!(CastleRights::from_index(i), *rights);
+        assert!(!CastleRights::ALL[..i].contains(rights));
+    }
+    assert_eq!(
+        CastleRights::from_index(NUM_CASTLE_RIGHTS),
+        CastleRights::NoRights
+    );
+}
+
+#[test]
+fn test_castle_rights_round_trip() {
+    let rights = [
+        CastleRights::NoRights,
+        CastleRights::KingSide,
+        CastleRights::QueenSide,
+        CastleRights::Both,
+        CastleRights::new(Some(File::G), Some(File::B)),
+        CastleRights::new(Some(File::F), None),
+        CastleRights::new(Some(File::H), Some(File::D)),
+    ];
+    for rights in rights.iter() {
+        let text = format!("{}", rights);
+        assert_eq!(CastleRights::from_str(&text).unwrap(), *rights, "{}", text);
+        let black = rights.to_string(Color::Black);
+        if *rights != CastleRights::NoRights {
+            assert_eq!(CastleRights::from_str(&black).unwrap(), *rights);
+        }
+    }
+    assert_eq!(CastleRights::from_str("QK").unwrap(), CastleRights::Both);
+    for text in ["", "KQk", "KQQ", "hH", "X", "K-"].iter() {
+        assert!(CastleRights::from_str(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn test_removing_both_sides_leaves_no_rights() {
+    for rights in CastleRights::ALL.iter() {
+        let removed = CastleSide::ALL
+            .iter()
+            .fold(*rights, |rights, side| rights.remove(*side));
+        assert_eq!(removed, CastleRights::NoRights);
+        assert_eq!(rights.remove(*rights), CastleRights::NoRights);
+    }
+    let chess960 = CastleRights::new(Some(File::G), Some(File::B));
+    assert_eq!(
+        chess960
+            .remove(CastleSide::KingSide)
+            .remove(CastleSide::QueenSide),
+        CastleRights::NoRights
+    );
+    assert_eq!(chess960.remove(CastleRights::Both), CastleRights::NoRights);
+}
@@ -1,14 +1,39 @@
 use crate::board::Board;
-use crate::error::Error;
+use crate::color::Color;
+use crate::error::{Error, Span};
 use crate::file::File;
 use crate::movegen::MoveGen;
-use crate::piece::Piece;
+use crate::piece::{Piece, PROMOTION_PIECES};
 use crate::rank::Rank;
 use crate::square::Square;
+use crate::symmetry::SymmetryOp;
 
-use std::cmp::Ordering;
-use std::fmt;
-use std::str::FromStr;
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+
+use alloc::string::{String, ToString};
+
+/// How a castle is written as the squares of a move.
+///
+/// `MoveGen` always writes them the same way, see `Board::castle_rook`, but `Board::make_move`,
+/// `Board::legal` and `ChessMove::from_coordinates` take either encoding.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum MoveEncoding {
+    /// The king moves to the square it lands on, such as `e1g1`, as in normal chess.
+    KingToDestination,
+    /// The king takes its own rook, such as `e1h1`, as GUIs write every castle in Chess960.
+    KingTakesRook,
+}
+
+/// How the moves given to `Board::apply_moves` are written.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum MoveFormat {
+    /// Coordinate notation, such as `e2e4` or `e7e8q`, with a castle in either `MoveEncoding`.
+    Coordinate,
+    /// SAN, such as `e4`, `Nbd7` or `O-O`.
+    San,
+}
 
 /// Represent a ChessMove in memory
 #[derive(Clone, Copy, Eq, PartialOrd, PartialEq, Default, Debug, Hash)]
@@ -30,6 +55,66 @@ impl ChessMove {
         }
     }
 
+    /// Give me this move on the board that `op` turns or flips the board it is on into, see
+    /// `Board::symmetries`.
+    ///
+    /// ```
+    /// use chess::{ChessMove, Square, SymmetryOp};
+    ///
+    /// let m = ChessMove::new(Square::G1, Square::F3, None);
+    /// let mirrored = ChessMove::new(Square::G8, Square::F6, None);
+    /// assert_eq!(m.transform(SymmetryOp::Mirror), mirrored);
+    /// ```
+    #[inline]
+    pub fn transform(&self, op: SymmetryOp) -> ChessMove {
+        ChessMove::new(op.square(self.source), op.square(self.dest), self.promotion)
+    }
+
+    /// Give me the move packed in 16 bits, to keep in a transposition table or on disk: the
+    /// destination in bits 0-5, the source in bits 6-11, and the promotion in bits 12-14, as 0
+    /// for none and 1-4 for a knight, bishop, rook or queen.  A castle is the king's move, as
+    /// `MoveGen` gives it.
+    ///
+    /// The layout is `stability::MOVE_ENCODING_VERSION`, and only changes with it.
+    ///
+    /// ```
+    /// use chess::{ChessMove, Piece, Square};
+    ///
+    /// let m = ChessMove::new(Square::E7, Square::E8, Some(Piece::Queen));
+    /// assert_eq!(m.to_u16(), 0x4D3C);
+    /// assert_eq!(ChessMove::from_u16(m.to_u16()), Some(m));
+    /// ```
+    #[inline]
+    pub fn to_u16(&self) -> u16 {
+        let promotion = match self.promotion {
+            Some(Piece::Knight) => 1,
+            Some(Piece::Bishop) => 2,
+            Some(Piece::Rook) => 3,
+            Some(Piece::Queen) => 4,
+            _ => 0,
+        };
+        (promotion << 12) | ((self.source.to_index() as u16) << 6) | self.dest.to_index() as u16
+    }
+
+    /// Read a move from the bits `ChessMove::to_u16` gives.  Bits that no move packs to are
+    /// `None`.  Like `ChessMove::new`, this does not check that the move is legal.
+    ///
+    /// The layout is `stability::MOVE_ENCODING_VERSION`, and only changes with it.
+    #[inline]
+    pub fn from_u16(bits: u16) -> Option<ChessMove> {
+        let promotion = match bits >> 12 {
+            0 => None,
+            1 => Some(Piece::Knight),
+            2 => Some(Piece::Bishop),
+            3 => Some(Piece::Rook),
+            4 => Some(Piece::Queen),
+            _ => return None,
+        };
+        let source = Square::new(((bits >> 6) & 63) as u8);
+        let dest = Square::new((bits & 63) as u8);
+        Some(ChessMove::new(source, dest, promotion))
+    }
+
     /// Get the source square (square the piece is currently on).
     #[inline]
     pub fn get_source(&self) -> Square {
@@ -47,6 +132,39 @@ impl ChessMove {
     pub fn get_promotion(&self) -> Option<Piece> {
         self.promotion
     }
+
+    /// Read a move in coordinate notation, such as `e2e4` or `e7e8q`, with a castle in either
+    /// `MoveEncoding`, and write it the way `MoveGen` does.
+    ///
+    /// Like `ChessMove::from_str`, this does not check that the move is legal.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Square};
+    ///
+    /// let board = Board::default();
+    /// let castles = ChessMove::new(Square::E1, Square::G1, None);
+    /// assert_eq!(ChessMove::from_coordinates(&board, "e1g1").unwrap(), castles);
+    /// assert_eq!(ChessMove::from_coordinates(&board, "e1h1").unwrap(), castles);
+    /// ```
+    pub fn from_coordinates(board: &Board, text: &str) -> Result<ChessMove, Error> {
+        Ok(board.normalize_move(ChessMove::from_str(text)?))
+    }
+
+    /// Write this move in coordinate notation, with a castle in `encoding`, see
+    /// `Board::encode_move`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, MoveEncoding, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    /// let castles = ChessMove::new(Square::E1, Square::G1, None);
+    /// assert_eq!(castles.to_coordinates(&board, MoveEncoding::KingToDestination), "e1g1");
+    /// assert_eq!(castles.to_coordinates(&board, MoveEncoding::KingTakesRook), "e1h1");
+    /// ```
+    pub fn to_coordinates(&self, board: &Board, encoding: MoveEncoding) -> String {
+        board.encode_move(*self, encoding).to_string()
+    }
     /// Convert a SAN (Standard Algebraic Notation) move into a `ChessMove`
     ///
     /// ```
@@ -59,22 +177,20 @@ impl ChessMove {
     /// );
     /// ```
     pub fn from_san(board: &Board, move_text: &str) -> Result<ChessMove, Error> {
-        // Castles first...
+        // Castles first, with the rook I castle to that side with, wherever it is
         if move_text == "O-O" || move_text == "O-O-O" {
-            let rank = board.side_to_move().to_my_backrank();
-            let source_file = File::E;
-            let dest_file = if move_text == "O-O" { File::G } else { File::C };
-
-            let m = ChessMove::new(
-                Square::make_square(rank, source_file),
-                Square::make_square(rank, dest_file),
-                None,
-            );
-            if MoveGen::new_legal(&board).any(|l| l == m) {
-                return Ok(m);
+            let color = board.side_to_move();
+            let rights = board.castle_rights(color);
+            let rook = if move_text == "O-O" {
+                rights.kingside_rook_square(color)
             } else {
-                return Err(Error::InvalidSanMove);
-            }
+                rights.queenside_rook_square(color)
+            };
+            return MoveGen::new_legal(&board)
+                .find(|m| rook.is_some() && board.castle_rook(*m) == rook)
+                .ok_or(Error::InvalidSanMove {
+                    span: Span::of(move_text),
+                });
         }
 
         // forms of SAN moves
@@ -127,31 +243,20 @@ impl ChessMove {
         // [Optional Check(mate) Specifier] ("" | "+" | "#")
         // [Optional En Passant Specifier] ("" | " e.p.")
 
-        let error = Error::InvalidSanMove;
+        let error = Error::InvalidSanMove {
+            span: Span::of(move_text),
+        };
         let mut cur_index: usize = 0;
         let moving_piece = match move_text
             .get(cur_index..(cur_index + 1))
             .ok_or(error.clone())?
+            .chars()
+            .next()
+            .and_then(Piece::from_char)
         {
-            "N" => {
-                cur_index += 1;
-                Piece::Knight
-            }
-            "B" => {
+            Some((piece, Color::White)) if piece != Piece::Pawn => {
                 cur_index += 1;
-                Piece::Bishop
-            }
-            "Q" => {
-                cur_index += 1;
-                Piece::Queen
-            }
-            "R" => {
-                cur_index += 1;
-                Piece::Rook
-            }
-            "K" => {
-                cur_index += 1;
-                Piece::King
+                piece
             }
             _ => Piece::Pawn,
         };
@@ -269,28 +374,16 @@ impl ChessMove {
             sq
         };
 
-        let promotion = if let Some(s) = move_text.get(cur_index..(cur_index + 1)) {
-            match s {
-                "N" => {
-                    cur_index += 1;
-                    Some(Piece::Knight)
-                }
-                "B" => {
-                    cur_index += 1;
-                    Some(Piece::Bishop)
-                }
-                "R" => {
-                    cur_index += 1;
-                    Some(Piece::Rook)
-                }
-                "Q" => {
-                    cur_index += 1;
-                    Some(Piece::Queen)
-                }
-                _ => None,
+        let promotion = match move_text
+            .get(cur_index..(cur_index + 1))
+            .and_then(|s| s.chars().next())
+            .and_then(Piece::from_char)
+        {
+            Some((piece, Color::White)) if PROMOTION_PIECES.contains(&piece) => {
+                cur_index += 1;
+                Some(piece)
             }
-        } else {
-            None
+            _ => None,
         };
 
         if let Some(s) = move_text.get(cur_index..(cur_index + 1)) {
@@ -359,7 +452,13 @@ impl ChessMove {
                 }
             }
 
-            if !ep && takes {
+            // an en passant capture lands on an empty square, with or without the " e.p."
+            let en_passant = moving_piece == Piece::Pawn
+                && board
+                    .en_passant()
+                    .map(|sq| sq.uforward(board.side_to_move()))
+                    == Some(m.get_dest());
+            if !ep && !en_passant && takes {
                 if board.piece_on(m.get_dest()).is_none() {
                     continue;
                 }
@@ -376,7 +475,7 @@ impl fmt::Display for ChessMove {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.promotion {
             None => write!(f, "{}{}", self.source, self.dest),
-            Some(x) => write!(f, "{}{}{}", self.source, self.dest, x),
+            Some(x) => write!(f, "{}{}{}", self.source, self.dest, x.to_char(Color::Black)),
         }
     }
 }
@@ -417,15 +516,17 @@ impl FromStr for ChessMove {
         let source = Square::from_str(s.get(0..2).ok_or(Error::InvalidUciMove)?)?;
         let dest = Square::from_str(s.get(2..4).ok_or(Error::InvalidUciMove)?)?;
 
+        // nothing but a promotion piece may follow the squares
+        if s.len() > 5 {
+            return Err(Error::InvalidUciMove);
+        }
+
         let mut promo = None;
         if s.len() == 5 {
-            promo = Some(match s.chars().last().ok_or(Error::InvalidUciMove)? {
-                'q' => Piece::Queen,
-                'r' => Piece::Rook,
-                'n' => Piece::Knight,
-                'b' => Piece::Bishop,
+            promo = match s.chars().last().and_then(Piece::from_char) {
+                Some((piece, Color::Black)) if PROMOTION_PIECES.contains(&piece) => Some(piece),
                 _ => return Err(Error::InvalidUciMove),
-            });
+            };
         }
 
         Ok(ChessMove::new(source, dest, promo))
@@ -440,3 +541,96 @@ fn test_basic_moves() {
         ChessMove::new(Square::E2, Square::E4, None)
     );
 }
+
+#[test]
+fn test_en_passant_san() {
+    use crate::board_builder::BoardBuilder;
+    use crate::variant::Variant;
+    use core::convert::TryInto;
+
+    let board: Board = BoardBuilder::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1")
+        .unwrap()
+        .variant(Variant::Standard)
+        .try_into()
+        .unwrap();
+    let m = ChessMove::new(Square::E5, Square::D6, None);
+    assert_eq!(ChessMove::from_san(&board, "exd6").unwrap(), m);
+    assert_eq!(ChessMove::from_san(&board, "exd6 e.p.").unwrap(), m);
+}
+
+#[test]
+fn test_castle_san() {
+    let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    assert_eq!(
+        ChessMove::from_san(&board, "O-O").unwrap(),
+        ChessMove::new(Square::E1, Square::G1, None)
+    );
+    assert_eq!(
+        ChessMove::from_san(&board, "O-O-O").unwrap(),
+        ChessMove::new(Square::E1, Square::C1, None)
+    );
+
+    // in Chess960 the king may already stand on g1, and castle onto its own rook
+    let board = Board::from_str("4k3/8/8/8/8/8/8/R5KR w HA - 0 1").unwrap();
+    assert_eq!(
+        ChessMove::from_san(&board, "O-O").unwrap(),
+        ChessMove::new(Square::G1, Square::H1, None)
+    );
+    assert_eq!(
+        ChessMove::from_san(&board, "O-O-O").unwrap(),
+        ChessMove::new(Square::G1, Square::A1, None)
+    );
+}
+
+#[test]
+fn test_castle_encodings() {
+    use crate::movegen::MoveGen;
+
+    // (fen, king to destination, king takes rook, what MoveGen writes)
+    let castles = [
+        // normal chess
+        (
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "e1g1",
+            "e1h1",
+            "e1g1",
+        ),
+        // Chess960, with the king moving far, one square and not at all
+        ("4k3/8/8/8/8/8/8/1K3R2 w F - 0 1", "b1g1", "b1f1", "b1f1"),
+        ("4k3/8/8/8/8/8/8/5K1R w H - 0 1", "f1h1", "f1h1", "f1h1"),
+        ("4k3/8/8/8/8/8/8/6KR w H - 0 1", "g1h1", "g1h1", "g1h1"),
+    ];
+    for (fen, destination, takes_rook, generated) in castles.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let generated = ChessMove::from_str(generated).unwrap();
+        assert!(MoveGen::new_legal(&board).any(|m| m == generated));
+        let castled = board.make_move_new(generated);
+
+        for text in [destination, takes_rook].iter() {
+            let m = ChessMove::from_str(text).unwrap();
+            assert!(board.legal(m), "{} {}", fen, text);
+            assert_eq!(board.make_move_new(m), castled, "{} {}", fen, text);
+            assert_eq!(board.normalize_move(m), generated, "{} {}", fen, text);
+            let parsed = ChessMove::from_coordinates(&board, text).unwrap();
+            assert_eq!(parsed, generated);
+        }
+        assert_eq!(
+            generated.to_coordinates(&board, MoveEncoding::KingToDestination),
+            *destination
+        );
+        assert_eq!(
+            generated.to_coordinates(&board, MoveEncoding::KingTakesRook),
+            *takes_rook
+        );
+    }
+
+    // a king stepping one square onto the g file does not castle
+    let board = Board::from_str("4k3/8/8/8/8/8/8/5K1R w H - 0 1").unwrap();
+    let step = ChessMove::new(Square::F1, Square::G1, None);
+    assert_eq!(board.castle_rook(step), None);
+    assert_eq!(board.normalize_move(step), step);
+    assert_eq!(
+        board.make_move_new(step).to_string(),
+        "4k3/8/8/8/8/8/8/6KR b - - 0 1"
+    );
+}
@@ -1,5 +1,8 @@
-use crate::{rank::Rank, bitboard::BitBoard};
-use std::ops::Not;
+use crate::error::Error;
+use crate::{bitboard::BitBoard, rank::Rank};
+use core::fmt;
+use core::ops::Not;
+use core::str::FromStr;
 
 /// Represent a color.
 #[derive(PartialOrd, PartialEq, Eq, Copy, Clone, Debug, Hash)]
@@ -14,32 +17,95 @@ pub const NUM_COLORS: usize = 2;
 pub const ALL_COLORS: [Color; NUM_COLORS] = [Color::White, Color::Black];
 
 impl Color {
+    /// Both colors, the same as `ALL_COLORS`.
+    pub const ALL: [Color; NUM_COLORS] = ALL_COLORS;
+
     /// Convert the `Color` to a `usize` for table lookups.
     #[inline]
-    pub fn to_index(&self) -> usize {
-        *self as usize
+    pub const fn to_index(self) -> usize {
+        self as usize
     }
 
-    /// Convert a `Color` to my backrank, which represents the starting rank
-    /// for my pieces.
+    /// Convert a `usize` to a `Color`, the inverse of `to_index`.  If i > 1, return `None`.
+    ///
+    /// ```
+    /// use chess::Color;
+    ///
+    /// assert_eq!(Color::from_index(1), Some(Color::Black));
+    /// assert_eq!(Color::from_index(2), None);
+    /// ```
     #[inline]
-    pub fn to_my_backrank(&self) -> Rank {
-        match *self {
+    pub const fn from_index(i: usize) -> Option<Color> {
+        match i {
+            0 => Some(Color::White),
+            1 => Some(Color::Black),
+            _ => None,
+        }
+    }
+
+    /// The rank my pieces start on.
+    ///
+    /// ```
+    /// use chess::{Color, Rank};
+    ///
+    /// assert_eq!(Color::White.back_rank(), Rank::First);
+    /// assert_eq!(Color::Black.back_rank(), Rank::Eighth);
+    /// ```
+    #[inline]
+    pub const fn back_rank(self) -> Rank {
+        match self {
             Color::White => Rank::First,
             Color::Black => Rank::Eighth,
         }
     }
 
-    /// Convert a `Color` to my opponents backrank, which represents the starting rank for the
-    /// opponents pieces.
+    /// The rank my pawns promote on, which is my opponent's back rank.
+    ///
+    /// ```
+    /// use chess::{Color, Rank};
+    ///
+    /// assert_eq!(Color::White.promotion_rank(), Rank::Eighth);
+    /// assert_eq!(Color::Black.promotion_rank(), Rank::First);
+    /// ```
     #[inline]
-    pub fn to_their_backrank(&self) -> Rank {
-        match *self {
+    pub const fn promotion_rank(self) -> Rank {
+        match self {
             Color::White => Rank::Eighth,
             Color::Black => Rank::First,
         }
     }
 
+    /// The rank my pawns land on when they move two squares, where they may be taken en
+    /// passant in normal chess.
+    ///
+    /// ```
+    /// use chess::{Color, Rank};
+    ///
+    /// assert_eq!(Color::White.double_push_rank(), Rank::Fourth);
+    /// assert_eq!(Color::Black.double_push_rank(), Rank::Fifth);
+    /// ```
+    #[inline]
+    pub const fn double_push_rank(self) -> Rank {
+        match self {
+            Color::White => Rank::Fourth,
+            Color::Black => Rank::Fifth,
+        }
+    }
+
+    /// Convert a `Color` to my backrank, which represents the starting rank
+    /// for my pieces.  This is the same as `back_rank`.
+    #[inline]
+    pub fn to_my_backrank(&self) -> Rank {
+        self.back_rank()
+    }
+
+    /// Convert a `Color` to my opponents backrank, which represents the starting rank for the
+    /// opponents pieces.  This is the same as `promotion_rank`.
+    #[inline]
+    pub fn to_their_backrank(&self) -> Rank {
+        self.promotion_rank()
+    }
+
     /// Convert a `Color` to my second rank, which represents the starting rank for my pawns.
     #[inline]
     pub fn to_second_rank(&self) -> Rank {
@@ -50,13 +116,10 @@ impl Color {
     }
 
     /// Convert a `Color` to my fourth rank, which represents the rank of my pawns when
-    /// moving two squares forward.
+    /// moving two squares forward.  This is the same as `double_push_rank`.
     #[inline]
     pub fn to_fourth_rank(&self) -> Rank {
-        match *self {
-            Color::White => Rank::Fourth,
-            Color::Black => Rank::Fifth,
-        }
+        self.double_push_rank()
     }
 
     /// Convert a `Color` to my seventh rank, which represents the rank before pawn promotion.
@@ -68,13 +131,35 @@ impl Color {
         }
     }
 
-    /// Convert a `Color` to a bit board that contains all the promotion squares
+    /// The squares of my back rank.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Color, Rank};
+    ///
+    /// assert_eq!(Color::White.to_my_backrank_bb(), BitBoard::from_rank(Rank::First));
+    /// ```
     #[inline]
-    pub fn to_promotion_board(&self) -> BitBoard {
-        match *self {
-            Color::White => BitBoard(0b1111111100000000000000000000000000000000000000000000000000000000),
-            Color::Black => BitBoard(0b0000000000000000000000000000000000000000000000000000000011111111),
-        }
+    pub const fn to_my_backrank_bb(self) -> BitBoard {
+        Rank::ALL_BB[self.back_rank() as usize]
+    }
+
+    /// The squares of my opponent's back rank.
+    #[inline]
+    pub const fn to_their_backrank_bb(self) -> BitBoard {
+        Rank::ALL_BB[self.promotion_rank() as usize]
+    }
+
+    /// The squares my pawns promote on, which is my opponent's back rank.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Color, Rank};
+    ///
+    /// assert_eq!(Color::Black.to_promotion_board(), BitBoard::from_rank(Rank::First));
+    /// assert_eq!(Color::Black.to_promotion_board(), Color::White.to_my_backrank_bb());
+    /// ```
+    #[inline]
+    pub const fn to_promotion_board(&self) -> BitBoard {
+        self.to_their_backrank_bb()
     }
 }
 
@@ -91,3 +176,117 @@ impl Not for Color {
         }
     }
 }
+
+impl fmt::Display for Color {
+    /// ```
+    /// use chess::Color;
+    ///
+    /// assert_eq!(Color::White.to_string(), "White");
+    /// assert_eq!(Color::Black.to_string(), "Black");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Color::White => "White",
+                Color::Black => "Black",
+            }
+        )
+    }
+}
+
+impl FromStr for Color {
+    type Err = Error;
+
+    /// Read a color from its FEN letter or its name, in any case.
+    ///
+    /// ```
+    /// use chess::Color;
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(Color::from_str("w").unwrap(), Color::White);
+    /// assert_eq!(Color::from_str("Black").unwrap(), Color::Black);
+    /// assert!(Color::from_str("red").is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("w") || s.eq_ignore_ascii_case("white") {
+            Ok(Color::White)
+        } else if s.eq_ignore_ascii_case("b") || s.eq_ignore_ascii_case("black") {
+            Ok(Color::Black)
+        } else {
+            Err(Error::InvalidColor)
+        }
+    }
+}
+
+#[test]
+fn test_color_round_trip() {
+    for color in ALL_COLORS.iter() {
+        assert_eq!(!!*color, *color);
+        assert_ne!(!*color, *color);
+        assert_eq!(Color::from_str(&color.to_string()).unwrap(), *color);
+        assert_eq!(
+            Color::from_str(&color.to_string().to_lowercase()).unwrap(),
+            *color
+        );
+        assert_eq!(Color::from_str(&color.to_string()[0..1]).unwrap(), *color);
+    }
+    assert_eq!(Color::from_str("b").unwrap(), Color::Black);
+    assert!(Color::from_str("").is_err());
+    assert!(Color::from_str("whites").is_err());
+}
+
+#[test]
+fn test_all_colors() {
+    for (i, color) in Color::ALL.iter().enumerate() {
+        assert_eq!(color.to_index(), i);
+        assert_eq!(Color::from_index(i), Some(*color));
+        assert!(!Color::ALL[..i].contains(color));
+    }
+    assert_eq!(Color::from_index(NUM_COLORS), None);
+}
+
+#[test]
+fn test_ranks_of_each_color() {
+    use crate::square::Square;
+
+    let ranks = |color: Color| {
+        [
+            color.back_rank(),
+            color.to_second_rank(),
+            color.double_push_rank(),
+            color.to_seventh_rank(),
+            color.promotion_rank(),
+        ]
+    };
+    let white = [Rank::First, Rank::Second, Rank::Fourth];
+    assert_eq!(ranks(Color::White)[..3], white);
+    assert_eq!(ranks(Color::White)[3..], [Rank::Seventh, Rank::Eighth]);
+    // black's ranks are white's seen from the other side
+    for (black, white) in ranks(Color::Black).iter().zip(ranks(Color::White).iter()) {
+        assert_eq!(black.to_index(), 7 - white.to_index());
+    }
+    for color in ALL_COLORS.iter() {
+        assert_eq!(color.to_my_backrank(), color.back_rank());
+        assert_eq!(color.to_their_backrank(), color.promotion_rank());
+        assert_eq!(color.to_fourth_rank(), color.double_push_rank());
+        assert_eq!(color.to_their_backrank(), (!*color).back_rank());
+
+        // a pawn two squares from its second rank is on the double push rank
+        for file in crate::file::ALL_FILES.iter() {
+            let start = Square::make_square(color.to_second_rank(), *file);
+            let dest = start.uforward(*color).uforward(*color);
+            assert_eq!(dest.get_rank(), color.double_push_rank());
+            let last = Square::make_square(color.to_seventh_rank(), *file).uforward(*color);
+            assert_eq!(last.get_rank(), color.promotion_rank());
+        }
+
+        let backrank = BitBoard::from_rank(color.back_rank());
+        assert_eq!(color.to_my_backrank_bb(), backrank);
+        let promotion = BitBoard::from_rank(color.promotion_rank());
+        assert_eq!(color.to_their_backrank_bb(), promotion);
+        assert_eq!(color.to_promotion_board(), (!*color).to_my_backrank_bb());
+        assert_eq!(backrank.popcnt(), 8);
+    }
+}
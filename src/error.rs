@@ -1,35 +1,337 @@
-use failure::Fail;
+use core::fmt;
+
+use alloc::format;
+use alloc::string::{String, ToString};
 
 /// Sometimes, bad stuff happens.
-#[derive(Clone, Debug, Fail)]
+///
+/// Every parser in this crate returns this one error, so `?` works on all of them.  With the
+/// `std` feature it implements `std::error::Error`, and through that `failure::Fail`.  More
+/// variants may be added later, so a `match` on it needs a wildcard arm.
+#[non_exhaustive]
+#[derive(Clone, Debug)]
 pub enum Error {
-    /// The FEN string is invalid
-    #[fail(display = "Invalid FEN string: {}", fen)]
-    InvalidFen { fen: String },
+    /// The FEN string is invalid, at `span`
+    InvalidFen { fen: String, span: Span },
 
     /// The board created from BoardBuilder was found to be invalid
-    #[fail(
-        display = "The board specified did not pass sanity checks.  Are you sure the kings exist and the side to move cannot capture the opposing king?"
-    )]
     InvalidBoard,
 
-    /// An attempt was made to create a square from an invalid string
-    #[fail(display = "The string specified does not contain a valid algebraic notation square")]
+    /// An attempt was made to create a square from an invalid string, or an index past 63
     InvalidSquare,
 
-    /// An attempt was made to create a move from an invalid SAN string
-    #[fail(display = "The string specified does not contain a valid SAN notation move")]
-    InvalidSanMove,
+    /// An attempt was made to create a move from an invalid SAN string, or one that is not
+    /// legal, which is the text at `span`
+    InvalidSanMove { span: Span },
 
     /// An atempt was made to create a move from an invalid UCI string
-    #[fail(display = "The string specified does not contain a valid UCI notation move")]
     InvalidUciMove,
 
     /// An attempt was made to convert a string not equal to "1"-"8" to a rank
-    #[fail(display = "The string specified does not contain a valid rank")]
     InvalidRank,
 
     /// An attempt was made to convert a string not equal to "a"-"h" to a file
-    #[fail(display = "The string specified does not contain a valid file")]
     InvalidFile,
+
+    /// An attempt was made to convert a string not equal to "standard" or "uno" to a variant
+    InvalidVariant,
+
+    /// An attempt was made to convert a string not equal to "w", "b", "white" or "black" to a
+    /// color
+    InvalidColor,
+
+    /// An attempt was made to read castle rights from a string other than "-" or the castling
+    /// letters of one side, such as "KQ" or "kq"
+    InvalidCastleRights,
+
+    /// An attempt was made to read a board from a diagram without eight ranks of eight squares
+    InvalidDiagram,
+
+    /// An attempt was made to read a file of positions without the header of this version
+    InvalidPositionFile,
+
+    /// An attempt was made to create a board with a pawn on the rank it promotes on, or in
+    /// normal chess on its own first rank
+    PawnOnBackRank,
+
+    /// An attempt was made to create a board where a side does not have exactly one king
+    InvalidKingCount,
+
+    /// An attempt was made to create a board where a side has more pieces than allowed
+    TooManyPieces,
+
+    /// An attempt was made to create a board, checking material strictly, where a side has more
+    /// pawns and promoted pieces than its eight pawns account for
+    ImpossibleMaterial,
+
+    /// An attempt was made to create a board where the side that is not to move is in check
+    OpponentInCheck,
+
+    /// An attempt was made to use a board of a variant whose rules the operation does not know
+    UnsupportedVariant,
+
+    /// An attempt was made to read an opening book that is not a sorted list of whole entries
+    InvalidBook,
+
+    /// An attempt was made to read a board from bytes that are not the canonical key of one
+    InvalidCanonicalKey,
+
+    /// An attempt was made to migrate bytes of a version of an encoding that this crate does not
+    /// know, or that are not the size that version gives
+    UnsupportedEncodingVersion,
+
+    /// An attempt was made to read an EPD line that is not a position followed by operations
+    /// ending in ';', which is the text at `span`
+    InvalidEpd { span: Span },
+
+    /// An attempt was made to make a move that is not legal in the position
+    IllegalMove,
+}
+
+impl fmt::Display for Error {
+    /// ```
+    /// use chess::Board;
+    /// use std::str::FromStr;
+    ///
+    /// let error = Board::from_str("8/8/8/8/8/8/8/8 w - - 0 1").unwrap_err();
+    /// assert!(error.to_string().starts_with("The board specified does not have exactly one king"));
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidFen { fen, .. } => write!(
+                f,
+                "Invalid FEN string: {}.  A FEN needs eight ranks of eight squares, the side to \
+                 move, the castle rights and the en passant square, separated by spaces",
+                fen
+            ),
+            Error::InvalidBoard => write!(
+                f,
+                "The board specified did not pass sanity checks.  Are you sure every castle right \
+                 has its king and rook, and the en passant square its pawn?"
+            ),
+            Error::InvalidSquare => write!(
+                f,
+                "The string specified does not contain a valid algebraic notation square, such \
+                 as \"e4\", or the index specified is not from 0 to 63"
+            ),
+            Error::InvalidSanMove { .. } => write!(
+                f,
+                "The string specified does not contain a valid SAN notation move, such as \
+                 \"Nf3\", that is legal in this position"
+            ),
+            Error::InvalidUciMove => write!(
+                f,
+                "The string specified does not contain a valid UCI notation move, such as \
+                 \"e2e4\" or \"e7e8q\""
+            ),
+            Error::InvalidRank => write!(f, "The string specified does not contain a valid rank"),
+            Error::InvalidFile => write!(f, "The string specified does not contain a valid file"),
+            Error::InvalidVariant => write!(
+                f,
+                "The string specified does not name a known variant, which are \"standard\" and \
+                 \"uno\""
+            ),
+            Error::InvalidColor => write!(
+                f,
+                "The string specified does not name a color, which are \"w\" or \"white\" and \
+                 \"b\" or \"black\""
+            ),
+            Error::InvalidCastleRights => write!(
+                f,
+                "The string specified does not contain the castle rights of one side, such as \
+                 \"KQ\", \"k\" or \"-\""
+            ),
+            Error::InvalidDiagram => write!(
+                f,
+                "The string specified is not a diagram of eight ranks, each with a piece letter, \
+                 '.' or ' ' for every square"
+            ),
+            Error::InvalidPositionFile => write!(
+                f,
+                "The bytes specified are not a file of positions of this version, or end before \
+                 all of its positions"
+            ),
+            Error::PawnOnBackRank => write!(
+                f,
+                "The board specified has a pawn on the rank it promotes on, or in normal chess on \
+                 its own first rank"
+            ),
+            Error::InvalidKingCount => write!(
+                f,
+                "The board specified does not have exactly one king of each color"
+            ),
+            Error::TooManyPieces => write!(
+                f,
+                "The board specified has more pieces of one color than are allowed"
+            ),
+            Error::ImpossibleMaterial => write!(
+                f,
+                "The board specified has more pawns and promoted pieces of one color than its \
+                 eight pawns can account for"
+            ),
+            Error::OpponentInCheck => write!(
+                f,
+                "The board specified leaves the side that is not to move in check"
+            ),
+            Error::UnsupportedVariant => write!(
+                f,
+                "The board specified is of a variant that this operation does not support"
+            ),
+            Error::InvalidBook => write!(
+                f,
+                "The bytes specified are not an opening book of whole entries sorted by hash"
+            ),
+            Error::InvalidCanonicalKey => write!(
+                f,
+                "The bytes specified are not the canonical key of a board"
+            ),
+            Error::UnsupportedEncodingVersion => write!(
+                f,
+                "The bytes specified are not of a version of the encoding that this crate can \
+                 migrate"
+            ),
+            Error::IllegalMove => write!(
+                f,
+                "The move specified is not legal in the position it was made in"
+            ),
+            Error::InvalidEpd { .. } => write!(
+                f,
+                "The string specified is not an EPD line: the four fields of a position, then \
+                 operations such as 'bm Nf3;', each ending in ';'"
+            ),
+        }
+    }
+}
+
+impl Error {
+    /// Where in the text that was read the error is, if it came from reading text.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::InvalidFen { span, .. }
+            | Error::InvalidSanMove { span }
+            | Error::InvalidEpd { span } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// Give me the message of the error, and if it has a `span`, the line of `src` it is on with
+    /// the span marked below it.  `src` is the text that was read.
+    ///
+    /// ```
+    /// use chess::Board;
+    /// use std::str::FromStr;
+    ///
+    /// let fen = "rnbqkbnr/pppppppp/8/8/4X3/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    /// let error = Board::from_str(fen).unwrap_err();
+    /// let message = error.display_with_source(fen);
+    /// assert!(message.ends_with(&format!("\n{}\n{}^", fen, " ".repeat(23))));
+    /// ```
+    pub fn display_with_source(&self, src: &str) -> String {
+        match self.span().and_then(|span| span.snippet(src)) {
+            Some(snippet) => format!("{}\n{}", self, snippet),
+            None => self.to_string(),
+        }
+    }
+}
+
+/// A range of bytes in the text a parser read, from `start` up to but not including `end`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The whole of `text`.
+    pub(crate) fn of(text: &str) -> Span {
+        Span {
+            start: 0,
+            end: text.len(),
+        }
+    }
+
+    /// The same span, in text that starts `offset` bytes later.
+    pub(crate) fn shift(self, offset: usize) -> Span {
+        Span {
+            start: self.start + offset,
+            end: self.end + offset,
+        }
+    }
+
+    // the line of `src` that the span starts on, with a caret under each character of it on that
+    // line, or at least one, or `None` if the span is not in `src`
+    fn snippet(self, src: &str) -> Option<String> {
+        let line_start = src.get(..self.start)?.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = src[self.start..]
+            .find('\n')
+            .map_or(src.len(), |i| self.start + i);
+        let marked = src.get(self.start..self.end.max(self.start).min(line_end))?;
+        let column = src[line_start..self.start].chars().count();
+        Some(format!(
+            "{}\n{}{}",
+            src[line_start..line_end].trim_end_matches('\r'),
+            " ".repeat(column),
+            "^".repeat(marked.chars().count().max(1))
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+// with `std`, `failure` implements `Fail` for every `std::error::Error`
+#[cfg(not(feature = "std"))]
+impl failure::Fail for Error {}
+
+/// A move of a sequence that `Board::apply_moves` could not make.
+#[derive(Clone, Debug)]
+pub struct ApplyError {
+    /// Where the move is in the sequence, counting from 0.
+    pub index: usize,
+    /// The move as it was given.
+    pub text: String,
+    /// The FEN of the position the move was made in, see `Board::to_tagged_fen`.
+    pub fen: String,
+    /// Why the move could not be read or made.
+    pub error: Error,
+}
+
+impl fmt::Display for ApplyError {
+    /// ```
+    /// use chess::{Board, MoveFormat};
+    ///
+    /// let error = Board::default()
+    ///     .apply_moves(["e4", "e5", "Ke3"].iter().copied(), MoveFormat::San)
+    ///     .unwrap_err();
+    /// assert!(error.to_string().starts_with("Move 2 (\"Ke3\") in "));
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Move {} ({:?}) in {} cannot be made: {}",
+            self.index, self.text, self.fen, self.error
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ApplyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl failure::Fail for ApplyError {}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_error_is_std_error() {
+    fn parse() -> Result<crate::Board, Box<dyn std::error::Error>> {
+        Ok("not a fen".parse::<crate::Board>()?)
+    }
+    let error = parse().unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("Invalid FEN string: not a fen."));
 }
@@ -1,5 +1,6 @@
+use crate::bitboard::BitBoard;
 use crate::error::Error;
-use std::str::FromStr;
+use core::str::FromStr;
 
 /// Describe a file (column) on a chess board
 #[repr(u8)]
@@ -31,6 +32,26 @@ pub const ALL_FILES: [File; NUM_FILES] = [
 ];
 
 impl File {
+    /// The squares on each file, from the a-file to the h-file.
+    ///
+    /// ```
+    /// use chess::{BitBoard, File, Square, EMPTY};
+    ///
+    /// let c5 = BitBoard::from_square(Square::C5);
+    /// assert_eq!(File::ALL_BB[File::C.to_index()] & c5, c5);
+    /// assert_eq!(File::ALL_BB[File::D.to_index()] & c5, EMPTY);
+    /// ```
+    pub const ALL_BB: [BitBoard; NUM_FILES] = [
+        BitBoard(0x0101_0101_0101_0101),
+        BitBoard(0x0202_0202_0202_0202),
+        BitBoard(0x0404_0404_0404_0404),
+        BitBoard(0x0808_0808_0808_0808),
+        BitBoard(0x1010_1010_1010_1010),
+        BitBoard(0x2020_2020_2020_2020),
+        BitBoard(0x4040_4040_4040_4040),
+        BitBoard(0x8080_8080_8080_8080),
+    ];
+
     /// Convert a `usize` into a `File` (the inverse of to_index).  If i > 7, wrap around.
     #[inline]
     pub fn from_index(i: usize) -> File {
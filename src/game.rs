@@ -3,8 +3,12 @@ use crate::chess_move::ChessMove;
 use crate::color::Color;
 use crate::error::Error;
 use crate::movegen::MoveGen;
-use crate::piece::Piece;
-use std::str::FromStr;
+use core::fmt;
+use core::str::FromStr;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// Contains all actions supported within the game
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Eq)]
@@ -28,15 +32,55 @@ pub enum GameResult {
     DrawDeclared,
 }
 
+/// Told about each change to a `Game` it subscribed to, see `Game::subscribe`.
+///
+/// The methods are called from the method of the `Game` that made the change, once it is made.
+/// They only see the position, not the game, so they cannot change the game while it calls
+/// them.  Each does nothing by default, so an observer only writes the ones it needs.  An
+/// observer is `Send` and `Sync`, so that a `Game` still is.
+pub trait GameObserver: Send + Sync {
+    /// `chess_move` was made, which gave `board`.  `ply` counts the moves of the game before it,
+    /// so the first move is ply 0.
+    fn on_move(&mut self, _ply: usize, _chess_move: ChessMove, _board: &Board) {}
+
+    /// `color` offered a draw.
+    fn on_draw_offer(&mut self, _color: Color) {}
+
+    /// The game ended with `result`, by a move, a draw or a resignation.
+    fn on_result(&mut self, _result: GameResult) {}
+}
+
 /// For UI/UCI Servers, store a game object which allows you to determine
 /// draw by 3 fold repitition, draw offers, resignations, and moves.
 ///
 /// This structure is slow compared to using `Board` directly, so it is
-/// not recommended for engines.
-#[derive(Clone, Debug)]
+/// not recommended for engines.  A search can find repetitions with a
+/// `BoardHistory` instead.
 pub struct Game {
     start_pos: Board,
     moves: Vec<Action>,
+    observers: Vec<Box<dyn GameObserver>>,
+}
+
+impl Clone for Game {
+    /// Copy the game, without its observers, which only watch the game they subscribed to.
+    fn clone(&self) -> Game {
+        Game {
+            start_pos: self.start_pos,
+            moves: self.moves.clone(),
+            observers: vec![],
+        }
+    }
+}
+
+impl fmt::Debug for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Game")
+            .field("start_pos", &self.start_pos)
+            .field("moves", &self.moves)
+            .field("observers", &self.observers.len())
+            .finish()
+    }
 }
 
 impl Game {
@@ -52,6 +96,7 @@ impl Game {
         Game {
             start_pos: Board::default(),
             moves: vec![],
+            observers: vec![],
         }
     }
 
@@ -67,6 +112,57 @@ impl Game {
         Game {
             start_pos: board,
             moves: vec![],
+            observers: vec![],
+        }
+    }
+
+    /// The position this game started from.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Game, Square};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(ChessMove::new(Square::E2, Square::E4, None));
+    /// assert_eq!(game.start_position(), Board::default());
+    /// ```
+    pub fn start_position(&self) -> Board {
+        self.start_pos
+    }
+
+    /// Tell `observer` about every move, draw offer and result from now on, as it happens, so
+    /// that it does not have to compare the game before and after each call.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Color, Game, GameObserver, GameResult};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// struct Results(Arc<Mutex<Vec<GameResult>>>);
+    ///
+    /// impl GameObserver for Results {
+    ///     fn on_result(&mut self, result: GameResult) {
+    ///         self.0.lock().unwrap().push(result);
+    ///     }
+    /// }
+    ///
+    /// let results = Arc::new(Mutex::new(vec![]));
+    /// let mut game = Game::new();
+    /// game.subscribe(Box::new(Results(results.clone())));
+    /// game.resign(Color::Black);
+    /// assert_eq!(*results.lock().unwrap(), vec![GameResult::BlackResigns]);
+    /// ```
+    pub fn subscribe(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    // tell the observers the result, once the game has one
+    fn notify_result(&mut self) {
+        if self.observers.is_empty() {
+            return;
+        }
+        if let Some(result) = self.result() {
+            for observer in self.observers.iter_mut() {
+                observer.on_result(result);
+            }
         }
     }
 
@@ -213,25 +309,17 @@ impl Game {
         for x in self.moves.iter() {
             match *x {
                 Action::MakeMove(m) => {
-                    let white_castle_rights = board.castle_rights(Color::White);
-                    let black_castle_rights = board.castle_rights(Color::Black);
-                    if board.piece_on(m.get_source()) == Some(Piece::Pawn) {
-                        reversible_moves = 0;
-                        legal_moves_per_turn.clear();
-                    } else if board.piece_on(m.get_dest()).is_some() {
+                    if board.is_zeroing(m) {
                         reversible_moves = 0;
-                        legal_moves_per_turn.clear();
                     } else {
                         reversible_moves += 1;
                     }
-                    board = board.make_move_new(m);
-
-                    if board.castle_rights(Color::White) != white_castle_rights
-                        || board.castle_rights(Color::Black) != black_castle_rights
-                    {
-                        reversible_moves = 0;
+                    // no earlier position can come back, so only look back this far
+                    if board.is_irreversible(m) {
                         legal_moves_per_turn.clear();
                     }
+                    board = board.make_move_new(m);
+
                     legal_moves_per_turn
                         .push((board.get_hash(), MoveGen::new_legal(&board).collect()));
                 }
@@ -288,6 +376,7 @@ impl Game {
     pub fn declare_draw(&mut self) -> bool {
         if self.can_declare_draw() {
             self.moves.push(Action::DeclareDraw);
+            self.notify_result();
             true
         } else {
             false
@@ -309,12 +398,26 @@ impl Game {
         if self.result().is_some() {
             return false;
         }
-        if self.current_position().legal(chess_move) {
-            self.moves.push(Action::MakeMove(chess_move));
-            true
-        } else {
-            false
+        let position = self.current_position();
+        if !position.legal(chess_move) {
+            return false;
         }
+        self.moves.push(Action::MakeMove(chess_move));
+
+        if !self.observers.is_empty() {
+            let board = position.make_move_new(chess_move);
+            let ply = self
+                .moves
+                .iter()
+                .filter(|action| matches!(action, Action::MakeMove(_)))
+                .count()
+                - 1;
+            for observer in self.observers.iter_mut() {
+                observer.on_move(ply, chess_move, &board);
+            }
+            self.notify_result();
+        }
+        true
     }
 
     /// Who's turn is it to move?
@@ -361,6 +464,9 @@ impl Game {
             return false;
         }
         self.moves.push(Action::OfferDraw(color));
+        for observer in self.observers.iter_mut() {
+            observer.on_draw_offer(color);
+        }
         return true;
     }
 
@@ -388,6 +494,7 @@ impl Game {
                 || self.moves[self.moves.len() - 1] == Action::OfferDraw(Color::Black)
             {
                 self.moves.push(Action::AcceptDraw);
+                self.notify_result();
                 return true;
             }
         }
@@ -395,6 +502,7 @@ impl Game {
         if self.moves.len() > 1 {
             if self.moves[self.moves.len() - 2] == Action::OfferDraw(!self.side_to_move()) {
                 self.moves.push(Action::AcceptDraw);
+                self.notify_result();
                 return true;
             }
         }
@@ -415,6 +523,7 @@ impl Game {
             return false;
         }
         self.moves.push(Action::Resign(color));
+        self.notify_result();
         return true;
     }
 }
@@ -427,15 +536,22 @@ impl FromStr for Game {
     }
 }
 
+// The games are normal chess.
 #[cfg(test)]
 pub fn fake_pgn_parser(moves: &str) -> Game {
-    moves
-        .split_whitespace()
-        .filter(|s| !s.ends_with("."))
-        .fold(Game::new(), |mut g, m| {
-            g.make_move(ChessMove::from_san(&g.current_position(), m).expect("Valid SAN Move"));
-            g
-        })
+    use crate::board_builder::BoardBuilder;
+    use crate::variant::Variant;
+    use core::convert::TryInto;
+
+    let board: Board = BoardBuilder::default()
+        .variant(Variant::Standard)
+        .try_into()
+        .unwrap();
+    let mut game = Game::new_with_board(board);
+    for m in board.parse_line(moves).expect("Valid SAN Moves") {
+        game.make_move(m);
+    }
+    game
 }
 
 #[test]
@@ -475,3 +591,103 @@ pub fn test_can_declare_draw() {
     let game = fake_pgn_parser("1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. Nf3 O-O 6. Be2 e5 7. O-O Nc6 8. d5 Ne7 9. Nd2 a5 10. Rb1 Nd7 11. a3 f5 12. b4 Kh8 13. f3 Ng8 14. Qc2 Ngf6 15. Nb5 axb4 16. axb4 Nh5 17. g3 Ndf6 18. c5 Bd7 19. Rb3 Nxg3 20. hxg3 Nh5 21. f4 exf4 22. c6 bxc6 23. dxc6 Nxg3 24. Rxg3 fxg3 25. cxd7 g2 26. Rf3 Qxd7 27. Bb2 fxe4 28. Rxf8+ Rxf8 29. Bxg7+ Qxg7 30. Qxe4 Qf6 31. Nf3 Qf4 32. Qe7 Rf7 33. Qe6 Rf6 34. Qe8+ Rf8 35. Qe7 Rf7 36. Qe6 Rf6 37. Qb3 g5 38. Nxc7 g4 39. Nd5 Qc1+ 40. Qd1 Qxd1+ 41. Bxd1 Rf5 42. Ne3 Rf4 43. Ne1 Rxb4 44. Bxg4 h5 45. Bf3 d5 46. N3xg2 h4 47. Nd3 Ra4 48. Ngf4 Kg7 49. Kg2 Kf6 50. Bxd5 Ra5 51. Bc6 Ra6 52. Bb7 Ra3 53. Be4 Ra4 54. Bd5 Ra5 55. Bc6 Ra6 56. Bf3 Kg5 57. Bb7 Ra1 58. Bc8 Ra4 59. Kf3 Rc4 60. Bd7 Kf6 61. Kg4 Rd4 62. Bc6 Rd8 63. Kxh4 Rg8 64. Be4 Rg1 65. Nh5+ Ke6 66. Ng3 Kf6 67. Kg4 Ra1 68. Bd5 Ra5 69. Bf3 Ra1 70. Kf4 Ke6 71. Nc5+ Kd6 72. Nge4+ Ke7 73. Ke5 Rf1 74. Bg4 Rg1 75. Be6 Re1 76. Bc8 Rc1 77. Kd4 Rd1+ 78. Nd3 Kf7 79. Ke3 Ra1 80. Kf4 Ke7 81. Nb4 Rc1 82. Nd5+ Kf7 83. Bd7 Rf1+ 84. Ke5 Ra1 85. Ng5+ Kg6 86. Nf3 Kg7 87. Bg4 Kg6 88. Nf4+ Kg7 89. Nd4 Re1+ 90. Kf5 Rc1 91. Be2 Re1 92. Bh5 Ra1 93. Nfe6+ Kh6 94. Be8 Ra8 95. Bc6 Ra1 96. Kf6 Kh7 97. Ng5+ Kh8 98. Nde6 Ra6 99. Be8 Ra8 100. Bh5 Ra1 101. Bg6 Rf1+ 102. Ke7 Ra1 103. Nf7+ Kg8 104. Nh6+ Kh8 105. Nf5 Ra7+ 106. Kf6 Ra1 107. Ne3 Re1 108. Nd5 Rg1 109. Bf5 Rf1 110. Ndf4 Ra1 111. Ng6+ Kg8 112. Ne7+ Kh8");
     assert!(!game.can_declare_draw());
 }
+
+#[cfg(test)]
+use crate::castle_rights::Castled;
+
+#[test]
+fn test_game_remembers_castling() {
+    let game = fake_pgn_parser("1. d4 d5 2. Nc3 Nc6 3. Bf4 Bf5 4. Qd2 Qd7 5. O-O-O e6 6. Kb1");
+    let position = game.current_position();
+    assert_eq!(position.castled(Color::White), Castled::QueenSide);
+    assert_eq!(position.castled(Color::Black), Castled::No);
+    assert_eq!(game.start_position().castled(Color::White), Castled::No);
+}
+
+#[cfg(test)]
+use alloc::string::{String, ToString};
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+#[derive(Clone, PartialEq, Debug)]
+enum Event {
+    Move(usize, ChessMove, String),
+    DrawOffer(Color),
+    Result(GameResult),
+}
+
+#[cfg(test)]
+struct Recorder(Arc<Mutex<Vec<Event>>>);
+
+#[cfg(test)]
+impl GameObserver for Recorder {
+    fn on_move(&mut self, ply: usize, chess_move: ChessMove, board: &Board) {
+        let event = Event::Move(ply, chess_move, board.to_string());
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_draw_offer(&mut self, color: Color) {
+        self.0.lock().unwrap().push(Event::DrawOffer(color));
+    }
+
+    fn on_result(&mut self, result: GameResult) {
+        self.0.lock().unwrap().push(Event::Result(result));
+    }
+}
+
+#[test]
+fn test_observers_see_each_change() {
+    let events = Arc::new(Mutex::new(vec![]));
+    let mut game = Game::new();
+    game.subscribe(Box::new(Recorder(events.clone())));
+
+    let m = |text: &str| ChessMove::from_str(text).unwrap();
+    assert!(game.make_move(m("e2e4")));
+    assert!(game.make_move(m("e7e5")));
+    // an illegal move changes nothing, so nobody is told
+    assert!(!game.make_move(m("e4e5")));
+    assert!(game.offer_draw(Color::White));
+    assert!(game.make_move(m("g1f3")));
+    assert!(game.accept_draw());
+    // and neither does anything after the game is over
+    assert!(!game.make_move(m("b8c6")));
+    assert!(!game.resign(Color::Black));
+
+    let position = |moves: &[&str]| {
+        let board = moves
+            .iter()
+            .fold(Board::default(), |board, text| board.make_move_new(m(text)));
+        board.to_string()
+    };
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            Event::Move(0, m("e2e4"), position(&["e2e4"])),
+            Event::Move(1, m("e7e5"), position(&["e2e4", "e7e5"])),
+            Event::DrawOffer(Color::White),
+            Event::Move(2, m("g1f3"), position(&["e2e4", "e7e5", "g1f3"])),
+            Event::Result(GameResult::DrawAccepted),
+        ]
+    );
+
+    // a copy of the game is not watched
+    let mut copy = game.clone();
+    copy.resign(Color::White);
+    assert_eq!(events.lock().unwrap().len(), 5);
+}
+
+#[test]
+fn test_observers_see_checkmate() {
+    let events = Arc::new(Mutex::new(vec![]));
+    let standard = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 variant:standard";
+    let mut game = Game::from_str(standard).unwrap();
+    game.subscribe(Box::new(Recorder(events.clone())));
+    for text in ["f2f3", "e7e5", "g2g4", "d8h4"].iter() {
+        assert!(game.make_move(ChessMove::from_str(text).unwrap()));
+    }
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 5);
+    assert!(matches!(events[3], Event::Move(3, _, _)));
+    assert_eq!(events[4], Event::Result(GameResult::BlackCheckmates));
+}
@@ -0,0 +1,77 @@
+use std::fs::File;
+use std::io::Write;
+
+use crate::bitboard::{BitBoard, EMPTY};
+use crate::square::{Square, ALL_SQUARES};
+
+// Given two squares, how many king moves does it take to get from one to the other?
+// This will be generated here, and then put into the magic_gen.rs as a const array.
+static mut KING_DISTANCE: [[u8; 64]; 64] = [[0; 64]; 64];
+
+// Given a radius and a square, which squares are exactly that many king moves away?
+// This will be generated here, and then put into the magic_gen.rs as a const array.
+static mut RINGS: [[BitBoard; 64]; 8] = [[EMPTY; 64]; 8];
+
+// Given a square, how many king moves does it take to get to the nearest edge?
+// This will be generated here, and then put into the magic_gen.rs as a const array.
+static mut EDGE_DISTANCE: [u8; 64] = [0; 64];
+
+// The Chebyshev distance between two squares.
+fn king_distance(src: Square, dest: Square) -> u8 {
+    let src_rank = src.get_rank().to_index() as i8;
+    let src_file = src.get_file().to_index() as i8;
+    let dest_rank = dest.get_rank().to_index() as i8;
+    let dest_file = dest.get_file().to_index() as i8;
+
+    (src_rank - dest_rank)
+        .abs()
+        .max((src_file - dest_file).abs()) as u8
+}
+
+// Generate the KING_DISTANCE, RINGS and EDGE_DISTANCE arrays.
+pub fn gen_distance() {
+    for src in ALL_SQUARES.iter() {
+        unsafe {
+            EDGE_DISTANCE[src.to_index()] = src.edge_distance();
+        }
+        for dest in ALL_SQUARES.iter() {
+            let distance = king_distance(*src, *dest);
+            unsafe {
+                KING_DISTANCE[src.to_index()][dest.to_index()] = distance;
+                RINGS[distance as usize][src.to_index()] |= BitBoard::from_square(*dest);
+            }
+        }
+    }
+}
+
+// Write the KING_DISTANCE, RINGS and EDGE_DISTANCE arrays to the specified file.
+pub fn write_distance(f: &mut File) {
+    writeln!(f, "const KING_DISTANCE: [[u8; 64]; 64] = [").unwrap();
+    for src in ALL_SQUARES.iter() {
+        write!(f, "    [").unwrap();
+        for dest in ALL_SQUARES.iter() {
+            unsafe { write!(f, "{}, ", KING_DISTANCE[src.to_index()][dest.to_index()]).unwrap() };
+        }
+        writeln!(f, "],").unwrap();
+    }
+    writeln!(f, "];").unwrap();
+
+    let rings = unsafe { RINGS };
+    writeln!(f, "const RINGS: [[BitBoard; 64]; 8] = [[").unwrap();
+    for (radius, ring) in rings.iter().enumerate() {
+        for bb in ring.iter() {
+            writeln!(f, "    BitBoard({}),", bb.0).unwrap();
+        }
+        if radius != rings.len() - 1 {
+            writeln!(f, "  ], [").unwrap();
+        }
+    }
+    writeln!(f, "]];").unwrap();
+
+    let edge_distance = unsafe { EDGE_DISTANCE };
+    write!(f, "const EDGE_DISTANCE: [u8; 64] = [").unwrap();
+    for distance in edge_distance.iter() {
+        write!(f, "{}, ", distance).unwrap();
+    }
+    writeln!(f, "];").unwrap();
+}
@@ -12,6 +12,7 @@ use std::fs::File;
 use std::path::Path;
 
 use crate::gen_tables::between::*;
+use crate::gen_tables::distance::*;
 use crate::gen_tables::king::*;
 use crate::gen_tables::knights::*;
 use crate::gen_tables::lines::*;
@@ -27,6 +28,7 @@ use crate::gen_tables::magic::*;
 pub fn generate_all_tables() {
     gen_lines();
     gen_between();
+    gen_distance();
     gen_bishop_rays();
     gen_rook_rays();
     gen_knight_moves();
@@ -46,6 +48,7 @@ pub fn generate_all_tables() {
     write_knight_moves(&mut f);
     write_rays(&mut f);
     write_between(&mut f);
+    write_distance(&mut f);
     write_lines(&mut f);
     write_pawn_attacks(&mut f);
     write_pawn_moves(&mut f);
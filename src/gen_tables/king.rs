@@ -4,7 +4,7 @@ use std::io::Write;
 use crate::bitboard::{BitBoard, EMPTY};
 use crate::color::ALL_COLORS;
 use crate::file::File as ChessFile;
-use crate::square::{Square, ALL_SQUARES};
+use crate::square::ALL_SQUARES;
 
 // Given a square, what are the valid king moves?
 static mut KING_MOVES: [BitBoard; 64] = [EMPTY; 64];
@@ -39,8 +39,8 @@ fn gen_kingside_castle_squares() {
     for color in ALL_COLORS.iter() {
         unsafe {
             KINGSIDE_CASTLE_SQUARES[color.to_index()] =
-                BitBoard::set(color.to_my_backrank(), ChessFile::F)
-                    ^ BitBoard::set(color.to_my_backrank(), ChessFile::G);
+                BitBoard::set(color.back_rank(), ChessFile::F)
+                    ^ BitBoard::set(color.back_rank(), ChessFile::G);
         }
     }
 }
@@ -49,22 +49,13 @@ fn gen_queenside_castle_squares() {
     for color in ALL_COLORS.iter() {
         unsafe {
             QUEENSIDE_CASTLE_SQUARES[color.to_index()] =
-                BitBoard::set(color.to_my_backrank(), ChessFile::B)
-                    ^ BitBoard::set(color.to_my_backrank(), ChessFile::C)
-                    ^ BitBoard::set(color.to_my_backrank(), ChessFile::D);
+                BitBoard::set(color.back_rank(), ChessFile::B)
+                    ^ BitBoard::set(color.back_rank(), ChessFile::C)
+                    ^ BitBoard::set(color.back_rank(), ChessFile::D);
         }
     }
 }
 
-fn gen_castle_moves() -> BitBoard {
-    BitBoard::from_square(Square::C1)
-        ^ BitBoard::from_square(Square::C8)
-        ^ BitBoard::from_square(Square::E1)
-        ^ BitBoard::from_square(Square::E8)
-        ^ BitBoard::from_square(Square::G1)
-        ^ BitBoard::from_square(Square::G8)
-}
-
 // Write the KING_MOVES array to the specified file.
 pub fn write_king_moves(f: &mut File) {
     write!(f, "const KING_MOVES: [BitBoard; 64] = [\n").unwrap();
@@ -94,11 +85,4 @@ pub fn write_king_moves(f: &mut File) {
         )
         .unwrap()
     };
-
-    write!(
-        f,
-        "const CASTLE_MOVES: BitBoard = BitBoard({});\n",
-        gen_castle_moves().0
-    )
-    .unwrap();
 }
@@ -1,7 +1,9 @@
 use rand::rngs::SmallRng;
 use rand::SeedableRng;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
+use std::ptr::{addr_of, addr_of_mut};
 
 use crate::bitboard::{BitBoard, EMPTY};
 use crate::gen_tables::magic_helpers::{
@@ -20,7 +22,7 @@ struct Magic {
     rightshift: u8,
 }
 
-// These numbers allow you to hash a set of blocking pieces, and get an index in the MOVES
+// These numbers allow you to hash a set of blocking pieces, and get an index in the MOVE_INDICES
 // array to return the valid moves, given a set of blocking pieces.
 // This will be generated here, but then put into the magic_gen.rs as a const array.
 static mut MAGIC_NUMBERS: [[Magic; NUM_SQUARES]; 2] = [[Magic {
@@ -30,19 +32,20 @@ static mut MAGIC_NUMBERS: [[Magic; NUM_SQUARES]; 2] = [[Magic {
     rightshift: 0,
 }; 64]; 2];
 
-// How many squares can a blocking piece be on for the rook?
+// How many entries of the MOVE_INDICES array are in use?
 static mut GENERATED_NUM_MOVES: usize = 0;
 
-// This is the valid move lookup table.  This will be generated here, then put into
-// the magic_gen.rs as a const array.
-static mut MOVES: [BitBoard; NUM_MOVES] = [EMPTY; NUM_MOVES];
+// This is the lookup table from a magic index to an entry in ATTACKS.  This will be generated
+// here, then put into the magic_gen.rs as a const array.
+static mut MOVE_INDICES: [u16; NUM_MOVES] = [0; NUM_MOVES];
 
-// When a MOVES bitboard is updated, update this with the rays that the MOVES bitboard
-// may have set.  This helps with compressing the MOVES array.
-static mut MOVE_RAYS: [BitBoard; NUM_MOVES] = [EMPTY; NUM_MOVES];
+// The distinct sets of rook or bishop moves for each square, which MOVE_INDICES points into.
+// Most sets of blocking pieces produce the same moves as some other set, so storing each set of
+// moves once and pointing at it with a u16 is far smaller than storing a BitBoard per index.
+static mut ATTACKS: Vec<BitBoard> = Vec::new();
 
 // Find a perfect hashing function for the move generation for a particular square and piece type
-// Store the resulting move array in MOVES[cur_offset...], and return the next offset
+// Store the resulting indices in MOVE_INDICES[cur_offset...], and return the next offset
 // to be used
 fn generate_magic(sq: Square, piece: Piece, cur_offset: usize) -> usize {
     let (questions, answers) = questions_and_answers(sq, piece);
@@ -55,28 +58,10 @@ fn generate_magic(sq: Square, piece: Piece, cur_offset: usize) -> usize {
         answers.iter().fold(EMPTY, |b, n| b | *n),
         get_rays(sq, piece)
     );
-    let mut new_offset = cur_offset;
-
-    for i in 0..cur_offset {
-        let mut found = true;
-        for j in 0..answers.len() {
-            unsafe {
-                if MOVE_RAYS[i + j] & get_rays(sq, piece) != EMPTY {
-                    found = false;
-                    break;
-                }
-            }
-        }
-        if found {
-            new_offset = i;
-            break;
-        }
-    }
-
     let mut new_magic = Magic {
         magic_number: EMPTY,
         mask: mask,
-        offset: new_offset as u32,
+        offset: cur_offset as u32,
         rightshift: ((questions.len() as u64).leading_zeros() + 1) as u8,
     };
 
@@ -109,19 +94,19 @@ fn generate_magic(sq: Square, piece: Piece, cur_offset: usize) -> usize {
     unsafe {
         MAGIC_NUMBERS[if piece == Piece::Rook { 0 } else { 1 }][sq.to_index()] = new_magic;
 
+        let attacks = &mut *addr_of_mut!(ATTACKS);
+        let mut seen = HashMap::new();
         for i in 0..questions.len() {
             let j = (new_magic.magic_number * questions[i]).to_size(new_magic.rightshift);
-            MOVES[(new_magic.offset as usize) + j] |= answers[i];
-            MOVE_RAYS[(new_magic.offset as usize) + j] |= get_rays(sq, piece);
-        }
-        if new_offset + questions.len() < cur_offset {
-            new_offset = cur_offset;
-        } else {
-            new_offset += questions.len();
+            let index = *seen.entry(answers[i]).or_insert_with(|| {
+                attacks.push(answers[i]);
+                attacks.len() - 1
+            });
+            MOVE_INDICES[cur_offset + j] = index as u16;
         }
-        GENERATED_NUM_MOVES = new_offset;
+        GENERATED_NUM_MOVES = cur_offset + questions.len();
     }
-    new_offset
+    cur_offset + questions.len()
 }
 
 // Generate the magic each square for both rooks and bishops.
@@ -134,7 +119,7 @@ pub fn gen_all_magic() {
     }
 }
 
-// Write the MAGIC_NUMBERS and MOVES arrays to the specified file.
+// Write the MAGIC_NUMBERS, MOVE_INDICES and ATTACKS arrays to the specified file.
 pub fn write_magic(f: &mut File) {
     write!(f, "#[derive(Copy, Clone)]\n").unwrap();
     write!(f, "struct Magic {{\n").unwrap();
@@ -161,11 +146,18 @@ pub fn write_magic(f: &mut File) {
     }
     write!(f, "]];\n").unwrap();
 
-    unsafe {
-        write!(f, "const MOVES: [BitBoard; {}] = [\n", GENERATED_NUM_MOVES).unwrap();
-        for i in 0..GENERATED_NUM_MOVES {
-            write!(f, "    BitBoard({}),\n", MOVES[i].0).unwrap();
-        }
+    let num_moves = unsafe { GENERATED_NUM_MOVES };
+    let move_indices = unsafe { &*addr_of!(MOVE_INDICES) };
+    writeln!(f, "static MOVE_INDICES: [u16; {}] = [", num_moves).unwrap();
+    for index in move_indices[..num_moves].iter() {
+        writeln!(f, "    {},", index).unwrap();
+    }
+    writeln!(f, "];").unwrap();
+
+    let attacks = unsafe { &*addr_of!(ATTACKS) };
+    writeln!(f, "static ATTACKS: [BitBoard; {}] = [", attacks.len()).unwrap();
+    for attack in attacks.iter() {
+        writeln!(f, "    BitBoard({}),", attack.0).unwrap();
     }
-    write!(f, "];\n").unwrap();
+    writeln!(f, "];").unwrap();
 }
@@ -10,6 +10,7 @@ extern crate rand;
 mod between;
 #[cfg(target_feature = "bmi2")]
 mod bmis;
+mod distance;
 mod generate_all_tables;
 mod king;
 mod knights;
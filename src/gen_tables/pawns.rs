@@ -3,8 +3,6 @@ use std::io::Write;
 
 use crate::bitboard::{BitBoard, EMPTY};
 use crate::color::ALL_COLORS;
-use crate::file::ALL_FILES;
-use crate::rank::Rank;
 use crate::square::ALL_SQUARES;
 
 // Given a square, what are the valid quiet pawn moves (non-captures)?
@@ -66,23 +64,15 @@ pub fn gen_pawn_attacks() {
 }
 
 pub fn gen_source_double_moves() -> BitBoard {
-    let mut result = BitBoard(0);
-    for rank in [Rank::Second, Rank::Seventh].iter() {
-        for file in ALL_FILES.iter() {
-            result ^= BitBoard::set(*rank, *file);
-        }
-    }
-    result
+    ALL_COLORS.iter().fold(EMPTY, |result, color| {
+        result | BitBoard::from_rank(color.to_second_rank())
+    })
 }
 
 pub fn gen_dest_double_moves() -> BitBoard {
-    let mut result = BitBoard(0);
-    for rank in [Rank::Fourth, Rank::Fifth].iter() {
-        for file in ALL_FILES.iter() {
-            result ^= BitBoard::set(*rank, *file);
-        }
-    }
-    result
+    ALL_COLORS.iter().fold(EMPTY, |result, color| {
+        result | BitBoard::from_rank(color.double_push_rank())
+    })
 }
 
 // Write the PAWN_MOVES array to the specified file.
@@ -2,15 +2,50 @@ use std::fs::File;
 use std::io::Write;
 // we use the same types as the rest of the library.
 use crate::color::NUM_COLORS;
+use crate::file::NUM_FILES;
 use crate::piece::NUM_PIECES;
 use crate::square::NUM_SQUARES;
-use rand::rngs::SmallRng;
-use rand::{RngCore, SeedableRng};
+
+// The zobrist keys must never change, because users persist hashes (opening books,
+// transposition tables on disk).  So rather than depending on whatever rand's SmallRng happens to
+// be on the build machine, the keys come from this fixed generator: PCG64 MCG (XSL RR output),
+// seeded from ZOBRIST_SEED the way rand_core 0.5's seed_from_u64 does.  That is exactly what
+// SmallRng was on 64-bit machines when the keys were first generated, so the keys are unchanged.
+// If the keys ever have to change, bump zobrist::VERSION.
+const ZOBRIST_SEED: u64 = 0xDEADBEEF12345678;
+
+struct ZobristRng {
+    state: u128,
+}
+
+impl ZobristRng {
+    fn seed_from_u64(mut seed: u64) -> ZobristRng {
+        // expand the seed into 128 bits with PCG32
+        let mut state: u128 = 0;
+        for i in 0..4 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(11634580027462260723);
+            let xorshifted = (((seed >> 18) ^ seed) >> 27) as u32;
+            let word = xorshifted.rotate_right((seed >> 59) as u32);
+            state |= (word as u128) << (32 * i);
+        }
+        ZobristRng { state: state | 1 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(0x2360_ED05_1FC6_5DA4_4385_DF64_9FCC_F645);
+        let xsl = ((self.state >> 64) as u64) ^ (self.state as u64);
+        xsl.rotate_right((self.state >> 122) as u32)
+    }
+}
 
 // write the ZOBRIEST_* arrays to a file.  I don't generate it, because its just
 // a bunch of random u64s
 pub fn write_zobrist(f: &mut File) {
-    let mut rng = SmallRng::seed_from_u64(0xDEADBEEF12345678);
+    let mut rng = ZobristRng::seed_from_u64(ZOBRIST_SEED);
 
     write!(f, "const SIDE_TO_MOVE: u64 = {};\n\n", rng.next_u64()).unwrap();
 
@@ -44,4 +79,53 @@ pub fn write_zobrist(f: &mut File) {
         }
     }
     write!(f, "]];\n\n").unwrap();
+
+    // added after the other keys, so that generating them does not change any of those
+    writeln!(
+        f,
+        "const ZOBRIST_PAWNS: [[u64; NUM_SQUARES]; NUM_COLORS] = [["
+    )
+    .unwrap();
+    for i in 0..NUM_COLORS {
+        for _ in 0..NUM_SQUARES {
+            writeln!(f, "    {},", rng.next_u64()).unwrap();
+        }
+        if i != NUM_COLORS - 1 {
+            writeln!(f, "  ], [").unwrap();
+        }
+    }
+    writeln!(f, "]];\n").unwrap();
+
+    // one key per color, piece and how many of that piece there already are
+    writeln!(
+        f,
+        "const ZOBRIST_MATERIAL: [[[u64; NUM_SQUARES / 4]; NUM_PIECES]; NUM_COLORS] = [[["
+    )
+    .unwrap();
+    for i in 0..NUM_COLORS {
+        for j in 0..NUM_PIECES {
+            for _ in 0..NUM_SQUARES / 4 {
+                writeln!(f, "    {},", rng.next_u64()).unwrap();
+            }
+            if j != NUM_PIECES - 1 {
+                writeln!(f, "   ], [").unwrap();
+            }
+        }
+        if i != NUM_COLORS - 1 {
+            writeln!(f, "  ]], [[").unwrap();
+        }
+    }
+    writeln!(f, "]]];\n").unwrap();
+
+    // only standard chess has en passant, one key per file and the color of the pawn that moved
+    writeln!(f, "const ZOBRIST_EP: [[u64; NUM_FILES]; NUM_COLORS] = [[").unwrap();
+    for i in 0..NUM_COLORS {
+        for _ in 0..NUM_FILES {
+            writeln!(f, "    {},", rng.next_u64()).unwrap();
+        }
+        if i != NUM_COLORS - 1 {
+            writeln!(f, "  ], [").unwrap();
+        }
+    }
+    writeln!(f, "]];\n").unwrap();
 }
@@ -0,0 +1,255 @@
+//! Conversions to and from the types of the `chess` crate on crates.io, behind the
+//! `interop-chess` feature, for tools such as opening books, tablebase probers and UIs that are
+//! written against it.
+//!
+//! `Square`, `Piece`, `Color` and `ChessMove` convert both ways with `From`.  A `Board` goes
+//! through its FEN with `TryFrom`, and always comes back from the `chess` crate in
+//! `Variant::Standard`, since that crate only plays normal chess.  It fails the other way if the
+//! position is not a valid normal chess position, such as one where the side not to move stands
+//! in check from afar under the uno rules, or if a side may castle with a rook off the a or h
+//! file, which the `chess` crate cannot castle with.
+//!
+//! Only the pieces and squares carry over, not the rules.  A move that is legal on one side may
+//! be illegal on the other, for example a capture under the uno rules of a piece that is
+//! defended, or a Chess960 castle, which is written as the king taking its own rook.
+//!
+//! ```
+//! use chess::{Board, ChessMove, Square, Variant};
+//! use std::convert::TryFrom;
+//!
+//! let board = Board::default();
+//! let theirs = upstream_chess::Board::try_from(&board).unwrap();
+//! assert_eq!(theirs.to_string(), board.to_string());
+//!
+//! let back = Board::try_from(&theirs).unwrap();
+//! assert_eq!(back.variant(), Variant::Standard);
+//!
+//! let m = ChessMove::new(Square::E2, Square::E4, None);
+//! assert!(theirs.legal(m.into()));
+//! ```
+
+use crate::board::Board;
+use crate::board_builder::BoardBuilder;
+use crate::castle_rights::{CastleRights, ALL_CASTLE_RIGHTS};
+use crate::chess_move::ChessMove;
+use crate::color::{Color, ALL_COLORS};
+use crate::error::{Error, Span};
+use crate::file::File;
+use crate::piece::Piece;
+use crate::square::Square;
+use crate::variant::Variant;
+use core::convert::{TryFrom, TryInto};
+use core::str::FromStr;
+
+use alloc::string::ToString;
+
+impl From<Square> for upstream_chess::Square {
+    fn from(square: Square) -> Self {
+        upstream_chess::ALL_SQUARES[square.to_index()]
+    }
+}
+
+impl From<upstream_chess::Square> for Square {
+    fn from(square: upstream_chess::Square) -> Self {
+        Square::new(square.to_int())
+    }
+}
+
+impl From<Piece> for upstream_chess::Piece {
+    fn from(piece: Piece) -> Self {
+        match piece {
+            Piece::Pawn => upstream_chess::Piece::Pawn,
+            Piece::Knight => upstream_chess::Piece::Knight,
+            Piece::Bishop => upstream_chess::Piece::Bishop,
+            Piece::Rook => upstream_chess::Piece::Rook,
+            Piece::Queen => upstream_chess::Piece::Queen,
+            Piece::King => upstream_chess::Piece::King,
+        }
+    }
+}
+
+impl From<upstream_chess::Piece> for Piece {
+    fn from(piece: upstream_chess::Piece) -> Self {
+        match piece {
+            upstream_chess::Piece::Pawn => Piece::Pawn,
+            upstream_chess::Piece::Knight => Piece::Knight,
+            upstream_chess::Piece::Bishop => Piece::Bishop,
+            upstream_chess::Piece::Rook => Piece::Rook,
+            upstream_chess::Piece::Queen => Piece::Queen,
+            upstream_chess::Piece::King => Piece::King,
+        }
+    }
+}
+
+impl From<Color> for upstream_chess::Color {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::White => upstream_chess::Color::White,
+            Color::Black => upstream_chess::Color::Black,
+        }
+    }
+}
+
+impl From<upstream_chess::Color> for Color {
+    fn from(color: upstream_chess::Color) -> Self {
+        match color {
+            upstream_chess::Color::White => Color::White,
+            upstream_chess::Color::Black => Color::Black,
+        }
+    }
+}
+
+impl From<ChessMove> for upstream_chess::ChessMove {
+    fn from(m: ChessMove) -> Self {
+        upstream_chess::ChessMove::new(
+            m.get_source().into(),
+            m.get_dest().into(),
+            m.get_promotion().map(|piece| piece.into()),
+        )
+    }
+}
+
+impl From<upstream_chess::ChessMove> for ChessMove {
+    fn from(m: upstream_chess::ChessMove) -> Self {
+        ChessMove::new(
+            m.get_source().into(),
+            m.get_dest().into(),
+            m.get_promotion().map(|piece| piece.into()),
+        )
+    }
+}
+
+impl TryFrom<&Board> for upstream_chess::Board {
+    type Error = Error;
+
+    fn try_from(board: &Board) -> Result<Self, Self::Error> {
+        // the `chess` crate only castles from the e file with the rooks on the a and h files
+        for color in ALL_COLORS.iter() {
+            let rights = board.castle_rights(*color);
+            if rights != CastleRights::NoRights
+                && (board.king_square(*color).get_file() != File::E
+                    || !ALL_CASTLE_RIGHTS.contains(&rights))
+            {
+                return Err(Error::InvalidBoard);
+            }
+        }
+
+        let fen = board.to_string();
+        upstream_chess::Board::from_str(&fen).map_err(|error| match error {
+            upstream_chess::Error::InvalidBoard => Error::InvalidBoard,
+            _ => Error::InvalidFen {
+                span: Span::of(&fen),
+                fen,
+            },
+        })
+    }
+}
+
+impl TryFrom<Board> for upstream_chess::Board {
+    type Error = Error;
+
+    fn try_from(board: Board) -> Result<Self, Self::Error> {
+        (&board).try_into()
+    }
+}
+
+impl TryFrom<&upstream_chess::Board> for Board {
+    type Error = Error;
+
+    fn try_from(board: &upstream_chess::Board) -> Result<Self, Self::Error> {
+        // the `chess` crate writes the pawn that can be taken en passant, not the square it
+        // passed, so that is taken from the board instead of the FEN
+        BoardBuilder::from_str(&board.to_string())?
+            .en_passant(board.en_passant().map(|sq| Square::from(sq).get_file()))
+            .variant(Variant::Standard)
+            .try_into()
+    }
+}
+
+impl TryFrom<upstream_chess::Board> for Board {
+    type Error = Error;
+
+    fn try_from(board: upstream_chess::Board) -> Result<Self, Self::Error> {
+        (&board).try_into()
+    }
+}
+
+#[cfg(test)]
+use crate::movegen::MoveGen;
+#[cfg(test)]
+use crate::square::ALL_SQUARES;
+#[cfg(test)]
+use std::collections::HashSet;
+
+#[test]
+fn test_small_types_round_trip() {
+    for square in ALL_SQUARES.iter() {
+        let theirs: upstream_chess::Square = (*square).into();
+        assert_eq!(theirs.to_string(), square.to_string());
+        assert_eq!(Square::from(theirs), *square);
+    }
+    for piece in crate::piece::ALL_PIECES.iter() {
+        let theirs: upstream_chess::Piece = (*piece).into();
+        assert_eq!(theirs.to_index(), piece.to_index());
+        assert_eq!(Piece::from(theirs), *piece);
+    }
+    for color in ALL_COLORS.iter() {
+        assert_eq!(Color::from(upstream_chess::Color::from(*color)), *color);
+    }
+}
+
+#[test]
+fn test_boards_and_moves_round_trip() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ];
+    for fen in fens.iter() {
+        let mut builder = BoardBuilder::from_str(fen).unwrap();
+        let board: Board = builder.variant(Variant::Standard).try_into().unwrap();
+        let theirs = upstream_chess::Board::try_from(&board).unwrap();
+        assert_eq!(theirs.en_passant().map(Square::from), board.en_passant());
+        assert_eq!(Board::try_from(&theirs).unwrap(), board);
+
+        // in normal chess both sides agree on every legal move
+        let moves: HashSet<ChessMove> = MoveGen::new_legal(&board).collect();
+        let their_moves: HashSet<ChessMove> = upstream_chess::MoveGen::new_legal(&theirs)
+            .map(ChessMove::from)
+            .collect();
+        assert_eq!(moves, their_moves, "{}", fen);
+        for m in moves.iter() {
+            let their_move = upstream_chess::ChessMove::from(*m);
+            assert_eq!(ChessMove::from(their_move), *m);
+            let child = Board::try_from(theirs.make_move_new(their_move)).unwrap();
+            assert_eq!(child, board.make_move_new(*m));
+        }
+    }
+}
+
+#[test]
+fn test_boards_that_do_not_convert() {
+    // an uno board comes back as normal chess
+    let board = Board::from_str("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    let back = Board::try_from(upstream_chess::Board::try_from(board).unwrap()).unwrap();
+    assert_eq!(back.variant(), Variant::Standard);
+    assert_eq!(back.to_string(), board.to_string());
+
+    // the black king stands in check from afar, which only the uno rules allow
+    let board = Board::from_str("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1").unwrap();
+    assert!(upstream_chess::Board::try_from(board).is_err());
+
+    // the `chess` crate cannot castle with a Chess960 rook
+    for fen in [
+        "4k3/8/8/8/8/8/8/1R2K1RR w G - 0 1",
+        "4k3/8/8/8/8/8/8/R4KR1 w KQ - 0 1",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        assert!(upstream_chess::Board::try_from(board).is_err(), "{}", fen);
+    }
+}
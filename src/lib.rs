@@ -1,4 +1,5 @@
 #![doc(html_root_url = "https://jordanbray.github.io/chess/")]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 //! # Rust Chess Library
 //! This is a chess move generation library for rust.  It is designed to be fast, so that it can be
 //! used in a chess engine or UI without performance issues.
@@ -17,6 +18,43 @@
 //! assert_eq!(movegen.len(), 20);
 //! ```
 //!
+//! ## Variants
+//!
+//! Every `Board` is played by one of the rules in `Variant`.  `Board::default()` and FEN strings
+//! give the uno variant, where a piece may only capture a piece that attacks it.  Normal chess is
+//! `Variant::Standard`, set with `BoardBuilder::variant`.
+//!
+//! ```
+//! use chess::{BoardBuilder, Board, MoveGen, Variant};
+//! use std::convert::TryInto;
+//!
+//! let board: Board = BoardBuilder::default()
+//!     .variant(Variant::Standard)
+//!     .try_into()
+//!     .expect("Valid Position");
+//! assert_eq!(MoveGen::movegen_perft_test(&board, 4), 197281);
+//! ```
+//!
+//! ## `no_std`
+//!
+//! The `std` feature is on by default.  With `default-features = false` the crate is `#![no_std]`
+//! and only needs the `alloc` crate, for FEN strings, `Game` history and `CacheTable`.  All of
+//! the lookup tables are generated by build.rs as constants, so nothing has to be initialized at
+//! runtime either way.
+//!
+//! ## WebAssembly
+//!
+//! `wasm32-unknown-unknown` needs no extra features.  Nothing uses OS randomness (build.rs seeds
+//! its own generator), and the lookup tables are constants, so there is nothing to initialize in
+//! the browser.  Only a few functions use threads or a clock, and they work without them there:
+//! `MoveGen::perft_parallel_depth` and `MoveGen::perft_parallel_report` count on the calling
+//! thread, and `MoveGen::perft_report`, `MoveGen::perft_parallel_report` and the
+//! `movegen-stats` feature leave their runs untimed.  The `pext` feature has no effect there,
+//! because wasm has no BMI2.  `tests/wasm.rs` is a smoke test for that target, run with
+//! `wasm-pack test --node`.
+//!
+
+extern crate alloc;
 
 mod board;
 pub use crate::board::*;
@@ -24,9 +62,17 @@ pub use crate::board::*;
 mod bitboard;
 pub use crate::bitboard::{BitBoard, EMPTY};
 
+#[cfg(feature = "attack-info")]
+mod attack_info;
+#[cfg(feature = "attack-info")]
+pub use crate::attack_info::AttackInfo;
+
 mod cache_table;
 pub use crate::cache_table::*;
 
+mod atomic_cache_table;
+pub use crate::atomic_cache_table::*;
+
 mod castle_rights;
 pub use crate::castle_rights::*;
 
@@ -44,9 +90,10 @@ pub use crate::file::*;
 
 mod magic;
 pub use crate::magic::{
-    between, get_adjacent_files, get_bishop_moves, get_bishop_rays, get_file, get_king_moves,
-    get_knight_moves, get_pawn_attacks, get_pawn_moves, get_pawn_quiets, get_rank, get_rook_moves,
-    get_rook_rays, line, EDGES,
+    attacks_of, between, get_adjacent_files, get_bishop_moves, get_bishop_rays, get_edge_distance,
+    get_file, get_king_distance, get_king_moves, get_knight_moves, get_pawn_attacks,
+    get_pawn_moves, get_pawn_quiets, get_queen_moves, get_rank, get_ring, get_rook_moves,
+    get_rook_rays, line, xray_bishop_attacks, xray_rook_attacks, EDGES,
 };
 
 #[cfg(target_feature = "bmi2")]
@@ -61,16 +108,67 @@ pub use crate::rank::*;
 mod square;
 pub use crate::square::*;
 
+mod variant;
+pub use crate::variant::*;
+
+mod symmetry;
+pub use crate::symmetry::*;
+
+mod capture_rules;
+pub use crate::capture_rules::*;
+
 mod movegen;
-pub use crate::movegen::MoveGen;
+pub use crate::movegen::{diff_legal_moves, MoveGen, PerftReport, StagedMoveGen};
+#[cfg(feature = "movegen-stats")]
+pub use crate::movegen::{stats_snapshot, MoveGenStats, SquareAndBitBoard};
+
+pub mod zobrist;
+
+pub mod stability;
+
+pub mod values;
 
-mod zobrist;
+pub mod uci;
+
+pub mod pawns;
 
 mod game;
-pub use crate::game::{Action, Game, GameResult};
+pub use crate::game::{Action, Game, GameObserver, GameResult};
 
 mod board_builder;
 pub use crate::board_builder::BoardBuilder;
 
+mod board_history;
+pub use crate::board_history::BoardHistory;
+
+mod accumulator;
+pub use crate::accumulator::Accumulator;
+
+mod render;
+pub use crate::render::RenderOptions;
+
 mod error;
-pub use crate::error::Error;
+pub use crate::error::{ApplyError, Error, Span};
+
+#[cfg(feature = "proptest")]
+mod arbitrary;
+
+#[cfg(test)]
+mod playouts;
+
+#[cfg(feature = "rand")]
+mod random;
+#[cfg(feature = "rand")]
+pub use crate::random::RandomBoardOptions;
+
+#[cfg(feature = "interop-chess")]
+mod interop;
+
+#[cfg(feature = "positions")]
+pub mod positions;
+
+#[cfg(feature = "syzygy")]
+pub mod tablebase;
+
+#[cfg(feature = "polyglot")]
+pub mod polyglot;
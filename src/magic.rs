@@ -1,10 +1,11 @@
 use crate::bitboard::{BitBoard, EMPTY};
 use crate::color::Color;
 use crate::file::File;
+use crate::piece::Piece;
 use crate::rank::Rank;
 use crate::square::Square;
 #[cfg(target_feature = "bmi2")]
-use std::arch::x86_64::{_pdep_u64, _pext_u64};
+use core::arch::x86_64::{_pdep_u64, _pext_u64};
 
 // Include the generated lookup tables
 include!(concat!(env!("OUT_DIR"), "/magic_gen.rs"));
@@ -22,19 +23,60 @@ pub fn get_rook_rays(sq: Square) -> BitBoard {
 }
 
 /// Get the moves for a rook on a particular square, given blockers blocking my movement.
+///
+/// With the `pext` feature enabled on a target with BMI2, this uses the `get_rook_moves_bmi`
+/// tables instead of magic bitboards.  The result is the same either way.
 #[inline]
 pub fn get_rook_moves(sq: Square, blockers: BitBoard) -> BitBoard {
+    #[cfg(all(feature = "pext", target_feature = "bmi2"))]
+    {
+        get_rook_moves_bmi(sq, blockers)
+    }
+    #[cfg(not(all(feature = "pext", target_feature = "bmi2")))]
+    {
+        get_rook_moves_magic(sq, blockers)
+    }
+}
+
+/// Get the moves for a rook on a particular square using magic bitboards.
+#[inline]
+#[cfg_attr(all(feature = "pext", target_feature = "bmi2"), allow(dead_code))]
+fn get_rook_moves_magic(sq: Square, blockers: BitBoard) -> BitBoard {
     unsafe {
         let magic: Magic = *MAGIC_NUMBERS
             .get_unchecked(ROOK)
             .get_unchecked(sq.to_int() as usize);
-        *MOVES.get_unchecked(
+        let index = *MOVE_INDICES.get_unchecked(
             (magic.offset as usize)
                 + (magic.magic_number * (blockers & magic.mask)).to_size(magic.rightshift),
-        ) & get_rook_rays(sq)
+        );
+        *ATTACKS.get_unchecked(index as usize)
     }
 }
 
+/// Get the squares a rook on `sq` would additionally see if the `blockers` that stop its rays
+/// in `occupied` were removed.  Only the first blocker on each ray is looked through.
+///
+/// ```
+/// use chess::{xray_rook_attacks, BitBoard, Square};
+///
+/// let occupied = BitBoard::from_square(Square::A3) | BitBoard::from_square(Square::A6);
+/// let blockers = BitBoard::from_square(Square::A3);
+///
+/// assert_eq!(
+///     xray_rook_attacks(Square::A1, occupied, blockers),
+///     BitBoard::from_square(Square::A4)
+///         | BitBoard::from_square(Square::A5)
+///         | BitBoard::from_square(Square::A6)
+/// );
+/// ```
+#[inline]
+pub fn xray_rook_attacks(sq: Square, occupied: BitBoard, blockers: BitBoard) -> BitBoard {
+    let attacks = get_rook_moves(sq, occupied);
+    let blockers = blockers & attacks;
+    attacks ^ get_rook_moves(sq, occupied ^ blockers)
+}
+
 /// Get the moves for a rook on a particular square, given blockers blocking my movement.
 #[cfg(target_feature = "bmi2")]
 #[inline]
@@ -52,19 +94,46 @@ pub fn get_rook_moves_bmi(sq: Square, blockers: BitBoard) -> BitBoard {
 }
 
 /// Get the moves for a bishop on a particular square, given blockers blocking my movement.
+///
+/// With the `pext` feature enabled on a target with BMI2, this uses the `get_bishop_moves_bmi`
+/// tables instead of magic bitboards.  The result is the same either way.
 #[inline]
 pub fn get_bishop_moves(sq: Square, blockers: BitBoard) -> BitBoard {
+    #[cfg(all(feature = "pext", target_feature = "bmi2"))]
+    {
+        get_bishop_moves_bmi(sq, blockers)
+    }
+    #[cfg(not(all(feature = "pext", target_feature = "bmi2")))]
+    {
+        get_bishop_moves_magic(sq, blockers)
+    }
+}
+
+/// Get the moves for a bishop on a particular square using magic bitboards.
+#[inline]
+#[cfg_attr(all(feature = "pext", target_feature = "bmi2"), allow(dead_code))]
+fn get_bishop_moves_magic(sq: Square, blockers: BitBoard) -> BitBoard {
     unsafe {
         let magic: Magic = *MAGIC_NUMBERS
             .get_unchecked(BISHOP)
             .get_unchecked(sq.to_int() as usize);
-        *MOVES.get_unchecked(
+        let index = *MOVE_INDICES.get_unchecked(
             (magic.offset as usize)
                 + (magic.magic_number * (blockers & magic.mask)).to_size(magic.rightshift),
-        ) & get_bishop_rays(sq)
+        );
+        *ATTACKS.get_unchecked(index as usize)
     }
 }
 
+/// Get the squares a bishop on `sq` would additionally see if the `blockers` that stop its rays
+/// in `occupied` were removed.  Only the first blocker on each ray is looked through.
+#[inline]
+pub fn xray_bishop_attacks(sq: Square, occupied: BitBoard, blockers: BitBoard) -> BitBoard {
+    let attacks = get_bishop_moves(sq, occupied);
+    let blockers = blockers & attacks;
+    attacks ^ get_bishop_moves(sq, occupied ^ blockers)
+}
+
 /// Get the moves for a bishop on a particular square, given blockers blocking my movement.
 #[inline]
 #[cfg(target_feature = "bmi2")]
@@ -81,6 +150,40 @@ pub fn get_bishop_moves_bmi(sq: Square, blockers: BitBoard) -> BitBoard {
     }
 }
 
+/// Get the moves for a queen on a particular square, given blockers blocking my movement.
+#[inline]
+pub fn get_queen_moves(sq: Square, blockers: BitBoard) -> BitBoard {
+    get_rook_moves(sq, blockers) ^ get_bishop_moves(sq, blockers)
+}
+
+/// Get the squares attacked by a `piece` of `color` on a particular square, given the pieces
+/// on the board.  The color only matters for pawns, whose attacks are returned whether or not
+/// there is anything on the attacked squares.
+///
+/// ```
+/// use chess::{attacks_of, get_knight_moves, get_queen_moves, Color, Piece, Square, EMPTY};
+///
+/// assert_eq!(
+///     attacks_of(Piece::Knight, Square::B1, Color::White, EMPTY),
+///     get_knight_moves(Square::B1)
+/// );
+/// assert_eq!(
+///     attacks_of(Piece::Queen, Square::D4, Color::Black, EMPTY),
+///     get_queen_moves(Square::D4, EMPTY)
+/// );
+/// ```
+#[inline]
+pub fn attacks_of(piece: Piece, sq: Square, color: Color, blockers: BitBoard) -> BitBoard {
+    match piece {
+        Piece::Pawn => get_pawn_attacks(sq, color, !EMPTY),
+        Piece::Knight => get_knight_moves(sq),
+        Piece::Bishop => get_bishop_moves(sq, blockers),
+        Piece::Rook => get_rook_moves(sq, blockers),
+        Piece::Queen => get_queen_moves(sq, blockers),
+        Piece::King => get_king_moves(sq),
+    }
+}
+
 /// Get the king moves for a particular square.
 #[inline]
 pub fn get_king_moves(sq: Square) -> BitBoard {
@@ -104,12 +207,6 @@ pub fn get_pawn_attacks(sq: Square, color: Color, blockers: BitBoard) -> BitBoar
             & blockers
     }
 }
-/// Get the legal destination castle squares for both players
-#[inline]
-pub fn get_castle_moves() -> BitBoard {
-    CASTLE_MOVES
-}
-
 /// Get the quiet pawn moves (non-captures) for a particular square, given the pawn's color and
 /// the potential blocking pieces.
 #[inline]
@@ -154,6 +251,59 @@ pub fn between(sq1: Square, sq2: Square) -> BitBoard {
     }
 }
 
+/// Get the number of king moves it takes to get from one square to another (the Chebyshev
+/// distance).
+///
+/// ```
+/// use chess::{get_king_distance, Square};
+///
+/// assert_eq!(get_king_distance(Square::A1, Square::H8), 7);
+/// assert_eq!(get_king_distance(Square::E4, Square::F6), 2);
+/// ```
+#[inline]
+pub fn get_king_distance(sq1: Square, sq2: Square) -> u8 {
+    unsafe {
+        *KING_DISTANCE
+            .get_unchecked(sq1.to_index())
+            .get_unchecked(sq2.to_index())
+    }
+}
+
+/// Get the number of king moves it takes to get from a square to the nearest edge of the board,
+/// the same as `Square::edge_distance` but from a table.
+///
+/// ```
+/// use chess::{get_edge_distance, Square};
+///
+/// assert_eq!(get_edge_distance(Square::H1), 0);
+/// assert_eq!(get_edge_distance(Square::C6), 2);
+/// ```
+#[inline]
+pub fn get_edge_distance(sq: Square) -> u8 {
+    unsafe { *EDGE_DISTANCE.get_unchecked(sq.to_index()) }
+}
+
+/// Get a `BitBoard` of the squares exactly `radius` king moves away from this square.  A radius
+/// of 0 is the square itself, and rings past a radius of 7 are always empty.
+///
+/// ```
+/// use chess::{get_king_moves, get_ring, Square};
+///
+/// assert_eq!(get_ring(Square::E4, 1), get_king_moves(Square::E4));
+/// ```
+#[inline]
+pub fn get_ring(sq: Square, radius: u8) -> BitBoard {
+    if radius as usize >= RINGS.len() {
+        EMPTY
+    } else {
+        unsafe {
+            *RINGS
+                .get_unchecked(radius as usize)
+                .get_unchecked(sq.to_index())
+        }
+    }
+}
+
 /// Get a `BitBoard` that represents all the squares on a particular rank.
 #[inline]
 pub fn get_rank(rank: Rank) -> BitBoard {
@@ -171,3 +321,246 @@ pub fn get_file(file: File) -> BitBoard {
 pub fn get_adjacent_files(file: File) -> BitBoard {
     unsafe { *ADJACENT_FILES.get_unchecked(file.to_index()) }
 }
+
+#[test]
+fn test_xray_rook_attacks_through_friendly_pawn() {
+    // a white rook on a1, a white pawn on a3 and a black queen on a6
+    let pawn = BitBoard::from_square(Square::A3);
+    let queen = BitBoard::from_square(Square::A6);
+    let occupied = pawn | queen;
+
+    assert_eq!(get_rook_moves(Square::A1, occupied) & queen, EMPTY);
+    assert_eq!(xray_rook_attacks(Square::A1, occupied, pawn) & queen, queen);
+    // nothing to look through if the pawn is not a blocker
+    assert_eq!(xray_rook_attacks(Square::A1, occupied, EMPTY), EMPTY);
+}
+
+#[test]
+fn test_xray_bishop_attacks_through_friendly_pawn() {
+    let pawn = BitBoard::from_square(Square::C3);
+    let queen = BitBoard::from_square(Square::F6);
+    let occupied = pawn | queen;
+
+    assert_eq!(get_bishop_moves(Square::A1, occupied) & queen, EMPTY);
+    assert_eq!(
+        xray_bishop_attacks(Square::A1, occupied, pawn),
+        BitBoard::from_square(Square::D4) | BitBoard::from_square(Square::E5) | queen
+    );
+}
+
+#[test]
+fn test_rings() {
+    assert_eq!(get_ring(Square::D4, 0), BitBoard::from_square(Square::D4));
+    assert_eq!(get_ring(Square::D4, 1).popcnt(), 8);
+    assert_eq!(get_ring(Square::D4, 2).popcnt(), 16);
+    assert_eq!(get_ring(Square::D4, 3).popcnt(), 24);
+    assert_eq!(get_ring(Square::D4, 4).popcnt(), 15);
+    assert_eq!(get_ring(Square::D4, 5), EMPTY);
+
+    assert_eq!(get_ring(Square::A1, 1).popcnt(), 3);
+    assert_eq!(get_ring(Square::A1, 2).popcnt(), 5);
+    assert_eq!(get_ring(Square::A1, 7).popcnt(), 15);
+    assert_eq!(get_ring(Square::A1, 8), EMPTY);
+    assert_eq!(get_ring(Square::A1, 255), EMPTY);
+}
+
+#[test]
+fn test_king_distance_matches_rings() {
+    use crate::square::ALL_SQUARES;
+
+    for src in ALL_SQUARES.iter() {
+        for dest in ALL_SQUARES.iter() {
+            let distance = get_king_distance(*src, *dest);
+            assert_eq!(distance, get_king_distance(*dest, *src));
+            assert_ne!(
+                get_ring(*src, distance) & BitBoard::from_square(*dest),
+                EMPTY
+            );
+        }
+    }
+}
+
+#[test]
+fn test_lookups_need_no_initialization() {
+    use crate::square::ALL_SQUARES;
+    use std::thread;
+
+    // The tables are generated by build.rs and compiled in as constants, so they can be used
+    // straight away from any number of threads without calling `construct()`.
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            thread::spawn(move || {
+                let blockers = BitBoard(0x0012_3400_0056_7800u64.rotate_left(i * 8));
+                ALL_SQUARES.iter().all(|sq| {
+                    get_rook_moves(*sq, EMPTY) == get_rook_rays(*sq)
+                        && get_bishop_moves(*sq, EMPTY) == get_bishop_rays(*sq)
+                        && get_rook_moves(*sq, blockers) & !get_rook_rays(*sq) == EMPTY
+                        && get_knight_moves(*sq).popcnt() >= 2
+                        && get_king_moves(*sq).popcnt() >= 3
+                })
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        assert!(handle.join().unwrap());
+    }
+}
+
+// the BMI2 tables only exist when building for BMI2, so this only runs in the CI job that
+// builds with `RUSTFLAGS="-C target-feature=+bmi2"`
+#[cfg(target_feature = "bmi2")]
+#[test]
+fn test_bmi_moves_match_magic() {
+    use crate::playouts::XorShift;
+    use crate::square::ALL_SQUARES;
+
+    let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15);
+    let mut next = || rng.next_u64();
+
+    for sq in ALL_SQUARES.iter() {
+        for _ in 0..4096 {
+            // sparse and dense occupancies both show up in real games
+            let blockers = BitBoard(next() & next());
+            assert_eq!(
+                get_rook_moves_bmi(*sq, blockers),
+                get_rook_moves_magic(*sq, blockers)
+            );
+            assert_eq!(
+                get_bishop_moves_bmi(*sq, blockers),
+                get_bishop_moves_magic(*sq, blockers)
+            );
+            let blockers = BitBoard(next());
+            assert_eq!(
+                get_rook_moves_bmi(*sq, blockers),
+                get_rook_moves_magic(*sq, blockers)
+            );
+            assert_eq!(
+                get_bishop_moves_bmi(*sq, blockers),
+                get_bishop_moves_magic(*sq, blockers)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_attacks_of_matches_specific_lookups() {
+    use crate::color::ALL_COLORS;
+    use crate::square::ALL_SQUARES;
+
+    let blockers = BitBoard(0x0042_1800_2400_8100);
+    for sq in ALL_SQUARES.iter() {
+        for color in ALL_COLORS.iter() {
+            let attacks = |piece| attacks_of(piece, *sq, *color, blockers);
+            assert_eq!(attacks(Piece::Pawn), get_pawn_attacks(*sq, *color, !EMPTY));
+            assert_eq!(attacks(Piece::Knight), get_knight_moves(*sq));
+            assert_eq!(attacks(Piece::Bishop), get_bishop_moves(*sq, blockers));
+            assert_eq!(attacks(Piece::Rook), get_rook_moves(*sq, blockers));
+            assert_eq!(
+                attacks(Piece::Queen),
+                get_rook_moves(*sq, blockers) | get_bishop_moves(*sq, blockers)
+            );
+            assert_eq!(attacks(Piece::King), get_king_moves(*sq));
+        }
+    }
+}
+
+#[test]
+fn test_edge_distance_is_the_distance_to_the_nearest_edge() {
+    use crate::square::ALL_SQUARES;
+
+    for sq in ALL_SQUARES.iter() {
+        let nearest = EDGES.map(|edge| get_king_distance(*sq, edge)).min();
+        let nearest = nearest.unwrap();
+        assert_eq!(sq.edge_distance(), nearest);
+        assert_eq!(get_edge_distance(*sq), nearest);
+    }
+}
+
+#[test]
+fn test_file_and_rank_boards() {
+    use crate::file::ALL_FILES;
+    use crate::rank::ALL_RANKS;
+    use crate::square::ALL_SQUARES;
+
+    let squares = |on: &dyn Fn(Square) -> bool| {
+        ALL_SQUARES
+            .iter()
+            .filter(|sq| on(**sq))
+            .fold(EMPTY, |bb, sq| bb | BitBoard::from_square(*sq))
+    };
+    for file in ALL_FILES.iter() {
+        let expected = squares(&|sq| sq.get_file() == *file);
+        assert_eq!(BitBoard::from_file(*file), expected);
+        assert_eq!(File::ALL_BB[file.to_index()], expected);
+        assert_eq!(get_file(*file), expected);
+        for other in ALL_FILES.iter() {
+            let between = |f| (*file < f && f < *other) || (*other < f && f < *file);
+            let expected = squares(&|sq| between(sq.get_file()));
+            assert_eq!(BitBoard::files_between(*file, *other), expected);
+        }
+    }
+    for rank in ALL_RANKS.iter() {
+        let expected = squares(&|sq| sq.get_rank() == *rank);
+        assert_eq!(BitBoard::from_rank(*rank), expected);
+        assert_eq!(Rank::ALL_BB[rank.to_index()], expected);
+        assert_eq!(get_rank(*rank), expected);
+    }
+}
+
+#[cfg(test)]
+fn slow_slider_moves(
+    sq: Square,
+    blockers: BitBoard,
+    directions: &[fn(&Square) -> Option<Square>],
+) -> BitBoard {
+    let mut result = EMPTY;
+    for step in directions.iter() {
+        let mut cur = sq;
+        while let Some(next) = step(&cur) {
+            result |= BitBoard::from_square(next);
+            if blockers & BitBoard::from_square(next) != EMPTY {
+                break;
+            }
+            cur = next;
+        }
+    }
+    result
+}
+
+#[test]
+fn test_magic_moves_match_ray_scan() {
+    use crate::square::ALL_SQUARES;
+
+    let rook: [fn(&Square) -> Option<Square>; 4] =
+        [Square::up, Square::down, Square::left, Square::right];
+    let bishop: [fn(&Square) -> Option<Square>; 4] = [
+        |sq| sq.up().and_then(|s| s.left()),
+        |sq| sq.up().and_then(|s| s.right()),
+        |sq| sq.down().and_then(|s| s.left()),
+        |sq| sq.down().and_then(|s| s.right()),
+    ];
+
+    for sq in ALL_SQUARES.iter() {
+        for (piece, directions) in [(ROOK, &rook), (BISHOP, &bishop)].iter() {
+            let mask = MAGIC_NUMBERS[*piece][sq.to_index()].mask;
+            // walk every subset of the mask, each with and without the squares outside it
+            let mut subset = EMPTY;
+            loop {
+                for blockers in [subset, subset | !mask].iter() {
+                    let expected = slow_slider_moves(*sq, *blockers, *directions);
+                    let actual = if *piece == ROOK {
+                        get_rook_moves_magic(*sq, *blockers)
+                    } else {
+                        get_bishop_moves_magic(*sq, *blockers)
+                    };
+                    assert_eq!(actual, expected);
+                }
+                subset = BitBoard(subset.0.wrapping_sub(mask.0) & mask.0);
+                if subset == EMPTY {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -1,15 +1,33 @@
 use crate::bitboard::{BitBoard, EMPTY};
 use crate::board::Board;
+use crate::board_builder::BoardBuilder;
+use crate::cache_table::CacheTable;
+use crate::capture_rules::{CaptureRules, StandardRules, UnoRules};
+use crate::castle_rights::{castle_move, castle_squares, CastleRights};
 use crate::chess_move::ChessMove;
-use crate::magic::between;
+use crate::color::Color;
+use crate::error::Error;
+use crate::magic::{between, get_adjacent_files, get_rank};
 use crate::movegen::piece_type::*;
-use crate::piece::{Piece, NUM_PROMOTION_PIECES, PROMOTION_PIECES};
+use crate::piece::{Piece, ALL_PIECES, NUM_PIECES, NUM_PROMOTION_PIECES, PROMOTION_PIECES};
 use crate::square::Square;
+use crate::variant::Variant;
+use alloc::vec::Vec;
 use arrayvec::ArrayVec;
+use core::cmp::Reverse;
+use core::convert::TryFrom;
+use core::fmt;
+use core::iter::ExactSizeIterator;
+#[cfg(feature = "movegen-stats")]
+use core::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
 use nodrop::NoDrop;
-use std::iter::ExactSizeIterator;
 
-#[derive(Copy, Clone, PartialEq, PartialOrd)]
+/// The moves of one piece, as `MoveGen` keeps them: the square it moves from and the squares it
+/// moves to, each of which is a promotion if `is_promotion`.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
 pub struct SquareAndBitBoard {
     square: Square,
     bitboard: BitBoard,
@@ -24,9 +42,267 @@ impl SquareAndBitBoard {
             promotion: promotion,
         }
     }
+
+    /// The square the piece moves from.
+    #[cfg(feature = "movegen-stats")]
+    pub fn get_square(&self) -> Square {
+        self.square
+    }
+
+    /// The squares the piece moves to.
+    #[cfg(feature = "movegen-stats")]
+    pub fn get_bitboard(&self) -> BitBoard {
+        self.bitboard
+    }
+
+    /// Is each of these moves a pawn promoting?
+    #[cfg(feature = "movegen-stats")]
+    pub fn is_promotion(&self) -> bool {
+        self.promotion
+    }
+}
+
+// One entry for each of my pieces, and one more for each pawn with both moves that promote and
+// moves that do not, which under the uno rules is any pawn that can capture onto the last rank.
+pub type MoveList = NoDrop<ArrayVec<SquareAndBitBoard, 24>>;
+
+/// Counts of the moves `MoveGen` generated, behind the `movegen-stats` feature, to see what
+/// the move generator does when a variant behaves in an unexpected way.
+///
+/// `MoveGen::stats` gives the counts of one generator, and `stats_snapshot` the totals of every
+/// generator so far.  A promotion counts once for each piece it may promote to, as the moves
+/// come out of the iterator.
+///
+/// ```
+/// use chess::{Board, MoveGen};
+///
+/// let stats = MoveGen::new_legal(&Board::default()).stats();
+/// assert_eq!(stats.moves(), 20);
+/// assert_eq!(stats.quiets, 20);
+/// ```
+#[cfg(feature = "movegen-stats")]
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct MoveGenStats {
+    /// How many generators were created.
+    pub generations: u64,
+    /// The moves of each kind of piece, by `Piece::to_index`.
+    pub moves_per_piece: [u64; NUM_PIECES],
+    /// The moves that capture, en passant included.
+    pub captures: u64,
+    /// The moves that do not.
+    pub quiets: u64,
+    /// The moves that promote, whether they capture or not.
+    pub promotions: u64,
+    /// The moves generated when the side to move was in check.
+    pub evasions: u64,
+    /// The nanoseconds spent generating the moves.  This is 0 without the `std` feature, and on
+    /// wasm32, which has no clock in `std`.
+    pub nanos: u64,
+}
+
+#[cfg(feature = "movegen-stats")]
+impl MoveGenStats {
+    /// All the moves generated.
+    pub fn moves(&self) -> u64 {
+        self.moves_per_piece.iter().sum()
+    }
+
+    // The counts of the moves of `movegen`, which were generated on `board`.
+    fn of(board: &Board, movegen: &MoveGen) -> MoveGenStats {
+        let mut stats = MoveGenStats {
+            generations: 1,
+            ..MoveGenStats::default()
+        };
+        let en_passant = match board.en_passant() {
+            Some(ep) => BitBoard::from_square(ep.uforward(board.side_to_move())),
+            None => EMPTY,
+        };
+        for entry in movegen.moves.iter() {
+            let piece = board.piece_on(entry.square).unwrap();
+            let dests = entry.bitboard & movegen.iterator_mask;
+            let mut captures = dests & board.color_combined(!board.side_to_move());
+            if piece == Piece::Pawn {
+                captures |= dests & en_passant;
+            }
+            let each = if entry.promotion {
+                movegen.promotions as u64
+            } else {
+                1
+            };
+            let moves = dests.popcnt() as u64 * each;
+            stats.moves_per_piece[piece.to_index()] += moves;
+            stats.captures += captures.popcnt() as u64 * each;
+            stats.quiets += (dests & !captures).popcnt() as u64 * each;
+            if entry.promotion {
+                stats.promotions += moves;
+            }
+        }
+        if *board.checkers() != EMPTY {
+            stats.evasions = stats.moves();
+        }
+        stats
+    }
+
+    fn fields(&self) -> [u64; STATS_FIELDS] {
+        let mut fields = [0; STATS_FIELDS];
+        fields[..NUM_PIECES].copy_from_slice(&self.moves_per_piece);
+        fields[NUM_PIECES..].copy_from_slice(&[
+            self.generations,
+            self.captures,
+            self.quiets,
+            self.promotions,
+            self.evasions,
+            self.nanos,
+        ]);
+        fields
+    }
+
+    fn from_fields(fields: [u64; STATS_FIELDS]) -> MoveGenStats {
+        let mut moves_per_piece = [0; NUM_PIECES];
+        moves_per_piece.copy_from_slice(&fields[..NUM_PIECES]);
+        MoveGenStats {
+            moves_per_piece,
+            generations: fields[NUM_PIECES],
+            captures: fields[NUM_PIECES + 1],
+            quiets: fields[NUM_PIECES + 2],
+            promotions: fields[NUM_PIECES + 3],
+            evasions: fields[NUM_PIECES + 4],
+            nanos: fields[NUM_PIECES + 5],
+        }
+    }
 }
 
-pub type MoveList = NoDrop<ArrayVec<SquareAndBitBoard, 18>>;
+// The counts of every generator so far, as the fields of a `MoveGenStats`.
+#[cfg(feature = "movegen-stats")]
+const STATS_FIELDS: usize = NUM_PIECES + 6;
+#[cfg(feature = "movegen-stats")]
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_MOVES: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "movegen-stats")]
+static TOTALS: [AtomicU64; STATS_FIELDS] = [NO_MOVES; STATS_FIELDS];
+
+/// The counts of every `MoveGen` created so far, in any thread, behind the `movegen-stats`
+/// feature.
+///
+/// ```
+/// use chess::{stats_snapshot, Board, MoveGen};
+///
+/// let before = stats_snapshot();
+/// MoveGen::new_legal(&Board::default());
+/// assert!(stats_snapshot().moves() >= before.moves() + 20);
+/// ```
+#[cfg(feature = "movegen-stats")]
+pub fn stats_snapshot() -> MoveGenStats {
+    let mut fields = [0; STATS_FIELDS];
+    for (field, total) in fields.iter_mut().zip(TOTALS.iter()) {
+        *field = total.load(AtomicOrdering::Relaxed);
+    }
+    MoveGenStats::from_fields(fields)
+}
+
+// The node counts stored by `MoveGen::perft_cached` share a u64 with the depth.
+const PERFT_COUNT_MASK: u64 = (1 << 56) - 1;
+
+// Times a run where there is a clock to do it with: with `std`, other than on wasm32, where
+// `std::time::Instant` panics.  Anywhere else the run is not timed.
+struct Stopwatch {
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    fn start() -> Stopwatch {
+        Stopwatch {
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        return Some(self.start.elapsed());
+        #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+        return None;
+    }
+}
+
+// Add up perft node counts.  Overflow panics in debug builds and saturates in release builds,
+// rather than silently wrapping around.
+fn sum_node_counts<I: Iterator<Item = u64>>(counts: I) -> u64 {
+    counts.fold(0, |total: u64, count| {
+        if cfg!(debug_assertions) {
+            total
+                .checked_add(count)
+                .expect("perft node count overflowed a u64, use MoveGen::perft_u128 instead")
+        } else {
+            total.saturating_add(count)
+        }
+    })
+}
+
+/// What a perft run found, from `MoveGen::perft_report` or `MoveGen::perft_parallel_report`:
+/// the nodes below each legal move, their total, and how long it took.
+///
+/// Its `Display` is the table engines print after perft, with a line for each root move.
+///
+/// ```
+/// use chess::{Board, MoveGen};
+///
+/// let report = MoveGen::perft_report(&Board::default(), 2);
+/// assert_eq!(report.nodes, 400);
+/// assert_eq!(report.divide.len(), 20);
+///
+/// let table = report.to_string();
+/// assert!(table.lines().any(|line| line == "e2e4: 20"));
+/// assert!(table.contains("\n\nNodes searched: 400\n"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PerftReport {
+    /// How many plies deep the run counted.
+    pub depth: usize,
+    /// The nodes below each legal move, in the order `MoveGen::new_legal` gives the moves.
+    pub divide: Vec<(ChessMove, u64)>,
+    /// All the nodes, the total of `divide`.
+    pub nodes: u64,
+    /// How long the run took.  This is `None` without the `std` feature, which the clock needs,
+    /// and on wasm32, which has no clock in `std`.
+    pub elapsed: Option<Duration>,
+}
+
+impl PerftReport {
+    /// How many nodes a second the run counted, if it was timed.
+    pub fn nodes_per_second(&self) -> Option<u64> {
+        let nanos = self.elapsed?.as_nanos().max(1);
+        let nps = self.nodes as u128 * 1_000_000_000 / nanos;
+        Some(nps.min(u64::MAX as u128) as u64)
+    }
+}
+
+impl fmt::Display for PerftReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (m, nodes) in self.divide.iter() {
+            writeln!(f, "{}: {}", m, nodes)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Nodes searched: {}", self.nodes)?;
+        if let (Some(elapsed), Some(nps)) = (self.elapsed, self.nodes_per_second()) {
+            writeln!(f, "Time: {} ms", elapsed.as_millis())?;
+            writeln!(f, "Nodes/second: {}", nps)?;
+        }
+        Ok(())
+    }
+}
+
+// Could none of the pieces of `color` capture on `square`, when they capture by the rules `R`?
+fn safe_with<R: CaptureRules>(board: &Board, square: Square, color: Color) -> bool {
+    let combined = *board.combined();
+    let target = BitBoard::from_square(square);
+    let mut pieces = *board.color_combined(color);
+    pieces.all(|src| {
+        let piece = board.piece_on(src).unwrap();
+        R::capture_targets(board, src, piece, color, combined) & target == EMPTY
+    })
+}
 
 /// An incremental move generator
 ///
@@ -84,62 +360,756 @@ pub type MoveList = NoDrop<ArrayVec<SquareAndBitBoard, 18>>;
 /// ```
 pub struct MoveGen {
     moves: MoveList,
+    // the source and destination of a promotion whose first pieces were given, and the index
+    // of the next of `PROMOTION_PIECES` to give, which is 0 when there is none
+    promoting: Option<(Square, Square)>,
     promotion_index: usize,
+    // how many of `PROMOTION_PIECES` each promotion is generated as, starting with the queen
+    promotions: usize,
     iterator_mask: BitBoard,
     index: usize,
+    #[cfg(feature = "movegen-stats")]
+    stats: MoveGenStats,
 }
 
 impl MoveGen {
+    /// An upper bound on the number of moves a `MoveGen` can produce for any position, for sizing
+    /// move buffers.  Generating moves never allocates, whatever this is set to.
+    ///
+    /// Quiet moves are bounded as in normal chess: at most 9 queens (27 squares each), 2 rooks
+    /// (14), 2 bishops (13), 2 knights (8) and a king (8, plus 2 castles), for 323.  A capture
+    /// takes a piece that attacks the capturing one under the uno rules, or that the capturing
+    /// one attacks in normal chess, and no piece attacks more than 8 others, so there are at most
+    /// 16 * 8 captures.  Only a capture onto the last rank promotes, where at most 8 pieces are
+    /// each captured from at most 8 squares, and each of those captures is 3 more moves for the
+    /// other promotions.  With at most 2 en passant captures that makes 645.
+    pub const MAX_LEGAL_MOVES: usize = 323 + 16 * 8 + 8 * 8 * (NUM_PROMOTION_PIECES - 1) + 2;
+
     #[inline(always)]
-    fn enumerate_moves(board: &Board) -> MoveList {
+    fn enumerate_moves<R>(board: &Board) -> MoveList
+    where
+        R: CaptureRules,
+    {
         let checkers = *board.checkers();
         let mask = !board.combined();
-        let mut movelist = NoDrop::new(ArrayVec::<SquareAndBitBoard, 18>::new());
+        let mut movelist = NoDrop::new(ArrayVec::<SquareAndBitBoard, 24>::new());
         
         if checkers == EMPTY {
-            PawnType::legals::<NotInCheckType>(&mut movelist, &board, mask);
-            KnightType::legals::<NotInCheckType>(&mut movelist, &board, mask);
-            BishopType::legals::<NotInCheckType>(&mut movelist, &board, mask);
-            RookType::legals::<NotInCheckType>(&mut movelist, &board, mask);
-            QueenType::legals::<NotInCheckType>(&mut movelist, &board, mask);
-            KingType::legals::<NotInCheckType>(&mut movelist, &board, mask);
+            PawnType::legals::<NotInCheckType, R>(&mut movelist, &board, mask);
+            KnightType::legals::<NotInCheckType, R>(&mut movelist, &board, mask);
+            BishopType::legals::<NotInCheckType, R>(&mut movelist, &board, mask);
+            RookType::legals::<NotInCheckType, R>(&mut movelist, &board, mask);
+            QueenType::legals::<NotInCheckType, R>(&mut movelist, &board, mask);
+            KingType::legals::<NotInCheckType, R>(&mut movelist, &board, mask);
+        } else {
+            MoveGen::enumerate_evasions_into::<R>(&mut movelist, board, mask);
+        }
+
+        movelist
+    }
+
+    // The moves out of check, when the side to move is in check: with one checker, capturing it,
+    // stepping in its way or moving my king, and with two, only moving my king.
+    #[inline(always)]
+    fn enumerate_evasions_into<R>(movelist: &mut MoveList, board: &Board, mask: BitBoard)
+    where
+        R: CaptureRules,
+    {
+        if board.checkers().popcnt() == 1 {
+            PawnType::legals::<InCheckType, R>(movelist, board, mask);
+            KnightType::legals::<InCheckType, R>(movelist, board, mask);
+            BishopType::legals::<InCheckType, R>(movelist, board, mask);
+            RookType::legals::<InCheckType, R>(movelist, board, mask);
+            QueenType::legals::<InCheckType, R>(movelist, board, mask);
+        }
+        KingType::legals::<InCheckType, R>(movelist, board, mask);
+    }
+
+    // The legal moves of the pieces of kind `piece` of the side to move.
+    fn enumerate_piece_moves<R>(board: &Board, piece: Piece) -> MoveList
+    where
+        R: CaptureRules,
+    {
+        let checkers = *board.checkers();
+        let mask = !board.combined();
+        let mut movelist = NoDrop::new(ArrayVec::<SquareAndBitBoard, 24>::new());
+
+        if checkers == EMPTY {
+            MoveGen::piece_legals::<NotInCheckType, R>(&mut movelist, board, mask, piece);
+        } else if checkers.popcnt() == 1 || piece == Piece::King {
+            MoveGen::piece_legals::<InCheckType, R>(&mut movelist, board, mask, piece);
+        }
+
+        movelist
+    }
+
+    fn piece_legals<T, R>(movelist: &mut MoveList, board: &Board, mask: BitBoard, piece: Piece)
+    where
+        T: CheckType,
+        R: CaptureRules,
+    {
+        match piece {
+            Piece::Pawn => PawnType::legals::<T, R>(movelist, board, mask),
+            Piece::Knight => KnightType::legals::<T, R>(movelist, board, mask),
+            Piece::Bishop => BishopType::legals::<T, R>(movelist, board, mask),
+            Piece::Rook => RookType::legals::<T, R>(movelist, board, mask),
+            Piece::Queen => QueenType::legals::<T, R>(movelist, board, mask),
+            Piece::King => KingType::legals::<T, R>(movelist, board, mask),
+        }
+    }
+
+    // Like `enumerate_moves`, but stop after the first kind of piece whose moves make `done`
+    // true of the moves so far.
+    fn enumerate_moves_until<R, F>(board: &Board, done: F) -> MoveList
+    where
+        R: CaptureRules,
+        F: FnMut(&MoveList) -> bool,
+    {
+        let checkers = *board.checkers();
+        let mask = !board.combined();
+        let mut movelist = NoDrop::new(ArrayVec::<SquareAndBitBoard, 24>::new());
+
+        if checkers == EMPTY {
+            MoveGen::legals_until::<NotInCheckType, R, F>(&mut movelist, board, mask, done);
         } else if checkers.popcnt() == 1 {
-            PawnType::legals::<InCheckType>(&mut movelist, &board, mask);
-            KnightType::legals::<InCheckType>(&mut movelist, &board, mask);
-            BishopType::legals::<InCheckType>(&mut movelist, &board, mask);
-            RookType::legals::<InCheckType>(&mut movelist, &board, mask);
-            QueenType::legals::<InCheckType>(&mut movelist, &board, mask);
-            KingType::legals::<InCheckType>(&mut movelist, &board, mask);
+            MoveGen::legals_until::<InCheckType, R, F>(&mut movelist, board, mask, done);
         } else {
-            KingType::legals::<InCheckType>(&mut movelist, &board, mask);
+            KingType::legals::<InCheckType, R>(&mut movelist, board, mask);
         }
 
         movelist
     }
 
-    /// Create a new `MoveGen` structure, only generating legal moves
+    fn legals_until<T, R, F>(movelist: &mut MoveList, board: &Board, mask: BitBoard, mut done: F)
+    where
+        T: CheckType,
+        R: CaptureRules,
+        F: FnMut(&MoveList) -> bool,
+    {
+        PawnType::legals::<T, R>(movelist, board, mask);
+        if done(movelist) {
+            return;
+        }
+        KnightType::legals::<T, R>(movelist, board, mask);
+        if done(movelist) {
+            return;
+        }
+        BishopType::legals::<T, R>(movelist, board, mask);
+        if done(movelist) {
+            return;
+        }
+        RookType::legals::<T, R>(movelist, board, mask);
+        if done(movelist) {
+            return;
+        }
+        QueenType::legals::<T, R>(movelist, board, mask);
+        if done(movelist) {
+            return;
+        }
+        KingType::legals::<T, R>(movelist, board, mask);
+    }
+
+    // The legal moves of `board`, generated one kind of piece at a time until `done` is true of
+    // the moves so far.  Then the moves of the other pieces may be missing.
+    fn new_legal_until<F>(board: &Board, done: F) -> MoveGen
+    where
+        F: FnMut(&MoveList) -> bool,
+    {
+        MoveGen::from_moves(match board.variant() {
+            Variant::Uno => MoveGen::enumerate_moves_until::<UnoRules, F>(board, done),
+            Variant::Standard => MoveGen::enumerate_moves_until::<StandardRules, F>(board, done),
+        })
+    }
+
+    // Can the side to move capture anything, for `Board::legal_captures_exist`?
+    pub(crate) fn has_legal_capture(board: &Board) -> bool {
+        let targets = *board.color_combined(!board.side_to_move());
+        let en_passant = match board.en_passant() {
+            Some(ep) => BitBoard::from_square(ep.uforward(board.side_to_move())),
+            None => EMPTY,
+        };
+        let captures = |moves: &MoveList| {
+            moves.iter().any(|entry| {
+                let mut captures = entry.bitboard & targets;
+                if board.piece_on(entry.square) == Some(Piece::Pawn) {
+                    captures |= entry.bitboard & en_passant;
+                }
+                captures != EMPTY
+            })
+        };
+        captures(&MoveGen::new_legal_until(board, captures).moves)
+    }
+
+    // The only legal move of the side to move, if there is exactly one, for `Board::only_move`.
+    pub(crate) fn only_move(board: &Board) -> Option<ChessMove> {
+        let mut movegen = MoveGen::new_legal_until(board, |moves| {
+            moves.len() > 1
+                || moves
+                    .iter()
+                    .any(|entry| entry.bitboard.popcnt() > 1 || entry.promotion)
+        });
+        match (movegen.next(), movegen.next()) {
+            (Some(m), None) => Some(m),
+            _ => None,
+        }
+    }
+
+    // Is `m`, written the way `MoveGen` does, a legal move of the piece on its source square,
+    // for `Board::sanitize_move`?  Only the moves of that kind of piece are generated.
+    pub(crate) fn is_piece_move(board: &Board, piece: Piece, m: ChessMove) -> bool {
+        let moves = match board.variant() {
+            Variant::Uno => MoveGen::enumerate_piece_moves::<UnoRules>(board, piece),
+            Variant::Standard => MoveGen::enumerate_piece_moves::<StandardRules>(board, piece),
+        };
+        let dest = BitBoard::from_square(m.get_dest());
+        let promotes = match m.get_promotion() {
+            Some(promotion) if !PROMOTION_PIECES.contains(&promotion) => return false,
+            promotion => promotion.is_some(),
+        };
+        moves.iter().any(|entry| {
+            entry.square == m.get_source()
+                && entry.bitboard & dest != EMPTY
+                && entry.promotion == promotes
+        })
+    }
+
+    // The moves of the pieces of the side to move when they capture by the rules `R`, for
+    // `MoveGen::new_legal_with`.  Each move is checked by making it.
+    fn enumerate_moves_with<R>(board: &Board) -> MoveList
+    where
+        R: CaptureRules,
+    {
+        let combined = *board.combined();
+        let color = board.side_to_move();
+        let mask = !combined;
+        let mut movelist = NoDrop::new(ArrayVec::<SquareAndBitBoard, 24>::new());
+
+        for src in *board.color_combined(color) {
+            let piece = board.piece_on(src).unwrap();
+            // the enemy king is never captured, a position where it could be cannot be reached
+            let mut moves = R::capture_targets(board, src, piece, color, combined)
+                & board.color_combined(!color)
+                & !board.pieces(Piece::King);
+            moves |= match piece {
+                Piece::Pawn => PawnType::pseudo_legals(src, color, combined, mask),
+                Piece::Knight => KnightType::pseudo_legals(src, color, combined, mask),
+                Piece::Bishop => BishopType::pseudo_legals(src, color, combined, mask),
+                Piece::Rook => RookType::pseudo_legals(src, color, combined, mask),
+                Piece::Queen => QueenType::pseudo_legals(src, color, combined, mask),
+                Piece::King => KingType::pseudo_legals(src, color, combined, mask),
+            };
+            if let (Piece::Pawn, Some(ep)) = (piece, board.en_passant()) {
+                let neighbours = get_rank(ep.get_rank()) & get_adjacent_files(ep.get_file());
+                if neighbours & BitBoard::from_square(src) != EMPTY {
+                    moves |= BitBoard::from_square(ep.uforward(color));
+                }
+            }
+
+            // the promotions are only tried as a queen: the piece I promote to cannot change
+            // whether my king can be captured
+            let promotions = color.to_promotion_board();
+            moves = moves
+                .filter(|dest| {
+                    let promotion = if piece == Piece::Pawn
+                        && promotions & BitBoard::from_square(*dest) != EMPTY
+                    {
+                        Some(Piece::Queen)
+                    } else {
+                        None
+                    };
+                    let after = board.make_move_new(ChessMove::new(src, *dest, promotion));
+                    safe_with::<R>(&after, after.king_square(color), !color)
+                })
+                .fold(EMPTY, |bb, dest| bb | BitBoard::from_square(dest));
+
+            if piece == Piece::King {
+                moves |= MoveGen::castles_with::<R>(board);
+            }
+
+            let promotion_moves = if piece == Piece::Pawn {
+                moves & promotions
+            } else {
+                EMPTY
+            };
+            if moves & !promotion_moves != EMPTY {
+                unsafe {
+                    movelist.push_unchecked(SquareAndBitBoard::new(
+                        src,
+                        moves & !promotion_moves,
+                        false,
+                    ));
+                }
+            }
+            if promotion_moves != EMPTY {
+                unsafe {
+                    movelist.push_unchecked(SquareAndBitBoard::new(src, promotion_moves, true));
+                }
+            }
+        }
+
+        movelist
+    }
+
+    // The castling moves of the side to move when pieces capture by the rules `R`.  My king has
+    // to be safe where it starts, on the squares it crosses with the rook taken off the board,
+    // since it may stand on one of them, and where it lands once the rook has moved too.
+    fn castles_with<R>(board: &Board) -> BitBoard
+    where
+        R: CaptureRules,
+    {
+        let color = board.side_to_move();
+        let ksq = board.king_square(color);
+        let rights = board.my_castle_rights();
+
+        let mut castles = EMPTY;
+        if rights == CastleRights::NoRights || !safe_with::<R>(board, ksq, !color) {
+            return castles;
+        }
+
+        let backrank = color.back_rank();
+        for file in [rights.kingside_rook(), rights.queenside_rook()]
+            .iter()
+            .flatten()
+        {
+            let rook = Square::make_square(backrank, *file);
+            if !KingType::castle_path_clear(board, rook) {
+                continue;
+            }
+            let (king_dest, _) = castle_squares(ksq, rook);
+            let mut without_rook = *board;
+            without_rook.xor(Piece::Rook, BitBoard::from_square(rook), color);
+            let crosses_safely = between(ksq, king_dest).all(|sq| {
+                let after = without_rook.make_move_new(ChessMove::new(ksq, sq, None));
+                safe_with::<R>(&after, sq, !color)
+            });
+            let m = castle_move(ksq, rook);
+            if crosses_safely && safe_with::<R>(&board.make_move_new(m), king_dest, !color) {
+                castles |= BitBoard::from_square(m.get_dest());
+            }
+        }
+        castles
+    }
+
+    // A `MoveGen` that iterates all of `moves`.
     #[inline(always)]
-    pub fn new_legal(board: &Board) -> MoveGen {
+    fn from_moves(moves: MoveList) -> MoveGen {
         MoveGen {
-            moves: MoveGen::enumerate_moves(board),
+            moves,
+            promoting: None,
             promotion_index: 0,
+            promotions: NUM_PROMOTION_PIECES,
             iterator_mask: !EMPTY,
             index: 0,
+            #[cfg(feature = "movegen-stats")]
+            stats: MoveGenStats::default(),
         }
     }
 
+    // Generate the moves of `board` with `generate`, and count them with the `movegen-stats`
+    // feature.
+    #[cfg(not(feature = "movegen-stats"))]
+    #[inline(always)]
+    fn recorded<F: FnOnce() -> MoveGen>(_board: &Board, generate: F) -> MoveGen {
+        generate()
+    }
+
+    #[cfg(feature = "movegen-stats")]
+    fn recorded<F: FnOnce() -> MoveGen>(board: &Board, generate: F) -> MoveGen {
+        let stopwatch = Stopwatch::start();
+        let mut movegen = generate();
+        let mut stats = MoveGenStats::of(board, &movegen);
+        if let Some(elapsed) = stopwatch.elapsed() {
+            stats.nanos = elapsed.as_nanos() as u64;
+        }
+        for (total, field) in TOTALS.iter().zip(stats.fields().iter()) {
+            total.fetch_add(*field, AtomicOrdering::Relaxed);
+        }
+        movegen.stats = stats;
+        movegen
+    }
+
+    /// The counts of the moves this generator made, behind the `movegen-stats` feature.  These
+    /// are the moves as they were generated, before any were iterated or masked away.
+    #[cfg(feature = "movegen-stats")]
+    pub fn stats(&self) -> MoveGenStats {
+        self.stats
+    }
+
+    /// Call `hook` on each entry of moves this generator has left to iterate, behind the
+    /// `movegen-stats` feature, to dump what it generated.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen, SquareAndBitBoard};
+    ///
+    /// fn dump(entry: &SquareAndBitBoard) {
+    ///     println!("{} {:?}", entry.get_square(), entry.get_bitboard());
+    /// }
+    ///
+    /// MoveGen::new_legal(&Board::default()).trace(dump);
+    /// ```
+    #[cfg(feature = "movegen-stats")]
+    pub fn trace(&self, hook: fn(&SquareAndBitBoard)) {
+        for entry in self.moves.iter() {
+            if entry.bitboard & self.iterator_mask == EMPTY {
+                break;
+            }
+            hook(entry);
+        }
+    }
+
+    // `MoveGen::new_legal`, without recording statistics.
+    #[inline(always)]
+    fn legal(board: &Board) -> MoveGen {
+        MoveGen::from_moves(match board.variant() {
+            Variant::Uno => MoveGen::enumerate_moves::<UnoRules>(board),
+            Variant::Standard => MoveGen::enumerate_moves::<StandardRules>(board),
+        })
+    }
+
+    /// Create a new `MoveGen` structure, only generating legal moves
+    #[inline(always)]
+    pub fn new_legal(board: &Board) -> MoveGen {
+        MoveGen::recorded(board, || MoveGen::legal(board))
+    }
+
+    /// Create a new `MoveGen` structure, generating the legal moves `color` would have if it were
+    /// its turn, such as the threats of my opponent.
+    ///
+    /// For my opponent this uses `Board::swap_side_to_move`, so there is no en passant, and it is
+    /// the same error when I am in check, since my opponent cannot be to move then.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, Color, MoveGen, Square};
+    ///
+    /// let board = Board::default().make_move_new(ChessMove::new(Square::E2, Square::E4, None));
+    /// let threats = MoveGen::new_legal_for(&board, Color::White).unwrap();
+    /// assert_eq!(threats.len(), 30);
+    /// assert_eq!(MoveGen::new_legal_for(&board, Color::Black).unwrap().len(), 20);
+    /// ```
+    pub fn new_legal_for(board: &Board, color: Color) -> Result<MoveGen, Error> {
+        if color == board.side_to_move() {
+            Ok(MoveGen::new_legal(board))
+        } else {
+            Ok(MoveGen::new_legal(&board.swap_side_to_move()?))
+        }
+    }
+
+    /// Create a new `MoveGen` structure, only generating the moves out of check, when the side to
+    /// move is in check
+    ///
+    /// These are the moves of my king off the squares that are attacked, the captures of a
+    /// single checker and the moves that block its line to my king, which are exactly the legal
+    /// moves.  This skips the test for whether I am in check, which the caller already made.  It
+    /// debug_assert!()s that I am, and otherwise generates the moves of `MoveGen::new_legal`.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/8/1r2q3/4K3 w - - 0 1").expect("Valid FEN");
+    /// assert_eq!(MoveGen::new_evasions(&board).len(), MoveGen::new_legal(&board).len());
+    /// ```
+    pub fn new_evasions(board: &Board) -> MoveGen {
+        debug_assert!(
+            *board.checkers() != EMPTY,
+            "evasions are only generated in check"
+        );
+        MoveGen::recorded(board, || {
+            if *board.checkers() == EMPTY {
+                return MoveGen::legal(board);
+            }
+
+            let mask = !board.combined();
+            let mut moves = NoDrop::new(ArrayVec::<SquareAndBitBoard, 24>::new());
+            match board.variant() {
+                Variant::Uno => {
+                    MoveGen::enumerate_evasions_into::<UnoRules>(&mut moves, board, mask)
+                }
+                Variant::Standard => {
+                    MoveGen::enumerate_evasions_into::<StandardRules>(&mut moves, board, mask)
+                }
+            }
+            MoveGen::from_moves(moves)
+        })
+    }
+
+    /// Create a `StagedMoveGen`, which generates the legal moves one kind of piece at a time, in
+    /// the order of `order`, and only once the moves of the kinds before it have been iterated
+    ///
+    /// A search that expects a cutoff from a queen or rook move can then try those moves before
+    /// the pawn moves are even generated.  The kinds left out of `order` come after the others,
+    /// in the order of `ALL_PIECES`, so every legal move is still generated, and a kind that is
+    /// given twice is generated the first time.  The order of `ALL_PIECES` gives the moves in the
+    /// order of `MoveGen::new_legal`.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, MoveGen, Piece, Square};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/8/PPPP4/R3K3 w - - 0 1").expect("Valid FEN");
+    /// let mut movegen = MoveGen::with_piece_order(&board, &[Piece::Rook]);
+    /// assert_eq!(movegen.next(), Some(ChessMove::new(Square::A1, Square::B1, None)));
+    /// // only the rook moves are generated so far
+    /// assert_eq!(movegen.generated(), 3);
+    /// assert_eq!(movegen.count(), MoveGen::new_legal(&board).len() - 1);
+    /// ```
+    pub fn with_piece_order(board: &Board, order: &[Piece]) -> StagedMoveGen {
+        let mut stages = [Piece::Pawn; NUM_PIECES];
+        let mut len = 0;
+        for piece in order.iter().chain(ALL_PIECES.iter()) {
+            if !stages[..len].contains(piece) {
+                stages[len] = *piece;
+                len += 1;
+            }
+        }
+        StagedMoveGen {
+            board: *board,
+            stages,
+            stage: 0,
+            moves: MoveGen::from_moves(NoDrop::new(ArrayVec::new())),
+            generated: 0,
+        }
+    }
+
+    /// Generate the legal moves of `board`, score each with `score`, and sort them from the
+    /// highest score down, which is how a search usually wants them
+    ///
+    /// The `Vec` is allocated once, with room for exactly the legal moves, and each move is put
+    /// in its place as it is scored.  The sort is stable: moves with the same score keep the
+    /// order `MoveGen::new_legal` gives them in.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").expect("Valid FEN");
+    /// let scored = MoveGen::collect_scored(&board, |board, m| board.see(m));
+    /// assert_eq!(scored[0].0.to_string(), "e4d5");
+    /// assert_eq!(scored[0].1, 100);
+    /// assert!(scored[1..].iter().all(|(_, score)| *score == 0));
+    /// ```
+    pub fn collect_scored<F>(board: &Board, mut score: F) -> Vec<(ChessMove, i32)>
+    where
+        F: FnMut(&Board, ChessMove) -> i32,
+    {
+        let movegen = MoveGen::new_legal(board);
+        let mut scored = Vec::with_capacity(movegen.len());
+        for m in movegen {
+            let s = score(board, m);
+            // after every move that scores as well, so that equal scores keep their order
+            let at = scored.partition_point(|(_, other): &(ChessMove, i32)| *other >= s);
+            scored.insert(at, (m, s));
+        }
+        scored
+    }
+
+    /// Sort `moves` from the highest score `score` gives down, calling it once for each move.
+    /// The sort is stable, so moves with the same score keep their order.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, MoveGen};
+    ///
+    /// let board = Board::default();
+    /// let mut moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+    /// // the knight moves first
+    /// let piece = |m: ChessMove| board.piece_on(m.get_source()).unwrap().to_index() as i32;
+    /// MoveGen::sort_moves(&mut moves, piece);
+    /// assert_eq!(moves[0].to_string(), "b1a3");
+    /// assert_eq!(moves[4].to_string(), "a2a3");
+    /// ```
+    pub fn sort_moves<F>(moves: &mut [ChessMove], mut score: F)
+    where
+        F: FnMut(ChessMove) -> i32,
+    {
+        moves.sort_by_cached_key(|m| Reverse(score(*m)));
+    }
+
+    /// Create a new `MoveGen` structure, generating the legal moves when pieces capture by the
+    /// rules `R` instead of those of the board's `Variant`
+    ///
+    /// Everything else, such as the quiet moves, castling and en passant, still follows the
+    /// board's variant.  A move is legal if no enemy piece could capture my king afterwards, and
+    /// castling also needs my king to be safe where it starts and on the squares it crosses.
+    /// Every move is made to find that out, so this is a lot slower than `MoveGen::new_legal`.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen, UnoRules};
+    ///
+    /// let board = Board::default();
+    /// assert_eq!(MoveGen::new_legal_with::<UnoRules>(&board).len(), 20);
+    /// ```
+    pub fn new_legal_with<R>(board: &Board) -> MoveGen
+    where
+        R: CaptureRules,
+    {
+        MoveGen::recorded(board, || {
+            MoveGen::from_moves(MoveGen::enumerate_moves_with::<R>(board))
+        })
+    }
+
+    /// Create a new `MoveGen` structure, only generating legal moves that give check
+    ///
+    /// The promotions of a pawn to a square are generated together, so in `Variant::Standard`
+    /// they are all generated if any one of them gives check.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen};
+    /// use std::str::FromStr;
+    ///
+    /// let board = Board::from_str("4k3/8/8/8/8/8/8/3RK3 w - - 0 1").expect("Valid FEN");
+    /// assert!(MoveGen::new_checks(&board).all(|m| board.gives_check(m)));
+    /// assert_eq!(MoveGen::new_checks(&board).len(), 2);
+    /// ```
+    pub fn new_checks(board: &Board) -> MoveGen {
+        MoveGen::recorded(board, || MoveGen::checks(board))
+    }
+
+    // `MoveGen::new_checks`, without recording statistics.
+    fn checks(board: &Board) -> MoveGen {
+        let mut movegen = MoveGen::legal(board);
+        let candidates = board.discovered_check_candidates(board.side_to_move());
+        for x in 0..movegen.moves.len() {
+            movegen.moves[x].bitboard = MoveGen::checking(board, movegen.moves[x], candidates);
+        }
+        // keep the moves that are left at the front of the list, where the iterator expects them
+        movegen.set_iterator_mask(!EMPTY);
+        movegen
+    }
+
+    // The destinations of `entry` that give check, where `candidates` are the
+    // `Board::discovered_check_candidates` of the side to move.
+    fn checking(board: &Board, entry: SquareAndBitBoard, candidates: BitBoard) -> BitBoard {
+        let piece = board.piece_on(entry.square).unwrap();
+        match board.variant() {
+            Variant::Uno => {
+                // a promotion checks from the same squares as a pawn, see `Board::check_squares`
+                entry.bitboard & board.check_squares(piece)
+            }
+            Variant::Standard
+                if !entry.promotion
+                    && piece != Piece::Pawn
+                    && piece != Piece::King
+                    && candidates & BitBoard::from_square(entry.square) == EMPTY =>
+            {
+                // no discovered check, castling or en passant
+                entry.bitboard & board.check_squares(piece)
+            }
+            Variant::Standard => {
+                // possibly a discovered check, castling or en passant, so look at every move
+                entry
+                    .bitboard
+                    .filter(|dest| {
+                        if entry.promotion {
+                            PROMOTION_PIECES.iter().any(|promotion| {
+                                board.gives_check(ChessMove::new(
+                                    entry.square,
+                                    *dest,
+                                    Some(*promotion),
+                                ))
+                            })
+                        } else {
+                            board.gives_check(ChessMove::new(entry.square, *dest, None))
+                        }
+                    })
+                    .fold(EMPTY, |bb, dest| bb | BitBoard::from_square(dest))
+            }
+        }
+    }
+
+    /// Create a new `MoveGen` structure for a quiescence search
+    ///
+    /// When I am in check, this generates every legal move, exactly like `MoveGen::new_legal`.
+    /// Otherwise it only generates the captures, by the rules of the board's `Variant`, and the
+    /// promotions, which are generated as a queen only.  With `include_checks` it also generates
+    /// the other moves that give check, as `MoveGen::new_checks` would.
+    ///
+    /// ```
+    /// use chess::{Board, ChessMove, MoveGen, Piece, Square};
+    /// use std::str::FromStr;
+    ///
+    /// // the rook attacks both the pawn and the knight, so either may take it, and the pawn may
+    /// // also promote
+    /// let board = Board::from_str("4k3/1P6/8/8/8/1r6/8/1N2K3 w - - 0 1").expect("Valid FEN");
+    /// let moves: Vec<ChessMove> = MoveGen::new_quiescence(&board, false).collect();
+    /// assert_eq!(
+    ///     moves,
+    ///     vec![
+    ///         ChessMove::new(Square::B7, Square::B3, None),
+    ///         ChessMove::new(Square::B7, Square::B8, Some(Piece::Queen)),
+    ///         ChessMove::new(Square::B1, Square::B3, None),
+    ///     ]
+    /// );
+    /// ```
+    pub fn new_quiescence(board: &Board, include_checks: bool) -> MoveGen {
+        MoveGen::recorded(board, || MoveGen::quiescence(board, include_checks))
+    }
+
+    // `MoveGen::new_quiescence`, without recording statistics.
+    fn quiescence(board: &Board, include_checks: bool) -> MoveGen {
+        let mut movegen = MoveGen::legal(board);
+        if *board.checkers() != EMPTY {
+            return movegen;
+        }
+
+        let candidates = board.discovered_check_candidates(board.side_to_move());
+        let captures = *board.color_combined(!board.side_to_move());
+        let en_passant = match board.en_passant() {
+            Some(ep) => BitBoard::from_square(ep.uforward(board.side_to_move())),
+            None => EMPTY,
+        };
+        for x in 0..movegen.moves.len() {
+            let entry = movegen.moves[x];
+            if entry.promotion {
+                continue;
+            }
+            let mut keep = entry.bitboard & captures;
+            if board.piece_on(entry.square) == Some(Piece::Pawn) {
+                keep |= entry.bitboard & en_passant;
+            }
+            if include_checks {
+                keep |= MoveGen::checking(board, entry, candidates);
+            }
+            movegen.moves[x].bitboard = keep;
+        }
+        movegen.promotions = 1;
+        // keep the moves that are left at the front of the list, where the iterator expects them
+        movegen.set_iterator_mask(!EMPTY);
+        movegen
+    }
+
     /// Never, ever, iterate any moves that land on the following squares
     pub fn remove_mask(&mut self, mask: BitBoard) {
+        if let Some((_, dest)) = self.promoting {
+            if mask & BitBoard::from_square(dest) != EMPTY {
+                self.promoting = None;
+                self.promotion_index = 0;
+            }
+        }
         for x in 0..self.moves.len() {
             self.moves[x].bitboard &= !mask;
         }
+        // an entry may have lost all its moves, so keep the others at the front
+        self.set_iterator_mask(self.iterator_mask);
     }
 
     /// Never, ever, iterate this move
+    ///
+    /// A promotion is removed together with the promotions to the other pieces on the same
+    /// square.  Gives whether the move was one of the moves left to iterate.
     pub fn remove_move(&mut self, chess_move: ChessMove) -> bool {
+        if self.promoting == Some((chess_move.get_source(), chess_move.get_dest())) {
+            self.promoting = None;
+            self.promotion_index = 0;
+            return true;
+        }
+        // a pawn may have one entry for its promotions and another for its other moves
+        let dest = BitBoard::from_square(chess_move.get_dest());
         for x in 0..self.moves.len() {
-            if self.moves[x].square == chess_move.get_source() {
-                self.moves[x].bitboard &= !BitBoard::from_square(chess_move.get_dest());
+            if self.moves[x].square == chess_move.get_source()
+                && self.moves[x].bitboard & dest != EMPTY
+            {
+                self.moves[x].bitboard &= !dest;
+                self.set_iterator_mask(self.iterator_mask);
                 return true;
             }
         }
@@ -149,6 +1119,11 @@ impl MoveGen {
     /// For now, Only iterate moves that land on the following squares
     /// Note: Once iteration is completed, you can pass in a mask of ! `EMPTY`
     ///       to get the remaining moves, or another mask
+    ///
+    /// The mask only picks which of the moves are iterated, and never removes any, so after
+    /// iterating some captures a wider mask still gives every move that was not iterated yet.
+    /// A pawn that gave some of its promotions on a square gives the rest of them first, even
+    /// if the new mask leaves that square out.
     pub fn set_iterator_mask(&mut self, mask: BitBoard) {
         self.iterator_mask = mask;
         self.index = 0;
@@ -193,9 +1168,11 @@ impl MoveGen {
             Piece::Pawn => true,
             Piece::King => {
                 let bb = between(chess_move.get_source(), chess_move.get_dest());
-                if bb.popcnt() == 1 {
+                // a king can capture from a distance under the uno rules, so only a quiet move
+                // castles
+                if bb.popcnt() == 1 && board.piece_on(chess_move.get_dest()).is_none() {
                     // castles
-                    if !KingType::legal_king_move(board, bb.to_square()) {
+                    if !KingType::legal_king_move(board, bb.to_square_unchecked()) {
                         false
                     } else {
                         KingType::legal_king_move(board, chess_move.get_dest())
@@ -207,8 +1184,11 @@ impl MoveGen {
         }
     }
 
-    /// Fastest perft test with this structure
+    /// Fastest perft test with this structure.  A `depth` of 0 counts the one position `board`.
     pub fn movegen_perft_test(board: &Board, depth: usize) -> usize {
+        if depth == 0 {
+            return 1;
+        }
         let iterable = MoveGen::new_legal(board);
 
         let mut result: usize = 0;
@@ -223,6 +1203,252 @@ impl MoveGen {
         }
     }
 
+    /// Perft test that remembers the node count of every subtree in `cache`, so a position
+    /// reached through several move orders is only searched once.
+    ///
+    /// Counts are stored under the board hash with the depth in their top 8 bits, and are only
+    /// reused when both match.  `CacheTable` keeps the whole 64-bit hash, so the result can only
+    /// be wrong if two different positions share a zobrist hash.  A `depth` of 0 counts the one
+    /// position `board`.
+    ///
+    /// ```
+    /// use chess::{Board, CacheTable, MoveGen};
+    ///
+    /// let board = Board::default();
+    /// let mut cache = CacheTable::new(65536, 0);
+    /// assert_eq!(
+    ///     MoveGen::perft_cached(&board, 4, &mut cache),
+    ///     MoveGen::movegen_perft_test(&board, 4) as u64
+    /// );
+    /// ```
+    pub fn perft_cached(board: &Board, depth: usize, cache: &mut CacheTable<u64>) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if depth == 1 {
+            return MoveGen::new_legal(board).len() as u64;
+        }
+
+        // the moves are only generated when the count is not in the cache
+        let hash = board.get_hash();
+        if let Some(entry) = cache.get(hash) {
+            if entry >> 56 == depth as u64 {
+                return entry & PERFT_COUNT_MASK;
+            }
+        }
+
+        let mut result: u64 = 0;
+        for m in MoveGen::new_legal(board) {
+            let bresult = board.make_move_new(m);
+            result += MoveGen::perft_cached(&bresult, depth - 1, cache);
+        }
+        cache.add(hash, ((depth as u64) << 56) | (result & PERFT_COUNT_MASK));
+        result
+    }
+
+    /// Perft test that counts in a `u128`, for depths where the node count might not fit in a
+    /// `u64`.  A `depth` of 0 counts the one position `board`.
+    pub fn perft_u128(board: &Board, depth: usize) -> u128 {
+        if depth == 0 {
+            return 1;
+        }
+        let iterable = MoveGen::new_legal(board);
+
+        if depth <= 1 {
+            iterable.len() as u128
+        } else {
+            let mut result: u128 = 0;
+            for m in iterable {
+                let bresult = board.make_move_new(m);
+                result += MoveGen::perft_u128(&bresult, depth - 1);
+            }
+            result
+        }
+    }
+
+    /// Count the nodes below each legal move, and the total of those counts.  At a `depth` of 0
+    /// there are no moves, and the total is the one position `board`.
+    ///
+    /// Each count is made in a `u64`, on 32-bit targets too.  If the total does not fit in a
+    /// `u64`, this panics in debug builds and saturates to `u64::MAX` in release builds.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen};
+    ///
+    /// let (divide, total) = MoveGen::perft_divide(&Board::default(), 3);
+    /// assert_eq!(divide.len(), 20);
+    /// assert_eq!(total, MoveGen::movegen_perft_test(&Board::default(), 3) as u64);
+    /// ```
+    pub fn perft_divide(board: &Board, depth: usize) -> (Vec<(ChessMove, u64)>, u64) {
+        if depth == 0 {
+            return (Vec::new(), 1);
+        }
+        let divide: Vec<(ChessMove, u64)> = MoveGen::new_legal(board)
+            .map(|m| (m, MoveGen::perft_u64(&board.make_move_new(m), depth - 1)))
+            .collect();
+        let total = sum_node_counts(divide.iter().map(|(_, nodes)| *nodes));
+        (divide, total)
+    }
+
+    // `movegen_perft_test`, counting in a `u64` whatever the size of a `usize`, so that a
+    // subtree of over 4 billion nodes does not wrap around on 32-bit targets.
+    fn perft_u64(board: &Board, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let iterable = MoveGen::new_legal(board);
+        if depth == 1 {
+            return iterable.len() as u64;
+        }
+        iterable
+            .map(|m| MoveGen::perft_u64(&board.make_move_new(m), depth - 1))
+            .sum()
+    }
+
+    /// Count the nodes below each legal move, as `MoveGen::perft_divide` does, and time it.  At a
+    /// `depth` of 0 there are no moves to divide by, and the one node is `board` itself.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen};
+    ///
+    /// let report = MoveGen::perft_report(&Board::default(), 2);
+    /// assert_eq!(report.nodes, 400);
+    /// # #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    /// assert!(report.nodes_per_second().is_some());
+    /// ```
+    pub fn perft_report(board: &Board, depth: usize) -> PerftReport {
+        let stopwatch = Stopwatch::start();
+        let (divide, nodes) = MoveGen::perft_divide(board, depth);
+        PerftReport {
+            depth,
+            divide,
+            nodes,
+            elapsed: stopwatch.elapsed(),
+        }
+    }
+
+    /// `MoveGen::perft_report`, spread across every available thread the way
+    /// `MoveGen::perft_parallel_depth` is, so that a run on one thread and a run on all of them
+    /// can be compared line by line.  The tree is split at least at the root, so that each root
+    /// move gets its own count, and a `depth` of 0 is the one node `board` as it is there.
+    /// Where there are no threads to spawn, such as on wasm32, it all runs on this one.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen};
+    ///
+    /// let board = Board::default();
+    /// let parallel = MoveGen::perft_parallel_report(&board, 4, 2);
+    /// assert_eq!(parallel.divide, MoveGen::perft_report(&board, 4).divide);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn perft_parallel_report(board: &Board, depth: usize, split_depth: usize) -> PerftReport {
+        let stopwatch = Stopwatch::start();
+        if depth == 0 {
+            return PerftReport {
+                depth,
+                divide: Vec::new(),
+                nodes: 1,
+                elapsed: stopwatch.elapsed(),
+            };
+        }
+        let moves: Vec<ChessMove> = MoveGen::new_legal(board).collect();
+
+        // the positions the threads count from, with the index of the root move each is below
+        let mut frontier: Vec<(usize, Board)> = moves
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (i, board.make_move_new(*m)))
+            .collect();
+        let split_depth = split_depth.max(1).min(depth.max(1));
+        for _ in 1..split_depth {
+            frontier = frontier
+                .iter()
+                .flat_map(|(i, b)| MoveGen::new_legal(b).map(move |m| (*i, b.make_move_new(m))))
+                .collect();
+        }
+
+        let remaining = depth.saturating_sub(split_depth);
+        // each thread counts the nodes below every root move in a u64 of its own
+        let next = AtomicUsize::new(0);
+        let work = || {
+            let mut counts = vec![0u64; moves.len()];
+            while let Some((i, b)) = frontier.get(next.fetch_add(1, Ordering::Relaxed)) {
+                counts[*i] += MoveGen::perft_u64(b, remaining);
+            }
+            counts
+        };
+        let counts: Vec<Vec<u64>> = match std::thread::available_parallelism() {
+            Ok(threads) => std::thread::scope(|s| {
+                let workers: Vec<_> = (0..threads.get()).map(|_| s.spawn(work)).collect();
+                workers.into_iter().map(|w| w.join().unwrap()).collect()
+            }),
+            Err(_) => vec![work()],
+        };
+
+        let divide: Vec<(ChessMove, u64)> = moves
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (*m, sum_node_counts(counts.iter().map(|c| c[i]))))
+            .collect();
+        let nodes = sum_node_counts(divide.iter().map(|(_, nodes)| *nodes));
+        PerftReport {
+            depth,
+            divide,
+            nodes,
+            elapsed: stopwatch.elapsed(),
+        }
+    }
+
+    /// Perft test spread across every available thread.
+    ///
+    /// The tree is first expanded `split_depth` plies deep, and the positions found there are
+    /// handed out to the threads one at a time.  A thread that finishes a small subtree just picks
+    /// up the next position, so one huge root move does not leave the other threads idle the way
+    /// splitting only at the root (a `split_depth` of 1) can.  The total is the same as
+    /// `movegen_perft_test`, and a `depth` of 0 counts the one position `board`.  Where there are
+    /// no threads to spawn, such as on wasm32, it all runs on this one.
+    ///
+    /// ```
+    /// use chess::{Board, MoveGen};
+    ///
+    /// let board = Board::default();
+    /// assert_eq!(
+    ///     MoveGen::perft_parallel_depth(&board, 4, 2),
+    ///     MoveGen::movegen_perft_test(&board, 4)
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn perft_parallel_depth(board: &Board, depth: usize, split_depth: usize) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+        let split_depth = split_depth.min(depth.saturating_sub(1));
+        let mut frontier = vec![*board];
+        for _ in 0..split_depth {
+            frontier = frontier
+                .iter()
+                .flat_map(|b| MoveGen::new_legal(b).map(move |m| b.make_move_new(m)))
+                .collect();
+        }
+
+        let remaining = depth - split_depth;
+        let next = AtomicUsize::new(0);
+        let work = || {
+            let mut result = 0;
+            while let Some(b) = frontier.get(next.fetch_add(1, Ordering::Relaxed)) {
+                result += MoveGen::movegen_perft_test(b, remaining);
+            }
+            result
+        };
+        match std::thread::available_parallelism() {
+            Ok(threads) => std::thread::scope(|s| {
+                let workers: Vec<_> = (0..threads.get()).map(|_| s.spawn(work)).collect();
+                workers.into_iter().map(|w| w.join().unwrap()).sum()
+            }),
+            Err(_) => work(),
+        }
+    }
+
     #[cfg(test)]
     /// Do a perft test after splitting the moves up into two groups
     pub fn movegen_perft_test_piecewise(board: &Board, depth: usize) -> usize {
@@ -240,7 +1466,7 @@ impl MoveGen {
         } else {
             iterable.set_iterator_mask(*targets);
             for x in &mut iterable {
-                let mut bresult = std::mem::MaybeUninit::<Board>::uninit();
+                let mut bresult = core::mem::MaybeUninit::<Board>::uninit();
                 unsafe {
                     board.make_move(x, &mut *bresult.as_mut_ptr());
                     result += MoveGen::movegen_perft_test(&*bresult.as_ptr(), depth - 1);
@@ -248,7 +1474,7 @@ impl MoveGen {
             }
             iterable.set_iterator_mask(!EMPTY);
             for x in &mut iterable {
-                let mut bresult = std::mem::MaybeUninit::<Board>::uninit();
+                let mut bresult = core::mem::MaybeUninit::<Board>::uninit();
                 unsafe {
                     board.make_move(x, &mut *bresult.as_mut_ptr());
                     result += MoveGen::movegen_perft_test(&*bresult.as_ptr(), depth - 1);
@@ -262,18 +1488,23 @@ impl MoveGen {
 impl ExactSizeIterator for MoveGen {
     /// Give the exact length of this iterator
     fn len(&self) -> usize {
-        let mut result = 0;
+        let mut result = if self.promoting.is_some() {
+            self.promotions - self.promotion_index
+        } else {
+            0
+        };
         for i in 0..self.moves.len() {
             if self.moves[i].bitboard & self.iterator_mask == EMPTY {
                 break;
             }
             if self.moves[i].promotion {
                 result += ((self.moves[i].bitboard & self.iterator_mask).popcnt() as usize)
-                    * NUM_PROMOTION_PIECES;
+                    * self.promotions;
             } else {
                 result += (self.moves[i].bitboard & self.iterator_mask).popcnt() as usize;
             }
         }
+        debug_assert!(result <= MoveGen::MAX_LEGAL_MOVES);
         result
     }
 }
@@ -289,7 +1520,16 @@ impl Iterator for MoveGen {
 
     /// Find the next chess move.
     fn next(&mut self) -> Option<ChessMove> {
-        if self.index >= self.moves.len()
+        if let Some((src, dest)) = self.promoting {
+            // finish the promotions of this square, which are no longer in `moves`
+            let result = ChessMove::new(src, dest, Some(PROMOTION_PIECES[self.promotion_index]));
+            self.promotion_index += 1;
+            if self.promotion_index >= self.promotions {
+                self.promoting = None;
+                self.promotion_index = 0;
+            }
+            Some(result)
+        } else if self.index >= self.moves.len()
             || self.moves[self.index].bitboard & self.iterator_mask == EMPTY
         {
             // are we done?
@@ -297,27 +1537,24 @@ impl Iterator for MoveGen {
         } else if self.moves[self.index].promotion {
             let moves = &mut self.moves[self.index];
 
-            let dest = (moves.bitboard & self.iterator_mask).to_square();
+            let dest = (moves.bitboard & self.iterator_mask).to_square_unchecked();
 
-            // deal with potential promotions for this pawn
-            let result = ChessMove::new(
-                moves.square,
-                dest,
-                Some(PROMOTION_PIECES[self.promotion_index]),
-            );
-            self.promotion_index += 1;
-            if self.promotion_index >= NUM_PROMOTION_PIECES {
-                moves.bitboard ^= BitBoard::from_square(dest);
-                self.promotion_index = 0;
-                if moves.bitboard & self.iterator_mask == EMPTY {
-                    self.index += 1;
-                }
+            // deal with potential promotions for this pawn, the first one now and the others
+            // through `promoting`, so that a new mask cannot mix up the squares
+            let result = ChessMove::new(moves.square, dest, Some(PROMOTION_PIECES[0]));
+            if self.promotions > 1 {
+                self.promoting = Some((moves.square, dest));
+                self.promotion_index = 1;
+            }
+            moves.bitboard ^= BitBoard::from_square(dest);
+            if moves.bitboard & self.iterator_mask == EMPTY {
+                self.index += 1;
             }
             Some(result)
         } else {
             // not a promotion move, so its a 'normal' move as far as this function is concerned
             let moves = &mut self.moves[self.index];
-            let dest = (moves.bitboard & self.iterator_mask).to_square();
+            let dest = (moves.bitboard & self.iterator_mask).to_square_unchecked();
 
             moves.bitboard ^= BitBoard::from_square(dest);
             if moves.bitboard & self.iterator_mask == EMPTY {
@@ -328,8 +1565,94 @@ impl Iterator for MoveGen {
     }
 }
 
+/// The legal moves of a board, generated one kind of piece at a time as they are iterated, see
+/// `MoveGen::with_piece_order`.
+pub struct StagedMoveGen {
+    board: Board,
+    stages: [Piece; NUM_PIECES],
+    stage: usize,
+    moves: MoveGen,
+    generated: usize,
+}
+
+impl StagedMoveGen {
+    /// How many moves have been generated so far, iterated or not.
+    pub fn generated(&self) -> usize {
+        self.generated
+    }
+}
+
+impl Iterator for StagedMoveGen {
+    type Item = ChessMove;
+
+    /// Find the next chess move, generating the moves of the next kinds of piece when the moves
+    /// of the kinds before them run out.
+    fn next(&mut self) -> Option<ChessMove> {
+        loop {
+            if let Some(m) = self.moves.next() {
+                return Some(m);
+            }
+            let piece = *self.stages.get(self.stage)?;
+            self.stage += 1;
+            self.moves = MoveGen::from_moves(match self.board.variant() {
+                Variant::Uno => MoveGen::enumerate_piece_moves::<UnoRules>(&self.board, piece),
+                Variant::Standard => {
+                    MoveGen::enumerate_piece_moves::<StandardRules>(&self.board, piece)
+                }
+            });
+            self.generated += self.moves.len();
+        }
+    }
+}
+
+/// Give me the moves of `board` that are legal under the normal rules only, and those that are
+/// legal under the uno rules only, each in the order `MoveGen::new_legal` gives them, whichever
+/// variant `board` is played by.  This shows where the variants part ways.
+///
+/// A position that cannot come up under one of the rules, such as one where the side not to move
+/// is in check under them, has no moves there.
+///
+/// ```
+/// use chess::{diff_legal_moves, Board, ChessMove};
+/// use std::str::FromStr;
+///
+/// // the pawn may take the rook on f8 in normal chess only, and the king may take it in uno,
+/// // since the rook attacks the king
+/// let board = Board::from_str("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1").unwrap();
+/// let (standard, uno) = diff_legal_moves(&board);
+/// assert!(standard.contains(&ChessMove::from_str("e7f8q").unwrap()));
+/// assert!(uno.contains(&ChessMove::from_str("c8f8").unwrap()));
+///
+/// assert_eq!(diff_legal_moves(&Board::default()), (vec![], vec![]));
+/// ```
+pub fn diff_legal_moves(board: &Board) -> (Vec<ChessMove>, Vec<ChessMove>) {
+    let legal = |variant| -> Vec<ChessMove> {
+        let mut builder = BoardBuilder::from(board);
+        builder.variant(variant);
+        match Board::try_from(&builder) {
+            Ok(board) => MoveGen::new_legal(&board).collect(),
+            Err(_) => Vec::new(),
+        }
+    };
+    let standard = legal(Variant::Standard);
+    let uno = legal(Variant::Uno);
+    let only = |moves: &[ChessMove], others: &[ChessMove]| -> Vec<ChessMove> {
+        let only = moves.iter().filter(|m| !others.contains(m));
+        only.copied().collect()
+    };
+    (only(&standard, &uno), only(&uno, &standard))
+}
+
 #[cfg(test)]
-use crate::board_builder::BoardBuilder;
+use crate::magic::{get_bishop_moves, get_king_moves, get_knight_moves, line};
+#[cfg(test)]
+use crate::magic::{get_queen_moves, get_rook_moves};
+#[cfg(test)]
+use crate::playouts::random_playouts;
+#[cfg(test)]
+use crate::square::ALL_SQUARES;
+#[cfg(test)]
+use crate::variant::ALL_VARIANTS;
 #[cfg(test)]
 use std::collections::HashSet;
 #[cfg(test)]
@@ -337,14 +1660,28 @@ use std::convert::TryInto;
 #[cfg(test)]
 use std::str::FromStr;
 
+// These are the perft counts of normal chess.
 #[cfg(test)]
 fn movegen_perft_test(fen: String, depth: usize, result: usize) {
-    let board: Board = BoardBuilder::from_str(&fen).unwrap().try_into().unwrap();
+    let board: Board = BoardBuilder::from_str(&fen)
+        .unwrap()
+        .variant(Variant::Standard)
+        .try_into()
+        .unwrap();
 
     assert_eq!(MoveGen::movegen_perft_test(&board, depth), result);
     assert_eq!(MoveGen::movegen_perft_test_piecewise(&board, depth), result);
 }
 
+#[test]
+fn movegen_perft_start_position() {
+    movegen_perft_test(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_owned(),
+        5,
+        4865609,
+    );
+}
+
 #[test]
 fn movegen_perft_kiwipete() {
     movegen_perft_test(
@@ -512,6 +1849,198 @@ fn movegen_issue_15() {
     let _ = MoveGen::new_legal(&board);
 }
 
+#[test]
+fn king_captures_from_a_distance() {
+    let capture = ChessMove::new(Square::E1, Square::E5, None);
+
+    // the rook on e5 attacks my king, so my king may take it, even with the rook on h5 defending
+    // it from afar
+    let fen = "4k3/8/8/4r2r/8/8/8/4K3 w - - 0 1";
+    let board = Board::from_str(fen).unwrap();
+    assert!(board.legal(capture));
+    assert!(MoveGen::legal_quick(&board, capture));
+    assert_eq!(
+        board.make_move_new(capture).king_square(Color::White),
+        Square::E5
+    );
+
+    // but not with the pawn on d6 defending it from next door
+    let board = Board::from_str("4k3/8/3p4/4r3/8/8/8/4K3 w - - 0 1").unwrap();
+    assert!(!board.legal(capture));
+
+    // nor when the rook does not attack my king
+    let board = Board::from_str("4k3/8/8/4r3/8/4P3/8/4K3 w - - 0 1").unwrap();
+    assert!(!board.legal(capture));
+
+    // in normal chess my king only captures next to it
+    let board: Board = BoardBuilder::from_str(fen)
+        .unwrap()
+        .variant(Variant::Standard)
+        .try_into()
+        .unwrap();
+    assert!(!board.legal(capture));
+
+    // a capture two squares away is not castling
+    let board = Board::from_str("4k3/8/8/8/8/4r3/8/4K3 w - - 0 1").unwrap();
+    let capture = ChessMove::new(Square::E1, Square::E3, None);
+    assert!(board.legal(capture));
+    assert!(MoveGen::legal_quick(&board, capture));
+}
+
+#[test]
+fn king_safety_follows_the_capture_rules() {
+    let variant_board = |fen: &str, variant: Variant| -> Board {
+        BoardBuilder::from_str(fen)
+            .unwrap()
+            .variant(variant)
+            .try_into()
+            .unwrap()
+    };
+    let king_moves = |board: &Board| {
+        MoveGen::new_legal(board)
+            .filter(|m| m.get_source() == board.king_square(board.side_to_move()))
+            .collect::<HashSet<_>>()
+    };
+
+    // The only difference is a rook on d8 that attacks d1 and d2 from afar, or a rook on f8 or
+    // g8 that attacks the squares castling crosses or lands on.  It cannot capture my king there
+    // in the uno variant, because my king would not attack it.
+    let pairs = [
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "3rk3/8/8/8/8/8/8/4K3 w - - 0 1",
+            2,
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K2R w K - 0 1",
+            "4kr2/8/8/8/8/8/8/4K2R w K - 0 1",
+            3,
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K2R w K - 0 1",
+            "4k1r1/8/8/8/8/8/8/4K2R w K - 0 1",
+            1,
+        ),
+    ];
+    for (quiet, attacked, lost) in pairs.iter() {
+        let uno = king_moves(&variant_board(quiet, Variant::Uno));
+        assert_eq!(uno, king_moves(&variant_board(attacked, Variant::Uno)));
+
+        let standard = king_moves(&variant_board(quiet, Variant::Standard));
+        let attacked = king_moves(&variant_board(attacked, Variant::Standard));
+        assert_eq!(uno, standard);
+        assert!(attacked.is_subset(&standard));
+        assert_eq!(standard.len() - attacked.len(), *lost);
+    }
+
+    // every king move is legal exactly when no enemy piece could capture my king afterwards
+    let start = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    random_playouts(&[start], &ALL_VARIANTS, 100, 60, |board, _| {
+        let color = board.side_to_move();
+        let ksq = board.king_square(color);
+        let candidates =
+            (get_king_moves(ksq) & !board.color_combined(color)) | board.capture_targets(ksq);
+        for dest in candidates {
+            let after = board.make_move_new(ChessMove::new(ksq, dest, None));
+            assert_eq!(
+                KingType::legal_king_move(board, dest),
+                after.capturable_by(dest) == EMPTY,
+                "{} {}",
+                board,
+                dest
+            );
+        }
+    });
+}
+
+#[test]
+fn castling_transit_squares() {
+    let castles = |fen: &str, variant: Variant| {
+        let board: Board = BoardBuilder::from_str(fen)
+            .unwrap()
+            .variant(variant)
+            .try_into()
+            .unwrap();
+        MoveGen::new_legal(&board)
+            .filter(|m| m.get_source() == Square::E1)
+            .filter(|m| m.get_dest() == Square::G1 || m.get_dest() == Square::C1)
+            .map(|m| m.get_dest())
+            .collect::<HashSet<_>>()
+    };
+    let both: HashSet<Square> = [Square::G1, Square::C1].iter().cloned().collect();
+
+    // the bishops cover f1 and d1 from afar, which only stops castling in normal chess
+    let covered = "4k3/8/8/1b5b/8/8/8/R3K2R w KQ - 0 1";
+    assert_eq!(castles(covered, Variant::Uno), both);
+    assert_eq!(castles(covered, Variant::Standard), HashSet::new());
+
+    // the pawn on g2 covers f1 from next door, which stops castling kingside in both
+    let covered = "4k3/8/8/8/8/8/6p1/R3K2R w KQ - 0 1";
+    let queenside: HashSet<Square> = [Square::C1].iter().cloned().collect();
+    assert_eq!(castles(covered, Variant::Uno), queenside);
+    assert_eq!(castles(covered, Variant::Standard), queenside);
+
+    // castle rights added by hand, without a rook to castle with or with the king off its rank
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/3K4/R6R w - - 0 1",
+    ]
+    .iter()
+    {
+        let mut board = Board::from_str(fen).unwrap();
+        #[allow(deprecated)]
+        board.add_castle_rights(Color::White, CastleRights::Both);
+        let ksq = board.king_square(Color::White);
+        let castles = MoveGen::new_legal(&board)
+            .filter(|m| m.get_source() == ksq)
+            .filter(|m| get_king_moves(ksq) & BitBoard::from_square(m.get_dest()) == EMPTY);
+        assert_eq!(castles.count(), 0);
+    }
+}
+
+#[test]
+fn chess960_castling() {
+    let board_of = |fen: &str, variant: Variant| -> Board {
+        BoardBuilder::from_str(fen)
+            .unwrap()
+            .variant(variant)
+            .try_into()
+            .unwrap()
+    };
+    let castle =
+        |board: &Board| MoveGen::new_legal(board).find(|m| board.castle_rook(*m).is_some());
+
+    // the king stays on g1 and only the rook moves
+    let board = board_of("4k3/8/8/8/8/8/8/6KR w H - 0 1", Variant::Standard);
+    let m = castle(&board).unwrap();
+    assert_eq!(m, ChessMove::new(Square::G1, Square::H1, None));
+    let castled = board.make_move_new(m);
+    assert_eq!(castled.piece_on(Square::G1), Some(Piece::King));
+    assert_eq!(castled.piece_on(Square::F1), Some(Piece::Rook));
+    assert_eq!(castled.castle_rights(Color::White), CastleRights::NoRights);
+
+    // the king and rook swap squares
+    let board = board_of("4k3/8/8/8/8/8/8/5KR1 w G - 0 1", Variant::Standard);
+    let castled = board.make_move_new(castle(&board).unwrap());
+    assert_eq!(castled.piece_on(Square::G1), Some(Piece::King));
+    assert_eq!(castled.piece_on(Square::F1), Some(Piece::Rook));
+
+    // the rook on b1 shields the king's landing square from the rook on a1 until it moves,
+    // which only matters in normal chess
+    for variant in ALL_VARIANTS.iter() {
+        let board = board_of("4k3/8/8/8/8/8/8/rR4K1 w B - 0 1", *variant);
+        let m = castle(&board);
+        match variant {
+            Variant::Standard => assert_eq!(m, None),
+            Variant::Uno => {
+                let castled = board.make_move_new(m.unwrap());
+                assert_eq!(castled.piece_on(Square::C1), Some(Piece::King));
+                assert_eq!(castled.piece_on(Square::D1), Some(Piece::Rook));
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 fn move_of(m: &str) -> ChessMove {
     let promo = if m.len() > 4 {
@@ -534,9 +2063,13 @@ fn move_of(m: &str) -> ChessMove {
 
 #[test]
 fn test_masked_move_gen() {
-    let board =
-        Board::from_str("r1bqkb1r/pp3ppp/5n2/2ppn1N1/4pP2/1BN1P3/PPPP2PP/R1BQ1RK1 w kq - 0 9")
-            .unwrap();
+    let board: Board = BoardBuilder::from_str(
+        "r1bqkb1r/pp3ppp/5n2/2ppn1N1/4pP2/1BN1P3/PPPP2PP/R1BQ1RK1 w kq - 0 9",
+    )
+    .unwrap()
+    .variant(Variant::Standard)
+    .try_into()
+    .unwrap();
 
     let mut capture_moves = MoveGen::new_legal(&board);
     let targets = *board.color_combined(!board.side_to_move());
@@ -557,3 +2090,705 @@ fn test_masked_move_gen() {
         expected.into_iter().collect()
     );
 }
+
+#[test]
+fn masks_keep_promotions_apart_from_other_moves() {
+    let sorted = |movegen: MoveGen| {
+        let len = movegen.len();
+        let mut moves: Vec<String> = movegen.map(|m| m.to_string()).collect();
+        assert_eq!(moves.len(), len);
+        moves.sort();
+        moves
+    };
+    let promotions = |m: &str| -> Vec<String> {
+        let mut moves: Vec<String> = ["b", "n", "q", "r"]
+            .iter()
+            .map(|piece| format!("{}{}", m, piece))
+            .collect();
+        moves.sort();
+        moves
+    };
+    let only = |squares: &[Square]| {
+        squares
+            .iter()
+            .fold(EMPTY, |bb, sq| bb | BitBoard::from_square(*sq))
+    };
+
+    // under the uno rules the pawn on b7 takes the rook on b3 that attacks it, which is not a
+    // promotion, next to pushing to b8 and taking the pawn on c8
+    let board = Board::from_str("2p1k3/1P6/8/8/8/1r6/8/4K3 w - - 0 1").unwrap();
+    let mut movegen = MoveGen::new_legal(&board);
+    movegen.set_iterator_mask(only(&[Square::C8]));
+    assert_eq!(sorted(movegen), promotions("b7c8"));
+
+    let mut movegen = MoveGen::new_legal(&board);
+    movegen.set_iterator_mask(only(&[Square::B3]));
+    assert_eq!(sorted(movegen), vec!["b7b3"]);
+
+    let mut movegen = MoveGen::new_legal(&board);
+    movegen.set_iterator_mask(only(&[Square::B3, Square::B8]));
+    let mut expected = promotions("b7b8");
+    expected.insert(0, "b7b3".to_string());
+    assert_eq!(sorted(movegen), expected);
+
+    // removing squares or moves leaves the other entry of the pawn as it was
+    let mut movegen = MoveGen::new_legal(&board);
+    movegen.remove_mask(only(&[Square::B3, Square::B8]));
+    movegen.set_iterator_mask(only(&[Square::B3, Square::B8, Square::C8]));
+    assert_eq!(sorted(movegen), promotions("b7c8"));
+    let mut movegen = MoveGen::new_legal(&board);
+    assert!(movegen.remove_move(move_of("b7c8q")));
+    assert!(!movegen.remove_move(move_of("b7c8q")));
+    assert!(movegen.remove_move(move_of("b7b3")));
+    movegen.set_iterator_mask(only(&[Square::B3, Square::B8, Square::C8]));
+    assert_eq!(sorted(movegen), promotions("b7b8"));
+
+    // a quiescence search only promotes to a queen, under a mask too
+    let mut movegen = MoveGen::new_quiescence(&board, false);
+    movegen.set_iterator_mask(only(&[Square::B3, Square::C8]));
+    assert_eq!(sorted(movegen), vec!["b7b3", "b7c8q"]);
+
+    // in normal chess every move of the pawn promotes
+    let board = Board::from_str("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1 variant:standard").unwrap();
+    for (square, m) in [(Square::A8, "b7a8"), (Square::B8, "b7b8")].iter() {
+        let mut movegen = MoveGen::new_legal(&board);
+        movegen.set_iterator_mask(only(&[*square]));
+        assert_eq!(sorted(movegen), promotions(m));
+    }
+}
+
+#[test]
+fn widening_the_mask_gives_back_the_other_moves() {
+    for fen in [
+        "r1bqkb1r/pp3ppp/5n2/2ppn1N1/4pP2/1BN1P3/PPPP2PP/R1BQ1RK1 w kq - 0 9 variant:standard",
+        "r1bqkb1r/pp3ppp/5n2/2ppn1N1/4pP2/1BN1P3/PPPP2PP/R1BQ1RK1 w kq - 0 9",
+        // the pawn takes on c8 and a8 and pushes to b8, so its promotions are spread over
+        // squares inside and outside the mask
+        "r1r1k3/1P6/8/8/8/8/8/4K3 w - - 0 1 variant:standard",
+        "n1n1k3/1P6/8/8/8/8/8/4K3 w - - 0 1 variant:standard",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        let all: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+
+        // stop after each number of captures, and in the middle of a promotion
+        for taken in 0..4 {
+            let mut movegen = MoveGen::new_legal(&board);
+            movegen.set_iterator_mask(*board.color_combined(!board.side_to_move()));
+            let mut seen: Vec<ChessMove> = movegen.by_ref().take(taken).collect();
+            assert!(seen.iter().all(|m| board.is_capture(*m)), "{}", fen);
+
+            movegen.set_iterator_mask(!EMPTY);
+            assert_eq!(movegen.len(), all.len() - seen.len(), "{}", fen);
+            seen.extend(movegen);
+            assert_eq!(seen.len(), all.len(), "{}", fen);
+            assert_eq!(
+                seen.into_iter().collect::<HashSet<_>>(),
+                all.iter().cloned().collect(),
+                "{}",
+                fen
+            );
+        }
+    }
+}
+
+#[test]
+fn perft_cached_matches_perft() {
+    let positions = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+        "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1",
+    ];
+    // small enough that many subtrees get overwritten before they are reused
+    let mut cache = CacheTable::new(4096, 0);
+    for fen in positions.iter() {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(
+            MoveGen::perft_cached(&board, 5, &mut cache),
+            MoveGen::movegen_perft_test(&board, 5) as u64
+        );
+    }
+}
+
+#[test]
+fn perft_divide_and_u128_match_perft() {
+    let board = Board::default();
+    let expected = MoveGen::movegen_perft_test(&board, 4);
+    let (divide, total) = MoveGen::perft_divide(&board, 4);
+    assert_eq!(divide.len(), 20);
+    assert_eq!(total, expected as u64);
+    assert_eq!(MoveGen::perft_u128(&board, 4), expected as u128);
+}
+
+#[test]
+fn perft_report_matches_perft() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let board = Board::from_str(fen).unwrap();
+    for depth in 1..4 {
+        let report = MoveGen::perft_report(&board, depth);
+        assert_eq!(report.depth, depth);
+        let nodes = MoveGen::movegen_perft_test(&board, depth);
+        assert_eq!(report.nodes, nodes as u64);
+        assert_eq!(report.divide, MoveGen::perft_divide(&board, depth).0);
+
+        // the table has a line for every root move, then the total, and the time if it has one
+        let table = report.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        let timed = if report.elapsed.is_some() { 2 } else { 0 };
+        assert_eq!(lines.len(), report.divide.len() + 2 + timed);
+        for ((m, nodes), line) in report.divide.iter().zip(lines.iter()) {
+            assert_eq!(*line, format!("{}: {}", m, nodes));
+        }
+        let total = format!("Nodes searched: {}", report.nodes);
+        assert_eq!(lines[report.divide.len() + 1], total);
+    }
+}
+
+#[test]
+fn perft_at_depth_zero_counts_the_position() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    for variant in ALL_VARIANTS.iter() {
+        let board: Board = BoardBuilder::from_str(fen)
+            .unwrap()
+            .variant(*variant)
+            .try_into()
+            .unwrap();
+        let mut cache = CacheTable::new(256, 0);
+        assert_eq!(MoveGen::movegen_perft_test(&board, 0), 1);
+        assert_eq!(MoveGen::perft_cached(&board, 0, &mut cache), 1);
+        assert_eq!(MoveGen::perft_u128(&board, 0), 1);
+        assert_eq!(MoveGen::perft_divide(&board, 0), (Vec::new(), 1));
+        let report = MoveGen::perft_report(&board, 0);
+        assert_eq!((report.divide.len(), report.nodes), (0, 1));
+        #[cfg(feature = "std")]
+        {
+            assert_eq!(MoveGen::perft_parallel_depth(&board, 0, 1), 1);
+            let report = MoveGen::perft_parallel_report(&board, 0, 1);
+            assert_eq!((report.divide.len(), report.nodes), (0, 1));
+        }
+    }
+}
+
+#[test]
+fn perft_node_count_overflow() {
+    let counts = [u64::MAX - 1, 1, 1];
+    if cfg!(debug_assertions) {
+        assert!(std::panic::catch_unwind(|| sum_node_counts(counts.iter().copied())).is_err());
+    } else {
+        assert_eq!(sum_node_counts(counts.iter().copied()), u64::MAX);
+    }
+    assert_eq!(sum_node_counts(counts[..2].iter().copied()), u64::MAX);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn perft_parallel_depth_matches_perft() {
+    let board =
+        Board::from_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let expected = MoveGen::movegen_perft_test(&board, 3);
+    for split_depth in 0..5 {
+        assert_eq!(
+            MoveGen::perft_parallel_depth(&board, 3, split_depth),
+            expected
+        );
+    }
+    assert_eq!(MoveGen::perft_parallel_depth(&Board::default(), 1, 1), 20);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn perft_parallel_depth_splits_at_most_above_the_leaves() {
+    let board = Board::default();
+    for split_depth in 0..3 {
+        assert_eq!(MoveGen::perft_parallel_depth(&board, 0, split_depth), 1);
+    }
+    assert_eq!(MoveGen::perft_parallel_depth(&board, 2, 2), 400);
+    assert_eq!(MoveGen::perft_parallel_depth(&board, 2, 9), 400);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn perft_parallel_report_matches_perft_report() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let board = Board::from_str(fen).unwrap();
+    for depth in 0..4 {
+        let serial = MoveGen::perft_report(&board, depth);
+        for split_depth in 0..5 {
+            let parallel = MoveGen::perft_parallel_report(&board, depth, split_depth);
+            assert_eq!(parallel.divide, serial.divide);
+            assert_eq!(parallel.nodes, serial.nodes);
+        }
+    }
+}
+
+#[test]
+fn gives_check_and_new_checks_match_make_move() {
+    use crate::variant::ALL_VARIANTS;
+
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        "3k4/1P4P1/8/8/8/8/8/4K3 w - - 0 1",
+        // discovered checks and en passant in standard chess
+        "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        "4k3/8/8/4B3/3N4/8/4R3/4K3 w - - 0 1",
+    ];
+    random_playouts(&starts, &ALL_VARIANTS, 500, 60, |board, moves| {
+        let checks: Vec<ChessMove> = moves
+            .iter()
+            .cloned()
+            .filter(|m| *board.make_move_new(*m).checkers() != EMPTY)
+            .collect();
+        for m in moves.iter() {
+            assert_eq!(board.gives_check(*m), checks.contains(m), "{} {}", board, m);
+        }
+
+        // every promotion to a square is generated if one of them checks
+        let expected: Vec<ChessMove> = moves
+            .iter()
+            .cloned()
+            .filter(|m| {
+                checks
+                    .iter()
+                    .any(|c| c.get_source() == m.get_source() && c.get_dest() == m.get_dest())
+            })
+            .collect();
+        let generated = MoveGen::new_checks(board);
+        assert_eq!(generated.len(), expected.len());
+        assert_eq!(generated.collect::<Vec<ChessMove>>(), expected, "{}", board);
+    });
+}
+
+#[test]
+fn quiescence_moves() {
+    let starts = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "n1n5/PPP5/8/2k5/8/8/5ppp/K4N1N b - - 0 1",
+        "4k3/8/8/8/8/8/3nr3/4K3 w - - 0 1",
+        "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1",
+    ];
+    let mut in_check = 0;
+    random_playouts(&starts, &ALL_VARIANTS, 200, 60, |board, moves| {
+        for include_checks in [false, true].iter() {
+            let generated = MoveGen::new_quiescence(board, *include_checks);
+            let len = generated.len();
+            let generated: Vec<ChessMove> = generated.collect();
+            assert_eq!(len, generated.len());
+
+            // when in check, exactly the moves of `new_legal`
+            if *board.checkers() != EMPTY {
+                in_check += 1;
+                assert_eq!(generated, moves, "{}", board);
+                continue;
+            }
+            let expected: HashSet<ChessMove> = moves
+                .iter()
+                .cloned()
+                .filter(|m| match m.get_promotion() {
+                    Some(promotion) => promotion == Piece::Queen,
+                    None => {
+                        board.capture_pattern(*m).is_some()
+                            || (*include_checks && board.gives_check(*m))
+                    }
+                })
+                .collect();
+            assert_eq!(generated.len(), expected.len(), "{}", board);
+            assert_eq!(generated.into_iter().collect::<HashSet<_>>(), expected);
+        }
+    });
+    assert!(in_check > 100);
+}
+
+#[test]
+fn pinned_pieces_capture_along_the_pin_only() {
+    // (fen, pinned piece, its destination, legal in normal chess); every one of these moves is
+    // legal under the uno rules, where nothing is pinned
+    let cases = [
+        // the knight on e2 is pinned by the rook on e8, and takes the knight that attacks it
+        (
+            "4r1k1/8/8/8/8/2n5/4N3/4K3 w - - 0 1",
+            Square::E2,
+            Square::C3,
+            false,
+        ),
+        // a rook pinned on the file takes the rook that pins it
+        (
+            "4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1",
+            Square::E2,
+            Square::E8,
+            true,
+        ),
+        // the bishop on f2 is pinned by the bishop on h4, and attacked by the one on d4
+        (
+            "6k1/8/8/8/3b3b/8/5B2/4K3 w - - 0 1",
+            Square::F2,
+            Square::D4,
+            false,
+        ),
+        (
+            "6k1/8/8/8/3b3b/8/5B2/4K3 w - - 0 1",
+            Square::F2,
+            Square::H4,
+            true,
+        ),
+        // the pawn on e2 is pinned by the rook on e8, and attacked by the bishop on d3
+        (
+            "4r1k1/8/8/8/8/3b4/4P3/4K3 w - - 0 1",
+            Square::E2,
+            Square::D3,
+            false,
+        ),
+        (
+            "4r1k1/8/8/8/8/3b4/4P3/4K3 w - - 0 1",
+            Square::E2,
+            Square::E4,
+            true,
+        ),
+        // the pawn on f2 is pinned by the bishop on g3, which it may take but not walk away from
+        (
+            "6k1/8/8/8/8/6b1/5P2/4K3 w - - 0 1",
+            Square::F2,
+            Square::G3,
+            true,
+        ),
+        (
+            "6k1/8/8/8/8/6b1/5P2/4K3 w - - 0 1",
+            Square::F2,
+            Square::F3,
+            false,
+        ),
+    ];
+    for (fen, src, dest, legal) in cases.iter() {
+        for variant in ALL_VARIANTS.iter() {
+            let board: Board = BoardBuilder::from_str(fen)
+                .unwrap()
+                .variant(*variant)
+                .try_into()
+                .unwrap();
+            let m = ChessMove::new(*src, *dest, None);
+            let (expected, pins) = match variant {
+                Variant::Standard => (*legal, BitBoard::from_square(*src)),
+                Variant::Uno => (true, EMPTY),
+            };
+            assert_eq!(*board.pinned(), pins, "{}", board);
+            assert_eq!(board.legal(m), expected, "{} {}", board, m);
+            assert_eq!(MoveGen::new_legal(&board).any(|x| x == m), expected);
+            let capture = board.piece_on(*dest).is_some();
+            let quiescence = MoveGen::new_quiescence(&board, false).any(|x| x == m);
+            assert_eq!(quiescence, expected && capture);
+        }
+    }
+}
+
+#[test]
+fn evasions_out_of_single_and_double_checks() {
+    for fen in [
+        // the rook checks and may be taken or blocked
+        "4k3/8/8/8/8/8/1BN5/r3K3 w - - 0 1 variant:standard",
+        // the knight and the rook check together, so only my king moves
+        "4k3/8/8/8/8/3n4/8/4K2r w - - 0 1 variant:standard",
+        // under the uno rules only the pieces next to my king check
+        "4k3/8/8/8/8/8/3nq3/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/3Rq3/4K3 w - - 0 1",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        assert_ne!(*board.checkers(), EMPTY, "{}", fen);
+        let evasions: Vec<ChessMove> = MoveGen::new_evasions(&board).collect();
+        let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        assert_eq!(evasions, moves, "{}", fen);
+    }
+
+    let board = Board::default();
+    if cfg!(debug_assertions) {
+        assert!(std::panic::catch_unwind(|| MoveGen::new_evasions(&board)).is_err());
+    } else {
+        assert_eq!(MoveGen::new_evasions(&board).len(), 20);
+    }
+}
+
+#[cfg(feature = "movegen-stats")]
+#[test]
+fn stats_count_the_generated_moves() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1 variant:standard",
+        "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1 variant:standard",
+        "4k3/8/8/8/8/8/1BN5/r3K3 w - - 0 1 variant:standard",
+        "4k3/8/8/8/8/8/3nq3/4K3 w - - 0 1",
+    ];
+    let before = stats_snapshot();
+    let mut expected_totals = MoveGenStats::default();
+    for fen in fens.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let mut generators = vec![
+            MoveGen::new_legal(&board),
+            MoveGen::new_checks(&board),
+            MoveGen::new_quiescence(&board, false),
+            MoveGen::new_quiescence(&board, true),
+        ];
+        if *board.checkers() != EMPTY {
+            generators.push(MoveGen::new_evasions(&board));
+        }
+        for movegen in generators.into_iter() {
+            let stats = movegen.stats();
+            let moves: Vec<ChessMove> = movegen.collect();
+
+            let mut expected = MoveGenStats {
+                generations: 1,
+                nanos: stats.nanos,
+                ..MoveGenStats::default()
+            };
+            for m in moves.iter() {
+                let piece = board.piece_on(m.get_source()).unwrap();
+                expected.moves_per_piece[piece.to_index()] += 1;
+                if board.is_capture(*m) {
+                    expected.captures += 1;
+                } else {
+                    expected.quiets += 1;
+                }
+                if m.get_promotion().is_some() {
+                    expected.promotions += 1;
+                }
+            }
+            if *board.checkers() != EMPTY {
+                expected.evasions = moves.len() as u64;
+            }
+            assert_eq!(stats, expected, "{}", fen);
+            assert_eq!(stats.moves(), moves.len() as u64);
+
+            for (total, field) in [
+                (&mut expected_totals.generations, stats.generations),
+                (&mut expected_totals.captures, stats.captures),
+                (&mut expected_totals.quiets, stats.quiets),
+                (&mut expected_totals.promotions, stats.promotions),
+                (&mut expected_totals.evasions, stats.evasions),
+            ]
+            .iter_mut()
+            {
+                **total += *field;
+            }
+        }
+    }
+
+    // other tests generate moves at the same time, so the totals grow by at least as much
+    let after = stats_snapshot();
+    assert!(after.generations >= before.generations + expected_totals.generations);
+    assert!(after.captures >= before.captures + expected_totals.captures);
+    assert!(after.quiets >= before.quiets + expected_totals.quiets);
+    assert!(after.promotions >= before.promotions + expected_totals.promotions);
+    assert!(after.evasions >= before.evasions + expected_totals.evasions);
+}
+
+#[cfg(feature = "movegen-stats")]
+#[test]
+fn trace_gives_the_entries_left() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    static ENTRIES: AtomicUsize = AtomicUsize::new(0);
+    fn count(entry: &SquareAndBitBoard) {
+        assert_ne!(entry.get_bitboard(), EMPTY);
+        ENTRIES.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // the eight pawns and the two knights
+    let mut movegen = MoveGen::new_legal(&Board::default());
+    movegen.trace(count);
+    assert_eq!(ENTRIES.swap(0, Ordering::Relaxed), 10);
+    // the knights only move to the third rank, so without it only the pawns are left
+    movegen.remove_mask(get_rank(crate::rank::Rank::Third));
+    movegen.set_iterator_mask(!EMPTY);
+    movegen.trace(count);
+    assert_eq!(ENTRIES.swap(0, Ordering::Relaxed), 8);
+}
+
+#[test]
+fn staged_moves_are_the_legal_moves() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 variant:standard",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard",
+        "2p1k3/1P6/8/8/8/1r6/8/4K3 w - - 0 1",
+        // one checker, and two
+        "4k3/8/8/8/8/8/1r2q3/4K3 w - - 0 1",
+        "4k3/8/8/8/1b6/8/8/r3K3 w - - 0 1 variant:standard",
+    ];
+    let orders: [&[Piece]; 4] = [
+        &ALL_PIECES,
+        &[Piece::Queen, Piece::Rook],
+        &[Piece::King, Piece::Queen, Piece::King, Piece::Pawn],
+        &[],
+    ];
+    for fen in fens.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let legal: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        let mut sorted = legal.clone();
+        sorted.sort();
+
+        // the order of `ALL_PIECES` is the order of `new_legal`, as is giving none
+        for order in [orders[0], orders[3]].iter() {
+            let staged: Vec<ChessMove> = MoveGen::with_piece_order(&board, order).collect();
+            assert_eq!(staged, legal, "{}", fen);
+        }
+        for order in orders.iter() {
+            let mut staged: Vec<ChessMove> = MoveGen::with_piece_order(&board, order).collect();
+            staged.sort();
+            assert_eq!(staged, sorted, "{}", fen);
+        }
+    }
+
+    // the rook moves come first, and nothing else is generated before they run out
+    let board = Board::default().make_move_new(ChessMove::new(Square::A2, Square::A4, None));
+    let board = board.make_move_new(ChessMove::new(Square::E7, Square::E5, None));
+    let mut movegen = MoveGen::with_piece_order(&board, &[Piece::Rook, Piece::Queen]);
+    assert_eq!(movegen.generated(), 0);
+    let rook = |dest| Some(ChessMove::new(Square::A1, dest, None));
+    assert_eq!(movegen.next(), rook(Square::A2));
+    assert_eq!(movegen.next(), rook(Square::A3));
+    assert_eq!(movegen.generated(), 2);
+    // the queen has no moves, so the pawns are next
+    assert_eq!(movegen.next().map(|m| m.get_source()), Some(Square::B2));
+    assert_eq!(movegen.generated(), 2 + 15);
+}
+
+#[test]
+fn scored_moves_are_sorted_stably() {
+    let board = Board::from_str(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 variant:standard",
+    )
+    .unwrap();
+    let legal: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+
+    // with the same score for every move, nothing moves
+    let scored = MoveGen::collect_scored(&board, |_, _| 0);
+    assert_eq!(scored.len(), legal.len());
+    assert_eq!(scored.capacity(), legal.len());
+    assert!(scored.iter().map(|(m, _)| *m).eq(legal.iter().copied()));
+
+    // the captures come first, the rest after them, and each in the order it was generated
+    let captures = |board: &Board, m: ChessMove| board.is_capture(m) as i32;
+    let expected: Vec<ChessMove> = legal
+        .iter()
+        .filter(|m| board.is_capture(**m))
+        .chain(legal.iter().filter(|m| !board.is_capture(**m)))
+        .copied()
+        .collect();
+    let scored = MoveGen::collect_scored(&board, captures);
+    assert!(scored.iter().map(|(m, _)| *m).eq(expected.iter().copied()));
+    for (m, score) in scored.iter() {
+        assert_eq!(*score, captures(&board, *m));
+    }
+
+    let mut moves = legal.clone();
+    MoveGen::sort_moves(&mut moves, |m| captures(&board, m));
+    assert_eq!(moves, expected);
+
+    // and higher scores come before lower ones
+    let mut moves = legal;
+    MoveGen::sort_moves(&mut moves, |m| board.see(m));
+    assert!(moves.windows(2).all(|w| board.see(w[0]) >= board.see(w[1])));
+}
+
+#[test]
+fn legal_moves_for_either_side() {
+    for fen in [
+        "r1bqkb1r/pp3ppp/5n2/2ppn1N1/4pP2/1BN1P3/PPPP2PP/R1BQ1RK1 w kq - 0 9 variant:standard",
+        "r1bqkb1r/pp3ppp/5n2/2ppn1N1/4pP2/1BN1P3/PPPP2PP/R1BQ1RK1 w kq - 0 9",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1 variant:standard",
+        "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1 variant:standard",
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        let color = board.side_to_move();
+        let mine: Vec<ChessMove> = MoveGen::new_legal_for(&board, color).unwrap().collect();
+        let legal: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        assert_eq!(mine, legal, "{}", fen);
+
+        let swapped = board.swap_side_to_move().unwrap();
+        let theirs: Vec<ChessMove> = MoveGen::new_legal_for(&board, !color).unwrap().collect();
+        let legal: Vec<ChessMove> = MoveGen::new_legal(&swapped).collect();
+        assert_eq!(theirs, legal, "{}", fen);
+        let mut movers = theirs.iter().map(|m| board.color_on(m.get_source()));
+        assert!(movers.all(|mover| mover == Some(!color)));
+    }
+
+    // black is in check, so it cannot be white's turn
+    let check = Board::from_str("4k3/8/8/8/8/8/8/4QK2 b - - 0 1 variant:standard").unwrap();
+    assert!(MoveGen::new_legal_for(&check, Color::Black).is_ok());
+    assert!(matches!(
+        MoveGen::new_legal_for(&check, Color::White),
+        Err(Error::OpponentInCheck)
+    ));
+}
+
+// A pinned piece's moves are masked with its pin line as they are generated, so none of them is
+// made and checked afterwards, unlike those of the king and en passant.
+#[test]
+fn pinned_pieces_move_along_the_pin() {
+    let fen = "4r1k1/8/8/q7/8/2B5/4R3/4KN1r w - - 0 1 variant:standard";
+    let board = Board::from_str(fen).unwrap();
+    let ksq = board.king_square(Color::White);
+    let pinned = [Square::F1, Square::E2, Square::C3];
+    assert_eq!(board.pinned().collect::<Vec<Square>>(), pinned);
+
+    let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+    for (src, count) in pinned.iter().zip([0, 6, 3].iter()) {
+        let from: Vec<&ChessMove> = moves.iter().filter(|m| m.get_source() == *src).collect();
+        assert_eq!(from.len(), *count, "{}", src);
+        for m in from {
+            assert!(line(*src, ksq) & BitBoard::from_square(m.get_dest()) != EMPTY);
+            assert!(board.make_move_new(*m).is_sane());
+        }
+    }
+}
+
+#[test]
+fn max_legal_moves_matches_its_bound() {
+    // the most squares a piece moves to, and the most pieces it attacks, which is one per ray
+    let most = |moves: &dyn Fn(Square) -> BitBoard| {
+        let counts = ALL_SQUARES.iter().map(|sq| moves(*sq).popcnt());
+        counts.max().unwrap()
+    };
+    assert_eq!(most(&|sq| get_queen_moves(sq, EMPTY)), 27);
+    assert_eq!(most(&|sq| get_rook_moves(sq, EMPTY)), 14);
+    assert_eq!(most(&|sq| get_bishop_moves(sq, EMPTY)), 13);
+    assert_eq!(most(&get_knight_moves), 8);
+    assert_eq!(most(&get_king_moves), 8);
+    assert_eq!(most(&|sq| get_queen_moves(sq, !EMPTY)), 8);
+
+    let quiet = 9 * 27 + 2 * 14 + 2 * 13 + 2 * 8 + 8 + 2;
+    assert_eq!(quiet, 323);
+    let captures = 16 * 8;
+    let promotions = 8 * 8 * (NUM_PROMOTION_PIECES - 1);
+    assert_eq!(MoveGen::MAX_LEGAL_MOVES, quiet + captures + promotions + 2);
+    assert_eq!(MoveGen::MAX_LEGAL_MOVES, 645);
+
+    // the most legal moves known for a position of normal chess, and one like it under the uno
+    // rules, without the pieces next to the black king that would give check
+    let crowded = [
+        (
+            "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1 variant:standard",
+            218,
+        ),
+        ("R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/3Q4/k1NN1KB1 w - - 0 1", 216),
+    ];
+    for (fen, moves) in crowded.iter() {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(MoveGen::new_legal(&board).len(), *moves);
+    }
+}
+
+#[test]
+fn move_list_holds_pawns_that_both_move_and_promote() {
+    // sixteen black pieces, where the pawns on a6 and h3 take the bishop on f1 and the pawn on
+    // c3 takes the rook on c1, each of which attacks it, and each of them also steps forward
+    let fen = "r2k2r1/1b1p2b1/p5p1/2p1np1n/N2pP1Pq/PPp2QRp/3N1P1P/2R1KB2 b - - 0 1";
+    let board = Board::from_str(fen).unwrap();
+    assert_eq!(MoveGen::new_legal(&board).len(), 54);
+}
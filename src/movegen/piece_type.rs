@@ -1,50 +1,70 @@
 use crate::bitboard::{BitBoard, EMPTY};
 use crate::board::Board;
+use crate::capture_rules::CaptureRules;
+use crate::castle_rights::{castle_move, castle_squares, CastleRights};
+use crate::chess_move::ChessMove;
 use crate::color::Color;
 use crate::movegen::{MoveList, SquareAndBitBoard};
 use crate::piece::Piece;
 use crate::square::Square;
 
 use crate::magic::{
-    get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_pawn_moves, get_rook_moves,
+    between, get_adjacent_files, get_bishop_moves, get_king_moves, get_knight_moves,
+    get_pawn_attacks, get_pawn_moves, get_queen_moves, get_rank, get_rook_moves, line,
 };
 
 pub trait PieceType {
     fn is(piece: Piece) -> bool;
     fn into_piece() -> Piece;
     #[inline(always)]
-    fn captures(src: Square, color: Color, combined: BitBoard, board: &Board) -> BitBoard
+    fn captures<R>(src: Square, color: Color, combined: BitBoard, board: &Board) -> BitBoard
+    where
+        R: CaptureRules,
     {
-        (
-            (get_bishop_moves(src, combined) & (board.pieces(Piece::Bishop) | board.pieces(Piece::Queen)))
-            | (get_rook_moves(src, combined) & (board.pieces(Piece::Rook) | board.pieces(Piece::Queen)))
-            | (get_pawn_attacks(src, color, *board.pieces(Piece::Pawn)))
-            | (get_knight_moves(src) & board.pieces(Piece::Knight))
-        ) & board.color_combined(!color)
+        R::capture_targets(board, src, Self::into_piece(), color, combined)
     }
     fn pseudo_legals(src: Square, color: Color, combined: BitBoard, mask: BitBoard) -> BitBoard;
     #[inline(always)]
-    fn legals<T>(movelist: &mut MoveList, board: &Board, mask: BitBoard)
+    fn legals<T, R>(movelist: &mut MoveList, board: &Board, mask: BitBoard)
     where
         T: CheckType,
+        R: CaptureRules,
     {
         let combined = board.combined();
         let color = board.side_to_move();
-        let pieces = board.pieces(Self::into_piece()) & board.color_combined(color);
+        let ksq = board.king_square(color);
+        let pieces = board.piece_squares(Self::into_piece(), color);
+        let pinned = board.pinned();
         let checkers = board.checkers();
 
-        if T::IN_CHECK {
-            for src in pieces {
-                let moves = (Self::pseudo_legals(src, color, *combined, mask) | Self::captures(src, color, *combined, &board)) & checkers;
-                if moves != EMPTY {
-                    unsafe {
-                        movelist.push_unchecked(SquareAndBitBoard::new(src, moves, false));
-                    }
+        let check_mask = if T::IN_CHECK {
+            // in check, so there is a checker
+            between(checkers.to_square_unchecked(), ksq) ^ checkers
+        } else {
+            !EMPTY
+        };
+
+        let mut rest = pieces & !pinned;
+        while let Some((src, others)) = rest.split_first() {
+            rest = others;
+            let moves = (Self::pseudo_legals(src, color, *combined, mask)
+                | Self::captures::<R>(src, color, *combined, &board))
+                & check_mask;
+            if moves != EMPTY {
+                unsafe {
+                    movelist.push_unchecked(SquareAndBitBoard::new(src, moves, false));
                 }
             }
-        } else {
-            for src in pieces {
-                let moves = Self::pseudo_legals(src, color, *combined, mask) | Self::captures(src, color, *combined, &board);
+        }
+
+        // a pinned piece can only move along the pin, which never gets me out of check
+        if !T::IN_CHECK {
+            let mut rest = pieces & pinned;
+            while let Some((src, others)) = rest.split_first() {
+                rest = others;
+                let moves = (Self::pseudo_legals(src, color, *combined, mask)
+                    | Self::captures::<R>(src, color, *combined, &board))
+                    & line(src, ksq);
                 if moves != EMPTY {
                     unsafe {
                         movelist.push_unchecked(SquareAndBitBoard::new(src, moves, false));
@@ -77,6 +97,48 @@ impl CheckType for NotInCheckType {
     const IN_CHECK: bool = false;
 }
 
+impl PawnType {
+    /// Is a particular en passant capture legal?  It removes two pieces from the same rank, so it
+    /// can uncover an attack on my king that a pin would not catch.
+    fn legal_ep_move(board: &Board, source: Square, dest: Square) -> bool {
+        let color = board.side_to_move();
+        let ksq = board.king_square(color);
+        let captured = BitBoard::from_square(board.en_passant().unwrap());
+        let combined = board.combined()
+            ^ captured
+            ^ BitBoard::from_square(source)
+            ^ BitBoard::from_square(dest);
+        let them = board.color_combined(!color) & !captured;
+
+        let bishops = (board.pieces(Piece::Bishop) | board.pieces(Piece::Queen)) & them;
+        let rooks = (board.pieces(Piece::Rook) | board.pieces(Piece::Queen)) & them;
+        (get_bishop_moves(ksq, combined) & bishops)
+            | (get_rook_moves(ksq, combined) & rooks)
+            | (get_knight_moves(ksq) & board.pieces(Piece::Knight) & them)
+            | get_pawn_attacks(ksq, color, board.pieces(Piece::Pawn) & them)
+            == EMPTY
+    }
+
+    // Add the moves of the pawn on `src`, keeping the promotions separate.
+    #[inline(always)]
+    fn push_moves(movelist: &mut MoveList, src: Square, color: Color, moves: BitBoard) {
+        let promotions = color.to_promotion_board();
+        let normal_moves = moves & !promotions;
+        let promotion_moves = moves & promotions;
+
+        if normal_moves != EMPTY {
+            unsafe {
+                movelist.push_unchecked(SquareAndBitBoard::new(src, normal_moves, false));
+            }
+        }
+        if promotion_moves != EMPTY {
+            unsafe {
+                movelist.push_unchecked(SquareAndBitBoard::new(src, promotion_moves, true));
+            }
+        }
+    }
+}
+
 impl PieceType for PawnType {
     fn is(piece: Piece) -> bool {
         piece == Piece::Pawn
@@ -92,66 +154,57 @@ impl PieceType for PawnType {
     }
 
     #[inline(always)]
-    fn legals<T>(movelist: &mut MoveList, board: &Board, mask: BitBoard)
+    fn legals<T, R>(movelist: &mut MoveList, board: &Board, mask: BitBoard)
     where
         T: CheckType,
+        R: CaptureRules,
     {
         let combined = board.combined();
         let color = board.side_to_move();
-        let pieces = board.pieces(Self::into_piece()) & board.color_combined(color);
+        let ksq = board.king_square(color);
+        let pieces = board.piece_squares(Self::into_piece(), color);
+        let pinned = board.pinned();
         let checkers = board.checkers();
 
-        if T::IN_CHECK {
-            for src in pieces {
-                let moves = (Self::pseudo_legals(src, color, *combined, mask) | Self::captures(src, color, *combined, &board)) & checkers;
-                let promotions = color.to_promotion_board();
-                let normal_moves = moves & !promotions;
-                let promotion_moves = moves & promotions;
+        let check_mask = if T::IN_CHECK {
+            // in check, so there is a checker
+            between(checkers.to_square_unchecked(), ksq) ^ checkers
+        } else {
+            !EMPTY
+        };
+
+        let mut rest = pieces & !pinned;
+        while let Some((src, others)) = rest.split_first() {
+            rest = others;
+            let moves = (Self::pseudo_legals(src, color, *combined, mask)
+                | Self::captures::<R>(src, color, *combined, &board))
+                & check_mask;
+            PawnType::push_moves(movelist, src, color, moves);
+        }
 
-                if normal_moves != EMPTY {
-                    unsafe {
-                        movelist.push_unchecked(SquareAndBitBoard::new(
-                            src,
-                            normal_moves,
-                            false,
-                        ));
-                    }
-                }
-                if promotion_moves != EMPTY {
-                    unsafe {
-                        movelist.push_unchecked(SquareAndBitBoard::new(
-                            src,
-                            promotion_moves,
-                            true,
-                        ));
-                    }
-                }
+        if !T::IN_CHECK {
+            let mut rest = pieces & pinned;
+            while let Some((src, others)) = rest.split_first() {
+                rest = others;
+                let moves = (Self::pseudo_legals(src, color, *combined, mask)
+                    | Self::captures::<R>(src, color, *combined, &board))
+                    & line(src, ksq);
+                PawnType::push_moves(movelist, src, color, moves);
             }
-        } else {
-            for src in pieces {
-                let moves = Self::pseudo_legals(src, color, *combined, mask) | Self::captures(src, color, *combined, &board);
-                let promotions = color.to_promotion_board();
-                let normal_moves = moves & !promotions;
-                let promotion_moves = moves & promotions;
+        }
 
-                if normal_moves != EMPTY {
+        if let Some(ep) = board.en_passant() {
+            let dest = ep.uforward(color);
+            for src in get_rank(ep.get_rank()) & get_adjacent_files(ep.get_file()) & pieces {
+                if PawnType::legal_ep_move(board, src, dest) {
                     unsafe {
                         movelist.push_unchecked(SquareAndBitBoard::new(
                             src,
-                            normal_moves,
+                            BitBoard::from_square(dest),
                             false,
                         ));
                     }
                 }
-                if promotion_moves != EMPTY {
-                    unsafe {
-                        movelist.push_unchecked(SquareAndBitBoard::new(
-                            src,
-                            promotion_moves,
-                            true,
-                        ));
-                    }
-                }
             }
         }
     }
@@ -213,15 +266,72 @@ impl PieceType for QueenType {
 
     #[inline(always)]
     fn pseudo_legals(src: Square, _color: Color, combined: BitBoard, mask: BitBoard) -> BitBoard {
-        (get_rook_moves(src, combined) ^ get_bishop_moves(src, combined)) & mask
+        get_queen_moves(src, combined) & mask
     }
 }
 
 impl KingType {
     /// Is a particular king move legal?
+    ///
+    /// `dest` is safe if no enemy piece could capture my king there.  In `Variant::Uno` a piece
+    /// may only capture a piece that attacks it, and a king only attacks the squares next to it,
+    /// so only an enemy piece next to `dest` matters.  That holds for a king capturing a piece
+    /// from a distance too: the square it lands on may be defended from afar, but not from next
+    /// door.
     #[inline(always)]
     pub fn legal_king_move(board: &Board, dest: Square) -> bool {
-        get_king_moves(dest) & board.color_combined(!board.side_to_move()) == EMPTY
+        let color = board.side_to_move();
+        // take my king off the board, so it does not block the sliders attacking it
+        let combined = (board.combined() ^ BitBoard::from_square(board.king_square(color)))
+            | BitBoard::from_square(dest);
+        !board.is_attacked_with(dest, !color, combined)
+    }
+
+    /// Are my king and a rook of mine on my back rank, with every square either of them crosses
+    /// or lands on when they castle empty, apart from their own?  Then I may castle with that
+    /// rook, as long as I have the rights and my king is safe on its way.  As in Chess960, the
+    /// paths of the king and the rook may overlap, and either of them may not move at all.
+    #[inline(always)]
+    pub fn castle_path_clear(board: &Board, rook: Square) -> bool {
+        let color = board.side_to_move();
+        let ksq = board.king_square(color);
+        let rooks = board.piece_squares(Piece::Rook, color);
+        if ksq.get_rank() != color.back_rank() || rooks & BitBoard::from_square(rook) == EMPTY {
+            return false;
+        }
+        let (king_dest, rook_dest) = castle_squares(ksq, rook);
+        let path = between(ksq, king_dest)
+            | BitBoard::from_square(king_dest)
+            | between(rook, rook_dest)
+            | BitBoard::from_square(rook_dest);
+        let others = board.combined() ^ BitBoard::from_square(ksq) ^ BitBoard::from_square(rook);
+        path & others == EMPTY
+    }
+
+    /// The castle with my rook on `rook`, if I may castle with it here.  The rook and the path
+    /// have to pass `castle_path_clear`, and no enemy piece may be able to capture my king on a
+    /// square it crosses or lands on.  The square it lands on is checked with the rook already
+    /// moved, which matters in Chess960 when the rook was shielding it.  Whether my king is in
+    /// check where it starts is up to the caller.
+    #[inline(always)]
+    pub fn castle(board: &Board, rook: Square) -> Option<ChessMove> {
+        if !KingType::castle_path_clear(board, rook) {
+            return None;
+        }
+        let color = board.side_to_move();
+        let ksq = board.king_square(color);
+        let (king_dest, rook_dest) = castle_squares(ksq, rook);
+        if between(ksq, king_dest).any(|sq| !KingType::legal_king_move(board, sq)) {
+            return None;
+        }
+        let combined =
+            (board.combined() ^ BitBoard::from_square(ksq) ^ BitBoard::from_square(rook))
+                | BitBoard::from_square(king_dest)
+                | BitBoard::from_square(rook_dest);
+        if board.is_attacked_with(king_dest, !color, combined) {
+            return None;
+        }
+        Some(castle_move(ksq, rook))
     }
 }
 
@@ -240,15 +350,20 @@ impl PieceType for KingType {
     }
 
     #[inline(always)]
-    fn legals<T>(movelist: &mut MoveList, board: &Board, mask: BitBoard)
+    fn legals<T, R>(movelist: &mut MoveList, board: &Board, mask: BitBoard)
     where
         T: CheckType,
+        R: CaptureRules,
     {
         let combined = board.combined();
         let color = board.side_to_move();
         let ksq = board.king_square(color);
 
-        let mut moves = Self::pseudo_legals(ksq, color, *combined, mask) | Self::captures(ksq, color, *combined, &board);
+        // My king captures like every other piece.  Under the uno rules that is any piece that
+        // attacks it, however far away, and `legal_king_move` then makes sure it does not land
+        // next to an enemy piece.
+        let mut moves = Self::pseudo_legals(ksq, color, *combined, mask)
+            | Self::captures::<R>(ksq, color, *combined, &board);
 
         let copy = moves;
         for dest in copy {
@@ -260,33 +375,25 @@ impl PieceType for KingType {
         // If we are not in check, we may be able to castle.
         // We can do so iff:
         //  * the `Board` structure says we can.
-        //  * the squares between my king and my rook are empty.
-        //  * no enemy pieces are attacking the squares between the king, and the kings
-        //    destination square.
-        //  ** This is determined by going to the left or right, and calling
-        //     'legal_king_move' for that square.
+        //  * my king and rook are on my back rank, because an edited position can have castle
+        //    rights without them.
+        //  * the squares my king and my rook cross and land on are empty, apart from the king
+        //    and the rook.
+        //  * no enemy piece could capture my king on the squares it crosses and lands on.
+        //  ** This is determined by calling 'legal_king_move' for those squares, so it follows
+        //     the capture rules of the variant just like every other king move.
+        // A castle onto my own rook is never one of the other king moves, so it can be added.
         if !T::IN_CHECK {
-            if board.my_castle_rights().has_kingside()
-                && (combined & board.my_castle_rights().kingside_squares(color)) == EMPTY
-            {
-                let middle = ksq.uright();
-                let right = middle.uright();
-                if KingType::legal_king_move(board, middle)
-                    && KingType::legal_king_move(board, right)
-                {
-                    moves ^= BitBoard::from_square(right);
-                }
-            }
-
-            if board.my_castle_rights().has_queenside()
-                && (combined & board.my_castle_rights().queenside_squares(color)) == EMPTY
-            {
-                let middle = ksq.uleft();
-                let left = middle.uleft();
-                if KingType::legal_king_move(board, middle)
-                    && KingType::legal_king_move(board, left)
+            let rights = board.my_castle_rights();
+            if rights != CastleRights::NoRights {
+                let backrank = color.back_rank();
+                for file in [rights.kingside_rook(), rights.queenside_rook()]
+                    .iter()
+                    .flatten()
                 {
-                    moves ^= BitBoard::from_square(left);
+                    if let Some(m) = KingType::castle(board, Square::make_square(backrank, *file)) {
+                        moves |= BitBoard::from_square(m.get_dest());
+                    }
                 }
             }
         }
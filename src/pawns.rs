@@ -0,0 +1,442 @@
+//! The pawn structure of a position.
+//!
+//! `PawnStructure::analyze` finds every kind of pawn below, for both sides, in one pass over the
+//! pawn bitboards.  The functions next to it answer the same questions for a single pawn, one at
+//! a time, for when only one is wanted.
+//!
+//! Only the pawns go into a `PawnStructure`, so it can be kept in a `CacheTable` under
+//! `Board::pawn_hash`, which changes exactly when the pawns do.  The kinds of pawns are the
+//! usual ones, from where the pawns stand, and so the same in every variant.
+//!
+//! ```
+//! use chess::pawns::PawnStructure;
+//! use chess::{BitBoard, Board, CacheTable, Color, Square};
+//! use std::str::FromStr;
+//!
+//! let board = Board::from_str("4k3/6p1/8/1P6/8/8/6PP/4K3 w - - 0 1").expect("Valid FEN");
+//! let mut table = CacheTable::new(1024, PawnStructure::default());
+//!
+//! let pawns = match table.get(board.pawn_hash()) {
+//!     Some(pawns) => pawns,
+//!     None => {
+//!         let pawns = PawnStructure::analyze(&board);
+//!         table.add(board.pawn_hash(), pawns);
+//!         pawns
+//!     }
+//! };
+//! assert_eq!(pawns.passed(Color::White), BitBoard::from_square(Square::B5));
+//! assert_eq!(pawns.islands(Color::White), 2);
+//! ```
+
+use crate::bitboard::{BitBoard, EMPTY};
+use crate::board::Board;
+use crate::color::{Color, ALL_COLORS, NUM_COLORS};
+use crate::file::File;
+use crate::magic::{get_adjacent_files, get_file, get_pawn_attacks, get_rank};
+use crate::piece::Piece;
+use crate::rank::Rank;
+use crate::square::Square;
+
+const FILE_A: u64 = File::ALL_BB[0].0;
+const FILE_H: u64 = File::ALL_BB[7].0;
+
+/// The kinds of pawns of both sides, and the files they leave open.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Hash)]
+pub struct PawnStructure {
+    passed: [BitBoard; NUM_COLORS],
+    isolated: [BitBoard; NUM_COLORS],
+    doubled: [BitBoard; NUM_COLORS],
+    backward: [BitBoard; NUM_COLORS],
+    connected: [BitBoard; NUM_COLORS],
+    half_open_files: [u8; NUM_COLORS],
+    open_files: u8,
+    islands: [u32; NUM_COLORS],
+}
+
+impl PawnStructure {
+    /// Find the pawn structure of `board`.
+    pub fn analyze(board: &Board) -> PawnStructure {
+        let mut result = PawnStructure::default();
+        let pawns = board.pieces(Piece::Pawn);
+        let files = |color: Color| file_mask((pawns & board.color_combined(color)).0);
+        result.open_files = !(files(Color::White) | files(Color::Black));
+
+        for color in ALL_COLORS.iter() {
+            let i = color.to_index();
+            let ours = (pawns & board.color_combined(*color)).0;
+            let theirs = (pawns & board.color_combined(!*color)).0;
+            let forward = |b: u64| step(b, *color);
+            let backward = |b: u64| step(b, !*color);
+            let sides = |b: u64| west(b) | east(b);
+
+            // the squares ahead of their pawns, and the ones they attack on the way there
+            let their_front = fill(backward(theirs), !*color);
+            let their_attacks = sides(backward(theirs));
+            let neighbours = ours & sides(fill(ours, Color::White) | fill(ours, Color::Black));
+            let supportable = ours & sides(fill(ours, *color));
+
+            result.passed[i] = BitBoard(ours & !(their_front | sides(their_front)));
+            result.isolated[i] = BitBoard(ours & !neighbours);
+            result.doubled[i] =
+                BitBoard(ours & (fill(forward(ours), *color) | fill(backward(ours), !*color)));
+            result.backward[i] =
+                BitBoard(neighbours & !supportable & backward(their_attacks | theirs));
+            result.connected[i] = BitBoard(ours & (sides(ours) | sides(forward(ours))));
+            result.half_open_files[i] = !files(*color) & files(!*color);
+            result.islands[i] = (files(*color) & !(files(*color) << 1)).count_ones();
+        }
+        result
+    }
+
+    /// The pawns of `color` with no pawn of the other side ahead of them, on their own file or
+    /// the files next to it.
+    pub fn passed(&self, color: Color) -> BitBoard {
+        self.passed[color.to_index()]
+    }
+
+    /// The pawns of `color` with no pawn of their own side on the files next to them.
+    pub fn isolated(&self, color: Color) -> BitBoard {
+        self.isolated[color.to_index()]
+    }
+
+    /// The pawns of `color` that share their file with another pawn of their own side.
+    pub fn doubled(&self, color: Color) -> BitBoard {
+        self.doubled[color.to_index()]
+    }
+
+    /// The pawns of `color` that have pawns of their own side on the files next to them, but all
+    /// further ahead, and whose square ahead is attacked or taken by a pawn of the other side.
+    /// No pawn can come up to defend them without the one in front being taken.
+    pub fn backward(&self, color: Color) -> BitBoard {
+        self.backward[color.to_index()]
+    }
+
+    /// The pawns of `color` that stand next to a pawn of their own side, or are defended by one.
+    pub fn connected(&self, color: Color) -> BitBoard {
+        self.connected[color.to_index()]
+    }
+
+    /// The files, as a bit for each from the a file up, with pawns of the other side but none of
+    /// `color`.
+    pub fn half_open_files(&self, color: Color) -> u8 {
+        self.half_open_files[color.to_index()]
+    }
+
+    /// The files, as a bit for each from the a file up, with no pawns at all.
+    pub fn open_files(&self) -> u8 {
+        self.open_files
+    }
+
+    /// The number of groups of pawns of `color` on files next to each other.
+    pub fn islands(&self, color: Color) -> u32 {
+        self.islands[color.to_index()]
+    }
+}
+
+/// Is the pawn on `square` passed?  See `PawnStructure::passed`.  This is `false` if there is
+/// no pawn on `square`.
+///
+/// ```
+/// use chess::pawns::is_passed;
+/// use chess::{Board, Square};
+/// use std::str::FromStr;
+///
+/// let board = Board::from_str("4k3/6p1/8/1P6/8/8/6PP/4K3 w - - 0 1").expect("Valid FEN");
+/// assert!(is_passed(&board, Square::B5));
+/// assert!(!is_passed(&board, Square::H2));
+/// ```
+pub fn is_passed(board: &Board, square: Square) -> bool {
+    match pawn_on(board, square) {
+        Some(color) => (their_pawns(board, color)
+            & (get_file(square.get_file()) | get_adjacent_files(square.get_file())))
+        .all(|sq| !ahead(sq, square, color)),
+        None => false,
+    }
+}
+
+/// Is the pawn on `square` isolated?  See `PawnStructure::isolated`.  This is `false` if there
+/// is no pawn on `square`.
+pub fn is_isolated(board: &Board, square: Square) -> bool {
+    match pawn_on(board, square) {
+        Some(color) => our_pawns(board, color) & get_adjacent_files(square.get_file()) == EMPTY,
+        None => false,
+    }
+}
+
+/// Is the pawn on `square` doubled?  See `PawnStructure::doubled`.  This is `false` if there is
+/// no pawn on `square`.
+pub fn is_doubled(board: &Board, square: Square) -> bool {
+    match pawn_on(board, square) {
+        Some(color) => (our_pawns(board, color) & get_file(square.get_file())).popcnt() > 1,
+        None => false,
+    }
+}
+
+/// Is the pawn on `square` backward?  See `PawnStructure::backward`.  This is `false` if there
+/// is no pawn on `square`.
+pub fn is_backward(board: &Board, square: Square) -> bool {
+    let color = match pawn_on(board, square) {
+        Some(color) => color,
+        None => return false,
+    };
+    let neighbours = our_pawns(board, color) & get_adjacent_files(square.get_file());
+    let stop = match square.forward(color) {
+        Some(stop) => stop,
+        None => return false,
+    };
+    neighbours != EMPTY
+        && neighbours.into_iter().all(|sq| ahead(sq, square, color))
+        && their_pawns(board, color).any(|sq| {
+            sq == stop || get_pawn_attacks(sq, !color, BitBoard::from_square(stop)) != EMPTY
+        })
+}
+
+/// Is the pawn on `square` connected?  See `PawnStructure::connected`.  This is `false` if there
+/// is no pawn on `square`.
+pub fn is_connected(board: &Board, square: Square) -> bool {
+    match pawn_on(board, square) {
+        Some(color) => {
+            let beside = get_adjacent_files(square.get_file()) & get_rank(square.get_rank());
+            our_pawns(board, color).any(|sq| {
+                beside & BitBoard::from_square(sq) != EMPTY
+                    || get_pawn_attacks(sq, color, BitBoard::from_square(square)) != EMPTY
+            })
+        }
+        None => false,
+    }
+}
+
+// the color of the pawn on `square`, if there is one
+fn pawn_on(board: &Board, square: Square) -> Option<Color> {
+    if board.piece_on(square) == Some(Piece::Pawn) {
+        board.color_on(square)
+    } else {
+        None
+    }
+}
+
+fn our_pawns(board: &Board, color: Color) -> BitBoard {
+    board.pieces(Piece::Pawn) & board.color_combined(color)
+}
+
+fn their_pawns(board: &Board, color: Color) -> BitBoard {
+    board.pieces(Piece::Pawn) & board.color_combined(!color)
+}
+
+// is `square` further up the board than `from`, as `color` sees it?
+fn ahead(square: Square, from: Square, color: Color) -> bool {
+    match color {
+        Color::White => square.get_rank() > from.get_rank(),
+        Color::Black => square.get_rank() < from.get_rank(),
+    }
+}
+
+// `b` moved one rank ahead, as `color` sees it
+fn step(b: u64, color: Color) -> u64 {
+    match color {
+        Color::White => b << 8,
+        Color::Black => b >> 8,
+    }
+}
+
+// `b` and every square ahead of it, as `color` sees it
+fn fill(mut b: u64, color: Color) -> u64 {
+    for shift in [8, 16, 32].iter() {
+        b |= match color {
+            Color::White => b << shift,
+            Color::Black => b >> shift,
+        };
+    }
+    b
+}
+
+fn west(b: u64) -> u64 {
+    (b >> 1) & !FILE_H
+}
+
+fn east(b: u64) -> u64 {
+    (b << 1) & !FILE_A
+}
+
+// a bit for each file with a square in `b`
+fn file_mask(b: u64) -> u8 {
+    (fill(b, Color::Black) & 0xFF) as u8
+}
+
+// the squares the pawns of `color` in `pawns` attack
+pub(crate) fn pawn_attacks(pawns: BitBoard, color: Color) -> BitBoard {
+    let ahead = step(pawns.0, color);
+    BitBoard(west(ahead) | east(ahead))
+}
+
+// the squares the pawns of `color` in `pawns` attack, now or after moving up any number of ranks
+pub(crate) fn pawn_attack_span(pawns: BitBoard, color: Color) -> BitBoard {
+    let span = fill(step(pawns.0, color), color);
+    BitBoard(west(span) | east(span))
+}
+
+// the four ranks on the side of `color`
+pub(crate) fn camp(color: Color) -> BitBoard {
+    let ranks = match color {
+        Color::White => &Rank::ALL_BB[..4],
+        Color::Black => &Rank::ALL_BB[4..],
+    };
+    ranks.iter().fold(EMPTY, |camp, rank| camp | *rank)
+}
+
+#[cfg(test)]
+use crate::square::ALL_SQUARES;
+#[cfg(test)]
+use core::str::FromStr;
+
+#[cfg(test)]
+fn squares(text: &str) -> BitBoard {
+    text.split_whitespace()
+        .map(|sq| BitBoard::from_square(Square::from_str(sq).unwrap()))
+        .fold(EMPTY, |a, b| a | b)
+}
+
+#[test]
+fn test_pawn_kinds() {
+    // (fen, color, [passed, isolated, doubled, backward, connected])
+    let cases = [
+        // a passed pawn, and one that is not since the pawn on the file next to it is ahead
+        (
+            "4k3/8/8/1P6/8/8/8/4K3 w - - 0 1",
+            Color::White,
+            ["b5", "b5", "", "", ""],
+        ),
+        (
+            "4k3/2p5/8/1P6/8/8/8/4K3 w - - 0 1",
+            Color::White,
+            ["", "b5", "", "", ""],
+        ),
+        (
+            "4k3/2p5/8/1P6/8/8/8/4K3 w - - 0 1",
+            Color::Black,
+            ["", "c7", "", "", ""],
+        ),
+        // a pawn behind the passed pawn on the file next to it does not stop it
+        (
+            "4k3/8/8/1P6/p7/8/8/4K3 w - - 0 1",
+            Color::White,
+            ["b5", "b5", "", "", ""],
+        ),
+        // doubled pawns, which are not passed behind an enemy pawn
+        (
+            "4k3/4p3/8/8/4P3/4P3/8/4K3 w - - 0 1",
+            Color::White,
+            ["", "e4 e3", "e4 e3", "", ""],
+        ),
+        // pawns next to each other and defending each other are connected, not isolated
+        (
+            "4k3/8/8/8/3PP3/5P2/8/4K3 w - - 0 1",
+            Color::White,
+            ["d4 e4 f3", "", "", "", "d4 e4"],
+        ),
+        (
+            "4k3/8/8/8/3P4/2P5/8/4K3 w - - 0 1",
+            Color::White,
+            ["d4 c3", "", "", "", "d4"],
+        ),
+        // the pawn on c3 cannot be defended and d5 attacks its square ahead
+        (
+            "4k3/8/8/3p4/1P6/2P5/8/4K3 w - - 0 1",
+            Color::White,
+            ["b4", "", "", "c3", "b4"],
+        ),
+        // but with no enemy pawn on its square ahead or beside it, it is not backward
+        (
+            "4k3/8/8/4p3/1P6/2P5/8/4K3 w - - 0 1",
+            Color::White,
+            ["b4 c3", "", "", "", "b4"],
+        ),
+        // a pawn blocked by an enemy pawn is backward too
+        (
+            "4k3/8/8/8/1Pp5/2P5/8/4K3 w - - 0 1",
+            Color::White,
+            ["b4", "", "", "c3", "b4"],
+        ),
+        (
+            "4k3/8/8/8/1Pp5/2P5/8/4K3 w - - 0 1",
+            Color::Black,
+            ["", "c4", "", "", ""],
+        ),
+    ];
+    for (fen, color, kinds) in cases.iter() {
+        let [passed, isolated, doubled, backward, connected] = kinds;
+        let board = Board::from_str(fen).unwrap();
+        let pawns = PawnStructure::analyze(&board);
+        assert_eq!(pawns.passed(*color), squares(passed), "passed {}", fen);
+        assert_eq!(
+            pawns.isolated(*color),
+            squares(isolated),
+            "isolated {}",
+            fen
+        );
+        assert_eq!(pawns.doubled(*color), squares(doubled), "doubled {}", fen);
+        assert_eq!(
+            pawns.backward(*color),
+            squares(backward),
+            "backward {}",
+            fen
+        );
+        assert_eq!(
+            pawns.connected(*color),
+            squares(connected),
+            "connected {}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn test_files_and_islands() {
+    let board = Board::from_str("4k3/pp4pp/8/8/8/8/P1P1P2P/4K3 w - - 0 1").unwrap();
+    let pawns = PawnStructure::analyze(&board);
+    // no pawns on the d or f files, white has none on b or g, and black has only the a, b, g
+    // and h files
+    assert_eq!(pawns.open_files(), 0b0010_1000);
+    assert_eq!(pawns.half_open_files(Color::White), 0b0100_0010);
+    assert_eq!(pawns.half_open_files(Color::Black), 0b0001_0100);
+    assert_eq!(pawns.islands(Color::White), 4);
+    assert_eq!(pawns.islands(Color::Black), 2);
+
+    let pawns = PawnStructure::analyze(&Board::default());
+    assert_eq!(pawns.open_files(), 0);
+    assert_eq!(pawns.half_open_files(Color::White), 0);
+    assert_eq!(pawns.islands(Color::Black), 1);
+
+    let board = Board::from_str("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let pawns = PawnStructure::analyze(&board);
+    assert_eq!(pawns.open_files(), 0xFF);
+    assert_eq!(pawns.islands(Color::White), 0);
+}
+
+#[test]
+fn test_analyze_matches_the_single_pawn_functions() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "4k3/pp1p1p1p/1p1P1P2/2P5/P3p3/1P2P1pP/2P3P1/4K3 w - - 0 1",
+        "4k3/1p1p4/2p1p3/3P4/2P1P3/1P3P2/P5P1/4K3 b - - 0 1",
+    ];
+    for fen in fens.iter() {
+        let board = Board::from_str(fen).unwrap();
+        let pawns = PawnStructure::analyze(&board);
+        for color in ALL_COLORS.iter() {
+            let matching = |test: fn(&Board, Square) -> bool| {
+                ALL_SQUARES
+                    .iter()
+                    .filter(|sq| board.color_on(**sq) == Some(*color) && test(&board, **sq))
+                    .fold(EMPTY, |a, sq| a | BitBoard::from_square(*sq))
+            };
+            assert_eq!(pawns.passed(*color), matching(is_passed), "{}", fen);
+            assert_eq!(pawns.isolated(*color), matching(is_isolated), "{}", fen);
+            assert_eq!(pawns.doubled(*color), matching(is_doubled), "{}", fen);
+            assert_eq!(pawns.backward(*color), matching(is_backward), "{}", fen);
+            assert_eq!(pawns.connected(*color), matching(is_connected), "{}", fen);
+        }
+    }
+}
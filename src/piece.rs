@@ -1,5 +1,7 @@
 use crate::color::Color;
-use std::fmt;
+use core::fmt;
+
+use alloc::string::{String, ToString};
 
 /// Represent a chess piece as a very simple enum
 #[derive(PartialEq, Eq, Ord, PartialOrd, Copy, Clone, Debug, Hash)]
@@ -25,17 +27,65 @@ pub const ALL_PIECES: [Piece; NUM_PIECES] = [
     Piece::King,
 ];
 
+/// The traditional value of each piece type in centipawns, indexed by `Piece::to_index()`.  The
+/// king has no material value.  `values::DEFAULT` is the same, as `Value`s.
+pub const PIECE_VALUES: [u32; NUM_PIECES] = [100, 300, 300, 500, 900, 0];
+
+/// The weight of each piece type attacking the squares around a king, for
+/// `Board::king_attack_units`, indexed by `Piece::to_index()`.  These are the usual units of two
+/// for a minor piece, three for a rook and five for a queen.  Pawns and the king add nothing.
+pub const KING_ATTACK_WEIGHTS: [u32; NUM_PIECES] = [0, 2, 2, 3, 5, 0];
+
+/// How much each piece type counts toward `Board::phase`, indexed by `Piece::to_index()`.  These
+/// are one for a minor piece, two for a rook and four for a queen, so that the pieces a game
+/// starts with add up to `MAX_PHASE`.
+pub const PHASE_WEIGHTS: [u8; NUM_PIECES] = [0, 1, 1, 2, 4, 0];
+
+/// The phase of a board with all the pieces a game starts with, and of no board above it.
+pub const MAX_PHASE: u8 = 24;
+
+/// How many of each piece type a side has, indexed by `Piece::to_index()`.
+pub type MaterialCount = [u8; NUM_PIECES];
+
 /// How many ways can I promote?
 pub const NUM_PROMOTION_PIECES: usize = 4;
 
 /// What pieces can I promote to?
-pub const PROMOTION_PIECES: [Piece; 4] = [Piece::Queen, Piece::Knight, Piece::Rook, Piece::Bishop];
+pub const PROMOTION_PIECES: [Piece; NUM_PROMOTION_PIECES] =
+    [Piece::Queen, Piece::Knight, Piece::Rook, Piece::Bishop];
 
 impl Piece {
+    /// Every piece type, the same as `ALL_PIECES`.
+    pub const ALL: [Piece; NUM_PIECES] = ALL_PIECES;
+
+    /// What pieces can I promote to?  The same as `PROMOTION_PIECES`.
+    pub const PROMOTION_PIECES: [Piece; NUM_PROMOTION_PIECES] = PROMOTION_PIECES;
+
     /// Convert the `Piece` to a `usize` for table lookups.
     #[inline]
-    pub fn to_index(&self) -> usize {
-        *self as usize
+    pub const fn to_index(self) -> usize {
+        self as usize
+    }
+
+    /// Convert a `usize` to a `Piece`, the inverse of `to_index`.  If i > 5, return `None`.
+    ///
+    /// ```
+    /// use chess::Piece;
+    ///
+    /// assert_eq!(Piece::from_index(1), Some(Piece::Knight));
+    /// assert_eq!(Piece::from_index(6), None);
+    /// ```
+    #[inline]
+    pub const fn from_index(i: usize) -> Option<Piece> {
+        match i {
+            0 => Some(Piece::Pawn),
+            1 => Some(Piece::Knight),
+            2 => Some(Piece::Bishop),
+            3 => Some(Piece::Rook),
+            4 => Some(Piece::Queen),
+            5 => Some(Piece::King),
+            _ => None,
+        }
     }
 
     /// Convert a piece with a color to a string.  White pieces are uppercase, black pieces are
@@ -49,28 +99,147 @@ impl Piece {
     /// ```
     #[inline]
     pub fn to_string(&self, color: Color) -> String {
-        let piece = format!("{}", self);
+        self.to_char(color).to_string()
+    }
+
+    /// Convert a piece with a color to its letter in a FEN.  White pieces are uppercase, black
+    /// pieces are lowercase.
+    ///
+    /// ```
+    /// use chess::{Piece, Color};
+    ///
+    /// assert_eq!(Piece::Pawn.to_char(Color::White), 'P');
+    /// assert_eq!(Piece::Queen.to_char(Color::Black), 'q');
+    /// ```
+    #[inline]
+    pub fn to_char(self, color: Color) -> char {
+        let piece = match self {
+            Piece::Pawn => 'p',
+            Piece::Knight => 'n',
+            Piece::Bishop => 'b',
+            Piece::Rook => 'r',
+            Piece::Queen => 'q',
+            Piece::King => 'k',
+        };
         if color == Color::White {
-            piece.to_uppercase()
+            piece.to_ascii_uppercase()
         } else {
             piece
         }
     }
+
+    /// Convert a letter from a FEN to a piece with a color.  The inverse of `Piece::to_char`.
+    ///
+    /// ```
+    /// use chess::{Piece, Color};
+    ///
+    /// assert_eq!(Piece::from_char('N'), Some((Piece::Knight, Color::White)));
+    /// assert_eq!(Piece::from_char('k'), Some((Piece::King, Color::Black)));
+    /// assert_eq!(Piece::from_char('x'), None);
+    /// ```
+    #[inline]
+    pub fn from_char(c: char) -> Option<(Piece, Color)> {
+        let piece = match c.to_ascii_lowercase() {
+            'p' => Piece::Pawn,
+            'n' => Piece::Knight,
+            'b' => Piece::Bishop,
+            'r' => Piece::Rook,
+            'q' => Piece::Queen,
+            'k' => Piece::King,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some((piece, color))
+    }
+
+    /// Convert a piece with a color to its unicode chess symbol.
+    ///
+    /// ```
+    /// use chess::{Piece, Color};
+    ///
+    /// assert_eq!(Piece::Pawn.to_unicode(Color::White), '♙');
+    /// assert_eq!(Piece::Pawn.to_unicode(Color::Black), '♟');
+    /// ```
+    #[inline]
+    pub fn to_unicode(self, color: Color) -> char {
+        match (self, color) {
+            (Piece::Pawn, Color::White) => '♙',
+            (Piece::Knight, Color::White) => '♘',
+            (Piece::Bishop, Color::White) => '♗',
+            (Piece::Rook, Color::White) => '♖',
+            (Piece::Queen, Color::White) => '♕',
+            (Piece::King, Color::White) => '♔',
+            (Piece::Pawn, Color::Black) => '♟',
+            (Piece::Knight, Color::Black) => '♞',
+            (Piece::Bishop, Color::Black) => '♝',
+            (Piece::Rook, Color::Black) => '♜',
+            (Piece::Queen, Color::Black) => '♛',
+            (Piece::King, Color::Black) => '♚',
+        }
+    }
 }
 
 impl fmt::Display for Piece {
+    /// The uppercase letter of the piece, as in SAN.
+    ///
+    /// ```
+    /// use chess::Piece;
+    ///
+    /// assert_eq!(format!("{}", Piece::Knight), "N");
+    /// ```
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match *self {
-                Piece::Pawn => "p",
-                Piece::Knight => "n",
-                Piece::Bishop => "b",
-                Piece::Rook => "r",
-                Piece::Queen => "q",
-                Piece::King => "k",
-            }
-        )
+        write!(f, "{}", self.to_char(Color::White))
+    }
+}
+
+#[cfg(test)]
+use crate::color::ALL_COLORS;
+
+#[test]
+fn test_char_round_trip() {
+    let mut letters = String::new();
+    let mut symbols = String::new();
+    for color in ALL_COLORS.iter() {
+        for piece in ALL_PIECES.iter() {
+            let c = piece.to_char(*color);
+            assert_eq!(Piece::from_char(c), Some((*piece, *color)));
+            assert_eq!(piece.to_string(*color), c.to_string());
+            letters.push(c);
+            symbols.push(piece.to_unicode(*color));
+        }
+    }
+    assert_eq!(letters, "PNBRQKpnbrqk");
+    assert_eq!(symbols, "♙♘♗♖♕♔♟♞♝♜♛♚");
+    assert_eq!(
+        ALL_PIECES
+            .iter()
+            .map(|p| format!("{}", p))
+            .collect::<String>(),
+        "PNBRQK"
+    );
+
+    for c in (0..=255u8).map(char::from) {
+        if !"PNBRQKpnbrqk".contains(c) {
+            assert_eq!(Piece::from_char(c), None);
+        }
+    }
+}
+
+#[test]
+fn test_all_pieces() {
+    for (i, piece) in Piece::ALL.iter().enumerate() {
+        assert_eq!(piece.to_index(), i);
+        assert_eq!(Piece::from_index(i), Some(*piece));
+        assert!(!Piece::ALL[..i].contains(piece));
+    }
+    assert_eq!(Piece::from_index(NUM_PIECES), None);
+
+    for (i, piece) in Piece::PROMOTION_PIECES.iter().enumerate() {
+        assert!(*piece != Piece::Pawn && *piece != Piece::King);
+        assert!(!Piece::PROMOTION_PIECES[..i].contains(piece));
     }
 }
@@ -0,0 +1,69 @@
+//! Random games for the tests.
+//!
+//! Many tests check that two ways of computing something agree, on every position of a few
+//! hundred random games.  `random_playouts` plays those games, and each test only says what
+//! to check in every position.
+
+use crate::board::Board;
+use crate::board_builder::BoardBuilder;
+use crate::chess_move::ChessMove;
+use crate::movegen::MoveGen;
+use crate::variant::Variant;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::str::FromStr;
+
+/// A small xorshift generator, so the tests do not need a dependency on `rand`.
+pub struct XorShift(u64);
+
+impl XorShift {
+    /// Start from `seed`, which must not be 0.
+    pub fn new(seed: u64) -> XorShift {
+        XorShift(seed)
+    }
+
+    /// Give me the next number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Give me one of `items`, which must not be empty.
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[(self.next_u64() % items.len() as u64) as usize]
+    }
+}
+
+/// Play `games` random games in each of `variants`, starting from the FENs in `starts` in turn,
+/// for at most `plies` moves each.  Every position on the way is given to `check` with its legal
+/// moves, before one of them is played.  A game ends early when there are none.
+pub fn random_playouts<F>(
+    starts: &[&str],
+    variants: &[Variant],
+    games: usize,
+    plies: usize,
+    mut check: F,
+) where
+    F: FnMut(&Board, &[ChessMove]),
+{
+    let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15);
+    for variant in variants.iter() {
+        for game in 0..games {
+            let mut board: Board = BoardBuilder::from_str(starts[game % starts.len()])
+                .unwrap()
+                .variant(*variant)
+                .try_into()
+                .unwrap();
+            for _ in 0..plies {
+                let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+                check(&board, &moves);
+                if moves.is_empty() {
+                    break;
+                }
+                board = board.make_move_new(rng.pick(&moves));
+            }
+        }
+    }
+}
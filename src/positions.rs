@@ -0,0 +1,571 @@
+//! Files of positions that are read without parsing, behind the `positions` feature.
+//!
+//! Every position is a `PackedBoard` of `PackedBoard::SIZE` bytes, with the hash the board had
+//! when it was written.  A file is a header, with the magic bytes `MAGIC`, the `VERSION` of the
+//! layout, the size of a position and how many there are, followed by the positions one after the
+//! other.  `Writer` and `Reader` stream a file, and `records` looks at the positions of a file
+//! that is already in memory, for example with mmap, without copying them.
+//!
+//! Nothing is checked as a position is read, so that a position that is skipped costs nothing.
+//! `PackedBoard::validate` turns it back into a `Board`, checking that it is sane and still has
+//! its hash.
+//!
+//! ```
+//! use chess::positions::{Reader, Writer};
+//! use chess::{Board, ChessMove, Square};
+//! use std::io::Cursor;
+//!
+//! let boards = [
+//!     Board::default(),
+//!     Board::default().make_move_new(ChessMove::new(Square::E2, Square::E4, None)),
+//! ];
+//! let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+//! for board in boards.iter() {
+//!     writer.write(board).unwrap();
+//! }
+//! let file = writer.finish().unwrap().into_inner();
+//!
+//! let reader = Reader::new(Cursor::new(&file)).unwrap();
+//! assert_eq!(reader.len(), 2);
+//! for (packed, board) in reader.zip(boards.iter()) {
+//!     let packed = packed.unwrap();
+//!     assert_eq!(packed.hash(), board.get_hash());
+//!     assert_eq!(packed.validate().unwrap(), *board);
+//! }
+//!
+//! let records = chess::positions::records(&file).unwrap();
+//! assert_eq!(records[1].validate().unwrap(), boards[1]);
+//! ```
+
+use crate::bitboard::BitBoard;
+use crate::board::Board;
+use crate::board_builder::BoardBuilder;
+use crate::castle_rights::{CastleRights, Castled};
+use crate::color::{Color, ALL_COLORS};
+use crate::error::Error;
+use crate::file::File;
+use crate::piece::ALL_PIECES;
+use crate::variant::ALL_VARIANTS;
+use core::convert::{TryFrom, TryInto};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// The first bytes of a file of positions.
+pub const MAGIC: [u8; 8] = *b"UNOCHESS";
+
+/// The version of the layout of `PackedBoard`, which changes whenever the layout or the hash
+/// keys do.
+pub const VERSION: u32 = 2;
+
+// the magic bytes, the version, the size of a position and how many there are
+const HEADER_SIZE: usize = 24;
+
+// where each field of a `PackedBoard` starts
+const PIECES: usize = 8;
+const CASTLE_RIGHTS: usize = 24;
+const EN_PASSANT: usize = 26;
+const FLAGS: usize = 27;
+const HASH: usize = 28;
+
+// `FLAGS` has black to move in the lowest bit, the index of the variant in `ALL_VARIANTS` in
+// the three above it, and how white and black castled in two bits each above those
+const BLACK_TO_MOVE: u8 = 1;
+const VARIANT_MASK: u8 = 7;
+const CASTLED: u32 = 4;
+
+// how a side castled, in its two bits of `FLAGS`
+const ALL_CASTLED: [Castled; 3] = [Castled::No, Castled::KingSide, Castled::QueenSide];
+
+// a missing file in the castle rights and en passant fields
+const NO_FILE: u8 = 0xF;
+
+/// A `Board` in a fixed layout of `PackedBoard::SIZE` bytes, with its hash.
+///
+/// The bytes are the occupied squares as a little-endian `u64`, then a nibble for each piece in
+/// the order of its square, low nibble first, with the piece in the low three bits and black in
+/// the fourth.  Then come the castle rooks of white and black, a byte each with the file of the
+/// kingside rook in the low nibble and the queenside rook in the high one, the file of the en
+/// passant square, a byte with the side to move, the variant and how each side castled, and
+/// the hash as a little-endian `u64`.
+///
+/// It is only bytes, so a slice of bytes can be looked at as packed boards, see `records`.
+///
+/// ```
+/// use chess::positions::PackedBoard;
+/// use chess::Board;
+///
+/// let board = Board::default();
+/// let packed = PackedBoard::from(&board);
+/// assert_eq!(packed.as_bytes().len(), PackedBoard::SIZE);
+/// assert_eq!(PackedBoard::from_bytes(*packed.as_bytes()), packed);
+/// assert_eq!(packed.validate().unwrap(), board);
+/// ```
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PackedBoard([u8; PackedBoard::SIZE]);
+
+impl PackedBoard {
+    /// How many bytes a packed board takes.
+    pub const SIZE: usize = 36;
+
+    /// Take the bytes of a packed board, which are only checked by `validate`.
+    pub fn from_bytes(bytes: [u8; PackedBoard::SIZE]) -> PackedBoard {
+        PackedBoard(bytes)
+    }
+
+    /// The bytes of the packed board.
+    pub fn as_bytes(&self) -> &[u8; PackedBoard::SIZE] {
+        &self.0
+    }
+
+    /// The hash the board had when it was packed, without unpacking it.
+    pub fn hash(&self) -> u64 {
+        u64::from_le_bytes(self.0[HASH..].try_into().unwrap())
+    }
+
+    /// Unpack the board, making sure that it is sane and that its hash is the one it was packed
+    /// with.
+    ///
+    /// ```
+    /// use chess::positions::PackedBoard;
+    /// use chess::Board;
+    ///
+    /// let mut bytes = *PackedBoard::from(&Board::default()).as_bytes();
+    /// // make the piece on a1 a king, a third one
+    /// bytes[8] = (bytes[8] & 0xF0) | 5;
+    /// assert!(PackedBoard::from_bytes(bytes).validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<Board, Error> {
+        let bytes = &self.0;
+        let occupied = BitBoard::new(u64::from_le_bytes(bytes[..PIECES].try_into().unwrap()));
+        if occupied.popcnt() > 32 {
+            return Err(Error::InvalidBoard);
+        }
+
+        let mut builder = BoardBuilder::new();
+        for (i, square) in occupied.enumerate() {
+            let nibble = (bytes[PIECES + i / 2] >> (4 * (i % 2))) & 0xF;
+            let piece = *ALL_PIECES
+                .get((nibble & 7) as usize)
+                .ok_or(Error::InvalidBoard)?;
+            let color = if nibble & 8 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            builder.piece(square, piece, color);
+        }
+
+        for color in ALL_COLORS.iter() {
+            let rooks = bytes[CASTLE_RIGHTS + color.to_index()];
+            let rights = CastleRights::new(file(rooks & 0xF)?, file(rooks >> 4)?);
+            builder.castle_rights(*color, rights);
+        }
+        builder.en_passant(file(bytes[EN_PASSANT])?);
+
+        let flags = bytes[FLAGS];
+        let side_to_move = if flags & BLACK_TO_MOVE == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let variant = *ALL_VARIANTS
+            .get((flags >> 1 & VARIANT_MASK) as usize)
+            .ok_or(Error::InvalidBoard)?;
+        builder.side_to_move(side_to_move).variant(variant);
+        for color in ALL_COLORS.iter() {
+            let castled = flags >> (CASTLED + 2 * color.to_index() as u32) & 3;
+            let castled = *ALL_CASTLED
+                .get(castled as usize)
+                .ok_or(Error::InvalidBoard)?;
+            builder.castled(*color, castled);
+        }
+
+        let board = Board::try_from(&builder)?;
+        if board.get_hash() != self.hash() {
+            return Err(Error::InvalidBoard);
+        }
+        Ok(board)
+    }
+}
+
+fn file(nibble: u8) -> Result<Option<File>, Error> {
+    match nibble {
+        0..=7 => Ok(Some(File::from_index(nibble as usize))),
+        NO_FILE => Ok(None),
+        _ => Err(Error::InvalidBoard),
+    }
+}
+
+fn file_nibble(file: Option<File>) -> u8 {
+    file.map_or(NO_FILE, |file| file.to_index() as u8)
+}
+
+impl From<&Board> for PackedBoard {
+    fn from(board: &Board) -> Self {
+        let mut bytes = [0; PackedBoard::SIZE];
+        bytes[..PIECES].copy_from_slice(&board.combined().0.to_le_bytes());
+        for (i, square) in (*board.combined()).enumerate() {
+            let piece = board.piece_on(square).unwrap().to_index() as u8;
+            let black = if board.color_on(square) == Some(Color::Black) {
+                8
+            } else {
+                0
+            };
+            bytes[PIECES + i / 2] |= (piece | black) << (4 * (i % 2));
+        }
+
+        for color in ALL_COLORS.iter() {
+            let rights = board.castle_rights(*color);
+            bytes[CASTLE_RIGHTS + color.to_index()] =
+                file_nibble(rights.kingside_rook()) | file_nibble(rights.queenside_rook()) << 4;
+        }
+        bytes[EN_PASSANT] = file_nibble(board.en_passant().map(|sq| sq.get_file()));
+
+        let variant = ALL_VARIANTS
+            .iter()
+            .position(|v| *v == board.variant())
+            .unwrap() as u8;
+        let black = (board.side_to_move() == Color::Black) as u8;
+        let castled = ALL_COLORS.iter().fold(0, |castled, color| {
+            let index = ALL_CASTLED
+                .iter()
+                .position(|c| *c == board.castled(*color))
+                .unwrap() as u8;
+            castled | index << (CASTLED + 2 * color.to_index() as u32)
+        });
+        bytes[FLAGS] = castled | variant << 1 | black;
+        bytes[HASH..].copy_from_slice(&board.get_hash().to_le_bytes());
+        PackedBoard(bytes)
+    }
+}
+
+impl From<Board> for PackedBoard {
+    fn from(board: Board) -> Self {
+        (&board).into()
+    }
+}
+
+impl TryFrom<&PackedBoard> for Board {
+    type Error = Error;
+
+    /// The same as `PackedBoard::validate`.
+    fn try_from(packed: &PackedBoard) -> Result<Self, Self::Error> {
+        packed.validate()
+    }
+}
+
+impl TryFrom<PackedBoard> for Board {
+    type Error = Error;
+
+    /// The same as `PackedBoard::validate`.
+    fn try_from(packed: PackedBoard) -> Result<Self, Self::Error> {
+        packed.validate()
+    }
+}
+
+fn header(count: u64) -> [u8; HEADER_SIZE] {
+    let mut header = [0; HEADER_SIZE];
+    header[..8].copy_from_slice(&MAGIC);
+    header[8..12].copy_from_slice(&VERSION.to_le_bytes());
+    header[12..16].copy_from_slice(&(PackedBoard::SIZE as u32).to_le_bytes());
+    header[16..].copy_from_slice(&count.to_le_bytes());
+    header
+}
+
+// how many positions follow the header, if it is one this version can read
+fn read_header(header: &[u8]) -> Result<u64, Error> {
+    if header.len() < HEADER_SIZE
+        || header[..8] != MAGIC
+        || header[8..12] != VERSION.to_le_bytes()
+        || header[12..16] != (PackedBoard::SIZE as u32).to_le_bytes()
+    {
+        return Err(Error::InvalidPositionFile);
+    }
+    Ok(u64::from_le_bytes(
+        header[16..HEADER_SIZE].try_into().unwrap(),
+    ))
+}
+
+/// Look at the positions of a whole file in memory, without copying them.
+///
+/// ```
+/// use chess::positions::{records, Writer};
+/// use chess::Board;
+/// use std::io::Cursor;
+///
+/// let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+/// writer.write(&Board::default()).unwrap();
+/// let file = writer.finish().unwrap().into_inner();
+///
+/// assert_eq!(records(&file).unwrap()[0].hash(), Board::default().get_hash());
+/// assert!(records(&file[..file.len() - 1]).is_err());
+/// assert!(records(b"not a file of positions").is_err());
+/// ```
+pub fn records(file: &[u8]) -> Result<&[PackedBoard], Error> {
+    let count = read_header(file)?;
+    let body = &file[HEADER_SIZE..];
+    if count > (body.len() / PackedBoard::SIZE) as u64 {
+        return Err(Error::InvalidPositionFile);
+    }
+    let count = count as usize;
+    // a `PackedBoard` is only bytes, so any bytes are one, and it is aligned like a byte
+    Ok(unsafe { core::slice::from_raw_parts(body.as_ptr() as *const PackedBoard, count) })
+}
+
+/// Write a file of positions.  The header is written first, and gets the number of positions
+/// once the writer is finished, so the file is not complete until then.
+pub struct Writer<W: Write + Seek> {
+    inner: W,
+    start: u64,
+    count: u64,
+}
+
+impl<W: Write + Seek> Writer<W> {
+    /// Start a file of positions where `inner` is now.
+    pub fn new(mut inner: W) -> io::Result<Writer<W>> {
+        let start = inner.stream_position()?;
+        inner.write_all(&header(0))?;
+        Ok(Writer {
+            inner,
+            start,
+            count: 0,
+        })
+    }
+
+    /// Add a position.
+    pub fn write(&mut self, board: &Board) -> io::Result<()> {
+        self.write_packed(&board.into())
+    }
+
+    /// Add a position that is already packed.
+    pub fn write_packed(&mut self, packed: &PackedBoard) -> io::Result<()> {
+        self.inner.write_all(packed.as_bytes())?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Write the number of positions into the header, and give back `inner`, which is left at the
+    /// end of the file.
+    pub fn finish(mut self) -> io::Result<W> {
+        let end = self.inner.stream_position()?;
+        self.inner.seek(SeekFrom::Start(self.start))?;
+        self.inner.write_all(&header(self.count))?;
+        self.inner.seek(SeekFrom::Start(end))?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Read a file of positions, as an iterator of `PackedBoard`s.
+///
+/// A file that ends early gives one error where the missing positions would have been, and then
+/// nothing.  A file that is not one of positions of this `VERSION` is an `InvalidData` error
+/// from `Reader::new`.
+pub struct Reader<R: Read> {
+    inner: R,
+    count: u64,
+    remaining: u64,
+}
+
+impl<R: Read> Reader<R> {
+    /// Read the header of a file of positions.
+    pub fn new(mut inner: R) -> io::Result<Reader<R>> {
+        let mut header = [0; HEADER_SIZE];
+        inner.read_exact(&mut header)?;
+        let count = read_header(&header)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Ok(Reader {
+            inner,
+            count,
+            remaining: count,
+        })
+    }
+
+    /// How many positions the file has, from its header.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    /// Does the file have no positions?
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = io::Result<PackedBoard>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let mut bytes = [0; PackedBoard::SIZE];
+        match self.inner.read_exact(&mut bytes) {
+            Ok(()) => {
+                self.remaining -= 1;
+                Some(Ok(PackedBoard(bytes)))
+            }
+            Err(error) => {
+                self.remaining = 0;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::chess_move::ChessMove;
+#[cfg(test)]
+use crate::movegen::MoveGen;
+#[cfg(test)]
+use core::str::FromStr;
+#[cfg(test)]
+use std::io::Cursor;
+
+// every position of a few random games from each start, in both variants
+#[cfg(test)]
+fn positions() -> Vec<Board> {
+    let starts = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        "n1n5/PPP5/8/2k5/8/8/5ppp/K4N1N b - - 0 1",
+    ];
+    let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut boards = Vec::new();
+    for fen in starts.iter() {
+        for variant in ALL_VARIANTS.iter() {
+            for _ in 0..10 {
+                let mut builder = BoardBuilder::from_str(fen).unwrap();
+                let mut board = Board::try_from(builder.variant(*variant)).unwrap();
+                for _ in 0..80 {
+                    boards.push(board);
+                    let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+                    if moves.is_empty() {
+                        break;
+                    }
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    board = board.make_move_new(moves[(seed % moves.len() as u64) as usize]);
+                }
+            }
+        }
+    }
+    boards
+}
+
+#[test]
+fn test_packed_board_round_trip() {
+    let boards = positions();
+    assert!(boards.iter().any(|board| board.en_passant().is_some()));
+    assert!(boards
+        .iter()
+        .any(|board| board.castled(Color::White) != Castled::No));
+    for board in boards.iter() {
+        let packed = PackedBoard::from(board);
+        assert_eq!(packed.hash(), board.get_hash());
+        assert_eq!(packed.validate().unwrap(), *board);
+        for color in ALL_COLORS.iter() {
+            let castled = packed.validate().unwrap().castled(*color);
+            assert_eq!(castled, board.castled(*color));
+        }
+        assert_eq!(
+            Board::try_from(packed).unwrap().to_tagged_fen(),
+            board.to_tagged_fen()
+        );
+    }
+}
+
+#[test]
+fn test_file_round_trip() {
+    let boards = positions();
+    let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+    for board in boards.iter() {
+        writer.write(board).unwrap();
+    }
+    let file = writer.finish().unwrap().into_inner();
+    assert_eq!(file.len(), HEADER_SIZE + boards.len() * PackedBoard::SIZE);
+
+    let reader = Reader::new(Cursor::new(&file)).unwrap();
+    assert_eq!(reader.len(), boards.len() as u64);
+    let read: Vec<Board> = reader
+        .map(|packed| packed.unwrap().validate().unwrap())
+        .collect();
+    assert_eq!(read, boards);
+
+    let packed = records(&file).unwrap();
+    assert_eq!(packed.len(), boards.len());
+    for (packed, board) in packed.iter().zip(boards.iter()) {
+        assert_eq!(packed.validate().unwrap(), *board);
+    }
+
+    // a file that ends early
+    let short = &file[..file.len() - 1];
+    let results: Vec<_> = Reader::new(Cursor::new(short)).unwrap().collect();
+    assert_eq!(results.len(), boards.len());
+    assert!(results.last().unwrap().is_err());
+    assert!(records(short).is_err());
+
+    // a file of another version
+    let mut other = file.clone();
+    other[8] += 1;
+    assert!(Reader::new(Cursor::new(&other)).is_err());
+    assert!(records(&other).is_err());
+}
+
+#[test]
+fn test_validate_rejects_bad_bytes() {
+    let packed = PackedBoard::from(&Board::default());
+    let with = |at: usize, byte: u8| {
+        let mut bytes = *packed.as_bytes();
+        bytes[at] = byte;
+        PackedBoard::from_bytes(bytes).validate()
+    };
+    // a seventh kind of piece, a file past h, a variant that does not exist
+    assert!(with(PIECES, 0x06).is_err());
+    assert!(with(CASTLE_RIGHTS, 0x08).is_err());
+    assert!(with(EN_PASSANT, 0x09).is_err());
+    assert!(with(FLAGS, 0x04).is_err());
+    assert!(with(FLAGS, 0x30).is_err());
+    // a hash the position does not have
+    assert!(with(HASH, packed.as_bytes()[HASH] ^ 1).is_err());
+
+    // the pieces of the start position as other pieces, by their index among the 32 squares
+    // with a piece on them, with the nibble of the new piece
+    let pieces = |changes: &[(usize, u8)]| {
+        let mut bytes = *packed.as_bytes();
+        for (i, nibble) in changes.iter() {
+            let shift = 4 * (i % 2);
+            bytes[PIECES + i / 2] = (bytes[PIECES + i / 2] & !(0xF << shift)) | (nibble << shift);
+        }
+        PackedBoard::from_bytes(bytes).validate()
+    };
+    const WHITE_PAWN: u8 = 0;
+    const WHITE_QUEEN: u8 = 4;
+    const BLACK_PAWN: u8 = 8;
+    // the white king on e1 is a queen, so white has no king
+    assert!(matches!(
+        pieces(&[(4, WHITE_QUEEN)]),
+        Err(Error::InvalidKingCount)
+    ));
+    // the black pawn on b7 is white, so white has seventeen pieces
+    assert!(matches!(
+        pieces(&[(17, WHITE_PAWN)]),
+        Err(Error::TooManyPieces)
+    ));
+    // and the white knight on b1 is a black pawn, on the rank it promotes on
+    assert!(matches!(
+        pieces(&[(17, WHITE_PAWN), (1, BLACK_PAWN)]),
+        Err(Error::PawnOnBackRank)
+    ));
+    // the black pawn on d7 is white, and checks the black king, with white to move
+    assert!(matches!(
+        pieces(&[(19, WHITE_PAWN), (8, BLACK_PAWN)]),
+        Err(Error::OpponentInCheck)
+    ));
+    // too many pieces
+    let mut bytes = *packed.as_bytes();
+    bytes[..PIECES].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(PackedBoard::from_bytes(bytes).validate().is_err());
+}
@@ -0,0 +1,251 @@
+//! Random positions, behind the `rand` feature.
+//!
+//! `Board::random` places the kings first, on squares that do not touch, and then every other
+//! piece on a random square where the position stays valid.  Pawns never go on the first or the
+//! eighth rank.  If no square works for a piece, for example because its side already has sixteen
+//! pieces, that piece is left out, so the lower end of a count is not always reached.  The same
+//! generator state always gives the same board.
+
+use crate::bitboard::{BitBoard, EMPTY};
+use crate::board::Board;
+use crate::board_builder::BoardBuilder;
+use crate::castle_rights::CastleRights;
+use crate::color::{Color, ALL_COLORS};
+use crate::file::{File, ALL_FILES};
+use crate::magic::get_king_moves;
+use crate::piece::{Piece, ALL_PIECES, NUM_PIECES};
+use crate::rank::Rank;
+use crate::square::{Square, ALL_SQUARES};
+use crate::variant::Variant;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// How `Board::random` sets up a position.
+///
+/// ```
+/// use chess::{Piece, RandomBoardOptions};
+///
+/// // a queen and up to four pawns for each side, and no castling
+/// let mut options = RandomBoardOptions {
+///     castle_rights: false,
+///     ..RandomBoardOptions::default()
+/// };
+/// options.pieces = [(0, 4), (0, 0), (0, 0), (0, 0), (1, 1)];
+/// assert_eq!(options.pieces[Piece::Queen.to_index()], (1, 1));
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RandomBoardOptions {
+    /// The rules the position is played by.
+    pub variant: Variant,
+    /// The fewest and the most pieces of each kind, from the pawn to the queen, that each side
+    /// gets next to its king.  The fewest must not be more than the most.
+    pub pieces: [(u8, u8); NUM_PIECES - 1],
+    /// Give castle rights, each with even odds, to the rooks that can castle.
+    pub castle_rights: bool,
+    /// Give an en passant square, with even odds, if a pawn could just have moved two squares and
+    /// can be taken.
+    pub en_passant: bool,
+    /// Let the side to move be in check.
+    pub allow_check: bool,
+}
+
+impl Default for RandomBoardOptions {
+    /// The default variant, with up to the pieces each side starts with, castling, en passant and
+    /// checks.
+    fn default() -> RandomBoardOptions {
+        RandomBoardOptions {
+            variant: Variant::default(),
+            pieces: [(0, 8), (0, 2), (0, 2), (0, 2), (0, 1)],
+            castle_rights: true,
+            en_passant: true,
+            allow_check: true,
+        }
+    }
+}
+
+pub(crate) fn random(rng: &mut impl Rng, options: RandomBoardOptions) -> Board {
+    let side_to_move = if rng.gen() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let white_king = Square::new(rng.gen_range(0, 64));
+    // the black king goes on the next square from a random one that does not touch the white king
+    let black_king = ALL_SQUARES
+        .iter()
+        .cycle()
+        .skip(rng.gen_range(0, 64))
+        .find(|sq| {
+            **sq != white_king && get_king_moves(white_king) & BitBoard::from_square(**sq) == EMPTY
+        })
+        .copied()
+        .unwrap();
+
+    let mut builder = BoardBuilder::new();
+    builder
+        .variant(options.variant)
+        .side_to_move(side_to_move)
+        .piece(white_king, Piece::King, Color::White)
+        .piece(black_king, Piece::King, Color::Black);
+
+    // shuffle the pieces, so that the last kind is not the one left out when a side is full
+    let mut pieces = Vec::new();
+    for color in ALL_COLORS.iter() {
+        for (piece, (fewest, most)) in ALL_PIECES.iter().zip(options.pieces.iter()) {
+            let count = rng.gen_range(*fewest as usize, *most as usize + 1);
+            pieces.extend((0..count).map(|_| (*piece, *color)));
+        }
+    }
+    pieces.shuffle(rng);
+    for (piece, color) in pieces.iter() {
+        place(rng, &mut builder, *piece, *color, options.allow_check);
+    }
+
+    if options.castle_rights {
+        for color in ALL_COLORS.iter() {
+            let rights = castle_rights(rng, &builder, *color);
+            builder.castle_rights(*color, rights);
+        }
+    }
+
+    if options.en_passant {
+        let files: Vec<File> = ALL_FILES
+            .iter()
+            .filter(|file| {
+                builder.en_passant(Some(**file));
+                Board::try_from(&builder)
+                    .ok()
+                    .and_then(|board| board.en_passant())
+                    .is_some()
+            })
+            .copied()
+            .collect();
+        let file = if rng.gen() {
+            files.choose(rng).copied()
+        } else {
+            None
+        };
+        builder.en_passant(file);
+    }
+
+    Board::try_from(&builder).expect("every piece was checked as it was added")
+}
+
+// Put `piece` on the first square from a random one where the position stays valid, if any.
+fn place(
+    rng: &mut impl Rng,
+    builder: &mut BoardBuilder,
+    piece: Piece,
+    color: Color,
+    allow_check: bool,
+) {
+    for square in ALL_SQUARES
+        .iter()
+        .cycle()
+        .skip(rng.gen_range(0, 64))
+        .take(64)
+    {
+        let rank = square.get_rank();
+        if builder[*square].is_some()
+            || (piece == Piece::Pawn && (rank == Rank::First || rank == Rank::Eighth))
+        {
+            continue;
+        }
+        builder.piece(*square, piece, color);
+        match Board::try_from(&*builder) {
+            Ok(board) if allow_check || *board.checkers() == EMPTY => return,
+            _ => {
+                builder.clear_square(*square);
+            }
+        }
+    }
+}
+
+// Each side of the king castles with its outermost rook on the back rank, with even odds.
+fn castle_rights(rng: &mut impl Rng, builder: &BoardBuilder, color: Color) -> CastleRights {
+    let backrank = color.back_rank();
+    let on =
+        |file: &File, piece| builder[Square::make_square(backrank, *file)] == Some((piece, color));
+    let king = match ALL_FILES.iter().find(|file| on(file, Piece::King)) {
+        Some(file) => *file,
+        None => return CastleRights::NoRights,
+    };
+    let rook = |file: &&File| on(file, Piece::Rook);
+    let kingside = ALL_FILES[king.to_index() + 1..].iter().rev().find(rook);
+    let queenside = ALL_FILES[..king.to_index()].iter().find(rook);
+    let kingside = kingside.copied().filter(|_| rng.gen());
+    let queenside = queenside.copied().filter(|_| rng.gen());
+    CastleRights::new(kingside, queenside)
+}
+
+#[cfg(test)]
+use rand::rngs::SmallRng;
+#[cfg(test)]
+use rand::SeedableRng;
+
+#[test]
+fn test_random_is_deterministic() {
+    for seed in 0..20 {
+        let options = RandomBoardOptions::default();
+        let first = Board::random(&mut SmallRng::seed_from_u64(seed), options);
+        let second = Board::random(&mut SmallRng::seed_from_u64(seed), options);
+        assert_eq!(first, second);
+        assert_eq!(first.to_tagged_fen(), second.to_tagged_fen());
+    }
+}
+
+#[test]
+fn test_random_boards_are_valid() {
+    use crate::movegen::MoveGen;
+    use core::str::FromStr;
+
+    let mut rng = SmallRng::seed_from_u64(0x2545_F491_4F6C_DD1D);
+    let mut castles = 0;
+    let mut en_passants = 0;
+    let mut checks = 0;
+    for i in 0..10_000 {
+        let options = RandomBoardOptions {
+            variant: if i % 2 == 0 {
+                Variant::Standard
+            } else {
+                Variant::Uno
+            },
+            allow_check: i % 3 != 0,
+            ..RandomBoardOptions::default()
+        };
+        let board = Board::random(&mut rng, options);
+        assert!(board.is_sane());
+        assert_eq!(board.variant(), options.variant);
+        assert_eq!(board.get_hash(), board.recompute_hash());
+        assert_eq!(Board::from_str(&board.to_tagged_fen()).unwrap(), board);
+        assert!(options.allow_check || *board.checkers() == EMPTY);
+        let back_ranks = BitBoard::from_rank(Rank::First) | BitBoard::from_rank(Rank::Eighth);
+        assert_eq!(*board.pieces(Piece::Pawn) & back_ranks, EMPTY);
+        for color in ALL_COLORS.iter() {
+            for (piece, (_, most)) in ALL_PIECES.iter().zip(options.pieces.iter()) {
+                assert!(board.piece_squares(*piece, *color).popcnt() <= *most as u32);
+            }
+        }
+        let _ = MoveGen::new_legal(&board).len();
+
+        castles += (board.castle_rights(Color::White) != CastleRights::NoRights) as usize;
+        en_passants += board.en_passant().is_some() as usize;
+        checks += (*board.checkers() != EMPTY) as usize;
+    }
+    // the options are used, not just allowed
+    assert!(castles > 0 && en_passants > 0 && checks > 0);
+
+    // a full set of pieces always fits
+    let options = RandomBoardOptions {
+        pieces: [(8, 8), (2, 2), (2, 2), (2, 2), (1, 1)],
+        allow_check: false,
+        ..RandomBoardOptions::default()
+    };
+    for _ in 0..100 {
+        let board = Board::random(&mut rng, options);
+        assert_eq!(board.combined().popcnt(), 32);
+        assert_eq!(*board.checkers(), EMPTY);
+    }
+}
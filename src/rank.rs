@@ -1,5 +1,6 @@
+use crate::bitboard::BitBoard;
 use crate::error::Error;
-use std::str::FromStr;
+use core::str::FromStr;
 
 /// Describe a rank (row) on a chess board
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Debug, Hash)]
@@ -31,6 +32,24 @@ pub const ALL_RANKS: [Rank; NUM_RANKS] = [
 ];
 
 impl Rank {
+    /// The squares on each rank, from the first rank to the eighth.
+    ///
+    /// ```
+    /// use chess::{BitBoard, Rank};
+    ///
+    /// assert_eq!(Rank::ALL_BB[Rank::Third.to_index()], BitBoard::new(0xFF << 16));
+    /// ```
+    pub const ALL_BB: [BitBoard; NUM_RANKS] = [
+        BitBoard(0x0000_0000_0000_00FF),
+        BitBoard(0x0000_0000_0000_FF00),
+        BitBoard(0x0000_0000_00FF_0000),
+        BitBoard(0x0000_0000_FF00_0000),
+        BitBoard(0x0000_00FF_0000_0000),
+        BitBoard(0x0000_FF00_0000_0000),
+        BitBoard(0x00FF_0000_0000_0000),
+        BitBoard(0xFF00_0000_0000_0000),
+    ];
+
     /// Convert a `usize` into a `Rank` (the inverse of to_index).  If the number is > 7, wrap
     /// around.
     #[inline]
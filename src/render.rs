@@ -0,0 +1,216 @@
+use crate::bitboard::{BitBoard, EMPTY};
+use crate::board::Board;
+use crate::chess_move::ChessMove;
+use crate::color::Color;
+use crate::file::ALL_FILES;
+use crate::rank::ALL_RANKS;
+use crate::square::Square;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// How `Board::render` draws a board.
+///
+/// ```
+/// use chess::{Color, RenderOptions};
+///
+/// let options = RenderOptions {
+///     perspective: Color::Black,
+///     unicode: true,
+///     ..RenderOptions::default()
+/// };
+/// assert_eq!(options.last_move, None);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RenderOptions {
+    /// Which side is at the bottom of the board.
+    pub perspective: Color,
+    /// Draw the pieces as unicode chess symbols instead of FEN letters.
+    pub unicode: bool,
+    /// The move that was just played, whose squares are marked with `[` and `]`.
+    pub last_move: Option<ChessMove>,
+    /// Color the squares and the marks with ANSI escape codes, for a terminal.
+    pub ansi_color: bool,
+}
+
+impl Default for RenderOptions {
+    /// White at the bottom, with FEN letters and no colors.
+    fn default() -> RenderOptions {
+        RenderOptions {
+            perspective: Color::White,
+            unicode: false,
+            last_move: None,
+            ansi_color: false,
+        }
+    }
+}
+
+// background colors for the light and dark squares, the last move and the checkers
+const LIGHT: &str = "47";
+const DARK: &str = "100";
+const LAST_MOVE: &str = "43";
+const CHECKER: &str = "41";
+// foreground colors for the pieces of each side
+const WHITE_PIECE: &str = "97";
+const BLACK_PIECE: &str = "30";
+const RESET: &str = "\x1b[0m";
+
+pub(crate) fn render(board: &Board, options: RenderOptions) -> String {
+    let mut ranks: Vec<_> = ALL_RANKS.iter().rev().collect();
+    let mut files: Vec<_> = ALL_FILES.iter().collect();
+    if options.perspective == Color::Black {
+        ranks.reverse();
+        files.reverse();
+    }
+
+    let last_move = options
+        .last_move
+        .map_or(Vec::new(), |m| vec![m.get_source(), m.get_dest()]);
+
+    let mut result = String::new();
+    for rank in ranks.iter() {
+        result.push_str(&format!("{} ", rank.to_index() + 1));
+        for file in files.iter() {
+            let square = Square::make_square(**rank, **file);
+            let (open, close, highlight) =
+                if board.checkers() & BitBoard::from_square(square) != EMPTY {
+                    ('(', ')', Some(CHECKER))
+                } else if last_move.contains(&square) {
+                    ('[', ']', Some(LAST_MOVE))
+                } else {
+                    (' ', ' ', None)
+                };
+            let piece = match (board.piece_on(square), board.color_on(square)) {
+                (Some(piece), Some(color)) if options.unicode => piece.to_unicode(color),
+                (Some(piece), Some(color)) => piece.to_char(color),
+                _ => '.',
+            };
+
+            if options.ansi_color {
+                let light = (rank.to_index() + file.to_index()) % 2 == 1;
+                let background = highlight.unwrap_or(if light { LIGHT } else { DARK });
+                let foreground = match board.color_on(square) {
+                    Some(Color::Black) => BLACK_PIECE,
+                    _ => WHITE_PIECE,
+                };
+                result.push_str(&format!("\x1b[{};{}m", background, foreground));
+            }
+            result.push(open);
+            result.push(piece);
+            result.push(close);
+        }
+        if options.ansi_color {
+            result.push_str(RESET);
+        }
+        result.push('\n');
+    }
+
+    result.push_str("  ");
+    for file in files.iter() {
+        result.push_str(&format!(" {} ", (b'a' + file.to_index() as u8) as char));
+    }
+    result.push('\n');
+    result
+}
+
+#[cfg(test)]
+use core::str::FromStr;
+
+#[test]
+fn test_render_default() {
+    assert_eq!(
+        Board::default().render(RenderOptions::default()),
+        "8  r  n  b  q  k  b  n  r \n\
+         7  p  p  p  p  p  p  p  p \n\
+         6  .  .  .  .  .  .  .  . \n\
+         5  .  .  .  .  .  .  .  . \n\
+         4  .  .  .  .  .  .  .  . \n\
+         3  .  .  .  .  .  .  .  . \n\
+         2  P  P  P  P  P  P  P  P \n\
+         1  R  N  B  Q  K  B  N  R \n\
+         \x20  a  b  c  d  e  f  g  h \n"
+    );
+}
+
+#[test]
+fn test_render_last_move_from_black() {
+    let m = ChessMove::new(Square::E2, Square::E4, None);
+    let board = Board::default().make_move_new(m);
+    let options = RenderOptions {
+        perspective: Color::Black,
+        unicode: true,
+        last_move: Some(m),
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        board.render(options),
+        "1  ♖  ♘  ♗  ♔  ♕  ♗  ♘  ♖ \n\
+         2  ♙  ♙  ♙ [.] ♙  ♙  ♙  ♙ \n\
+         3  .  .  .  .  .  .  .  . \n\
+         4  .  .  . [♙] .  .  .  . \n\
+         5  .  .  .  .  .  .  .  . \n\
+         6  .  .  .  .  .  .  .  . \n\
+         7  ♟  ♟  ♟  ♟  ♟  ♟  ♟  ♟ \n\
+         8  ♜  ♞  ♝  ♚  ♛  ♝  ♞  ♜ \n\
+         \x20  h  g  f  e  d  c  b  a \n"
+    );
+}
+
+#[test]
+fn test_render_checkers() {
+    // the rook just moved next to the king, and checks it
+    let board = Board::from_str("4k3/4R3/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    let options = RenderOptions {
+        last_move: Some(ChessMove::new(Square::E2, Square::E7, None)),
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        board.render(options),
+        "8  .  .  .  .  k  .  .  . \n\
+         7  .  .  .  . (R) .  .  . \n\
+         6  .  .  .  .  .  .  .  . \n\
+         5  .  .  .  .  .  .  .  . \n\
+         4  .  .  .  .  .  .  .  . \n\
+         3  .  .  .  .  .  .  .  . \n\
+         2  .  .  .  . [.] .  .  . \n\
+         1  .  .  .  .  K  .  .  . \n\
+         \x20  a  b  c  d  e  f  g  h \n"
+    );
+}
+
+#[test]
+fn test_render_ansi_strips_to_plain() {
+    fn strip(ansi: &str) -> String {
+        let mut result = String::new();
+        let mut escape = false;
+        for c in ansi.chars() {
+            match c {
+                '\x1b' => escape = true,
+                'm' if escape => escape = false,
+                _ if escape => {}
+                _ => result.push(c),
+            }
+        }
+        result
+    }
+
+    let board = Board::from_str("4k3/4R3/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    for perspective in [Color::White, Color::Black].iter() {
+        for unicode in [false, true].iter() {
+            let plain = RenderOptions {
+                perspective: *perspective,
+                unicode: *unicode,
+                last_move: Some(ChessMove::new(Square::E2, Square::E7, None)),
+                ansi_color: false,
+            };
+            let ansi = board.render(RenderOptions {
+                ansi_color: true,
+                ..plain
+            });
+            assert!(ansi.contains("\x1b["));
+            assert_eq!(strip(&ansi), board.render(plain));
+        }
+    }
+}
@@ -2,12 +2,93 @@ use crate::color::Color;
 use crate::error::Error;
 use crate::file::File;
 use crate::rank::Rank;
-use std::fmt;
-use std::str::FromStr;
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// Represent a square on the chess board
-#[derive(PartialEq, Ord, Eq, PartialOrd, Copy, Clone, Debug, Hash)]
-pub struct Square(u8);
+///
+/// A square is stored in one byte as its index, from 0 for a1 to 63 for h8 going along each
+/// rank first, which `to_int` and `to_index` give and every serialized format in this crate
+/// uses.  The other 192 values of the byte are left free, so an `Option<Square>` is one byte as
+/// well.
+#[derive(PartialEq, Ord, Eq, PartialOrd, Copy, Clone, Hash)]
+pub struct Square(Index);
+
+// The index of a square, as an enum so that the compiler knows the values past 63 are free
+#[repr(u8)]
+#[derive(PartialEq, Ord, Eq, PartialOrd, Copy, Clone, Hash)]
+enum Index {
+    A1 = 0,
+    B1 = 1,
+    C1 = 2,
+    D1 = 3,
+    E1 = 4,
+    F1 = 5,
+    G1 = 6,
+    H1 = 7,
+    A2 = 8,
+    B2 = 9,
+    C2 = 10,
+    D2 = 11,
+    E2 = 12,
+    F2 = 13,
+    G2 = 14,
+    H2 = 15,
+    A3 = 16,
+    B3 = 17,
+    C3 = 18,
+    D3 = 19,
+    E3 = 20,
+    F3 = 21,
+    G3 = 22,
+    H3 = 23,
+    A4 = 24,
+    B4 = 25,
+    C4 = 26,
+    D4 = 27,
+    E4 = 28,
+    F4 = 29,
+    G4 = 30,
+    H4 = 31,
+    A5 = 32,
+    B5 = 33,
+    C5 = 34,
+    D5 = 35,
+    E5 = 36,
+    F5 = 37,
+    G5 = 38,
+    H5 = 39,
+    A6 = 40,
+    B6 = 41,
+    C6 = 42,
+    D6 = 43,
+    E6 = 44,
+    F6 = 45,
+    G6 = 46,
+    H6 = 47,
+    A7 = 48,
+    B7 = 49,
+    C7 = 50,
+    D7 = 51,
+    E7 = 52,
+    F7 = 53,
+    G7 = 54,
+    H7 = 55,
+    A8 = 56,
+    B8 = 57,
+    C8 = 58,
+    D8 = 59,
+    E8 = 60,
+    F8 = 61,
+    G8 = 62,
+    H8 = 63,
+}
+
+const _: () = assert!(core::mem::size_of::<Option<Square>>() == 1);
 
 /// How many squares are there?
 pub const NUM_SQUARES: usize = 64;
@@ -29,6 +110,9 @@ impl Default for Square {
 }
 
 impl Square {
+    /// Every square, the same as `ALL_SQUARES`.
+    pub const ALL: [Square; NUM_SQUARES] = ALL_SQUARES;
+
     /// Create a new square, given an index.
     ///
     /// ```
@@ -43,7 +127,14 @@ impl Square {
     /// ```
     #[inline]
     pub fn new(sq: u8) -> Square {
-        Square(sq & 63)
+        Square::from_int(sq)
+    }
+
+    // the square with the index `sq & 63`
+    #[inline]
+    const fn from_int(sq: u8) -> Square {
+        // every value below 64 is an `Index`
+        Square(unsafe { core::mem::transmute::<u8, Index>(sq & 63) })
     }
 
     /// Make a square given a rank and a file
@@ -65,7 +156,7 @@ impl Square {
     /// ```
     #[inline]
     pub fn make_square(rank: Rank, file: File) -> Square {
-        Square((rank.to_index() as u8) << 3 ^ (file.to_index() as u8))
+        Square::from_int((rank.to_index() as u8) << 3 ^ (file.to_index() as u8))
     }
 
     /// Return the rank given this square.
@@ -79,7 +170,7 @@ impl Square {
     /// ```
     #[inline]
     pub fn get_rank(&self) -> Rank {
-        Rank::from_index((self.0 >> 3) as usize)
+        Rank::from_index(self.to_index() >> 3)
     }
 
     /// Return the file given this square.
@@ -93,7 +184,24 @@ impl Square {
     /// ```
     #[inline]
     pub fn get_file(&self) -> File {
-        File::from_index((self.0 & 7) as usize)
+        File::from_index(self.to_index() & 7)
+    }
+
+    /// How many king moves does it take to get from me to the nearest edge of the board?  The
+    /// squares on the edge are 0 away and the four in the middle 3, see `get_edge_distance`.
+    ///
+    /// ```
+    /// use chess::Square;
+    ///
+    /// assert_eq!(Square::A5.edge_distance(), 0);
+    /// assert_eq!(Square::B7.edge_distance(), 1);
+    /// assert_eq!(Square::E4.edge_distance(), 3);
+    /// ```
+    #[inline]
+    pub fn edge_distance(&self) -> u8 {
+        let rank = self.get_rank().to_index();
+        let file = self.get_file().to_index();
+        rank.min(7 - rank).min(file).min(7 - file) as u8
     }
 
     /// If there is a square above me, return that.  Otherwise, None.
@@ -350,7 +458,7 @@ impl Square {
     /// ```
     #[inline]
     pub fn to_int(&self) -> u8 {
-        self.0
+        self.0 as u8
     }
 
     /// Convert this `Square` to a `usize` for table lookup purposes
@@ -364,10 +472,28 @@ impl Square {
     /// assert_eq!(Square::make_square(Rank::Eighth, File::H).to_index(), 63);
     /// ```
     #[inline]
-    pub fn to_index(&self) -> usize {
+    pub const fn to_index(self) -> usize {
         self.0 as usize
     }
 
+    /// Convert a `usize` to a `Square`, the inverse of `to_index`.  Unlike `Square::new`, this
+    /// does not wrap around: if i > 63, return `None`.
+    ///
+    /// ```
+    /// use chess::Square;
+    ///
+    /// assert_eq!(Square::from_index(8), Some(Square::A2));
+    /// assert_eq!(Square::from_index(64), None);
+    /// ```
+    #[inline]
+    pub const fn from_index(i: usize) -> Option<Square> {
+        if i < NUM_SQUARES {
+            Some(Square::from_int(i as u8))
+        } else {
+            None
+        }
+    }
+
     /// Convert a UCI `String` to a square.  If invalid, return `None`
     ///
     /// ```
@@ -392,7 +518,7 @@ impl Square {
     ///
     /// assert_eq!(Square::A1, Square::make_square(Rank::First, File::A));
     /// ```
-    pub const A1: Square = Square(0);
+    pub const A1: Square = Square(Index::A1);
 
     /// The B1 square on the chess board
     ///
@@ -401,7 +527,7 @@ impl Square {
     ///
     /// assert_eq!(Square::B1, Square::make_square(Rank::First, File::B));
     /// ```
-    pub const B1: Square = Square(1);
+    pub const B1: Square = Square(Index::B1);
 
     /// The C1 square on the chess board
     ///
@@ -410,7 +536,7 @@ impl Square {
     ///
     /// assert_eq!(Square::C1, Square::make_square(Rank::First, File::C));
     /// ```
-    pub const C1: Square = Square(2);
+    pub const C1: Square = Square(Index::C1);
 
     /// The D1 square on the chess board
     ///
@@ -419,7 +545,7 @@ impl Square {
     ///
     /// assert_eq!(Square::D1, Square::make_square(Rank::First, File::D));
     /// ```
-    pub const D1: Square = Square(3);
+    pub const D1: Square = Square(Index::D1);
 
     /// The E1 square on the chess board
     ///
@@ -428,7 +554,7 @@ impl Square {
     ///
     /// assert_eq!(Square::E1, Square::make_square(Rank::First, File::E));
     /// ```
-    pub const E1: Square = Square(4);
+    pub const E1: Square = Square(Index::E1);
 
     /// The F1 square on the chess board
     ///
@@ -437,7 +563,7 @@ impl Square {
     ///
     /// assert_eq!(Square::F1, Square::make_square(Rank::First, File::F));
     /// ```
-    pub const F1: Square = Square(5);
+    pub const F1: Square = Square(Index::F1);
 
     /// The G1 square on the chess board
     ///
@@ -446,7 +572,7 @@ impl Square {
     ///
     /// assert_eq!(Square::G1, Square::make_square(Rank::First, File::G));
     /// ```
-    pub const G1: Square = Square(6);
+    pub const G1: Square = Square(Index::G1);
 
     /// The H1 square on the chess board
     ///
@@ -455,7 +581,7 @@ impl Square {
     ///
     /// assert_eq!(Square::H1, Square::make_square(Rank::First, File::H));
     /// ```
-    pub const H1: Square = Square(7);
+    pub const H1: Square = Square(Index::H1);
 
     /// The A2 square on the chess board
     ///
@@ -464,7 +590,7 @@ impl Square {
     ///
     /// assert_eq!(Square::A2, Square::make_square(Rank::Second, File::A));
     /// ```
-    pub const A2: Square = Square(8);
+    pub const A2: Square = Square(Index::A2);
 
     /// The B2 square on the chess board
     ///
@@ -473,7 +599,7 @@ impl Square {
     ///
     /// assert_eq!(Square::B2, Square::make_square(Rank::Second, File::B));
     /// ```
-    pub const B2: Square = Square(9);
+    pub const B2: Square = Square(Index::B2);
 
     /// The C2 square on the chess board
     ///
@@ -482,7 +608,7 @@ impl Square {
     ///
     /// assert_eq!(Square::C2, Square::make_square(Rank::Second, File::C));
     /// ```
-    pub const C2: Square = Square(10);
+    pub const C2: Square = Square(Index::C2);
 
     /// The D2 square on the chess board
     ///
@@ -491,7 +617,7 @@ impl Square {
     ///
     /// assert_eq!(Square::D2, Square::make_square(Rank::Second, File::D));
     /// ```
-    pub const D2: Square = Square(11);
+    pub const D2: Square = Square(Index::D2);
 
     /// The E2 square on the chess board
     ///
@@ -500,7 +626,7 @@ impl Square {
     ///
     /// assert_eq!(Square::E2, Square::make_square(Rank::Second, File::E));
     /// ```
-    pub const E2: Square = Square(12);
+    pub const E2: Square = Square(Index::E2);
 
     /// The F2 square on the chess board
     ///
@@ -509,7 +635,7 @@ impl Square {
     ///
     /// assert_eq!(Square::F2, Square::make_square(Rank::Second, File::F));
     /// ```
-    pub const F2: Square = Square(13);
+    pub const F2: Square = Square(Index::F2);
 
     /// The G2 square on the chess board
     ///
@@ -518,7 +644,7 @@ impl Square {
     ///
     /// assert_eq!(Square::G2, Square::make_square(Rank::Second, File::G));
     /// ```
-    pub const G2: Square = Square(14);
+    pub const G2: Square = Square(Index::G2);
 
     /// The H2 square on the chess board
     ///
@@ -527,7 +653,7 @@ impl Square {
     ///
     /// assert_eq!(Square::H2, Square::make_square(Rank::Second, File::H));
     /// ```
-    pub const H2: Square = Square(15);
+    pub const H2: Square = Square(Index::H2);
 
     /// The A3 square on the chess board
     ///
@@ -536,7 +662,7 @@ impl Square {
     ///
     /// assert_eq!(Square::A3, Square::make_square(Rank::Third, File::A));
     /// ```
-    pub const A3: Square = Square(16);
+    pub const A3: Square = Square(Index::A3);
 
     /// The B3 square on the chess board
     ///
@@ -545,7 +671,7 @@ impl Square {
     ///
     /// assert_eq!(Square::B3, Square::make_square(Rank::Third, File::B));
     /// ```
-    pub const B3: Square = Square(17);
+    pub const B3: Square = Square(Index::B3);
 
     /// The C3 square on the chess board
     ///
@@ -554,7 +680,7 @@ impl Square {
     ///
     /// assert_eq!(Square::C3, Square::make_square(Rank::Third, File::C));
     /// ```
-    pub const C3: Square = Square(18);
+    pub const C3: Square = Square(Index::C3);
 
     /// The D3 square on the chess board
     ///
@@ -563,7 +689,7 @@ impl Square {
     ///
     /// assert_eq!(Square::D3, Square::make_square(Rank::Third, File::D));
     /// ```
-    pub const D3: Square = Square(19);
+    pub const D3: Square = Square(Index::D3);
 
     /// The E3 square on the chess board
     ///
@@ -572,7 +698,7 @@ impl Square {
     ///
     /// assert_eq!(Square::E3, Square::make_square(Rank::Third, File::E));
     /// ```
-    pub const E3: Square = Square(20);
+    pub const E3: Square = Square(Index::E3);
 
     /// The F3 square on the chess board
     ///
@@ -581,7 +707,7 @@ impl Square {
     ///
     /// assert_eq!(Square::F3, Square::make_square(Rank::Third, File::F));
     /// ```
-    pub const F3: Square = Square(21);
+    pub const F3: Square = Square(Index::F3);
 
     /// The G3 square on the chess board
     ///
@@ -590,7 +716,7 @@ impl Square {
     ///
     /// assert_eq!(Square::G3, Square::make_square(Rank::Third, File::G));
     /// ```
-    pub const G3: Square = Square(22);
+    pub const G3: Square = Square(Index::G3);
 
     /// The H3 square on the chess board
     ///
@@ -599,7 +725,7 @@ impl Square {
     ///
     /// assert_eq!(Square::H3, Square::make_square(Rank::Third, File::H));
     /// ```
-    pub const H3: Square = Square(23);
+    pub const H3: Square = Square(Index::H3);
 
     /// The A4 square on the chess board
     ///
@@ -608,7 +734,7 @@ impl Square {
     ///
     /// assert_eq!(Square::A4, Square::make_square(Rank::Fourth, File::A));
     /// ```
-    pub const A4: Square = Square(24);
+    pub const A4: Square = Square(Index::A4);
 
     /// The B4 square on the chess board
     ///
@@ -617,7 +743,7 @@ impl Square {
     ///
     /// assert_eq!(Square::B4, Square::make_square(Rank::Fourth, File::B));
     /// ```
-    pub const B4: Square = Square(25);
+    pub const B4: Square = Square(Index::B4);
 
     /// The C4 square on the chess board
     ///
@@ -626,7 +752,7 @@ impl Square {
     ///
     /// assert_eq!(Square::C4, Square::make_square(Rank::Fourth, File::C));
     /// ```
-    pub const C4: Square = Square(26);
+    pub const C4: Square = Square(Index::C4);
 
     /// The D4 square on the chess board
     ///
@@ -635,7 +761,7 @@ impl Square {
     ///
     /// assert_eq!(Square::D4, Square::make_square(Rank::Fourth, File::D));
     /// ```
-    pub const D4: Square = Square(27);
+    pub const D4: Square = Square(Index::D4);
 
     /// The E4 square on the chess board
     ///
@@ -644,7 +770,7 @@ impl Square {
     ///
     /// assert_eq!(Square::E4, Square::make_square(Rank::Fourth, File::E));
     /// ```
-    pub const E4: Square = Square(28);
+    pub const E4: Square = Square(Index::E4);
 
     /// The F4 square on the chess board
     ///
@@ -653,7 +779,7 @@ impl Square {
     ///
     /// assert_eq!(Square::F4, Square::make_square(Rank::Fourth, File::F));
     /// ```
-    pub const F4: Square = Square(29);
+    pub const F4: Square = Square(Index::F4);
 
     /// The G4 square on the chess board
     ///
@@ -662,7 +788,7 @@ impl Square {
     ///
     /// assert_eq!(Square::G4, Square::make_square(Rank::Fourth, File::G));
     /// ```
-    pub const G4: Square = Square(30);
+    pub const G4: Square = Square(Index::G4);
 
     /// The H4 square on the chess board
     ///
@@ -671,7 +797,7 @@ impl Square {
     ///
     /// assert_eq!(Square::H4, Square::make_square(Rank::Fourth, File::H));
     /// ```
-    pub const H4: Square = Square(31);
+    pub const H4: Square = Square(Index::H4);
 
     /// The A5 square on the chess board
     ///
@@ -680,7 +806,7 @@ impl Square {
     ///
     /// assert_eq!(Square::A5, Square::make_square(Rank::Fifth, File::A));
     /// ```
-    pub const A5: Square = Square(32);
+    pub const A5: Square = Square(Index::A5);
 
     /// The B5 square on the chess board
     ///
@@ -689,7 +815,7 @@ impl Square {
     ///
     /// assert_eq!(Square::B5, Square::make_square(Rank::Fifth, File::B));
     /// ```
-    pub const B5: Square = Square(33);
+    pub const B5: Square = Square(Index::B5);
 
     /// The C5 square on the chess board
     ///
@@ -698,7 +824,7 @@ impl Square {
     ///
     /// assert_eq!(Square::C5, Square::make_square(Rank::Fifth, File::C));
     /// ```
-    pub const C5: Square = Square(34);
+    pub const C5: Square = Square(Index::C5);
 
     /// The D5 square on the chess board
     ///
@@ -707,7 +833,7 @@ impl Square {
     ///
     /// assert_eq!(Square::D5, Square::make_square(Rank::Fifth, File::D));
     /// ```
-    pub const D5: Square = Square(35);
+    pub const D5: Square = Square(Index::D5);
 
     /// The E5 square on the chess board
     ///
@@ -716,7 +842,7 @@ impl Square {
     ///
     /// assert_eq!(Square::E5, Square::make_square(Rank::Fifth, File::E));
     /// ```
-    pub const E5: Square = Square(36);
+    pub const E5: Square = Square(Index::E5);
 
     /// The F5 square on the chess board
     ///
@@ -725,7 +851,7 @@ impl Square {
     ///
     /// assert_eq!(Square::F5, Square::make_square(Rank::Fifth, File::F));
     /// ```
-    pub const F5: Square = Square(37);
+    pub const F5: Square = Square(Index::F5);
 
     /// The G5 square on the chess board
     ///
@@ -734,7 +860,7 @@ impl Square {
     ///
     /// assert_eq!(Square::G5, Square::make_square(Rank::Fifth, File::G));
     /// ```
-    pub const G5: Square = Square(38);
+    pub const G5: Square = Square(Index::G5);
 
     /// The H5 square on the chess board
     ///
@@ -743,7 +869,7 @@ impl Square {
     ///
     /// assert_eq!(Square::H5, Square::make_square(Rank::Fifth, File::H));
     /// ```
-    pub const H5: Square = Square(39);
+    pub const H5: Square = Square(Index::H5);
 
     /// The A6 square on the chess board
     ///
@@ -752,7 +878,7 @@ impl Square {
     ///
     /// assert_eq!(Square::A6, Square::make_square(Rank::Sixth, File::A));
     /// ```
-    pub const A6: Square = Square(40);
+    pub const A6: Square = Square(Index::A6);
 
     /// The B6 square on the chess board
     ///
@@ -761,7 +887,7 @@ impl Square {
     ///
     /// assert_eq!(Square::B6, Square::make_square(Rank::Sixth, File::B));
     /// ```
-    pub const B6: Square = Square(41);
+    pub const B6: Square = Square(Index::B6);
 
     /// The C6 square on the chess board
     ///
@@ -770,7 +896,7 @@ impl Square {
     ///
     /// assert_eq!(Square::C6, Square::make_square(Rank::Sixth, File::C));
     /// ```
-    pub const C6: Square = Square(42);
+    pub const C6: Square = Square(Index::C6);
 
     /// The D6 square on the chess board
     ///
@@ -779,7 +905,7 @@ impl Square {
     ///
     /// assert_eq!(Square::D6, Square::make_square(Rank::Sixth, File::D));
     /// ```
-    pub const D6: Square = Square(43);
+    pub const D6: Square = Square(Index::D6);
 
     /// The E6 square on the chess board
     ///
@@ -788,7 +914,7 @@ impl Square {
     ///
     /// assert_eq!(Square::E6, Square::make_square(Rank::Sixth, File::E));
     /// ```
-    pub const E6: Square = Square(44);
+    pub const E6: Square = Square(Index::E6);
 
     /// The F6 square on the chess board
     ///
@@ -797,7 +923,7 @@ impl Square {
     ///
     /// assert_eq!(Square::F6, Square::make_square(Rank::Sixth, File::F));
     /// ```
-    pub const F6: Square = Square(45);
+    pub const F6: Square = Square(Index::F6);
 
     /// The G6 square on the chess board
     ///
@@ -806,7 +932,7 @@ impl Square {
     ///
     /// assert_eq!(Square::G6, Square::make_square(Rank::Sixth, File::G));
     /// ```
-    pub const G6: Square = Square(46);
+    pub const G6: Square = Square(Index::G6);
 
     /// The H6 square on the chess board
     ///
@@ -815,7 +941,7 @@ impl Square {
     ///
     /// assert_eq!(Square::H6, Square::make_square(Rank::Sixth, File::H));
     /// ```
-    pub const H6: Square = Square(47);
+    pub const H6: Square = Square(Index::H6);
 
     /// The A7 square on the chess board
     ///
@@ -824,7 +950,7 @@ impl Square {
     ///
     /// assert_eq!(Square::A7, Square::make_square(Rank::Seventh, File::A));
     /// ```
-    pub const A7: Square = Square(48);
+    pub const A7: Square = Square(Index::A7);
 
     /// The B7 square on the chess board
     ///
@@ -833,7 +959,7 @@ impl Square {
     ///
     /// assert_eq!(Square::B7, Square::make_square(Rank::Seventh, File::B));
     /// ```
-    pub const B7: Square = Square(49);
+    pub const B7: Square = Square(Index::B7);
 
     /// The C7 square on the chess board
     ///
@@ -842,7 +968,7 @@ impl Square {
     ///
     /// assert_eq!(Square::C7, Square::make_square(Rank::Seventh, File::C));
     /// ```
-    pub const C7: Square = Square(50);
+    pub const C7: Square = Square(Index::C7);
 
     /// The D7 square on the chess board
     ///
@@ -851,7 +977,7 @@ impl Square {
     ///
     /// assert_eq!(Square::D7, Square::make_square(Rank::Seventh, File::D));
     /// ```
-    pub const D7: Square = Square(51);
+    pub const D7: Square = Square(Index::D7);
 
     /// The E7 square on the chess board
     ///
@@ -860,7 +986,7 @@ impl Square {
     ///
     /// assert_eq!(Square::E7, Square::make_square(Rank::Seventh, File::E));
     /// ```
-    pub const E7: Square = Square(52);
+    pub const E7: Square = Square(Index::E7);
 
     /// The F7 square on the chess board
     ///
@@ -869,7 +995,7 @@ impl Square {
     ///
     /// assert_eq!(Square::F7, Square::make_square(Rank::Seventh, File::F));
     /// ```
-    pub const F7: Square = Square(53);
+    pub const F7: Square = Square(Index::F7);
 
     /// The G7 square on the chess board
     ///
@@ -878,7 +1004,7 @@ impl Square {
     ///
     /// assert_eq!(Square::G7, Square::make_square(Rank::Seventh, File::G));
     /// ```
-    pub const G7: Square = Square(54);
+    pub const G7: Square = Square(Index::G7);
 
     /// The H7 square on the chess board
     ///
@@ -887,7 +1013,7 @@ impl Square {
     ///
     /// assert_eq!(Square::H7, Square::make_square(Rank::Seventh, File::H));
     /// ```
-    pub const H7: Square = Square(55);
+    pub const H7: Square = Square(Index::H7);
 
     /// The A8 square on the chess board
     ///
@@ -896,7 +1022,7 @@ impl Square {
     ///
     /// assert_eq!(Square::A8, Square::make_square(Rank::Eighth, File::A));
     /// ```
-    pub const A8: Square = Square(56);
+    pub const A8: Square = Square(Index::A8);
 
     /// The B8 square on the chess board
     ///
@@ -905,7 +1031,7 @@ impl Square {
     ///
     /// assert_eq!(Square::B8, Square::make_square(Rank::Eighth, File::B));
     /// ```
-    pub const B8: Square = Square(57);
+    pub const B8: Square = Square(Index::B8);
 
     /// The C8 square on the chess board
     ///
@@ -914,7 +1040,7 @@ impl Square {
     ///
     /// assert_eq!(Square::C8, Square::make_square(Rank::Eighth, File::C));
     /// ```
-    pub const C8: Square = Square(58);
+    pub const C8: Square = Square(Index::C8);
 
     /// The D8 square on the chess board
     ///
@@ -923,7 +1049,7 @@ impl Square {
     ///
     /// assert_eq!(Square::D8, Square::make_square(Rank::Eighth, File::D));
     /// ```
-    pub const D8: Square = Square(59);
+    pub const D8: Square = Square(Index::D8);
 
     /// The E8 square on the chess board
     ///
@@ -932,7 +1058,7 @@ impl Square {
     ///
     /// assert_eq!(Square::E8, Square::make_square(Rank::Eighth, File::E));
     /// ```
-    pub const E8: Square = Square(60);
+    pub const E8: Square = Square(Index::E8);
 
     /// The F8 square on the chess board
     ///
@@ -941,7 +1067,7 @@ impl Square {
     ///
     /// assert_eq!(Square::F8, Square::make_square(Rank::Eighth, File::F));
     /// ```
-    pub const F8: Square = Square(61);
+    pub const F8: Square = Square(Index::F8);
 
     /// The G8 square on the chess board
     ///
@@ -950,7 +1076,7 @@ impl Square {
     ///
     /// assert_eq!(Square::G8, Square::make_square(Rank::Eighth, File::G));
     /// ```
-    pub const G8: Square = Square(62);
+    pub const G8: Square = Square(Index::G8);
 
     /// The H8 square on the chess board
     ///
@@ -959,7 +1085,13 @@ impl Square {
     ///
     /// assert_eq!(Square::H8, Square::make_square(Rank::Eighth, File::H));
     /// ```
-    pub const H8: Square = Square(63);
+    pub const H8: Square = Square(Index::H8);
+}
+
+impl fmt::Debug for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Square").field(&self.to_int()).finish()
+    }
 }
 
 impl fmt::Display for Square {
@@ -967,8 +1099,8 @@ impl fmt::Display for Square {
         write!(
             f,
             "{}{}",
-            (('a' as u8) + ((self.0 & 7) as u8)) as char,
-            (('1' as u8) + ((self.0 >> 3) as u8)) as char
+            (('a' as u8) + (self.to_int() & 7)) as char,
+            (('1' as u8) + (self.to_int() >> 3)) as char
         )
     }
 }
@@ -1000,6 +1132,54 @@ impl FromStr for Square {
     }
 }
 
+impl TryFrom<u8> for Square {
+    type Error = Error;
+
+    /// Convert an index from 0 for a1 to 63 for h8 to a `Square`.  Unlike `Square::new`, this
+    /// does not wrap around.
+    ///
+    /// ```
+    /// use chess::Square;
+    /// use std::convert::TryFrom;
+    ///
+    /// assert_eq!(Square::try_from(28u8).unwrap(), Square::E4);
+    /// assert!(Square::try_from(64u8).is_err());
+    /// ```
+    fn try_from(i: u8) -> Result<Self, Self::Error> {
+        Square::try_from(usize::from(i))
+    }
+}
+
+impl TryFrom<u16> for Square {
+    type Error = Error;
+
+    fn try_from(i: u16) -> Result<Self, Self::Error> {
+        Square::try_from(usize::from(i))
+    }
+}
+
+impl TryFrom<usize> for Square {
+    type Error = Error;
+
+    fn try_from(i: usize) -> Result<Self, Self::Error> {
+        Square::from_index(i).ok_or(Error::InvalidSquare)
+    }
+}
+
+impl From<Square> for u8 {
+    /// The index of the square, see `Square::to_int`.
+    fn from(sq: Square) -> Self {
+        sq.to_int()
+    }
+}
+
+impl From<Square> for usize {
+    /// The index of the square, see `Square::to_index`.
+    fn from(sq: Square) -> Self {
+        sq.to_index()
+    }
+}
+
 /// A list of every square on the chessboard.
 ///
 /// ```
@@ -1015,69 +1195,107 @@ impl FromStr for Square {
 ///
 /// assert_eq!(new_universe, universe);
 /// ```
-pub const ALL_SQUARES: [Square; 64] = [
-    Square(0),
-    Square(1),
-    Square(2),
-    Square(3),
-    Square(4),
-    Square(5),
-    Square(6),
-    Square(7),
-    Square(8),
-    Square(9),
-    Square(10),
-    Square(11),
-    Square(12),
-    Square(13),
-    Square(14),
-    Square(15),
-    Square(16),
-    Square(17),
-    Square(18),
-    Square(19),
-    Square(20),
-    Square(21),
-    Square(22),
-    Square(23),
-    Square(24),
-    Square(25),
-    Square(26),
-    Square(27),
-    Square(28),
-    Square(29),
-    Square(30),
-    Square(31),
-    Square(32),
-    Square(33),
-    Square(34),
-    Square(35),
-    Square(36),
-    Square(37),
-    Square(38),
-    Square(39),
-    Square(40),
-    Square(41),
-    Square(42),
-    Square(43),
-    Square(44),
-    Square(45),
-    Square(46),
-    Square(47),
-    Square(48),
-    Square(49),
-    Square(50),
-    Square(51),
-    Square(52),
-    Square(53),
-    Square(54),
-    Square(55),
-    Square(56),
-    Square(57),
-    Square(58),
-    Square(59),
-    Square(60),
-    Square(61),
-    Square(62),
-    Square(63),
+pub const ALL_SQUARES: [Square; NUM_SQUARES] = [
+    Square::A1,
+    Square::B1,
+    Square::C1,
+    Square::D1,
+    Square::E1,
+    Square::F1,
+    Square::G1,
+    Square::H1,
+    Square::A2,
+    Square::B2,
+    Square::C2,
+    Square::D2,
+    Square::E2,
+    Square::F2,
+    Square::G2,
+    Square::H2,
+    Square::A3,
+    Square::B3,
+    Square::C3,
+    Square::D3,
+    Square::E3,
+    Square::F3,
+    Square::G3,
+    Square::H3,
+    Square::A4,
+    Square::B4,
+    Square::C4,
+    Square::D4,
+    Square::E4,
+    Square::F4,
+    Square::G4,
+    Square::H4,
+    Square::A5,
+    Square::B5,
+    Square::C5,
+    Square::D5,
+    Square::E5,
+    Square::F5,
+    Square::G5,
+    Square::H5,
+    Square::A6,
+    Square::B6,
+    Square::C6,
+    Square::D6,
+    Square::E6,
+    Square::F6,
+    Square::G6,
+    Square::H6,
+    Square::A7,
+    Square::B7,
+    Square::C7,
+    Square::D7,
+    Square::E7,
+    Square::F7,
+    Square::G7,
+    Square::H7,
+    Square::A8,
+    Square::B8,
+    Square::C8,
+    Square::D8,
+    Square::E8,
+    Square::F8,
+    Square::G8,
+    Square::H8,
 ];
+
+#[cfg(test)]
+use crate::bitboard::{BitBoard, EMPTY};
+
+#[test]
+fn test_all_squares() {
+    let mut seen = EMPTY;
+    for (i, square) in Square::ALL.iter().enumerate() {
+        assert_eq!(square.to_index(), i);
+        assert_eq!(Square::from_index(i), Some(*square));
+        seen ^= BitBoard::from_square(*square);
+        assert_eq!(seen.popcnt() as usize, i + 1);
+    }
+    assert_eq!(seen, !EMPTY);
+    assert_eq!(Square::from_index(NUM_SQUARES), None);
+}
+
+#[test]
+fn test_square_conversions_check_bounds() {
+    for i in 0..=u8::MAX {
+        let expected = Square::from_index(usize::from(i));
+        assert_eq!(Square::try_from(i).ok(), expected);
+        assert_eq!(Square::try_from(u16::from(i)).ok(), expected);
+        assert_eq!(Square::try_from(usize::from(i)).ok(), expected);
+        if let Some(square) = expected {
+            assert_eq!(u8::from(square), i);
+            assert_eq!(usize::from(square), usize::from(i));
+            assert_eq!(Square::new(i), square);
+        }
+    }
+    for error in [Square::try_from(256u16), Square::try_from(usize::MAX)].iter() {
+        assert!(matches!(error, Err(Error::InvalidSquare)));
+    }
+
+    // the free values of the byte hold `None`
+    assert_eq!(core::mem::size_of::<Option<Square>>(), 1);
+    assert_eq!(format!("{:?}", Some(Square::H8)), "Some(Square(63))");
+}
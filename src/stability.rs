@@ -0,0 +1,190 @@
+//! The versions of the encodings that other programs keep: hashes in opening books and
+//! transposition tables on disk, packed moves, and canonical keys in position databases.
+//!
+//! Each version is bumped only with a semver-major release of this crate, and only when its
+//! encoding gives different values.  The values are pinned by the tests of this module, so an
+//! encoding can't change by accident.
+//!
+//! * `HASH_SCHEME_VERSION` covers `Board::get_hash`.  `polyglot::hash` is the Polyglot standard's,
+//!   and never changes.
+//! * `MOVE_ENCODING_VERSION` covers `ChessMove::to_u16` and `ChessMove::from_u16`.
+//! * `CANONICAL_KEY_VERSION` covers `Board::canonical_key` and `Board::from_canonical_key`.
+//!
+//! ```
+//! use chess::stability::{migrate, Encoding, MOVE_ENCODING_VERSION};
+//! use chess::{ChessMove, Square};
+//!
+//! // a move kept with the version it was written in
+//! let m = ChessMove::new(Square::E2, Square::E4, None);
+//! let stored = (MOVE_ENCODING_VERSION, m.to_u16().to_le_bytes());
+//!
+//! let bytes = migrate(Encoding::Move, stored.0, &stored.1).unwrap();
+//! let bits = u16::from_le_bytes([bytes[0], bytes[1]]);
+//! assert_eq!(ChessMove::from_u16(bits), Some(m));
+//! ```
+
+use crate::error::Error;
+use crate::zobrist;
+use alloc::vec::Vec;
+
+/// Which version of `Board::get_hash` this crate gives.
+pub const HASH_SCHEME_VERSION: u32 = zobrist::VERSION;
+
+/// Which version of `ChessMove::to_u16` this crate gives.
+pub const MOVE_ENCODING_VERSION: u32 = 1;
+
+/// Which version of `Board::canonical_key` this crate gives.
+pub const CANONICAL_KEY_VERSION: u32 = 1;
+
+/// One of the encodings that has a version, see `migrate`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Encoding {
+    /// `Board::get_hash`, as 8 little-endian bytes
+    Hash,
+    /// `ChessMove::to_u16`, as 2 little-endian bytes
+    Move,
+    /// `Board::canonical_key`, as its 24 bytes
+    CanonicalKey,
+}
+
+impl Encoding {
+    /// Give me the version of this encoding that this crate gives.
+    #[inline]
+    pub fn version(self) -> u32 {
+        match self {
+            Encoding::Hash => HASH_SCHEME_VERSION,
+            Encoding::Move => MOVE_ENCODING_VERSION,
+            Encoding::CanonicalKey => CANONICAL_KEY_VERSION,
+        }
+    }
+
+    /// How many bytes is one value of this encoding?
+    #[inline]
+    pub fn size(self) -> usize {
+        match self {
+            Encoding::Hash => 8,
+            Encoding::Move => 2,
+            Encoding::CanonicalKey => 24,
+        }
+    }
+}
+
+/// Give me `bytes`, written with `version` of `encoding`, as the version this crate gives.
+///
+/// Every encoding is still at its first version, so this only gives the bytes of the current
+/// version back as they are.  It is here so that code keeping these values can call it now, and
+/// get the values of a new version once there is one.  A version this crate does not know, or
+/// bytes that are not the size of one value, are an `Error::UnsupportedEncodingVersion`.
+///
+/// ```
+/// use chess::stability::{migrate, Encoding, CANONICAL_KEY_VERSION};
+/// use chess::Board;
+///
+/// let key = Board::default().canonical_key();
+/// assert_eq!(migrate(Encoding::CanonicalKey, CANONICAL_KEY_VERSION, &key).unwrap(), key);
+/// assert!(migrate(Encoding::CanonicalKey, CANONICAL_KEY_VERSION + 1, &key).is_err());
+/// ```
+pub fn migrate(encoding: Encoding, version: u32, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    if version != encoding.version() || bytes.len() != encoding.size() {
+        return Err(Error::UnsupportedEncodingVersion);
+    }
+    Ok(bytes.to_vec())
+}
+
+#[cfg(test)]
+use crate::board::Board;
+#[cfg(test)]
+use crate::chess_move::ChessMove;
+#[cfg(test)]
+use crate::piece::Piece;
+#[cfg(test)]
+use crate::square::Square;
+#[cfg(test)]
+use core::str::FromStr;
+
+// If any of these change, every value users have stored is wrong.  Don't update these numbers
+// without also bumping the version they belong to, in a semver-major release.
+
+#[test]
+fn test_pinned_versions() {
+    assert_eq!(HASH_SCHEME_VERSION, 1);
+    assert_eq!(MOVE_ENCODING_VERSION, 1);
+    assert_eq!(CANONICAL_KEY_VERSION, 1);
+}
+
+#[test]
+fn test_pinned_hashes() {
+    let pinned = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            0x7d39_4ec8_095e_d75a,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            0x03f0_7af7_7cbf_779c,
+        ),
+        (
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard",
+            0x53b7_ddce_911f_5250,
+        ),
+    ];
+    for (fen, hash) in pinned.iter() {
+        assert_eq!(Board::from_str(fen).unwrap().get_hash(), *hash, "{}", fen);
+    }
+}
+
+#[test]
+fn test_pinned_moves() {
+    let pinned = [
+        (ChessMove::new(Square::E2, Square::E4, None), 0x031C),
+        (ChessMove::new(Square::G8, Square::F6, None), 0x0FAD),
+        // castles are the king's move
+        (ChessMove::new(Square::E1, Square::G1, None), 0x0106),
+        (
+            ChessMove::new(Square::A2, Square::B1, Some(Piece::Knight)),
+            0x1201,
+        ),
+        (
+            ChessMove::new(Square::H7, Square::H8, Some(Piece::Queen)),
+            0x4DFF,
+        ),
+    ];
+    for (m, bits) in pinned.iter() {
+        assert_eq!(m.to_u16(), *bits, "{}", m);
+        assert_eq!(ChessMove::from_u16(*bits), Some(*m));
+    }
+    assert_eq!(ChessMove::from_u16(0x5000), None);
+}
+
+#[test]
+fn test_pinned_canonical_keys() {
+    let pinned: [(&str, [u8; 24]); 2] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            [
+                0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x1C, 0x42, 0x25, 0xC1, 0x00, 0x00,
+                0x00, 0x00, 0x66, 0x66, 0x66, 0x66, 0x7C, 0xA8, 0x8B, 0xC7,
+            ],
+        ),
+        (
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1 variant:standard",
+            [
+                0x10, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x10, 0xDF, 0xB0, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+        ),
+    ];
+    for (fen, key) in pinned.iter() {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(board.canonical_key(), *key, "{}", fen);
+        assert_eq!(Board::from_canonical_key(key).unwrap(), board);
+    }
+}
+
+#[test]
+fn test_migrate_checks_the_size() {
+    let bits = 0x031Cu16.to_le_bytes();
+    assert_eq!(migrate(Encoding::Move, 1, &bits).unwrap(), bits.to_vec());
+    assert!(migrate(Encoding::Move, 0, &bits).is_err());
+    assert!(migrate(Encoding::Hash, 1, &bits).is_err());
+}
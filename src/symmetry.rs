@@ -0,0 +1,101 @@
+use crate::bitboard::BitBoard;
+use crate::square::Square;
+
+/// A way to turn or flip a position that keeps it the same game, see `Board::symmetries`.
+///
+/// ```
+/// use chess::{Square, SymmetryOp};
+///
+/// assert_eq!(SymmetryOp::Mirror.square(Square::B3), Square::B6);
+/// assert_eq!(SymmetryOp::Rotate90Cw.square(Square::B3), Square::C7);
+/// assert!(SymmetryOp::Mirror.swaps_colors());
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum SymmetryOp {
+    /// Flip the board top to bottom and swap the colors, so that black plays white's game.  This
+    /// works for any position.
+    Mirror,
+    /// Flip the board top to bottom, so a1 goes to a8.
+    FlipVertical,
+    /// Flip the board left to right, so a1 goes to h1.
+    FlipHorizontal,
+    /// Flip the board about the a1-h8 diagonal, so a8 goes to h1.
+    FlipDiagonal,
+    /// Flip the board about the a8-h1 diagonal, so a1 goes to h8.
+    FlipAntiDiagonal,
+    /// Turn the board a quarter clockwise, so a1 goes to a8.
+    Rotate90Cw,
+    /// Turn the board halfway around, so a1 goes to h8.
+    Rotate180,
+    /// Turn the board a quarter counterclockwise, so a1 goes to h1.
+    Rotate90Ccw,
+}
+
+/// How many symmetries are there, besides leaving the position as it is?
+pub const NUM_SYMMETRY_OPS: usize = 8;
+
+/// An array of every symmetry, the mirror first.
+pub const ALL_SYMMETRY_OPS: [SymmetryOp; NUM_SYMMETRY_OPS] = [
+    SymmetryOp::Mirror,
+    SymmetryOp::FlipVertical,
+    SymmetryOp::FlipHorizontal,
+    SymmetryOp::FlipDiagonal,
+    SymmetryOp::FlipAntiDiagonal,
+    SymmetryOp::Rotate90Cw,
+    SymmetryOp::Rotate180,
+    SymmetryOp::Rotate90Ccw,
+];
+
+impl SymmetryOp {
+    /// Give me the squares of `bb` moved where this symmetry takes them.
+    #[inline]
+    pub fn bitboard(self, bb: BitBoard) -> BitBoard {
+        match self {
+            SymmetryOp::Mirror | SymmetryOp::FlipVertical => bb.flip_vertical(),
+            SymmetryOp::FlipHorizontal => bb.flip_horizontal(),
+            SymmetryOp::FlipDiagonal => bb.flip_diagonal(),
+            SymmetryOp::FlipAntiDiagonal => bb.flip_diagonal().rotate_180(),
+            SymmetryOp::Rotate90Cw => bb.rotate_90_cw(),
+            SymmetryOp::Rotate180 => bb.rotate_180(),
+            SymmetryOp::Rotate90Ccw => bb.rotate_90_ccw(),
+        }
+    }
+
+    /// Give me the square this symmetry takes `square` to.
+    #[inline]
+    pub fn square(self, square: Square) -> Square {
+        let bb = self.bitboard(BitBoard::from_square(square));
+        // one square stays one square
+        bb.to_square_unchecked()
+    }
+
+    /// Does this symmetry swap the colors, and so the side to move?
+    #[inline]
+    pub fn swaps_colors(self) -> bool {
+        self == SymmetryOp::Mirror
+    }
+}
+
+#[cfg(test)]
+use crate::square::ALL_SQUARES;
+
+#[test]
+fn test_symmetries_move_every_square() {
+    for op in ALL_SYMMETRY_OPS.iter() {
+        let mut seen = BitBoard::new(0);
+        for sq in ALL_SQUARES.iter() {
+            let to = op.square(*sq);
+            seen |= BitBoard::from_square(to);
+            // flips are their own inverse, and the quarter turns are each other's
+            let back = match op {
+                SymmetryOp::Rotate90Cw => SymmetryOp::Rotate90Ccw,
+                SymmetryOp::Rotate90Ccw => SymmetryOp::Rotate90Cw,
+                _ => *op,
+            };
+            assert_eq!(back.square(to), *sq);
+        }
+        assert_eq!(seen, !BitBoard::new(0));
+    }
+    assert_eq!(SymmetryOp::FlipAntiDiagonal.square(Square::A1), Square::H8);
+    assert_eq!(SymmetryOp::FlipAntiDiagonal.square(Square::B1), Square::H7);
+}
@@ -0,0 +1,222 @@
+//! Syzygy endgame tablebase probing, behind the `syzygy` feature.
+//!
+//! `Tablebases::load` opens the WDL tables (`.rtbw` files) in a directory, and `probe_wdl` looks
+//! up whether the side to move of a `Board` wins, draws or loses with best play.  The probing is
+//! done by the [shakmaty-syzygy](https://crates.io/crates/shakmaty-syzygy) crate, which is
+//! licensed under the GPL, unlike this crate.
+//!
+//! The tables were generated under the rules of normal chess, so only boards in
+//! `Variant::Standard` can be probed.  Under the uno rules a defended piece cannot be taken and a
+//! king is only in check from the squares around it, so the moves, and with them the results,
+//! are not the ones in the tables.  Probing an uno board is an error rather than a wrong answer.
+//!
+//! A board is not in the tables, and `probe_wdl` gives `None`, if a side may still castle, if it
+//! has more pieces than the largest table that was loaded, or if the table for its material is
+//! missing.  A pawn that can be taken en passant is part of the position and is probed as such.
+//!
+//! ```no_run
+//! use chess::tablebase::{Tablebases, Wdl};
+//! use chess::{BoardBuilder, Board, Variant};
+//! use std::convert::TryInto;
+//! use std::str::FromStr;
+//!
+//! let tables = Tablebases::load("/path/to/syzygy").unwrap();
+//! let board: Board = BoardBuilder::from_str("8/8/8/8/8/2k5/8/KQ6 w - - 0 1")
+//!     .unwrap()
+//!     .variant(Variant::Standard)
+//!     .try_into()
+//!     .unwrap();
+//! assert_eq!(tables.probe_wdl(&board).unwrap(), Some(Wdl::Win));
+//! ```
+
+use crate::board::Board;
+use crate::castle_rights::CastleRights;
+use crate::color::ALL_COLORS;
+use crate::error::{Error, Span};
+use crate::variant::Variant;
+use core::str::FromStr;
+use shakmaty::fen::Fen;
+use shakmaty::{CastlingMode, Chess, PositionError};
+use shakmaty_syzygy::{SyzygyError, Tablebase};
+use std::io;
+use std::path::Path;
+use std::string::ToString;
+
+/// The result of a position for the side to move, with best play from both sides, under the
+/// 50-move rule.
+///
+/// A cursed win can only be forced in more than fifty moves without a capture or a pawn move, so
+/// it is a draw if the other side claims one, and a blessed loss is the same from the other
+/// side.  The variants are ordered from worst to best for the side to move.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    /// The same result for the other side.
+    ///
+    /// ```
+    /// use chess::tablebase::Wdl;
+    ///
+    /// assert_eq!(Wdl::CursedWin.flip(), Wdl::BlessedLoss);
+    /// assert_eq!(Wdl::Draw.flip(), Wdl::Draw);
+    /// ```
+    pub fn flip(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+impl From<shakmaty_syzygy::Wdl> for Wdl {
+    fn from(wdl: shakmaty_syzygy::Wdl) -> Self {
+        match wdl {
+            shakmaty_syzygy::Wdl::Loss => Wdl::Loss,
+            shakmaty_syzygy::Wdl::BlessedLoss => Wdl::BlessedLoss,
+            shakmaty_syzygy::Wdl::Draw => Wdl::Draw,
+            shakmaty_syzygy::Wdl::CursedWin => Wdl::CursedWin,
+            shakmaty_syzygy::Wdl::Win => Wdl::Win,
+        }
+    }
+}
+
+/// A set of Syzygy WDL tables for normal chess.
+pub struct Tablebases {
+    tables: Tablebase<Chess>,
+}
+
+impl Tablebases {
+    /// Open every table in `dir`.  The tables are read as they are probed, not here.
+    pub fn load<P: AsRef<Path>>(dir: P) -> io::Result<Tablebases> {
+        let mut tables = Tablebase::new();
+        tables.add_directory(dir)?;
+        Ok(Tablebases { tables })
+    }
+
+    /// The most pieces, counting the kings, of any table that was loaded.
+    pub fn max_pieces(&self) -> usize {
+        self.tables.max_pieces()
+    }
+
+    /// Look up the result of `board` for its side to move.
+    ///
+    /// `Board` does not count the moves since the last capture or pawn move, so the result is
+    /// the one right after such a move.  A later position in the same game may have used up some
+    /// of the fifty moves, and turned a win into a cursed win.
+    ///
+    /// Gives `Ok(None)` if the board is not in the loaded tables, as described in the module
+    /// documentation, and `Err(Error::UnsupportedVariant)` for a board that is not in
+    /// `Variant::Standard`.
+    pub fn probe_wdl(&self, board: &Board) -> Result<Option<Wdl>, Error> {
+        if board.variant() != Variant::Standard {
+            return Err(Error::UnsupportedVariant);
+        }
+        if ALL_COLORS
+            .iter()
+            .any(|color| board.castle_rights(*color) != CastleRights::NoRights)
+        {
+            return Ok(None);
+        }
+
+        let fen = board.to_string();
+        let position: Chess = Fen::from_str(&fen)
+            .map_err(|_| Error::InvalidFen {
+                fen: fen.clone(),
+                span: Span::of(&fen),
+            })?
+            .into_position(CastlingMode::Standard)
+            .or_else(PositionError::ignore_impossible_check)
+            .or_else(PositionError::ignore_too_much_material)
+            .map_err(|_| Error::InvalidBoard)?;
+
+        match self.tables.probe_wdl_after_zeroing(&position) {
+            Ok(wdl) => Ok(Some(wdl.into())),
+            Err(SyzygyError::Castling)
+            | Err(SyzygyError::TooManyPieces)
+            | Err(SyzygyError::MissingTable { .. }) => Ok(None),
+            Err(error) => panic!("the tablebase could not be read: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::board_builder::BoardBuilder;
+#[cfg(test)]
+use core::convert::TryInto;
+
+#[cfg(test)]
+fn fixture() -> Tablebases {
+    Tablebases::load(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy")).unwrap()
+}
+
+#[cfg(test)]
+fn standard(fen: &str) -> Board {
+    BoardBuilder::from_str(fen)
+        .unwrap()
+        .variant(Variant::Standard)
+        .try_into()
+        .unwrap()
+}
+
+#[test]
+fn test_probe_wdl() {
+    let tables = fixture();
+    assert_eq!(tables.max_pieces(), 3);
+
+    for (fen, wdl) in [
+        ("8/8/8/8/8/2k5/8/KQ6 w - - 0 1", Wdl::Win),
+        ("8/8/8/8/8/2k5/8/KQ6 b - - 0 1", Wdl::Loss),
+        // black has no move
+        ("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", Wdl::Draw),
+        // black takes the queen
+        ("8/8/8/8/8/8/1k6/1Q5K b - - 0 1", Wdl::Draw),
+        ("8/8/8/8/8/8/1q6/K1k5 w - - 0 1", Wdl::Loss),
+        ("8/8/8/3k4/8/8/8/R3K3 w - - 0 1", Wdl::Win),
+        // the bare kings need no table
+        ("8/8/8/3k4/8/8/8/4K3 w - - 0 1", Wdl::Draw),
+    ]
+    .iter()
+    {
+        assert_eq!(
+            tables.probe_wdl(&standard(fen)).unwrap(),
+            Some(*wdl),
+            "{}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn test_probe_wdl_not_in_tables() {
+    let tables = fixture();
+    for fen in [
+        // a side may castle
+        "8/8/8/3k4/8/8/8/R3K3 w Q - 0 1",
+        // there is no table for the material
+        "8/8/8/3k4/8/8/8/3BK3 w - - 0 1",
+        "8/8/8/2k5/8/8/8/RR2K3 w - - 0 1",
+    ]
+    .iter()
+    {
+        assert_eq!(tables.probe_wdl(&standard(fen)).unwrap(), None, "{}", fen);
+    }
+}
+
+#[test]
+fn test_probe_wdl_uno_is_an_error() {
+    let board = Board::from_str("8/8/8/8/8/2k5/8/KQ6 w - - 0 1").unwrap();
+    assert_eq!(board.variant(), Variant::Uno);
+    match fixture().probe_wdl(&board) {
+        Err(Error::UnsupportedVariant) => {}
+        result => panic!("{:?}", result),
+    }
+}
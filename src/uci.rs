@@ -0,0 +1,212 @@
+//! Helpers for engines that talk to a GUI over UCI.
+//!
+//! A GUI picks the variant with the `UCI_Variant` option, and asks for Chess960 with
+//! `UCI_Chess960`.  A GUI in 960 mode writes every castle as the king taking its own rook, but
+//! from the squares of normal chess this crate writes it as the king moving two squares (`e1g1`
+//! instead of `e1h1`), so `parse_move` and `move_to_string` convert between the two.  Any other
+//! castle is the king moving onto its rook either way, see `Board::castle_rook`.
+
+use crate::board::Board;
+use crate::chess_move::{ChessMove, MoveEncoding};
+use crate::error::Error;
+use crate::variant::Variant;
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+/// The name of the `UCI_Variant` option.
+pub const VARIANT_OPTION: &str = "UCI_Variant";
+
+/// The name of the `UCI_Chess960` option.
+pub const CHESS960_OPTION: &str = "UCI_Chess960";
+
+/// The `UCI_Variant` value for a variant.  Normal chess is `"chess"`, as other engines call it.
+///
+/// ```
+/// use chess::uci::variant_name;
+/// use chess::Variant;
+///
+/// assert_eq!(variant_name(Variant::Standard), "chess");
+/// assert_eq!(variant_name(Variant::Uno), "uno");
+/// ```
+pub fn variant_name(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Standard => "chess",
+        Variant::Uno => "uno",
+    }
+}
+
+/// Read a `UCI_Variant` value.  This takes the names from `variant_name` and the names used in
+/// tagged FENs, in any case.
+///
+/// ```
+/// use chess::uci::parse_variant;
+/// use chess::Variant;
+///
+/// assert_eq!(parse_variant("chess"), Some(Variant::Standard));
+/// assert_eq!(parse_variant("standard"), Some(Variant::Standard));
+/// assert_eq!(parse_variant("Uno"), Some(Variant::Uno));
+/// assert_eq!(parse_variant("crazyhouse"), None);
+/// ```
+pub fn parse_variant(name: &str) -> Option<Variant> {
+    if name.eq_ignore_ascii_case("chess") || name.eq_ignore_ascii_case("standard") {
+        Some(Variant::Standard)
+    } else if name.eq_ignore_ascii_case("uno") {
+        Some(Variant::Uno)
+    } else {
+        None
+    }
+}
+
+/// Read the options a GUI has set, as (name, value) pairs, and give back the `Variant` to play
+/// and whether moves use the Chess960 castling format.
+///
+/// Option names are not case sensitive.  Without a `UCI_Variant` the variant is
+/// `Variant::default()`, and without a `UCI_Chess960` of `true` moves use the normal format.
+/// Returns `None` if `UCI_Variant` names a variant this crate does not know.
+///
+/// ```
+/// use chess::uci::variant_from_options;
+/// use chess::Variant;
+///
+/// let options = [("UCI_Variant", "chess"), ("UCI_Chess960", "true"), ("Hash", "16")];
+/// assert_eq!(
+///     variant_from_options(options.iter().copied()),
+///     Some((Variant::Standard, true))
+/// );
+///
+/// assert_eq!(variant_from_options(Vec::<(&str, &str)>::new()), Some((Variant::Uno, false)));
+/// assert_eq!(variant_from_options(vec![("uci_variant", "atomic")]), None);
+/// ```
+pub fn variant_from_options<I, K, V>(options: I) -> Option<(Variant, bool)>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut variant = Variant::default();
+    let mut chess960 = false;
+    for (name, value) in options {
+        let (name, value) = (name.as_ref(), value.as_ref());
+        if name.eq_ignore_ascii_case(VARIANT_OPTION) {
+            variant = parse_variant(value)?;
+        } else if name.eq_ignore_ascii_case(CHESS960_OPTION) {
+            chess960 = value.eq_ignore_ascii_case("true");
+        }
+    }
+    Some((variant, chess960))
+}
+
+/// Parse a move in UCI coordinate notation.  With `chess960`, a castle is read in either
+/// `MoveEncoding`, as `ChessMove::from_coordinates` does, and so a king moving onto a rook of its
+/// own color is read as castling with that rook.
+///
+/// Like `ChessMove::from_str`, this does not check that the move is legal.
+///
+/// ```
+/// use chess::uci::parse_move;
+/// use chess::{Board, ChessMove, Square};
+/// use std::str::FromStr;
+///
+/// let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+///
+/// let castles = ChessMove::new(Square::E1, Square::G1, None);
+/// assert_eq!(parse_move(&board, "e1h1", true).unwrap(), castles);
+/// assert_eq!(parse_move(&board, "e1g1", false).unwrap(), castles);
+/// ```
+pub fn parse_move(board: &Board, text: &str, chess960: bool) -> Result<ChessMove, Error> {
+    if chess960 {
+        ChessMove::from_coordinates(board, text)
+    } else {
+        ChessMove::from_str(text)
+    }
+}
+
+/// Write a move in UCI coordinate notation.  With `chess960`, castling is written as the king
+/// taking its own rook.
+///
+/// ```
+/// use chess::uci::move_to_string;
+/// use chess::{Board, ChessMove, Square};
+/// use std::str::FromStr;
+///
+/// let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+///
+/// let castles = ChessMove::new(Square::E1, Square::C1, None);
+/// assert_eq!(move_to_string(&board, castles, true), "e1a1");
+/// assert_eq!(move_to_string(&board, castles, false), "e1c1");
+/// ```
+pub fn move_to_string(board: &Board, m: ChessMove, chess960: bool) -> String {
+    if chess960 {
+        m.to_coordinates(board, MoveEncoding::KingTakesRook)
+    } else {
+        m.to_string()
+    }
+}
+
+#[cfg(test)]
+use crate::square::Square;
+#[cfg(test)]
+use crate::variant::ALL_VARIANTS;
+
+#[test]
+fn test_variant_names_round_trip() {
+    for variant in ALL_VARIANTS.iter() {
+        assert_eq!(parse_variant(variant_name(*variant)), Some(*variant));
+        assert_eq!(parse_variant(&variant.to_string()), Some(*variant));
+        assert_eq!(
+            variant_from_options([(VARIANT_OPTION, variant_name(*variant))].iter().copied()),
+            Some((*variant, false))
+        );
+    }
+    assert_eq!(
+        variant_from_options(
+            [("uci_chess960", "TRUE"), ("UCI_Variant", "uno")]
+                .iter()
+                .copied()
+        ),
+        Some((Variant::Uno, true))
+    );
+    assert_eq!(
+        variant_from_options([(CHESS960_OPTION, "false")].iter().copied()),
+        Some((Variant::default(), false))
+    );
+}
+
+#[test]
+fn test_chess960_move_format() {
+    let board = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+
+    // every castling move, and what a 960 GUI writes for it
+    for (normal, chess960) in [("e8g8", "e8h8"), ("e8c8", "e8a8"), ("e1g1", "e1h1")].iter() {
+        let m = ChessMove::from_str(normal).unwrap();
+        assert_eq!(parse_move(&board, chess960, true).unwrap(), m);
+        assert_eq!(move_to_string(&board, m, true), *chess960);
+        assert_eq!(move_to_string(&board, m, false), *normal);
+    }
+
+    // without the flag, the king taking its own rook is read as written
+    assert_eq!(
+        parse_move(&board, "e8h8", false).unwrap(),
+        ChessMove::new(Square::E8, Square::H8, None)
+    );
+
+    // other moves are the same either way, including a rook move next to the king
+    for text in ["a8d8", "h1h8", "e8f8", "a1a8"].iter() {
+        let m = ChessMove::from_str(text).unwrap();
+        assert_eq!(parse_move(&board, text, true).unwrap(), m);
+        assert_eq!(move_to_string(&board, m, true), *text);
+    }
+
+    assert!(parse_move(&board, "e8", true).is_err());
+
+    // in a Chess960 position the king may stand off e1, and castling with the g rook keeps it
+    // on g1 and moves the rook to f1
+    let board = Board::from_str("4k3/8/8/8/8/8/8/1R2K1RR w G - 0 1").unwrap();
+    let m = parse_move(&board, "e1g1", true).unwrap();
+    assert_eq!(board.castle_rook(m), Some(Square::G1));
+    assert_eq!(move_to_string(&board, m, true), "e1g1");
+    assert_eq!(
+        format!("{}", board.make_move_new(m)),
+        "4k3/8/8/8/8/8/8/1R3RKR b - - 0 1"
+    );
+}
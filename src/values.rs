@@ -0,0 +1,172 @@
+//! Piece values and scores, for the functions that weigh material.
+//!
+//! `PAWN` to `KING` are the default centipawn values, the same as `PIECE_VALUES`.  The functions
+//! that weigh material, such as `Board::see_with` and `Board::material_balance_with`, take any
+//! `ValueTable`, so an engine can use its own numbers, and the ones without `_with` use
+//! `DEFAULT`.
+//!
+//! ```
+//! use chess::values::{Value, ValueTable, DEFAULT};
+//! use chess::Piece;
+//!
+//! // an engine that thinks bishops are worth a bit more
+//! let mut mine = DEFAULT;
+//! mine[Piece::Bishop.to_index()] = Value(330);
+//! assert!(mine.value(Piece::Bishop) > mine.value(Piece::Knight));
+//! ```
+
+use crate::piece::{Piece, NUM_PIECES, PIECE_VALUES};
+use core::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// A score in centipawns, or a mate.  The arithmetic saturates, so that adding to a mate score
+/// never wraps around to a lost one.
+///
+/// ```
+/// use chess::values::{Value, PAWN};
+///
+/// assert_eq!(PAWN * 3 - Value(50), Value(250));
+/// assert_eq!(Value(i32::MAX) + PAWN, Value(i32::MAX));
+///
+/// let mate = Value::mate_in(3);
+/// assert!(mate.is_mate() && -mate == Value::mated_in(3));
+/// assert!(Value::mate_in(1) > mate);
+/// assert!(!(PAWN * 9).is_mate());
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Default)]
+pub struct Value(pub i32);
+
+/// The most plies away a mate is scored as one.  A score within this many plies of `Value::MATE`
+/// is a mate.
+pub const MAX_MATE_PLY: u32 = 256;
+
+impl Value {
+    /// An even score.
+    pub const ZERO: Value = Value(0);
+
+    /// The score of giving mate now, which no other score of a position is above.
+    pub const MATE: Value = Value(32_000);
+
+    /// Give me the score of giving mate in `ply` plies, which is lower the longer the mate takes.
+    #[inline]
+    pub fn mate_in(ply: u32) -> Value {
+        Value(Value::MATE.0 - ply.min(MAX_MATE_PLY) as i32)
+    }
+
+    /// Give me the score of being mated in `ply` plies.
+    #[inline]
+    pub fn mated_in(ply: u32) -> Value {
+        -Value::mate_in(ply)
+    }
+
+    /// Is this the score of a mate, for either side?
+    #[inline]
+    pub fn is_mate(self) -> bool {
+        self.0.saturating_abs() >= Value::mate_in(MAX_MATE_PLY).0
+    }
+}
+
+impl Add for Value {
+    type Output = Value;
+
+    #[inline]
+    fn add(self, other: Value) -> Value {
+        Value(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Value {
+    #[inline]
+    fn add_assign(&mut self, other: Value) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Value {
+    type Output = Value;
+
+    #[inline]
+    fn sub(self, other: Value) -> Value {
+        Value(self.0.saturating_sub(other.0))
+    }
+}
+
+impl SubAssign for Value {
+    #[inline]
+    fn sub_assign(&mut self, other: Value) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for Value {
+    type Output = Value;
+
+    #[inline]
+    fn neg(self) -> Value {
+        Value(self.0.saturating_neg())
+    }
+}
+
+impl Mul<i32> for Value {
+    type Output = Value;
+
+    #[inline]
+    fn mul(self, other: i32) -> Value {
+        Value(self.0.saturating_mul(other))
+    }
+}
+
+/// The default value of a pawn.
+pub const PAWN: Value = Value(PIECE_VALUES[0] as i32);
+
+/// The default value of a knight.
+pub const KNIGHT: Value = Value(PIECE_VALUES[1] as i32);
+
+/// The default value of a bishop.
+pub const BISHOP: Value = Value(PIECE_VALUES[2] as i32);
+
+/// The default value of a rook.
+pub const ROOK: Value = Value(PIECE_VALUES[3] as i32);
+
+/// The default value of a queen.
+pub const QUEEN: Value = Value(PIECE_VALUES[4] as i32);
+
+/// The default value of a king, which has no material value, as it is never traded.
+pub const KING: Value = Value(PIECE_VALUES[5] as i32);
+
+/// The default values, indexed by `Piece::to_index()`.
+pub const DEFAULT: [Value; NUM_PIECES] = [PAWN, KNIGHT, BISHOP, ROOK, QUEEN, KING];
+
+/// The value of each piece, for the functions that weigh material.
+///
+/// An array of values, indexed by `Piece::to_index()` as `DEFAULT` is, is one.
+pub trait ValueTable {
+    /// Give me the value of `piece`.
+    fn value(&self, piece: Piece) -> Value;
+}
+
+impl ValueTable for [Value; NUM_PIECES] {
+    #[inline]
+    fn value(&self, piece: Piece) -> Value {
+        self[piece.to_index()]
+    }
+}
+
+#[test]
+fn test_value_saturates() {
+    assert_eq!(-Value(i32::MIN), Value(i32::MAX));
+    assert_eq!(Value(i32::MIN) - PAWN, Value(i32::MIN));
+    assert_eq!(Value(i32::MAX) * 2, Value(i32::MAX));
+    let mut value = QUEEN;
+    value -= ROOK;
+    value += PAWN;
+    assert_eq!(value, Value(500));
+}
+
+#[test]
+fn test_mate_scores() {
+    assert_eq!(Value::mate_in(0), Value::MATE);
+    assert!(Value::mated_in(2).is_mate());
+    assert!(Value::mated_in(2) < Value::mated_in(4));
+    assert!(Value::mate_in(MAX_MATE_PLY).is_mate());
+    assert!(!(Value::mate_in(MAX_MATE_PLY) - Value(1)).is_mate());
+}
@@ -0,0 +1,73 @@
+use crate::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+/// Which rules a `Board` is played by.
+///
+/// ```
+/// use chess::{Board, BoardBuilder, MoveGen, Variant};
+/// use std::convert::TryInto;
+///
+/// assert_eq!(Board::default().variant(), Variant::Uno);
+///
+/// let standard: Board = BoardBuilder::default()
+///     .variant(Variant::Standard)
+///     .try_into()
+///     .expect("Valid Position");
+/// assert_eq!(MoveGen::movegen_perft_test(&standard, 3), 8902);
+/// ```
+#[derive(PartialEq, Eq, Ord, PartialOrd, Copy, Clone, Debug, Hash)]
+pub enum Variant {
+    /// Normal chess.
+    Standard,
+    /// A piece may only capture a piece that attacks it, and only a piece next to the king gives
+    /// check.  So there are no pins, and no en passant.
+    Uno,
+}
+
+/// How many variants are there?
+pub const NUM_VARIANTS: usize = 2;
+
+/// An array of every variant.
+pub const ALL_VARIANTS: [Variant; NUM_VARIANTS] = [Variant::Standard, Variant::Uno];
+
+impl Default for Variant {
+    /// The uno variant, which is what this crate started out as.
+    #[inline]
+    fn default() -> Variant {
+        Variant::Uno
+    }
+}
+
+impl fmt::Display for Variant {
+    /// The name of the variant, as it is written in a tagged FEN.
+    ///
+    /// ```
+    /// use chess::Variant;
+    ///
+    /// assert_eq!(Variant::Standard.to_string(), "standard");
+    /// assert_eq!(Variant::Uno.to_string(), "uno");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Variant::Standard => "standard",
+                Variant::Uno => "uno",
+            }
+        )
+    }
+}
+
+impl FromStr for Variant {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(Variant::Standard),
+            "uno" => Ok(Variant::Uno),
+            _ => Err(Error::InvalidVariant),
+        }
+    }
+}
@@ -1,8 +1,21 @@
+//! The zobrist keys behind `Board::get_hash`.
+//!
+//! The keys are generated by build.rs from a fixed seed with a fixed generator, so they are the
+//! same on every machine and in every release.  Hashes can be persisted (opening books,
+//! transposition tables on disk) as long as `VERSION` is the same.  Any change to the keys will
+//! come with a new `VERSION`.
+//!
+//! The en passant keys are only used by `Variant::Standard`, as the uno variant has no en passant.
+
 use crate::castle_rights::CastleRights;
 use crate::color::{Color, NUM_COLORS};
+use crate::file::{File, NUM_FILES};
 use crate::piece::{Piece, NUM_PIECES};
 use crate::square::{Square, NUM_SQUARES};
 
+/// Which set of zobrist keys this crate uses.  It only changes if the keys do.
+pub const VERSION: u32 = 1;
+
 /// Create a completely blank type.  This allows all the functions to be part of this type, which I
 /// think is a bit cleaner than bare functions everywhere.
 pub struct Zobrist;
@@ -22,6 +35,32 @@ impl Zobrist {
         }
     }
 
+    /// Get the value for a pawn in the pawn hash.  These are separate from the `piece` values.
+    #[inline]
+    pub fn pawn(square: Square, color: Color) -> u64 {
+        unsafe {
+            *ZOBRIST_PAWNS
+                .get_unchecked(color.to_index())
+                .get_unchecked(square.to_index())
+        }
+    }
+
+    /// Get the value for adding a `piece` of `color` to the material key, when there are already
+    /// `count` of them on the board.
+    #[inline]
+    pub fn material(piece: Piece, count: u32, color: Color) -> u64 {
+        unsafe {
+            *ZOBRIST_MATERIAL
+                .get_unchecked(color.to_index())
+                .get_unchecked(piece.to_index())
+                .get_unchecked(count as usize)
+        }
+    }
+
+    /// Get the value for the castle rights of `color`.  This only depends on which sides it may
+    /// castle to, as `CastleRights::to_index` does, so in Chess960 two positions that only differ
+    /// in which rook may still castle hash the same.  Within one game that cannot happen, since
+    /// the rook a right belongs to never changes.
     #[inline]
     pub fn castles(castle_rights: CastleRights, color: Color) -> u64 {
         unsafe {
@@ -35,4 +74,84 @@ impl Zobrist {
     pub fn color() -> u64 {
         SIDE_TO_MOVE
     }
+
+    /// Get the value for `color` having just moved a pawn two squares on `file`, so that it can
+    /// be captured en passant.
+    #[inline]
+    pub fn en_passant(file: File, color: Color) -> u64 {
+        unsafe {
+            *ZOBRIST_EP
+                .get_unchecked(color.to_index())
+                .get_unchecked(file.to_index())
+        }
+    }
+}
+
+/// The key for a `piece` of `color` on `square`.
+///
+/// ```
+/// use chess::zobrist::{castle_key, piece_key, turn_key};
+/// use chess::{Board, CastleRights, Color, Piece, Square};
+/// use std::str::FromStr;
+///
+/// let board = Board::from_str("4k3/8/8/8/8/8/8/4K3 b - - 0 1").expect("Valid FEN");
+/// assert_eq!(
+///     board.get_hash(),
+///     piece_key(Piece::King, Color::White, Square::E1)
+///         ^ piece_key(Piece::King, Color::Black, Square::E8)
+///         ^ castle_key(CastleRights::NoRights, Color::White)
+///         ^ castle_key(CastleRights::NoRights, Color::Black)
+///         ^ turn_key()
+/// );
+/// ```
+#[inline]
+pub fn piece_key(piece: Piece, color: Color, square: Square) -> u64 {
+    Zobrist::piece(piece, square, color)
+}
+
+/// The key for `color` having `castle_rights`.  Every hash includes one for each color, even
+/// when it has no rights.
+#[inline]
+pub fn castle_key(castle_rights: CastleRights, color: Color) -> u64 {
+    Zobrist::castles(castle_rights, color)
+}
+
+/// The key that is added when black is to move.
+#[inline]
+pub fn turn_key() -> u64 {
+    Zobrist::color()
+}
+
+/// The key that is added when a pawn of `color` on `file` can be captured en passant.  Only
+/// `Variant::Standard` has en passant.
+#[inline]
+pub fn ep_key(file: File, color: Color) -> u64 {
+    Zobrist::en_passant(file, color)
+}
+
+#[test]
+fn test_pinned_hashes() {
+    use crate::board::Board;
+    use core::str::FromStr;
+
+    // If any of these change, every hash users have stored is wrong.  Don't update these numbers
+    // without also bumping VERSION.
+    let pinned = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            9023329949471135578u64,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            9322854110900140515,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            283861979892774812,
+        ),
+        ("4k3/8/8/8/8/8/8/4K3 b - - 0 1", 2144369128537324665),
+    ];
+    for (fen, hash) in pinned.iter() {
+        assert_eq!(Board::from_str(fen).unwrap().get_hash(), *hash, "{}", fen);
+    }
 }
@@ -0,0 +1,52 @@
+// Checks that generating moves never touches the heap.  The counting allocator only counts the
+// allocations of the thread that is running the test, so the test harness does not get in the way.
+
+use chess::{Board, MoveGen};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::str::FromStr;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[test]
+fn test_movegen_does_not_allocate() {
+    let boards: Vec<Board> = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+    ]
+    .iter()
+    .map(|fen| Board::from_str(fen).unwrap())
+    .collect();
+
+    let before = ALLOCATIONS.with(|count| count.get());
+    let mut nodes = 0;
+    for board in boards.iter() {
+        let movegen = MoveGen::new_legal(board);
+        assert!(movegen.len() <= MoveGen::MAX_LEGAL_MOVES);
+        nodes += movegen.len();
+        nodes += MoveGen::movegen_perft_test(board, 3);
+    }
+    let after = ALLOCATIONS.with(|count| count.get());
+
+    assert!(nodes > 0);
+    assert_eq!(after - before, 0);
+}
@@ -0,0 +1,29 @@
+// Run with `cargo test --no-default-features --test no_std_build` to check that the library still
+// builds as `#![no_std]`.  The library is compiled without `std` in that case, while this test
+// binary itself still links std for the test harness.
+
+use chess::{BitBoard, Board, ChessMove, MoveGen, Square, EMPTY};
+use core::str::FromStr;
+
+#[test]
+fn test_core_api_without_std() {
+    let board = Board::default();
+    assert_eq!(MoveGen::new_legal(&board).len(), 20);
+    // not the 8902 of normal chess, because of the uno capture rules
+    assert_eq!(MoveGen::movegen_perft_test(&board, 3), 8932);
+
+    let m = ChessMove::new(Square::E2, Square::E4, None);
+    assert!(board.legal(m));
+    assert_ne!(
+        *board.make_move_new(m).combined() & BitBoard::from_square(Square::E4),
+        EMPTY
+    );
+}
+
+#[test]
+fn test_fen_without_std() {
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+    let board = Board::from_str(fen).expect("valid FEN");
+    assert_eq!(format!("{}", board), fen);
+    assert!(Board::from_str("not a fen").is_err());
+}
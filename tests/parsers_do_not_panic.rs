@@ -0,0 +1,160 @@
+// Feeds random bytes, random ASCII and mangled versions of valid input to every parser, which have
+// to return an error instead of panicking.  Every board that does parse also has its moves
+// generated and played, so a FEN that slips past validation cannot panic later on either.
+
+use chess::{
+    uci, Board, BoardBuilder, ChessMove, Color, File, Game, MoveGen, Rank, Square, Variant,
+};
+use std::str::FromStr;
+
+const FENS: [&str; 5] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1 variant:standard",
+    "n1n5/PPP5/8/2k5/8/8/5ppp/K4N1N b - - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 variant:uno",
+];
+
+const MOVES: [&str; 10] = [
+    "e4",
+    "Nf3",
+    "exd6 e.p.",
+    "O-O",
+    "O-O-O",
+    "bxa8=Q+",
+    "Raxb1#",
+    "e2e4",
+    "e7e8q",
+    "e1h1",
+];
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn bytes(&mut self) -> String {
+        let len = self.below(80);
+        let bytes: Vec<u8> = (0..len).map(|_| self.next() as u8).collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    fn ascii(&mut self) -> String {
+        let len = self.below(80);
+        (0..len)
+            .map(|_| (b' ' + self.below(95) as u8) as char)
+            .collect()
+    }
+
+    // `text` with a few characters replaced, inserted or removed
+    fn mangle(&mut self, text: &str) -> String {
+        const ALPHABET: &[u8] = b"pnbrqkPNBRQK0123456789/- abcdefghwx=+#Oe.:";
+        let mut chars: Vec<char> = text.chars().collect();
+        for _ in 0..=self.below(4) {
+            let at = self.below(chars.len() + 1);
+            let c = ALPHABET[self.below(ALPHABET.len())] as char;
+            match self.below(3) {
+                0 if at < chars.len() => chars[at] = c,
+                1 => chars.insert(at, c),
+                _ if at < chars.len() => {
+                    chars.remove(at);
+                }
+                _ => chars.push(c),
+            }
+        }
+        chars.into_iter().collect()
+    }
+
+    fn input(&mut self, valid: &[&str]) -> String {
+        match self.below(3) {
+            0 => self.bytes(),
+            1 => self.ascii(),
+            _ => {
+                let text = valid[self.below(valid.len())];
+                self.mangle(text)
+            }
+        }
+    }
+}
+
+fn play(board: &Board, rng: &mut Rng) {
+    let mut board = *board;
+    for _ in 0..20 {
+        let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        if moves.is_empty() {
+            break;
+        }
+        board = board.make_move_new(moves[rng.below(moves.len())]);
+    }
+}
+
+#[test]
+fn fen_parsers_do_not_panic() {
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+    for _ in 0..20_000 {
+        let text = rng.input(&FENS);
+        if let Ok(board) = Board::from_str(&text) {
+            play(&board, &mut rng);
+        }
+        let _ = BoardBuilder::from_str(&text);
+        let _ = Game::from_str(&text);
+    }
+}
+
+#[test]
+fn diagram_parser_does_not_panic() {
+    let mut rng = Rng(0x94D0_49BB_1331_11EB);
+    let diagrams: Vec<String> = FENS
+        .iter()
+        .map(|fen| Board::from_str(fen).unwrap().to_ascii())
+        .collect();
+    let diagrams: Vec<&str> = diagrams.iter().map(|diagram| diagram.as_str()).collect();
+    for _ in 0..20_000 {
+        let text = rng.input(&diagrams);
+        if let Ok(board) = Board::from_diagram(&text) {
+            play(&board, &mut rng);
+        }
+    }
+}
+
+#[test]
+fn move_parsers_do_not_panic() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    let boards: Vec<Board> = FENS
+        .iter()
+        .map(|fen| Board::from_str(fen).unwrap())
+        .collect();
+    for _ in 0..20_000 {
+        let text = rng.input(&MOVES);
+        let board = &boards[rng.below(boards.len())];
+        let _ = ChessMove::from_san(board, &text);
+        let _ = ChessMove::from_str(&text);
+        let _ = uci::parse_move(board, &text, true);
+        let _ = uci::parse_move(board, &text, false);
+    }
+}
+
+#[test]
+fn small_parsers_do_not_panic() {
+    let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+    for _ in 0..20_000 {
+        let text = rng.input(&[
+            "e4", "h8", "a", "1", "white", "b", "standard", "uno", "chess",
+        ]);
+        let _ = Square::from_str(&text);
+        let _ = File::from_str(&text);
+        let _ = Rank::from_str(&text);
+        let _ = Color::from_str(&text);
+        let _ = Variant::from_str(&text);
+        let _ = uci::parse_variant(&text);
+    }
+}
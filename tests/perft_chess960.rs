@@ -0,0 +1,54 @@
+// Perft counts for Chess960 positions in normal chess, as published for Reinhard Scharnagl's
+// test positions.  The kings and rooks start off their usual files, so castling may move the
+// king onto its own rook, or the rook past the king, or either of them not at all.
+
+use chess::{Board, MoveGen};
+use std::str::FromStr;
+
+// (fen, node counts at depths 1 to 5)
+const POSITIONS: [(&str, [usize; 5]); 5] = [
+    (
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9 variant:standard",
+        [21, 528, 12189, 326672, 8146062],
+    ),
+    (
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9 variant:standard",
+        [21, 807, 18002, 667366, 16253601],
+    ),
+    (
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9 variant:standard",
+        [20, 479, 10471, 273318, 6417013],
+    ),
+    (
+        "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9 variant:standard",
+        [22, 593, 13440, 382958, 9183776],
+    ),
+    (
+        "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9 variant:standard",
+        [28, 1120, 31058, 1171749, 34030312],
+    ),
+];
+
+#[test]
+fn perft_chess960() {
+    for (fen, counts) in POSITIONS.iter() {
+        let board = Board::from_str(fen).unwrap();
+        for (depth, count) in counts.iter().enumerate() {
+            assert_eq!(
+                MoveGen::movegen_perft_test(&board, depth + 1),
+                *count,
+                "{} at depth {}",
+                fen,
+                depth + 1
+            );
+        }
+    }
+}
+
+#[test]
+fn chess960_fens_round_trip() {
+    for (fen, _) in POSITIONS.iter() {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(Board::from_str(&board.to_tagged_fen()).unwrap(), board);
+    }
+}
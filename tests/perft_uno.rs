@@ -0,0 +1,184 @@
+// Perft counts for the uno variant.  No other engine plays by these rules, so the counts below
+// come from the slow move generator in `tests/reference_movegen/mod.rs`, and `MoveGen` has to agree
+// with both the table and the reference itself.
+
+mod reference_movegen;
+
+use chess::{Board, ChessMove, MoveGen};
+use reference_movegen::Position;
+use std::str::FromStr;
+
+// (fen, node counts at depths 1 to 5)
+const POSITIONS: [(&str, [u64; 5]); 18] = [
+    // the start position, where no piece attacks another yet
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        [20, 400, 8932, 199871, 4964978],
+    ),
+    // kiwipete, full of pieces that attack each other and with all castling rights
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        [47, 2035, 93470, 3943885, 179076895],
+    ),
+    // sliders aimed at the kings, which would pin and check them in normal chess
+    (
+        "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+        [29, 1235, 37529, 1687566, 52221673],
+    ),
+    // nothing but castling and rook moves
+    (
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        [26, 614, 15865, 388452, 10079513],
+    ),
+    // the rook on f8 attacks the king on c8 from a distance, so the king may take it
+    (
+        "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+        [10, 157, 1760, 28824, 414412],
+    ),
+    // a king and a rook on the same rank as the enemy rook, which is no pin here
+    (
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        [15, 185, 2829, 38971, 617222],
+    ),
+    // a double push that would allow en passant in normal chess
+    (
+        "rnbqkbnr/ppp2pp1/4p3/3N4/3PpPp1/8/PPP3PP/R1B1KBNR b KQkq f3 0 1",
+        [39, 1396, 51363, 1821807, 65882269],
+    ),
+    // a pawn that a rook attacks along the rank
+    (
+        "3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1",
+        [17, 111, 1835, 12473, 204097],
+    ),
+    // a queen and a knight against a pawn about to promote
+    (
+        "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1",
+        [27, 253, 6901, 65128, 1799865],
+    ),
+    // a lone pawn promoting next to the enemy king
+    ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", [10, 16, 194, 383, 5421]),
+    // promotions by pushing, and by capturing the knights that attack the pawns
+    (
+        "n1n5/PPP5/8/2k5/8/8/5ppp/K4N1N b - - 0 1",
+        [23, 452, 10051, 192830, 4361925],
+    ),
+    // a middlegame where white has castled
+    (
+        "r1bqkb1r/pp3ppp/5n2/2ppn1N1/4pP2/1BN1P3/PPPP2PP/R1BQ1RK1 w kq - 0 9",
+        [29, 1246, 38414, 1601907, 51121517],
+    ),
+    // the knight on f2 checks nothing, but it attacks the queen and the rook
+    (
+        "rnbqkb1r/pp1p1ppp/2p5/4P3/2B5/8/PPP1NnPP/RNBQK2R w KQkq - 0 6",
+        [3, 98, 3630, 120474, 4695449],
+    ),
+    // a symmetrical middlegame where both sides have castled
+    (
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        [46, 2100, 92596, 4045935, 173130353],
+    ),
+    // two rooks that attack each other and nothing else
+    (
+        "4k3/8/8/4r2r/8/8/8/4K3 w - - 0 1",
+        [6, 142, 938, 25264, 156941],
+    ),
+    // a double check, where the king can take neither checker without standing next to the
+    // other one
+    ("4k3/8/8/8/8/8/3nr3/4K3 w - - 0 1", [0, 0, 0, 0, 0]),
+    // pawns that capture the knights attacking them, one of them backwards
+    (
+        "4k3/8/8/8/3n4/8/2P1P3/n3K3 w - - 0 1",
+        [11, 129, 1128, 14865, 124855],
+    ),
+    // castling rights while the bishops attack the squares the kings cross
+    (
+        "r3k2r/8/8/1b5b/8/8/6p1/R3K2R w KQkq - 0 1",
+        [20, 756, 14598, 562825, 11651815],
+    ),
+];
+
+// The reference visits every node, so it only checks the counts up to this size.
+const REFERENCE_LIMIT: u64 = 200_000;
+
+#[test]
+fn perft_uno_movegen() {
+    for (fen, counts) in POSITIONS.iter() {
+        let board = Board::from_str(fen).unwrap();
+        for (depth, count) in counts.iter().enumerate() {
+            assert_eq!(
+                MoveGen::movegen_perft_test(&board, depth + 1),
+                *count as usize,
+                "{} at depth {}",
+                fen,
+                depth + 1
+            );
+        }
+    }
+}
+
+#[test]
+fn perft_uno_reference() {
+    for (fen, counts) in POSITIONS.iter() {
+        let position = Position::from_fen(fen);
+        for (depth, count) in counts.iter().enumerate() {
+            if *count > REFERENCE_LIMIT {
+                break;
+            }
+            assert_eq!(
+                position.perft(depth + 1),
+                *count,
+                "{} at depth {}",
+                fen,
+                depth + 1
+            );
+        }
+    }
+}
+
+#[test]
+fn reference_start_position() {
+    let position = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    // no piece attacks another in the start position, so it begins like normal chess
+    assert_eq!(position.perft(1), 20);
+    assert_eq!(position.perft(2), 400);
+}
+
+fn sorted_uci(moves: impl Iterator<Item = String>) -> Vec<String> {
+    let mut moves: Vec<String> = moves.collect();
+    moves.sort();
+    moves
+}
+
+#[test]
+fn random_games_match_reference() {
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for (fen, _) in POSITIONS.iter() {
+        for _ in 0..10 {
+            let mut board = Board::from_str(fen).unwrap();
+            let mut position = Position::from_fen(fen);
+            for _ in 0..80 {
+                let reference = position.legal_moves();
+                let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+                assert_eq!(
+                    sorted_uci(moves.iter().map(|m| m.to_string())),
+                    sorted_uci(reference.iter().map(|m| m.to_string())),
+                    "{}",
+                    board
+                );
+                if moves.is_empty() {
+                    break;
+                }
+                let m = moves[(next() % moves.len() as u64) as usize];
+                let uci = m.to_string();
+                board = board.make_move_new(m);
+                position = position.make(reference.iter().find(|r| r.to_string() == uci).unwrap());
+            }
+        }
+    }
+}
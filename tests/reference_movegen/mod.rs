@@ -0,0 +1,409 @@
+// A slow move generator for the uno variant, written to be easy to check by hand rather than
+// fast.  It shares no code with the crate: the board is a plain array of 64 squares, every rule
+// is a loop over squares, and a move is legal if, after making it, no enemy piece could capture
+// my king.  `tests/perft_uno.rs` uses it as the reference for `MoveGen`.
+//
+// The uno rules, as this file implements them:
+//
+// * Pieces move to empty squares as in normal chess, including pawn double pushes and castling.
+// * A piece may capture an enemy piece only if that piece attacks it, and then moves onto the
+//   attacker's square, however far away it is.
+// * A king is in check if an enemy piece could capture it, so only when an enemy piece stands
+//   next to it.  Castling needs the king to be safe where it starts, crosses and lands.
+// * A pawn that lands on its last rank promotes, whether it got there by a push or a capture.
+// * There is no en passant.
+
+use std::fmt;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Piece {
+    pub white: bool,
+    pub kind: Kind,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    pub promotion: Option<Kind>,
+    pub castle: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Position {
+    // a1 is 0, b1 is 1, ..., h8 is 63
+    pub squares: [Option<Piece>; 64],
+    pub white_to_move: bool,
+    // white kingside, white queenside, black kingside, black queenside
+    pub castling: [bool; 4],
+}
+
+const KNIGHT_JUMPS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_STEPS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const PROMOTIONS: [Kind; 4] = [Kind::Queen, Kind::Rook, Kind::Bishop, Kind::Knight];
+
+fn file_of(square: usize) -> i32 {
+    (square % 8) as i32
+}
+
+fn rank_of(square: usize) -> i32 {
+    (square / 8) as i32
+}
+
+// The square `df` files and `dr` ranks away from `square`, if that is still on the board.
+fn offset(square: usize, df: i32, dr: i32) -> Option<usize> {
+    let file = file_of(square) + df;
+    let rank = rank_of(square) + dr;
+    if (0..8).contains(&file) && (0..8).contains(&rank) {
+        Some((rank * 8 + file) as usize)
+    } else {
+        None
+    }
+}
+
+fn square_name(square: usize) -> String {
+    let file = (b'a' + file_of(square) as u8) as char;
+    let rank = (b'1' + rank_of(square) as u8) as char;
+    format!("{}{}", file, rank)
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", square_name(self.from), square_name(self.to))?;
+        match self.promotion {
+            Some(Kind::Queen) => write!(f, "q"),
+            Some(Kind::Rook) => write!(f, "r"),
+            Some(Kind::Bishop) => write!(f, "b"),
+            Some(Kind::Knight) => write!(f, "n"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Position {
+    // Only the first three fields of the FEN matter: there is no en passant in the uno variant,
+    // and perft does not care about the move counters.
+    pub fn from_fen(fen: &str) -> Position {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let mut squares = [None; 64];
+        let mut rank = 7;
+        let mut file = 0;
+        for c in fields[0].chars() {
+            match c {
+                '/' => {
+                    rank -= 1;
+                    file = 0;
+                }
+                '1'..='8' => file += c.to_digit(10).unwrap() as usize,
+                _ => {
+                    let kind = match c.to_ascii_lowercase() {
+                        'p' => Kind::Pawn,
+                        'n' => Kind::Knight,
+                        'b' => Kind::Bishop,
+                        'r' => Kind::Rook,
+                        'q' => Kind::Queen,
+                        'k' => Kind::King,
+                        _ => panic!("bad piece {} in {}", c, fen),
+                    };
+                    squares[rank * 8 + file] = Some(Piece {
+                        white: c.is_ascii_uppercase(),
+                        kind,
+                    });
+                    file += 1;
+                }
+            }
+        }
+        let castling = fields[2];
+        Position {
+            squares,
+            white_to_move: fields[1] == "w",
+            castling: [
+                castling.contains('K'),
+                castling.contains('Q'),
+                castling.contains('k'),
+                castling.contains('q'),
+            ],
+        }
+    }
+
+    // Every square the piece on `from` attacks, whether empty or not.  A slider stops at the
+    // first piece in its way, and attacks that piece.
+    fn attacks(&self, from: usize) -> Vec<usize> {
+        let piece = self.squares[from].unwrap();
+        let mut result = Vec::new();
+        let mut slide = |directions: &[(i32, i32)]| {
+            for (df, dr) in directions.iter() {
+                let mut square = from;
+                while let Some(next) = offset(square, *df, *dr) {
+                    result.push(next);
+                    if self.squares[next].is_some() {
+                        break;
+                    }
+                    square = next;
+                }
+            }
+        };
+        match piece.kind {
+            Kind::Bishop => slide(&BISHOP_DIRECTIONS),
+            Kind::Rook => slide(&ROOK_DIRECTIONS),
+            Kind::Queen => {
+                slide(&BISHOP_DIRECTIONS);
+                slide(&ROOK_DIRECTIONS);
+            }
+            Kind::Knight => result.extend(
+                KNIGHT_JUMPS
+                    .iter()
+                    .filter_map(|(df, dr)| offset(from, *df, *dr)),
+            ),
+            Kind::King => result.extend(
+                KING_STEPS
+                    .iter()
+                    .filter_map(|(df, dr)| offset(from, *df, *dr)),
+            ),
+            Kind::Pawn => {
+                let forward = if piece.white { 1 } else { -1 };
+                result.extend([-1, 1].iter().filter_map(|df| offset(from, *df, forward)));
+            }
+        }
+        result
+    }
+
+    // May the piece on `from` capture the piece on `to`?  Only if they are enemies, and the
+    // piece on `to` attacks `from`.
+    fn can_capture(&self, from: usize, to: usize) -> bool {
+        match (self.squares[from], self.squares[to]) {
+            (Some(a), Some(b)) if a.white != b.white => self.attacks(to).contains(&from),
+            _ => false,
+        }
+    }
+
+    fn king_square(&self, white: bool) -> usize {
+        (0..64)
+            .find(|sq| {
+                self.squares[*sq]
+                    == Some(Piece {
+                        white,
+                        kind: Kind::King,
+                    })
+            })
+            .unwrap()
+    }
+
+    // Could any enemy piece capture the king of `white`?  That is any enemy piece the king
+    // attacks, the same test as `can_capture` with the king as the victim.
+    fn king_capturable(&self, white: bool) -> bool {
+        let king = self.king_square(white);
+        self.attacks(king)
+            .iter()
+            .any(|sq| self.squares[*sq].map(|p| p.white) == Some(!white))
+    }
+
+    fn is_empty(&self, square: usize) -> bool {
+        self.squares[square].is_none()
+    }
+
+    // The moves of the side to move, before checking whether they leave the king capturable.
+    fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let white = self.white_to_move;
+        let mut moves = Vec::new();
+        let mut add = |from: usize, to: usize, last_rank: bool| {
+            if last_rank {
+                for promotion in PROMOTIONS.iter() {
+                    moves.push(Move {
+                        from,
+                        to,
+                        promotion: Some(*promotion),
+                        castle: false,
+                    });
+                }
+            } else {
+                moves.push(Move {
+                    from,
+                    to,
+                    promotion: None,
+                    castle: false,
+                });
+            }
+        };
+
+        for from in 0..64 {
+            let piece = match self.squares[from] {
+                Some(piece) if piece.white == white => piece,
+                _ => continue,
+            };
+            let promotes =
+                |to: usize| piece.kind == Kind::Pawn && rank_of(to) == if white { 7 } else { 0 };
+
+            // quiet moves
+            if piece.kind == Kind::Pawn {
+                let forward = if white { 1 } else { -1 };
+                if let Some(one) = offset(from, 0, forward) {
+                    if self.is_empty(one) {
+                        add(from, one, promotes(one));
+                        let second_rank = if white { 1 } else { 6 };
+                        if rank_of(from) == second_rank {
+                            let two = offset(one, 0, forward).unwrap();
+                            if self.is_empty(two) {
+                                add(from, two, false);
+                            }
+                        }
+                    }
+                }
+            } else {
+                for to in self.attacks(from) {
+                    if self.is_empty(to) {
+                        add(from, to, false);
+                    }
+                }
+            }
+
+            // captures, under the uno rule; the enemy king is never left where it can be
+            // captured, so it is never taken
+            for to in 0..64 {
+                let king = self.squares[to].map(|p| p.kind) == Some(Kind::King);
+                if !king && self.can_capture(from, to) {
+                    add(from, to, promotes(to));
+                }
+            }
+        }
+
+        // castling: the rights, the king and rook where they start, nothing between them, and
+        // the king safe where it starts and on the square it crosses (the square it lands on is
+        // checked with every other move)
+        let (rights, back) = if white { (0, 0) } else { (2, 56) };
+        let king = Some(Piece {
+            white,
+            kind: Kind::King,
+        });
+        let rook = Some(Piece {
+            white,
+            kind: Kind::Rook,
+        });
+        let sides = [
+            (
+                self.castling[rights],
+                back + 7,
+                vec![back + 5, back + 6],
+                back + 5,
+                back + 6,
+            ),
+            (
+                self.castling[rights + 1],
+                back,
+                vec![back + 1, back + 2, back + 3],
+                back + 3,
+                back + 2,
+            ),
+        ];
+        for (allowed, rook_square, empty, crossed, to) in sides.iter() {
+            if *allowed
+                && self.squares[back + 4] == king
+                && self.squares[*rook_square] == rook
+                && empty.iter().all(|sq| self.is_empty(*sq))
+                && !self.king_capturable(white)
+            {
+                let mut step = self.clone();
+                step.squares[*crossed] = step.squares[back + 4].take();
+                if !step.king_capturable(white) {
+                    moves.push(Move {
+                        from: back + 4,
+                        to: *to,
+                        promotion: None,
+                        castle: true,
+                    });
+                }
+            }
+        }
+
+        moves
+    }
+
+    pub fn make(&self, m: &Move) -> Position {
+        let mut result = self.clone();
+        let mut piece = result.squares[m.from].take().unwrap();
+        if let Some(promotion) = m.promotion {
+            piece.kind = promotion;
+        }
+        result.squares[m.to] = Some(piece);
+
+        if m.castle {
+            let back = if piece.white { 0 } else { 56 };
+            let (rook_from, rook_to) = if m.to == back + 6 {
+                (back + 7, back + 5)
+            } else {
+                (back, back + 3)
+            };
+            result.squares[rook_to] = result.squares[rook_from].take();
+        }
+
+        // moving from or onto a king or rook square loses those castle rights
+        for square in [m.from, m.to].iter() {
+            match square {
+                4 => {
+                    result.castling[0] = false;
+                    result.castling[1] = false;
+                }
+                7 => result.castling[0] = false,
+                0 => result.castling[1] = false,
+                60 => {
+                    result.castling[2] = false;
+                    result.castling[3] = false;
+                }
+                63 => result.castling[2] = false,
+                56 => result.castling[3] = false,
+                _ => {}
+            }
+        }
+
+        result.white_to_move = !result.white_to_move;
+        result
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|m| !self.make(m).king_capturable(self.white_to_move))
+            .collect()
+    }
+
+    pub fn perft(&self, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves.iter().map(|m| self.make(m).perft(depth - 1)).sum()
+    }
+}
@@ -0,0 +1,170 @@
+// Inputs that are likely to crash a parser, kept as tests so they are checked without running
+// the fuzz targets under fuzz/.  Each parser has to return an error, or a board or move that
+// holds up, instead of panicking.
+
+use chess::{Board, BoardBuilder, ChessMove, Error, MoveFormat, MoveGen, Square};
+use std::str::FromStr;
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn parse_fen(fen: &str) -> Result<Board, Error> {
+    let _ = BoardBuilder::from_str(fen);
+    let board = Board::from_str(fen)?;
+    assert_eq!(Board::from_str(&board.to_tagged_fen()).unwrap(), board);
+    for m in MoveGen::new_legal(&board) {
+        assert!(board.make_move_new(m).is_sane());
+    }
+    Ok(board)
+}
+
+#[test]
+fn fen_ranks_that_overflow() {
+    for fen in [
+        "9/8/8/8/8/8/8/4K2k w - - 0 1",
+        "88/8/8/8/8/8/8/4K2k w - - 0 1",
+        "k7p/8/8/8/8/8/8/4K3 w - - 0 1",
+        "k8/8/8/8/8/8/8/4K3 w - - 0 1",
+        "k6/8/8/8/8/8/8/8/8/4K3 w - - 0 1",
+        "k7/8/8/8/8/8/8/4K3/ w - - 0 1",
+        "/k7/8/8/8/8/8/8/4K3 w - - 0 1",
+        "k7//8/8/8/8/8/8/4K3 w - - 0 1",
+        "00000000k7/8/8/8/8/8/8/4K3 w - - 0 1",
+    ]
+    .iter()
+    {
+        assert!(
+            matches!(parse_fen(fen), Err(Error::InvalidFen { .. })),
+            "{}",
+            fen
+        );
+    }
+    // eight squares counted in pieces of four are still a rank
+    assert!(parse_fen("44/k7/8/8/8/8/8/4K3 w - - 0 1").is_ok());
+}
+
+#[test]
+fn fen_fields_with_multi_byte_characters() {
+    for fen in [
+        "rnbqkbnr/ppppppp\u{e9}/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/\u{1f600}/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR \u{e9} KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 variant:\u{fc}no",
+    ]
+    .iter()
+    {
+        assert!(
+            matches!(parse_fen(fen), Err(Error::InvalidFen { .. })),
+            "{}",
+            fen
+        );
+    }
+
+    // castle rights and en passant squares that are not understood are left out
+    let start = Board::from_str(START).unwrap();
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq\u{300} - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e\u{300}3 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq \u{e9}3 0 1",
+    ]
+    .iter()
+    {
+        assert_eq!(parse_fen(fen).unwrap(), start, "{}", fen);
+    }
+}
+
+#[test]
+fn fen_move_counters_are_not_read() {
+    let start = Board::from_str(START).unwrap();
+    for counters in [
+        "99999999999999999999999999 1",
+        "0 340282366920938463463374607431768211456",
+        "-1 -1",
+        "\u{1f600} \u{1f600}",
+    ]
+    .iter()
+    {
+        let fen = format!(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - {}",
+            counters
+        );
+        assert_eq!(parse_fen(&fen).unwrap(), start, "{}", fen);
+    }
+}
+
+#[test]
+fn san_moves_that_are_not_moves() {
+    let castles = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    for san in [
+        "O-O-O-O",
+        "O-O-",
+        "O-",
+        "O",
+        "O-O-O-O-O-O-O-O",
+        "",
+        "x",
+        "=",
+        "+",
+        "e",
+        "e9",
+        "i4",
+        "Nf",
+        "Kx",
+        "e8=",
+        "e8=K",
+        "\u{e9}4",
+        "N\u{e9}3",
+        "e\u{300}4",
+        "R\u{1f600}",
+        "exd6 e.p.\u{e9}",
+    ]
+    .iter()
+    {
+        assert!(
+            matches!(
+                ChessMove::from_san(&castles, san),
+                Err(Error::InvalidSanMove { .. })
+            ),
+            "{}",
+            san
+        );
+    }
+    assert_eq!(
+        ChessMove::from_san(&castles, "O-O-O").unwrap(),
+        ChessMove::new(Square::E1, Square::C1, None)
+    );
+}
+
+#[test]
+fn move_sequences_that_are_not_moves() {
+    let board = Board::default();
+    for moves in [
+        &["e4", "O-O-O-O"][..],
+        &["e2e4", "\u{e9}7e5"][..],
+        &["e2e4", "e7e5\u{300}"][..],
+        &["e4", ""][..],
+    ]
+    .iter()
+    {
+        let format = if moves[0] == "e4" {
+            MoveFormat::San
+        } else {
+            MoveFormat::Coordinate
+        };
+        let error = board
+            .apply_moves(moves.iter().copied(), format)
+            .unwrap_err();
+        assert_eq!((error.index, error.text.as_str()), (1, moves[1]));
+    }
+}
+
+#[test]
+fn coordinate_moves_with_text_after_them() {
+    for text in ["e2e4\u{300}", "e2e4 ", "e2e4e4", "e7e8qq", "e7e8\u{e9}"].iter() {
+        assert!(
+            matches!(ChessMove::from_str(text), Err(Error::InvalidUciMove)),
+            "{}",
+            text
+        );
+    }
+    assert!(ChessMove::from_str("e7e8q").is_ok());
+}
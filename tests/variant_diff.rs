@@ -0,0 +1,147 @@
+// Perft under the normal rules and the uno rules from the same positions, to show where the two
+// variants part ways.  The report for the start position is kept in `tests/variant_diff.expected`.
+// When the capture rules change on purpose, run this with `BLESS=1` to write it again, and check
+// the difference in review.
+
+use chess::{diff_legal_moves, Board, BoardBuilder, ChessMove, MoveGen, Variant};
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::str::FromStr;
+
+const EXPECTED: &str = "tests/variant_diff.expected";
+
+fn with_variant(board: &Board, variant: Variant) -> Option<Board> {
+    let mut builder = BoardBuilder::from(board);
+    builder.variant(variant);
+    Board::try_from(&builder).ok()
+}
+
+fn perft(board: &Board, variant: Variant, depth: usize) -> usize {
+    with_variant(board, variant).map_or(0, |board| MoveGen::movegen_perft_test(&board, depth))
+}
+
+// The first depth up to `max_depth` at which the counts differ.
+fn divergence(board: &Board, max_depth: usize) -> Option<usize> {
+    (1..=max_depth)
+        .find(|depth| perft(board, Variant::Standard, *depth) != perft(board, Variant::Uno, *depth))
+}
+
+// The first position, walking the moves that are legal under both rules in order, whose legal
+// moves differ, with the moves that lead to it.  `depth` is how many moves it may be away.
+fn first_difference(
+    board: &Board,
+    depth: usize,
+    line: &mut Vec<ChessMove>,
+) -> Option<(Vec<ChessMove>, Vec<ChessMove>)> {
+    let (standard, uno) = diff_legal_moves(board);
+    if !standard.is_empty() || !uno.is_empty() {
+        return Some((standard, uno));
+    }
+    if depth == 0 {
+        return None;
+    }
+    for m in MoveGen::new_legal(board) {
+        line.push(m);
+        if let Some(diff) = first_difference(&board.make_move_new(m), depth - 1, line) {
+            return Some(diff);
+        }
+        line.pop();
+    }
+    None
+}
+
+fn moves(moves: &[ChessMove]) -> String {
+    if moves.is_empty() {
+        return "-".to_string();
+    }
+    let moves: Vec<String> = moves.iter().map(ChessMove::to_string).collect();
+    moves.join(" ")
+}
+
+fn report(board: &Board, depth: usize) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", board).unwrap();
+    writeln!(out, "depth standard uno").unwrap();
+    for d in 1..=depth {
+        let standard = perft(board, Variant::Standard, d);
+        let uno = perft(board, Variant::Uno, d);
+        writeln!(out, "{} {} {}", d, standard, uno).unwrap();
+    }
+
+    let mut line = vec![];
+    match first_difference(board, depth - 1, &mut line) {
+        None => writeln!(out, "no difference").unwrap(),
+        Some((standard, uno)) => {
+            writeln!(out, "first difference after: {}", moves(&line)).unwrap();
+            writeln!(out, "standard only: {}", moves(&standard)).unwrap();
+            writeln!(out, "uno only: {}", moves(&uno)).unwrap();
+        }
+    }
+
+    // the root moves whose subtrees differ
+    let divide = |variant| {
+        with_variant(board, variant).map_or(vec![], |board| MoveGen::perft_divide(&board, depth).0)
+    };
+    let standard = divide(Variant::Standard);
+    let uno = divide(Variant::Uno);
+    let mut roots: Vec<ChessMove> = standard.iter().chain(uno.iter()).map(|(m, _)| *m).collect();
+    roots.sort_by_key(ChessMove::to_string);
+    roots.dedup();
+    let nodes = |divide: &[(ChessMove, u64)], m| {
+        divide
+            .iter()
+            .find(|(other, _)| *other == m)
+            .map_or(0, |(_, nodes)| *nodes)
+    };
+    for m in roots {
+        let (standard, uno) = (nodes(&standard, m), nodes(&uno, m));
+        if standard != uno {
+            writeln!(out, "{} {} {}", m, standard, uno).unwrap();
+        }
+    }
+    out
+}
+
+#[test]
+fn start_position_report() {
+    let report = report(&Board::default(), 3);
+    if std::env::var_os("BLESS").is_some() {
+        std::fs::write(EXPECTED, &report).unwrap();
+    }
+    let expected = std::fs::read_to_string(EXPECTED).unwrap();
+    assert_eq!(
+        report, expected,
+        "run with BLESS=1 to write {} again",
+        EXPECTED
+    );
+}
+
+#[test]
+fn first_divergence() {
+    // (fen, the first depth at which the counts differ)
+    for (fen, depth) in [
+        // nothing attacks anything for two moves
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            Some(3),
+        ),
+        // full of pieces that attack each other
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            Some(1),
+        ),
+        // the rook attacks the king from a distance, and the pawn can take it in normal chess
+        ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", Some(1)),
+        // the kings alone move the same under both rules
+        ("8/8/8/3k4/8/8/8/4K3 w - - 0 1", None),
+    ]
+    .iter()
+    {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(divergence(&board, 3), *depth, "{}", fen);
+
+        // and there is a position on the way where the moves differ
+        let found = first_difference(&board, 2, &mut vec![]).is_some();
+        assert_eq!(found, depth.is_some(), "{}", fen);
+    }
+}
@@ -0,0 +1,17 @@
+// Smoke test for `wasm32-unknown-unknown`, run with `wasm-pack test --node`.  This is empty on
+// every other target.
+#![cfg(target_arch = "wasm32")]
+
+use chess::{Board, MoveGen};
+use core::str::FromStr;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn test_wasm_fen_movegen_and_perft() {
+    let board =
+        Board::from_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .expect("valid FEN");
+    assert_eq!(MoveGen::new_legal(&board).len(), 47);
+    assert_eq!(MoveGen::movegen_perft_test(&board, 3), 93470);
+    assert_eq!(MoveGen::movegen_perft_test(&Board::default(), 3), 8932);
+}